@@ -16,8 +16,7 @@ fn main() {
 
     loop {
         b.poll(2).unwrap();
-        let physical_pin = b.get_physical_pin(pin);
-        println!("analog value: {}", physical_pin.value);
+        println!("analog value: {}", b.try_value(pin).unwrap());
         std::thread::sleep(std::time::Duration::from_millis(200));
     }
 }
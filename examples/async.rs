@@ -14,7 +14,7 @@ pub async fn main() -> Result<()> {
     board.generate_board_state().await?;
 
     let mut board_communicator = board.get_board();
-    let mut board_communicator2 = board.get_board();
+    let mut board_communicator2 = board_communicator.clone();
 
     // Backend IO
     let _x = tokio::task::spawn(async move { board.poll().await });
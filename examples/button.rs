@@ -10,23 +10,20 @@ fn main() {
     println!("protocol version {}", b.protocol_version());
 
     let led = PinId::Digital(13);
-    let button_num: u8 = 2;
-    let button = PinId::Digital(button_num);
+    let button = PinId::Digital(2);
 
     b.set_pin_mode(led, PinMode::Output).unwrap();
     b.set_pin_mode(button, PinMode::Input).unwrap();
 
-    b.report_digital(button, true).unwrap();
+    b.report_digital_pin(button, true).unwrap();
+    b.on_change(button, |change| {
+        println!("{}", if change.new == 0 { "off" } else { "on" })
+    });
 
     loop {
         b.poll(1).unwrap();
-        if b.pins()[button_num as usize].value == 0 {
-            println!("off");
-            b.digital_write(led, 0).unwrap();
-        } else {
-            println!("on");
-            b.digital_write(led, 1).unwrap();
-        }
+        let value = b.try_value(button).unwrap();
+        b.digital_write(led, value).unwrap();
 
         std::thread::sleep(std::time::Duration::from_millis(3));
     }
@@ -13,11 +13,11 @@ fn main() {
 
     b.set_pin_mode(pin, PinMode::Pwm).unwrap();
 
+    let fade = std::time::Duration::from_secs(1);
     loop {
-        for value in 0..255 {
-            b.analog_write(pin, value).unwrap();
-            println!("{}", value);
-            std::thread::sleep(std::time::Duration::from_millis(3));
-        }
+        b.ramp_duty(pin, 0.0, 1.0, fade, pwm::RampCurve::Gamma)
+            .unwrap();
+        b.ramp_duty(pin, 1.0, 0.0, fade, pwm::RampCurve::Gamma)
+            .unwrap();
     }
 }
@@ -11,12 +11,19 @@ fn main() {
     println!("protocol version {}", b.protocol_version());
 
     b.set_pin_mode(pin, PinMode::Servo).unwrap();
+    b.servo_config(pin, 500, 2500).unwrap();
 
+    let sweep = std::time::Duration::from_secs(3);
     loop {
-        for value in 0..180 {
-            b.analog_write(pin, value).unwrap();
-            println!("{}", value);
-            std::thread::sleep(std::time::Duration::from_millis(200));
-        }
+        b.ramp(
+            pin,
+            pwm::Ramp::new(0, 180, sweep, pwm::RampCurve::EaseInOut),
+        )
+        .unwrap();
+        b.ramp(
+            pin,
+            pwm::Ramp::new(180, 0, sweep, pwm::RampCurve::EaseInOut),
+        )
+        .unwrap();
     }
 }
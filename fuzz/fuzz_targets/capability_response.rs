@@ -0,0 +1,7 @@
+#![no_main]
+use firmata::message::CapabilityResponse;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = CapabilityResponse::deserialize(data);
+});
@@ -0,0 +1,16 @@
+#![no_main]
+use bytes::BytesMut;
+use firmata::asynchronous::boardio::MessageOut;
+use firmata::asynchronous::network::FirmataCodec;
+use libfuzzer_sys::fuzz_target;
+use tokio_util::codec::{Decoder, Encoder};
+
+// Encodes arbitrary messages and decodes the bytes back, neither side may panic.
+fuzz_target!(|messages: Vec<MessageOut>| {
+    let mut codec = FirmataCodec::default();
+    let mut buf = BytesMut::new();
+    for message in messages {
+        let _ = codec.encode(message, &mut buf);
+    }
+    while let Ok(Some(_)) = codec.decode(&mut buf) {}
+});
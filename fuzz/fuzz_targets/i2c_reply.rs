@@ -0,0 +1,7 @@
+#![no_main]
+use firmata::message::I2cReply;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = I2cReply::deserialize(data);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+use bytes::BytesMut;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = firmata::fuzzing::parse_data(&mut BytesMut::from(data), true);
+    let _ = firmata::fuzzing::parse_data(&mut BytesMut::from(data), false);
+});
@@ -0,0 +1,7 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = firmata::fuzzing::read_and_parse_system(&mut &data[..], true);
+    let _ = firmata::fuzzing::read_and_parse_system(&mut &data[..], false);
+});
@@ -0,0 +1,65 @@
+//! Encoding of analog and PWM writes, escalating to EXTENDED_ANALOG where the compact
+//! ANALOG_MESSAGE can not carry the pin or the value.
+use crate::message;
+use crate::protocol::{MidiCommand, SysexCommand};
+use crate::{FirmataError, Result};
+
+/// The highest pin the compact message can address, it is sent in the command nibble.
+pub const MAX_COMPACT_PIN: u8 = 0x0F;
+/// The largest value the compact message can carry in its two 7 bit bytes.
+pub const MAX_COMPACT_VALUE: u16 = 0x3FFF;
+
+/// Whether a write has to be sent as EXTENDED_ANALOG.
+#[must_use]
+pub const fn needs_extended(pin: u8, value: u16) -> bool {
+    pin > MAX_COMPACT_PIN || value > MAX_COMPACT_VALUE
+}
+
+/// Encodes a write as ANALOG_MESSAGE, or as EXTENDED_ANALOG if [`needs_extended`].
+/// # Errors
+/// Returns [`FirmataError::OutOfRange`] if the pin does not fit EXTENDED_ANALOG.
+pub fn encode_write(pin: u8, value: u16) -> Result<Vec<u8>> {
+    if needs_extended(pin, value) {
+        return encode_extended(pin, value);
+    }
+    let [lsb, msb] = message::encode_u14(value);
+    Ok(vec![MidiCommand::AnalogMessage.with_channel(pin), lsb, msb])
+}
+
+/// Encodes a write as EXTENDED_ANALOG, the value is sent 7 bits at a time with a
+/// third byte only for values beyond 14 bits.
+/// # Errors
+/// Returns [`FirmataError::OutOfRange`] if the pin does not fit in 7 bits.
+pub fn encode_extended(pin: u8, value: u16) -> Result<Vec<u8>> {
+    if pin > 0x7F {
+        return Err(FirmataError::OutOfRange(
+            "EXTENDED_ANALOG addresses pins up to 127",
+        ));
+    }
+    let mut payload = vec![pin, (value & 0x7F) as u8, ((value >> 7) & 0x7F) as u8];
+    if value > MAX_COMPACT_VALUE {
+        payload.push((value >> 14) as u8);
+    }
+    Ok(SysexCommand::ExtendedAnalog.frame(&payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extended_writes_reject_pins_beyond_seven_bits() {
+        assert_eq!(
+            encode_extended(127, 1).unwrap(),
+            vec![0xF0, 0x6F, 0x7F, 0x01, 0x00, 0xF7]
+        );
+        assert!(matches!(
+            encode_extended(128, 1),
+            Err(FirmataError::OutOfRange(_))
+        ));
+        assert!(matches!(
+            encode_write(200, 1),
+            Err(FirmataError::OutOfRange(_))
+        ));
+    }
+}
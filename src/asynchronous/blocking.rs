@@ -0,0 +1,224 @@
+use super::board::Board;
+use super::boardio::{BoardIo, ShutdownPolicy, State};
+use crate::{FirmataError, Pin, PinId, PinMode, Result};
+use std::future::Future;
+use std::marker::{Send, Unpin};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::runtime::{Builder, Runtime, RuntimeFlavor};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// A blocking wrapper around the async [`Board`].
+///
+/// The wrapper owns a small tokio runtime which drives the [`BoardIo`] poll loop in
+/// the background, so non-async applications can use the async stack as plain
+/// blocking calls.
+#[derive(Debug)]
+pub struct BlockingBoard {
+    runtime: Runtime,
+    board: Board,
+    io_task: JoinHandle<Result<()>>,
+    shutdown: oneshot::Sender<ShutdownPolicy>,
+}
+
+impl BlockingBoard {
+    /// Creates a [`BlockingBoard`] with its own single worker runtime, populates the
+    /// board state and starts polling in the background.
+    /// # Errors
+    /// Returns [`crate::FirmataError::IoError`] if the runtime could not be built or any
+    /// error raised while generating the board state.
+    pub fn create<T, U>(conn_read: T, conn_write: U) -> Result<Self>
+    where
+        T: AsyncReadExt + Unpin + Send + 'static,
+        U: AsyncWriteExt + Unpin + Send + 'static,
+    {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()?;
+        Self::with_runtime(runtime, conn_read, conn_write)
+    }
+
+    /// Creates a [`BlockingBoard`] on a user supplied runtime. This is useful when the
+    /// transport itself has to be created inside of a runtime, e.g. a `TcpStream`. The
+    /// runtime has to be multi threaded, a current thread runtime would only drive the
+    /// poll loop while a call blocks on it.
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the runtime is not multi threaded or any
+    /// error raised while generating the board state.
+    pub fn with_runtime<T, U>(runtime: Runtime, conn_read: T, conn_write: U) -> Result<Self>
+    where
+        T: AsyncReadExt + Unpin + Send + 'static,
+        U: AsyncWriteExt + Unpin + Send + 'static,
+    {
+        if runtime.handle().runtime_flavor() != RuntimeFlavor::MultiThread {
+            return Err(FirmataError::StateError(
+                "a blocking board needs a multi threaded runtime",
+            ));
+        }
+        let mut board_io = BoardIo::create(conn_read, conn_write);
+        runtime.block_on(board_io.generate_board_state())?;
+        let board = board_io.get_board();
+        let (shutdown, requested) = oneshot::channel();
+        let io_task = runtime.spawn(async move {
+            board_io
+                .poll_until(async { requested.await.unwrap_or_default() })
+                .await
+        });
+        Ok(Self {
+            runtime,
+            board,
+            io_task,
+            shutdown,
+        })
+    }
+
+    /// Stops the background poll loop and closes the transport, see [`BoardIo::shutdown`].
+    /// # Errors
+    /// Returns the error that stopped the poll loop or an error raised while shutting down.
+    pub fn shutdown(self, policy: ShutdownPolicy) -> Result<()> {
+        let _ = self.shutdown.send(policy);
+        match self.runtime.block_on(self.io_task) {
+            Ok(result) => result,
+            Err(e) => Err(std::io::Error::other(e).into()),
+        }
+    }
+
+    /// Runs a future to completion on the owned runtime.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// Returns a clone of the underlying async [`Board`] handle.
+    pub fn board(&self) -> Board {
+        self.board.clone()
+    }
+
+    /// Returns true if the background poll loop is no longer running.
+    pub fn is_finished(&self) -> bool {
+        self.io_task.is_finished()
+    }
+
+    pub fn pins(&self) -> Vec<Pin> {
+        self.board.pins()
+    }
+
+    /// See [`Board::pin`].
+    /// # Errors
+    /// Returns [`crate::FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn pin(&self, pin: PinId) -> Result<Pin> {
+        self.board.pin(pin)
+    }
+
+    /// See [`Board::try_value`].
+    /// # Errors
+    /// Returns [`crate::FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn try_value(&self, pin: PinId) -> Result<u16> {
+        self.board.try_value(pin)
+    }
+
+    pub fn get_pin_value(&self, pin: PinId) -> Result<u16> {
+        self.board.get_pin_value(pin)
+    }
+
+    pub fn protocol_version(&self) -> String {
+        self.board.protocol_version()
+    }
+
+    pub fn firmware_name(&self) -> String {
+        self.board.firmware_name()
+    }
+
+    pub fn firmware_version(&self) -> String {
+        self.board.firmware_version()
+    }
+
+    /// See [`Board::wait_for_timeout`].
+    /// # Errors
+    /// Returns [`crate::FirmataError::Timeout`] if the predicate was not satisfied in time.
+    pub fn wait_for<F: FnMut(&State) -> bool>(
+        &self,
+        predicate: F,
+        timeout: std::time::Duration,
+    ) -> Result<State> {
+        self.block_on(self.board.wait_for_timeout(predicate, timeout))
+    }
+
+    pub fn query_analog_mapping(&mut self) -> Result<()> {
+        self.runtime.block_on(self.board.query_analog_mapping())
+    }
+
+    pub fn query_capabilities(&mut self) -> Result<()> {
+        self.runtime.block_on(self.board.query_capabilities())
+    }
+
+    pub fn query_firmware(&mut self) -> Result<()> {
+        self.runtime.block_on(self.board.query_firmware())
+    }
+
+    pub fn report_digital(&mut self, pin: PinId, state: bool) -> Result<()> {
+        self.runtime.block_on(self.board.report_digital(pin, state))
+    }
+
+    pub fn report_digital_port(&mut self, port: u8, state: bool) -> Result<()> {
+        self.runtime
+            .block_on(self.board.report_digital_port(port, state))
+    }
+
+    pub fn report_digital_pin(&mut self, pin: PinId, state: bool) -> Result<()> {
+        self.runtime
+            .block_on(self.board.report_digital_pin(pin, state))
+    }
+
+    pub fn report_analog(&mut self, pin: PinId, state: bool) -> Result<()> {
+        self.runtime.block_on(self.board.report_analog(pin, state))
+    }
+
+    /// See [`Board::claim_output`].
+    /// # Errors
+    /// Returns [`crate::FirmataError::PinBusy`] if another handle owns the pin.
+    pub fn claim_output(&self, pin: PinId) -> Result<()> {
+        self.board.claim_output(pin)
+    }
+
+    pub fn release(&self, pin: PinId) -> bool {
+        self.board.release(pin)
+    }
+
+    /// See [`Board::reserve`].
+    /// # Errors
+    /// Returns [`crate::FirmataError::PinReserved`] if another component reserved the pin.
+    pub fn reserve(&self, pin: PinId, owner: &str, purpose: &str) -> Result<()> {
+        self.board.reserve(pin, owner, purpose)
+    }
+
+    pub fn analog_write(&mut self, pin: PinId, output: u16) -> Result<()> {
+        self.runtime.block_on(self.board.analog_write(pin, output))
+    }
+
+    pub fn analog_write_group(&mut self, writes: &[(PinId, u16)]) -> Result<()> {
+        self.runtime.block_on(self.board.analog_write_group(writes))
+    }
+
+    pub fn digital_write(&mut self, pin: PinId, output: bool) -> Result<()> {
+        self.runtime.block_on(self.board.digital_write(pin, output))
+    }
+
+    pub fn digital_port_write(&mut self, port: u8, mask: u8, value: u8) -> Result<()> {
+        self.runtime
+            .block_on(self.board.digital_port_write(port, mask, value))
+    }
+
+    pub fn string_write(&mut self, string: &str) -> Result<()> {
+        self.runtime.block_on(self.board.string_write(string))
+    }
+
+    pub fn set_pin_mode(&mut self, pin: PinId, mode: PinMode) -> Result<()> {
+        self.runtime.block_on(self.board.set_pin_mode(pin, mode))
+    }
+
+    pub fn sampling_interval(&mut self, duration: std::time::Duration) -> Result<()> {
+        self.runtime
+            .block_on(self.board.sampling_interval(duration))
+    }
+}
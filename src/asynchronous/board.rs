@@ -1,18 +1,416 @@
 use super::boardio::MessageOut::*;
 use super::boardio::{MessageOut, State};
-use crate::{FirmataError, Pin, PinId, PinMode, Result};
+use super::decimate::{self, Decimator};
+use super::events::BoardEvent;
+use super::i2c::I2c;
+use super::observer::BoardObserver;
+use super::pins::{self, AnalogPin, InputPin, OutputPin, PwmPin, ServoPin};
+use super::pwm::PwmOutput;
+use super::scene::{Scene, SceneBook};
+use super::scheduler::{Action, Schedule, ScheduledTask};
+use super::text::TextChannel;
+use super::uart::SerialStream;
+use super::watchdog::WatchdogStatus;
+use crate::analog;
+use crate::board_info::BoardInfo;
+use crate::calibration::{Calibration, CalibrationTable};
+use crate::capability_report::CapabilityReport;
+use crate::capture::{Capture, CaptureRequest};
+use crate::comparator::Comparator;
+use crate::compat::{self, Feature};
+use crate::digital;
+use crate::event_log::{EventLog, LogEntry};
+use crate::features::FeatureSet;
+use crate::i2c::{self, I2cConfig, I2cReadOptions, I2cRetryPolicy};
+use crate::journal::Journal;
+use crate::message::{OneWireReply, SchedulerReply};
+use crate::onewire::{self, OneWireAddress, OneWireRequest};
+use crate::pin_config::{PinConfigReport, PinConfigResult, PinConfigStatus};
+use crate::profile::BoardProfile;
+use crate::pulse::{self, PulseRequest};
+use crate::pwm;
+use crate::reservation::{Reservation, ReservationRegistry};
+use crate::sampling::{self, SampleDivider, SamplingConfig};
+use crate::scheduler::{self, SchedulerRequest, TaskState};
+use crate::servo;
+use crate::session::Session;
+use crate::stepper::{self, StepperConfig, MAX_STEPPERS, MAX_STEPPER_GROUPS};
+use crate::transform::{SensorTransform, TransformTable};
+use crate::uart::{self, SerialReadMode, SerialRequest};
+use crate::{FirmataError, I2CReply, Pin, PinId, PinMode, PinStates, Result, Stats};
+use futures::{Stream, StreamExt};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
 use tokio::sync::mpsc;
 use tokio::sync::watch;
 
-#[derive(Debug, Clone)]
+/// Client side settings shared by every [`Board`] handle of a [`super::boardio::BoardIo`].
+#[derive(Debug, Default)]
+pub(crate) struct Shared {
+    pub calibration: RwLock<CalibrationTable>,
+    pub debounce: RwLock<BTreeMap<u8, Duration>>,
+    pub comparators: RwLock<BTreeMap<u8, Comparator>>,
+    /// Armed captures, removed by the board io once recorded.
+    pub captures: RwLock<BTreeMap<u8, CaptureRequest>>,
+    pub sampling: RwLock<SampleDivider>,
+    pub journal: RwLock<Journal>,
+    pub labels: RwLock<BTreeMap<u8, String>>,
+    pub auto_report: AtomicBool,
+    pub transforms: RwLock<TransformTable>,
+    pub scenes: RwLock<SceneBook>,
+    /// Claimed pins and the handle that owns them.
+    pub claims: RwLock<BTreeMap<u8, Claim>>,
+    pub next_handle: AtomicU64,
+    pub reservations: RwLock<ReservationRegistry>,
+    pub write_timeout: RwLock<Option<Duration>>,
+    pub event_log: RwLock<Option<EventLog>>,
+    /// Value channels of the per pin subscriptions, fed by the board io.
+    pub pin_watchers: RwLock<BTreeMap<u8, watch::Sender<u16>>>,
+    /// The correlation id of the next OneWire read, unique across handles.
+    pub next_onewire_id: AtomicU16,
+}
+
+/// The handle that claimed a pin and the component it reserved the pin for, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Claim {
+    pub handle: u64,
+    pub owner: Option<String>,
+}
+
+impl Claim {
+    /// The error for another handle using the pin, naming the owner if there is one.
+    fn busy(&self, pin: u8) -> FirmataError {
+        let holder = self.owner.as_ref().map_or_else(
+            || "another board handle".to_string(),
+            |owner| format!("'{owner}'"),
+        );
+        FirmataError::PinBusy(pin, holder)
+    }
+}
+
+impl Shared {
+    /// A receiver of a pin's value changes, subscriptions of the same pin share a channel.
+    pub(crate) fn watch_pin(&self, pin: u8, current: u16) -> watch::Receiver<u16> {
+        match self.pin_watchers.write() {
+            Ok(mut watchers) => watchers
+                .entry(pin)
+                .or_insert_with(|| watch::channel(current).0)
+                .subscribe(),
+            Err(_) => watch::channel(current).1,
+        }
+    }
+}
+
+/// The values a pin changes to, the stream ends once the board io is dropped.
+pub(crate) fn pin_changes(values: watch::Receiver<u16>) -> impl Stream<Item = u16> {
+    futures::stream::unfold(values, |mut values| async move {
+        values.changed().await.ok()?;
+        let value = *values.borrow_and_update();
+        Some((value, values))
+    })
+}
+
+/// A handle to a board driven by a [`super::boardio::BoardIo`]. Handles are cheap to
+/// clone and are `Send + Sync`, so every task can own one without going back to
+/// [`super::boardio::BoardIo::get_board`].
+#[derive(Debug)]
 pub struct Board {
     state: watch::Receiver<State>,
     tx: mpsc::Sender<MessageOut>,
+    shared: Arc<Shared>,
+    events: broadcast::Receiver<BoardEvent>,
+    id: u64,
+    decimator: Option<Decimator>,
+}
+
+impl Clone for Board {
+    /// The clone only receives events published after it was created and does not
+    /// own the pins claimed by the original.
+    fn clone(&self) -> Self {
+        let mut board = Self::create_shared(
+            self.state.clone(),
+            self.tx.clone(),
+            self.shared.clone(),
+            self.events.resubscribe(),
+        );
+        board.set_report_interval(self.report_interval());
+        board
+    }
 }
 
+impl Drop for Board {
+    /// Releases the pins claimed by this handle.
+    fn drop(&mut self) {
+        if let Ok(mut claims) = self.shared.claims.write() {
+            claims.retain(|_, claim| claim.handle != self.id);
+        }
+    }
+}
+
+// Handles are moved into spawned tasks, losing either bound would break that.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync + Clone>() {}
+    assert_send_sync::<Board>();
+};
+
 impl Board {
+    /// Creates a handle without events, [`Board::next_event`] fails straight away.
     pub fn create(state: watch::Receiver<State>, tx: mpsc::Sender<MessageOut>) -> Self {
-        Self { state, tx }
+        let (_, events) = broadcast::channel(1);
+        Self::create_shared(state, tx, Arc::default(), events)
+    }
+
+    pub(crate) fn create_shared(
+        state: watch::Receiver<State>,
+        tx: mpsc::Sender<MessageOut>,
+        shared: Arc<Shared>,
+        events: broadcast::Receiver<BoardEvent>,
+    ) -> Self {
+        let id = shared.next_handle.fetch_add(1, Ordering::Relaxed);
+        Self {
+            state,
+            tx,
+            shared,
+            events,
+            id,
+            decimator: None,
+        }
+    }
+
+    /// Claims a pin for this handle, writes and mode changes from every other handle
+    /// fail with [`FirmataError::PinBusy`] until the pin is released or this handle is
+    /// dropped. Claiming a pin this handle already owns succeeds.
+    /// # Errors
+    /// Returns [`FirmataError::PinBusy`] if another handle owns the pin.
+    pub fn claim_output(&self, pin: PinId) -> Result<()> {
+        let pin_out = self.convert_pin_id_to_u8(pin);
+        let mut claims = self
+            .shared
+            .claims
+            .write()
+            .map_err(|_| FirmataError::StateError("pin claims lock poisoned"))?;
+        match claims.get(&pin_out) {
+            Some(claim) if claim.handle != self.id => Err(claim.busy(pin_out)),
+            Some(_) => Ok(()),
+            None => {
+                claims.insert(
+                    pin_out,
+                    Claim {
+                        handle: self.id,
+                        owner: None,
+                    },
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Releases a pin claimed by this handle, returns whether it was owned by it.
+    pub fn release(&self, pin: PinId) -> bool {
+        let pin_out = self.convert_pin_id_to_u8(pin);
+        self.shared.claims.write().is_ok_and(|mut claims| {
+            if claims
+                .get(&pin_out)
+                .is_some_and(|claim| claim.handle == self.id)
+            {
+                claims.remove(&pin_out);
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    /// Whether a pin is claimed by any handle.
+    pub fn is_claimed(&self, pin: PinId) -> bool {
+        let pin_out = self.convert_pin_id_to_u8(pin);
+        self.shared
+            .claims
+            .read()
+            .is_ok_and(|claims| claims.contains_key(&pin_out))
+    }
+
+    /// Records that a component uses a pin, shared by every handle of the board, and
+    /// claims the pin for this handle like [`Board::claim_output`]. Writes from other
+    /// handles then fail with [`FirmataError::PinBusy`] naming the owner.
+    /// # Errors
+    /// Returns [`FirmataError::PinReserved`] naming the owner if another component
+    /// reserved the pin or [`FirmataError::PinBusy`] if another handle claimed it.
+    pub fn reserve(&self, pin: PinId, owner: &str, purpose: &str) -> Result<()> {
+        let pin = self.convert_pin_id_to_u8(pin);
+        let mut claims = self
+            .shared
+            .claims
+            .write()
+            .map_err(|_| FirmataError::StateError("pin claims lock poisoned"))?;
+        if let Some(claim) = claims.get(&pin) {
+            if claim.handle != self.id && claim.owner.as_deref() != Some(owner) {
+                return Err(claim.busy(pin));
+            }
+        }
+        self.shared
+            .reservations
+            .write()
+            .map_err(|_| FirmataError::StateError("pin reservations lock poisoned"))?
+            .reserve(pin, owner, purpose)?;
+        claims.insert(
+            pin,
+            Claim {
+                handle: self.id,
+                owner: Some(owner.to_string()),
+            },
+        );
+        Ok(())
+    }
+
+    /// Releases a reservation of the owner and the claim that came with it.
+    pub fn release_reservation(&self, pin: PinId, owner: &str) -> Option<Reservation> {
+        let pin = self.convert_pin_id_to_u8(pin);
+        if let Ok(mut claims) = self.shared.claims.write() {
+            if claims
+                .get(&pin)
+                .is_some_and(|claim| claim.owner.as_deref() == Some(owner))
+            {
+                claims.remove(&pin);
+            }
+        }
+        self.shared
+            .reservations
+            .write()
+            .ok()
+            .and_then(|mut registry| registry.release(pin, owner))
+    }
+
+    pub fn reservation(&self, pin: PinId) -> Option<Reservation> {
+        let pin = self.convert_pin_id_to_u8(pin);
+        self.shared
+            .reservations
+            .read()
+            .ok()
+            .and_then(|registry| registry.get(pin).cloned())
+    }
+
+    /// Every reservation made through any handle of the board.
+    pub fn reservations(&self) -> ReservationRegistry {
+        self.shared
+            .reservations
+            .read()
+            .map(|registry| registry.clone())
+            .unwrap_or_default()
+    }
+
+    /// Queues a message for the board io, waiting at most the write timeout for room.
+    async fn send(&self, message: MessageOut) -> Result<()> {
+        let timeout = self.shared.write_timeout.read().ok().and_then(|v| *v);
+        let Some(timeout) = timeout else {
+            self.tx.send(message).await?;
+            return Ok(());
+        };
+        // Reserving keeps the message, so it is only described if the wait timed out.
+        match tokio::time::timeout(timeout, self.tx.reserve()).await {
+            Ok(Ok(permit)) => {
+                permit.send(message);
+                Ok(())
+            }
+            Ok(Err(_)) => Err(mpsc::error::SendError(message).into()),
+            Err(_) => Err(FirmataError::WriteTimeout(timeout, format!("{message:?}"))),
+        }
+    }
+
+    fn check_claim(&self, pin: u8) -> Result<()> {
+        match self.shared.claims.read() {
+            Ok(claims) => match claims.get(&pin) {
+                Some(claim) if claim.handle != self.id => Err(claim.busy(pin)),
+                _ => Ok(()),
+            },
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// A read-only handle that sees the same state and events as this one.
+    pub fn observer(&self) -> BoardObserver {
+        BoardObserver::new(
+            self.state.clone(),
+            self.shared.clone(),
+            self.events.resubscribe(),
+        )
+    }
+
+    /// Runs an action on a schedule in a background task, the task stops when the
+    /// returned handle is cancelled or dropped.
+    pub fn schedule(&self, schedule: Schedule, action: Action) -> ScheduledTask {
+        ScheduledTask::spawn(self.clone(), schedule, action)
+    }
+
+    /// Stores a scene under a name for every handle of the board, replacing a scene of
+    /// the same name.
+    pub fn define_scene(&self, name: &str, scene: Scene) {
+        if let Ok(mut book) = self.shared.scenes.write() {
+            book.scenes.insert(name.to_string(), scene);
+        }
+    }
+
+    pub fn remove_scene(&self, name: &str) -> Option<Scene> {
+        self.shared
+            .scenes
+            .write()
+            .ok()
+            .and_then(|mut book| book.scenes.remove(name))
+    }
+
+    pub fn scenes(&self) -> SceneBook {
+        self.shared
+            .scenes
+            .read()
+            .map(|book| book.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replaces every scene, e.g. with scenes loaded by [`SceneBook::load`].
+    pub fn load_scenes(&self, scenes: SceneBook) {
+        if let Ok(mut book) = self.shared.scenes.write() {
+            *book = scenes;
+        }
+    }
+
+    /// Runs a stored scene to the end.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if no scene has the name or an error if a
+    /// write could not be sent.
+    pub async fn run_scene(&mut self, name: &str) -> Result<()> {
+        let scene = self
+            .shared
+            .scenes
+            .read()
+            .ok()
+            .and_then(|book| book.scenes.get(name).cloned())
+            .ok_or(FirmataError::NotFoundError("no scene with that name"))?;
+        scene.run(self).await
+    }
+
+    /// Waits for the next event published since the previous call. Only
+    /// [`EVENT_CAPACITY`](super::events::EVENT_CAPACITY) events are buffered, a handle
+    /// that falls further behind receives [`BoardEvent::Lagged`] with the number of
+    /// events it missed before the oldest event that is still buffered.
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] once the [`super::boardio::BoardIo`] is gone.
+    pub async fn next_event(&mut self) -> Result<BoardEvent> {
+        decimate::next_event(&mut self.events, &mut self.decimator).await
+    }
+
+    /// Limits [`BoardEvent::PinChanged`] events for this handle to one per pin and
+    /// interval, e.g. 100 ms for a display refreshing at 10 Hz. Changes in between are
+    /// merged, other handles and the firmware sampling interval are not affected.
+    /// `None` delivers every change.
+    pub fn set_report_interval(&mut self, interval: Option<Duration>) {
+        self.decimator = interval.map(Decimator::new);
+    }
+
+    pub fn report_interval(&self) -> Option<Duration> {
+        self.decimator.as_ref().map(Decimator::interval)
     }
 
     fn get_state(&self) -> State {
@@ -20,18 +418,32 @@ impl Board {
     }
 
     fn convert_pin_id_to_u8(&self, pin: PinId) -> u8 {
-        let analog_offset = self.get_state().pin_state.analog_pin_start;
-        match pin {
-            PinId::Analog(v) => v + analog_offset,
-            PinId::Digital(v) => v,
-            PinId::Pin(v) => v,
-        }
+        self.state.borrow().pin_state.pin_id_to_u8(pin)
+    }
+
+    /// The pin index a pin id resolves to, as used in [`BoardEvent::PinChanged`].
+    pub fn pin_index(&self, pin: PinId) -> u8 {
+        self.convert_pin_id_to_u8(pin)
     }
 
     pub fn pins(&self) -> Vec<Pin> {
         self.get_state().pin_state.pins
     }
 
+    /// Returns the pin an id resolves to through the analog mapping.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn pin(&self, pin: PinId) -> Result<Pin> {
+        self.state.borrow().pin_state.get(pin).cloned()
+    }
+
+    /// Returns the current value of a pin.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn try_value(&self, pin: PinId) -> Result<u16> {
+        self.state.borrow().pin_state.get(pin).map(|v| v.value)
+    }
+
     pub fn get_pin_value(&self, pin: PinId) -> Result<u16> {
         let pins = self.pins();
         let pin_id = self.convert_pin_id_to_u8(pin) as usize;
@@ -44,6 +456,235 @@ impl Board {
         }
     }
 
+    /// Debounces a digital input inside of the board io, the pin value only changes once
+    /// a new level has been held for the whole window. The undebounced value stays
+    /// available through [`Board::raw_pin_value`].
+    pub fn set_debounce(&self, pin: PinId, window: Duration) {
+        let pin = self.convert_pin_id_to_u8(pin);
+        if let Ok(mut debounce) = self.shared.debounce.write() {
+            debounce.insert(pin, window);
+        }
+    }
+
+    /// Records every message the board io receives into the log, replacing and
+    /// returning the previous log. `None` stops recording.
+    pub fn set_event_log(&self, log: Option<EventLog>) -> Option<EventLog> {
+        self.shared
+            .event_log
+            .write()
+            .ok()
+            .and_then(|mut current| std::mem::replace(&mut *current, log))
+    }
+
+    /// The entries of the event log within `window` before the newest one, empty
+    /// without a log. See [`EventLog::last`].
+    /// # Errors
+    /// Returns an error if the storage of the log could not be read.
+    pub fn event_log_entries(&self, window: Duration) -> Result<Vec<LogEntry>> {
+        match self.shared.event_log.read().as_deref() {
+            Ok(Some(log)) => log.last(window),
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Gives an analog pin a digital view with hysteresis inside of the board io. The
+    /// level is kept in [`State::comparator_levels`] and every crossing is published as
+    /// [`BoardEvent::ComparatorEdge`], the first value reported publishes the initial
+    /// level.
+    pub fn set_comparator(&self, pin: PinId, comparator: Comparator) {
+        let pin = self.convert_pin_id_to_u8(pin);
+        if let Ok(mut comparators) = self.shared.comparators.write() {
+            comparators.insert(pin, comparator);
+        }
+    }
+
+    /// Removes the comparator of a pin, its level is dropped with the next value.
+    pub fn clear_comparator(&self, pin: PinId) {
+        let pin = self.convert_pin_id_to_u8(pin);
+        if let Ok(mut comparators) = self.shared.comparators.write() {
+            comparators.remove(&pin);
+        }
+    }
+
+    /// The level of a pin's comparator, `None` without a comparator or before the
+    /// first value arrived.
+    pub fn comparator_level(&self, pin: PinId) -> Option<bool> {
+        let pin = self.convert_pin_id_to_u8(pin);
+        self.state.borrow().comparator_levels.get(&pin).copied()
+    }
+
+    /// The values an analog pin changes to, after oversampling and deadbands. Changes
+    /// are detected inside of the board io, so the stream is not woken by other pins.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn subscribe_analog(&self, pin: PinId) -> Result<impl Stream<Item = u16>> {
+        let current = self.pin(pin)?.value;
+        let pin = self.convert_pin_id_to_u8(pin);
+        Ok(pin_changes(self.shared.watch_pin(pin, current)))
+    }
+
+    /// The levels a digital pin changes to, after debouncing, see
+    /// [`Board::subscribe_analog`].
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn subscribe_digital(&self, pin: PinId) -> Result<impl Stream<Item = bool>> {
+        Ok(self.subscribe_analog(pin)?.map(|value| value != 0))
+    }
+
+    /// Arms a capture of a pin's raw values inside of the board io, replacing one that
+    /// is armed already. Once the trigger fired and every sample arrived the capture is
+    /// published as [`BoardEvent::Capture`] and disarmed. The pin has to be reported.
+    pub fn arm_capture(&self, pin: PinId, request: CaptureRequest) {
+        let pin = self.convert_pin_id_to_u8(pin);
+        if let Ok(mut captures) = self.shared.captures.write() {
+            captures.insert(pin, request);
+        }
+    }
+
+    /// Drops the capture armed on a pin, samples recorded so far are discarded.
+    pub fn disarm_capture(&self, pin: PinId) {
+        let pin = self.convert_pin_id_to_u8(pin);
+        if let Ok(mut captures) = self.shared.captures.write() {
+            captures.remove(&pin);
+        }
+    }
+
+    /// Arms a capture, see [`Board::arm_capture`], and waits for it.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the capture did not complete in time, it is
+    /// disarmed then, or [`FirmataError::StateError`] if the board io stopped first.
+    pub async fn capture(
+        &self,
+        pin: PinId,
+        request: CaptureRequest,
+        timeout: Duration,
+    ) -> Result<Capture> {
+        let pin_out = self.convert_pin_id_to_u8(pin);
+        let mut events = self.events.resubscribe();
+        self.arm_capture(pin, request);
+        let captured = tokio::time::timeout(timeout, async {
+            loop {
+                match events.recv().await {
+                    Ok(BoardEvent::Capture(capture)) if capture.pin == pin_out => {
+                        return Ok(capture)
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => {
+                        return Err(FirmataError::StateError("the board io task has stopped"))
+                    }
+                }
+            }
+        })
+        .await;
+        captured.unwrap_or_else(|_| {
+            self.disarm_capture(pin);
+            Err(FirmataError::Timeout(format!(
+                "capture of pin {pin_out} did not complete within {}",
+                timeout.as_millis()
+            )))
+        })
+    }
+
+    /// Makes [`Board::set_pin_mode`] enable reporting for input and analog pins, the
+    /// port of an input pin or the channel of an analog pin. Off by default.
+    pub fn set_auto_report(&self, enable: bool) {
+        self.shared.auto_report.store(enable, Ordering::Relaxed);
+    }
+
+    pub fn clear_debounce(&self, pin: PinId) {
+        let pin = self.convert_pin_id_to_u8(pin);
+        if let Ok(mut debounce) = self.shared.debounce.write() {
+            debounce.remove(&pin);
+        }
+    }
+
+    /// Returns the last reported value of a pin, ignoring debouncing.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the pin does not exist.
+    pub fn raw_pin_value(&self, pin: PinId) -> Result<u16> {
+        let pin_id = self.convert_pin_id_to_u8(pin);
+        match self.get_state().raw_values.get(&pin_id) {
+            Some(value) => Ok(*value),
+            None => self.get_pin_value(pin),
+        }
+    }
+
+    /// Sets the calibration used by [`Board::read_calibrated`] for a pin.
+    pub fn set_calibration(&self, pin: PinId, calibration: Calibration) {
+        let pin = self.convert_pin_id_to_u8(pin);
+        if let Ok(mut table) = self.shared.calibration.write() {
+            table.set(pin, calibration);
+        }
+    }
+
+    pub fn clear_calibration(&self, pin: PinId) {
+        let pin = self.convert_pin_id_to_u8(pin);
+        if let Ok(mut table) = self.shared.calibration.write() {
+            table.remove(pin);
+        }
+    }
+
+    pub fn calibration_table(&self) -> CalibrationTable {
+        self.shared
+            .calibration
+            .read()
+            .map(|table| table.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replaces every calibration, e.g. with a table loaded by [`CalibrationTable::load`].
+    pub fn load_calibration(&self, calibration: CalibrationTable) {
+        if let Ok(mut table) = self.shared.calibration.write() {
+            *table = calibration;
+        }
+    }
+
+    /// Returns the current value of a pin converted by its calibration.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the pin does not exist.
+    pub fn read_calibrated(&self, pin: PinId) -> Result<f64> {
+        let pins = self.pins();
+        let pin_id = self.convert_pin_id_to_u8(pin);
+        let physical = pins.get(pin_id as usize).ok_or(FirmataError::OutOfRange(
+            "tried to address pin a pin that exceeded the max pin index",
+        ))?;
+        Ok(self.calibration_table().convert(pin_id, physical))
+    }
+
+    /// Sets the sensor model used by [`Board::read_engineering`] for a pin.
+    pub fn set_transform<S: SensorTransform + 'static>(&self, pin: PinId, transform: S) {
+        let pin = self.convert_pin_id_to_u8(pin);
+        if let Ok(mut table) = self.shared.transforms.write() {
+            table.set(pin, transform);
+        }
+    }
+
+    pub fn clear_transform(&self, pin: PinId) {
+        let pin = self.convert_pin_id_to_u8(pin);
+        if let Ok(mut table) = self.shared.transforms.write() {
+            table.remove(pin);
+        }
+    }
+
+    /// Returns the current value of a pin in physical units, pins without a sensor
+    /// transform are converted by their calibration.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the pin does not exist.
+    pub fn read_engineering(&self, pin: PinId) -> Result<f64> {
+        let physical = self.pin(pin)?;
+        let pin_id = self.convert_pin_id_to_u8(pin);
+        let converted = self
+            .shared
+            .transforms
+            .read()
+            .ok()
+            .and_then(|table| table.convert(pin_id, &physical));
+        match converted {
+            Some(value) => Ok(value),
+            None => Ok(self.calibration_table().convert(pin_id, &physical)),
+        }
+    }
+
     pub fn protocol_version(&self) -> String {
         self.get_state().protocol_version
     }
@@ -56,78 +697,1489 @@ impl Board {
         self.get_state().firmware_version
     }
 
+    pub fn stats(&self) -> Stats {
+        self.get_state().stats
+    }
+
+    pub fn watchdog_status(&self) -> WatchdogStatus {
+        self.get_state().watchdog
+    }
+
+    /// Times a firmware query round trip, the result is also recorded in [`Board::stats`].
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if no reply arrived within the timeout.
+    pub async fn ping(&mut self, timeout: Duration) -> Result<Duration> {
+        let mut state = self.state.clone();
+        let round_trips = state.borrow_and_update().stats.round_trips;
+        self.send(ReportFirmware).await?;
+        let wait = async {
+            loop {
+                if state.changed().await.is_err() {
+                    return Err(FirmataError::StateError("board io has stopped"));
+                }
+                let stats = &state.borrow().stats;
+                if stats.round_trips > round_trips {
+                    if let Some(latency) = stats.last_latency {
+                        return Ok(latency);
+                    }
+                }
+            }
+        };
+        tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| FirmataError::Timeout(format!("{}", timeout.as_millis())))?
+    }
+
+    /// Resolves the first time the pin is observed at the given level. Digital reporting
+    /// has to be enabled for the pin's port for the value to change.
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the board io stopped before the level was seen.
+    pub async fn latch(&self, pin: PinId, level: bool) -> Result<()> {
+        let index = self.convert_pin_id_to_u8(pin) as usize;
+        self.wait_for(|state| {
+            state
+                .pin_state
+                .pins
+                .get(index)
+                .is_some_and(|v| (v.value != 0) == level)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// See [`Board::latch`].
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the board io stopped before the level was seen.
+    pub async fn latch_high(&self, pin: PinId) -> Result<()> {
+        self.latch(pin, true).await
+    }
+
+    /// See [`Board::latch`].
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the board io stopped before the level was seen.
+    pub async fn latch_low(&self, pin: PinId) -> Result<()> {
+        self.latch(pin, false).await
+    }
+
+    /// Waits until the board state satisfies the predicate and returns that state, the
+    /// current state is checked first.
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the board io stopped first.
+    pub async fn wait_for<F: FnMut(&State) -> bool>(&self, predicate: F) -> Result<State> {
+        let mut state = self.state.clone();
+        let matched = state
+            .wait_for(predicate)
+            .await
+            .map_err(|_| FirmataError::StateError("board io has stopped"))?;
+        Ok(matched.clone())
+    }
+
+    /// See [`Board::wait_for`].
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the predicate was not satisfied in time or
+    /// [`FirmataError::StateError`] if the board io stopped first.
+    pub async fn wait_for_timeout<F: FnMut(&State) -> bool>(
+        &self,
+        predicate: F,
+        timeout: Duration,
+    ) -> Result<State> {
+        tokio::time::timeout(timeout, self.wait_for(predicate))
+            .await
+            .map_err(|_| FirmataError::Timeout(format!("{}", timeout.as_millis())))?
+    }
+
+    /// Fails if the protocol version reported by the firmware lacks the feature.
+    fn require(&self, feature: Feature) -> Result<()> {
+        compat::require(&self.state.borrow().protocol_version, feature)
+    }
+
+    pub async fn query_protocol_version(&mut self) -> Result<()> {
+        self.send(ProtocolVersionQuery).await?;
+        Ok(())
+    }
+
     pub async fn query_analog_mapping(&mut self) -> Result<()> {
-        self.tx.send(AnalogMappingQuery).await?;
+        self.require(Feature::AnalogMappingQuery)?;
+        self.send(AnalogMappingQuery).await?;
         Ok(())
     }
 
     pub async fn query_capabilities(&mut self) -> Result<()> {
-        self.tx.send(CapabilityQuery).await?;
+        self.require(Feature::CapabilityQuery)?;
+        self.send(CapabilityQuery).await?;
         Ok(())
     }
 
     pub async fn query_firmware(&mut self) -> Result<()> {
-        self.tx.send(ReportFirmware).await?;
+        self.send(ReportFirmware).await?;
         Ok(())
     }
 
+    /// Takes the pins from a profile instead of querying the board, for firmwares that
+    /// do not answer the capability or analog mapping queries. Waits until the board io
+    /// applied them.
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the profile is inconsistent or the board
+    /// io stopped first.
+    pub async fn assume_profile(&mut self, profile: &BoardProfile) -> Result<()> {
+        let pins = profile.pin_states()?;
+        let mut events = self.events.resubscribe();
+        self.send(AssumePins(pins)).await?;
+        loop {
+            match events.recv().await {
+                Ok(BoardEvent::PinsUpdated) => return Ok(()),
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => {
+                    return Err(FirmataError::StateError("the board io task has stopped"))
+                }
+            }
+        }
+    }
+
+    /// Sends the protocol version, firmware, capability and analog mapping queries in one
+    /// flush and waits until every one of them was answered, e.g. to refresh the pins
+    /// after the firmware was reconfigured. Returns the state with all answers applied.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] naming the queries that were not answered in
+    /// time or [`FirmataError::StateError`] if the board io stopped first.
+    pub async fn query_all(&mut self, timeout: Duration) -> Result<State> {
+        self.require(Feature::CapabilityQuery)?;
+        self.require(Feature::AnalogMappingQuery)?;
+        let round_trips = self.get_state().stats.round_trips;
+        let mut events = self.events.resubscribe();
+        self.send(QueryAll).await?;
+        // The firmware answers in order, so the capability response updates the pins
+        // before the analog mapping does and the firmware reply comes last.
+        let mut pending = vec![
+            "protocol version",
+            "capability",
+            "analog mapping",
+            "firmware",
+        ];
+        let answered = tokio::time::timeout(timeout, async {
+            while !pending.is_empty() {
+                let answer = match events.recv().await {
+                    Ok(BoardEvent::ProtocolVersion(_)) => "protocol version",
+                    Ok(BoardEvent::Firmware { .. }) => "firmware",
+                    Ok(BoardEvent::PinsUpdated) if pending.contains(&"capability") => "capability",
+                    Ok(BoardEvent::PinsUpdated) => "analog mapping",
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => {
+                        return Err(FirmataError::StateError("the board io task has stopped"))
+                    }
+                };
+                pending.retain(|query| *query != answer);
+            }
+            Ok(())
+        })
+        .await;
+        match answered {
+            Ok(result) => result?,
+            Err(_) => {
+                return Err(FirmataError::Timeout(format!(
+                    "no reply to the {} queries within {}",
+                    pending.join(", "),
+                    timeout.as_millis()
+                )))
+            }
+        }
+        // The events are published before the state, the firmware reply is counted as a
+        // round trip once every earlier reply was applied.
+        self.wait_for(|state| state.stats.round_trips > round_trips)
+            .await
+    }
+
+    /// Sends a sysex command the crate does not model, e.g. a feature of a custom
+    /// firmware.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the command or a payload byte does not
+    /// fit in 7 bits or an error if the message could not be sent.
+    pub async fn send_sysex(&mut self, command: u8, payload: &[u8]) -> Result<()> {
+        if command > 0x7F || payload.iter().any(|v| *v > 0x7F) {
+            return Err(FirmataError::OutOfRange(
+                "sysex command and payload must be 7 bit bytes",
+            ));
+        }
+        self.send(Sysex(command, payload.to_vec())).await?;
+        Ok(())
+    }
+
+    /// Sends a sysex command and waits for the first reply the matcher accepts, given
+    /// the command and payload of every sysex the crate does not model. Returns the
+    /// payload of the reply. The board io has to decode unknown sysex commands, see
+    /// [`super::boardio::BoardIo::set_lenient`].
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if no reply matched in time,
+    /// [`FirmataError::OutOfRange`] if the request does not fit in 7 bit bytes or
+    /// [`FirmataError::StateError`] if the board io stopped first.
+    pub async fn sysex_request<F: FnMut(u8, &[u8]) -> bool>(
+        &mut self,
+        command: u8,
+        payload: &[u8],
+        mut matcher: F,
+        timeout: Duration,
+    ) -> Result<Vec<u8>> {
+        let mut events = self.events.resubscribe();
+        self.send_sysex(command, payload).await?;
+        tokio::time::timeout(timeout, async {
+            loop {
+                match events.recv().await {
+                    Ok(BoardEvent::UnknownSysex { command, payload })
+                        if matcher(command, &payload) =>
+                    {
+                        return Ok(payload)
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => {
+                        return Err(FirmataError::StateError("the board io task has stopped"))
+                    }
+                }
+            }
+        })
+        .await
+        .map_err(|_| {
+            FirmataError::Timeout(format!(
+                "no reply to sysex {command:#04x} within {}",
+                timeout.as_millis()
+            ))
+        })?
+    }
+
     //pub async fn i2c_data(&mut self) -> &mut Vec<I2CReply> {
     //    &mut self.i2c_data
     //}
 
-    //pub async fn i2c_config(&mut self, delay: u16) -> Result<()> {
-    //    self.tx.send(I2cConfig(delay)).await?;
-    //    Ok(())
-    //}
+    /// The firmware, protocol version and size of the board in one snapshot.
+    pub fn info(&self) -> BoardInfo {
+        let state = self.state.borrow();
+        BoardInfo::new(
+            &state.firmware_name,
+            &state.firmware_version,
+            &state.protocol_version,
+            &state.pin_state,
+        )
+    }
 
-    //pub async fn i2c_read(&mut self, address: u8, size: u16) -> Result<()> {
-    //    self.tx.send(I2cRead(address, size)).await?;
-    //    Ok(())
-    //}
+    /// A summary of every pin for display, see [`CapabilityReport`].
+    pub fn capability_report(&self) -> CapabilityReport {
+        let labels = self
+            .shared
+            .labels
+            .read()
+            .map(|labels| labels.clone())
+            .unwrap_or_default();
+        let state = self.state.borrow();
+        CapabilityReport::new(
+            &state.firmware_name,
+            &state.firmware_version,
+            &state.protocol_version,
+            &state.pin_state,
+            &labels,
+        )
+    }
 
-    //pub async fn i2c_write(&mut self, address: u8, data: &[u8]) -> Result<()> {
-    //    let data: Vec<u8> = data.to_vec();
-    //    self.tx.send(I2cWrite(address, data)).await?;
-    //    Ok(())
-    //}
+    /// The optional features detected from the capabilities and protocol version.
+    pub fn features(&self) -> FeatureSet {
+        let state = self.state.borrow();
+        FeatureSet::detect(&state.pin_state, &state.protocol_version)
+    }
+
+    /// Returns a handle for I2C, only available if a pin of the board supports it.
+    /// # Errors
+    /// Returns [`FirmataError::UnsupportedByFirmware`] if I2C was not detected.
+    pub fn i2c(&self) -> Result<I2c> {
+        self.features().require_i2c()?;
+        Ok(I2c::new(self.clone()))
+    }
+
+    /// Configures the I2C bus, the address size and restart become the defaults of
+    /// following reads. The configuration is kept in the state and journaled, so it is
+    /// sent again when the board is reconnected.
+    /// # Errors
+    /// Returns [`FirmataError::UnsupportedByFirmware`] without I2C support or an error if
+    /// the message could not be sent.
+    pub async fn i2c_config(&mut self, config: I2cConfig) -> Result<()> {
+        self.require(Feature::I2c)?;
+        self.send(MessageOut::I2cConfig(config)).await?;
+        Ok(())
+    }
+
+    /// The last configuration sent with [`Board::i2c_config`].
+    pub fn active_i2c_config(&self) -> Option<I2cConfig> {
+        self.state.borrow().i2c_config
+    }
+
+    pub async fn i2c_read(&mut self, address: u8, size: u16) -> Result<()> {
+        self.i2c_read_with(address, I2cReadOptions::new(size)).await
+    }
+
+    /// Requests a read with a register and the restart bit, the reply is published as
+    /// [`BoardEvent::I2cReply`].
+    /// # Errors
+    /// Returns an error if the firmware has no I2C support or the message could not be sent.
+    pub async fn i2c_read_with(&mut self, address: u8, options: I2cReadOptions) -> Result<()> {
+        self.require(Feature::I2c)?;
+        self.send(I2cRead(address, options)).await?;
+        Ok(())
+    }
+
+    /// Reads from a device and waits for the reply, the request is sent again each time
+    /// an attempt times out.
+    /// # Errors
+    /// Returns [`FirmataError::I2cTimeout`] if every attempt timed out.
+    pub async fn i2c_read_reply(
+        &mut self,
+        address: u8,
+        options: I2cReadOptions,
+        policy: I2cRetryPolicy,
+    ) -> Result<I2CReply> {
+        let mut events = self.events.resubscribe();
+        for _ in 0..=policy.retries {
+            self.i2c_read_with(address, options).await?;
+            let reply = tokio::time::timeout(policy.timeout, async {
+                loop {
+                    match events.recv().await {
+                        Ok(BoardEvent::I2cReply(reply))
+                            if i2c::reply_matches(&reply, address, &options) =>
+                        {
+                            return Ok(reply)
+                        }
+                        Ok(_) | Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => {
+                            return Err(FirmataError::StateError("the board io task has stopped"))
+                        }
+                    }
+                }
+            })
+            .await;
+            if let Ok(reply) = reply {
+                return reply;
+            }
+        }
+        Err(FirmataError::I2cTimeout(address, options.register))
+    }
+
+    /// Reads `size` bytes from a register of a device and waits up to the timeout for
+    /// the reply, without retries. See [`Board::i2c_read_reply`] for retries and the
+    /// other read options.
+    /// # Errors
+    /// Returns [`FirmataError::I2cTimeout`] if no reply arrived in time.
+    pub async fn i2c_read_await(
+        &mut self,
+        address: u8,
+        register: u16,
+        size: u16,
+        timeout: Duration,
+    ) -> Result<I2CReply> {
+        let options = I2cReadOptions::new(size).register(register);
+        let policy = I2cRetryPolicy {
+            timeout,
+            retries: 0,
+        };
+        self.i2c_read_reply(address, options, policy).await
+    }
+
+    /// Writes bytes to a device, e.g. a register followed by its new value.
+    /// # Errors
+    /// Returns an error if the firmware has no I2C support or the message could not be sent.
+    pub async fn i2c_write(&mut self, address: u8, data: &[u8]) -> Result<()> {
+        self.require(Feature::I2c)?;
+        self.send(I2cWrite(address, data.to_vec())).await?;
+        Ok(())
+    }
+
+    /// The configuration commands issued through every handle of the board, in order.
+    pub fn journal(&self) -> Journal {
+        self.shared
+            .journal
+            .read()
+            .map(|journal| journal.clone())
+            .unwrap_or_default()
+    }
+
+    /// Re-issues every configuration command in the journal, e.g. to set up a board
+    /// the same way as another one.
+    /// # Errors
+    /// Returns an error if the messages could not be sent.
+    pub async fn replay(&mut self, journal: &Journal) -> Result<()> {
+        for entry in journal.entries() {
+            self.send(entry.clone().into()).await?;
+        }
+        Ok(())
+    }
+
+    /// Names a pin, labels are kept with the session.
+    pub fn set_label(&self, pin: PinId, label: &str) {
+        let pin = self.convert_pin_id_to_u8(pin);
+        if let Ok(mut labels) = self.shared.labels.write() {
+            labels.insert(pin, label.to_string());
+        }
+    }
+
+    pub fn label(&self, pin: PinId) -> Option<String> {
+        let pin = self.convert_pin_id_to_u8(pin);
+        self.shared
+            .labels
+            .read()
+            .ok()
+            .and_then(|labels| labels.get(&pin).cloned())
+    }
+
+    /// The configuration, labels and calibration of the board.
+    pub fn session(&self) -> Session {
+        Session {
+            journal: self.journal(),
+            labels: self
+                .shared
+                .labels
+                .read()
+                .map(|labels| labels.clone())
+                .unwrap_or_default(),
+            calibration: self.calibration_table(),
+        }
+    }
+
+    /// Takes over the labels and calibration of a session and replays its journal.
+    /// # Errors
+    /// Returns an error if the configuration could not be sent.
+    pub async fn restore_session(&mut self, session: &Session) -> Result<()> {
+        if let Ok(mut labels) = self.shared.labels.write() {
+            labels.clone_from(&session.labels);
+        }
+        self.load_calibration(session.calibration.clone());
+        self.replay(&session.journal).await
+    }
 
+    /// Writes the session to a file, see [`Board::session`].
+    /// # Errors
+    /// Returns an error if the file could not be written.
+    pub fn export_session<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        self.session().save(path)
+    }
+
+    /// Reads a session from a file and restores it, see [`Board::restore_session`].
+    /// # Errors
+    /// Returns an error if the file could not be read or the configuration not be sent.
+    pub async fn import_session<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<()> {
+        let session = Session::load(path)?;
+        self.restore_session(&session).await
+    }
+
+    /// Resets the firmware to its power on configuration.
+    pub async fn system_reset(&mut self) -> Result<()> {
+        self.send(SystemReset).await?;
+        Ok(())
+    }
+
+    /// Enables or disables reporting of a digital port, the protocol reports the eight
+    /// pins of a port together. The pin number is taken as the port number, prefer
+    /// [`Board::report_digital_port`] or [`Board::report_digital_pin`].
     pub async fn report_digital(&mut self, pin: PinId, state: bool) -> Result<()> {
         let pin_out = self.convert_pin_id_to_u8(pin);
-        self.tx.send(ReportDigital(pin_out, state)).await?;
+        self.report_digital_port(pin_out, state).await
+    }
+
+    /// Enables or disables reporting of the eight pins of a digital port.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the port is above 15.
+    pub async fn report_digital_port(&mut self, port: u8, state: bool) -> Result<()> {
+        if port > 0x0F {
+            return Err(FirmataError::OutOfRange("digital port exceeds 15"));
+        }
+        self.send(ReportDigital(port, state)).await?;
         Ok(())
     }
 
+    /// Enables or disables reporting of the port a pin belongs to, the other pins of
+    /// the port are reported as well.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub async fn report_digital_pin(&mut self, pin: PinId, state: bool) -> Result<()> {
+        self.pin(pin)?;
+        let port = self.convert_pin_id_to_u8(pin) / 8;
+        self.report_digital_port(port, state).await
+    }
+
+    /// Enables or disables reporting for the analog channel of a pin.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin or
+    /// [`FirmataError::OutOfRange`] if the pin has no analog channel.
     pub async fn report_analog(&mut self, pin: PinId, state: bool) -> Result<()> {
-        let pin_out = self.convert_pin_id_to_u8(pin);
-        self.tx.send(ReportAnalog(pin_out, state)).await?;
+        let channel = self.state.borrow().pin_state.analog_channel(pin)?;
+        self.send(ReportAnalog(channel, state)).await?;
+        Ok(())
+    }
+
+    /// Enables or disables reporting for every digital port the board has.
+    /// # Errors
+    /// Returns [`FirmataError::UninitializedError`] if the pins have not been queried.
+    pub async fn report_all_digital(&mut self, state: bool) -> Result<()> {
+        let ports = self.get_state().pin_state.ports();
+        if ports == 0 {
+            return Err(FirmataError::UninitializedError(
+                "pins have not been queried, the ports are unknown",
+            ));
+        }
+        for port in 0..ports {
+            self.send(ReportDigital(port, state)).await?;
+        }
+        Ok(())
+    }
+
+    /// Enables or disables reporting for every analog channel the board has.
+    /// # Errors
+    /// Returns [`FirmataError::UninitializedError`] if the analog mapping is unknown.
+    pub async fn report_all_analog(&mut self, state: bool) -> Result<()> {
+        let pins: Vec<u8> = self
+            .get_state()
+            .pin_state
+            .analog_channels
+            .into_keys()
+            .collect();
+        if pins.is_empty() {
+            return Err(FirmataError::UninitializedError(
+                "analog mapping has not been queried, the channels are unknown",
+            ));
+        }
+        for pin in pins {
+            self.report_analog(PinId::Pin(pin), state).await?;
+        }
+        Ok(())
+    }
+
+    /// Stops every digital and analog report, e.g. before shutting down so the board
+    /// does not keep streaming to a closed connection.
+    /// # Errors
+    /// Returns an error if the messages could not be sent.
+    pub async fn quiesce(&mut self) -> Result<()> {
+        let pin_state = self.get_state().pin_state;
+        if pin_state.ports() > 0 {
+            self.report_all_digital(false).await?;
+        }
+        if !pin_state.analog_channels.is_empty() {
+            self.report_all_analog(false).await?;
+        }
         Ok(())
     }
 
     pub async fn analog_write(&mut self, pin: PinId, output: u16) -> Result<()> {
         let pin_out = self.convert_pin_id_to_u8(pin);
-        self.tx.send(AnalogWrite(pin_out, output)).await?;
+        self.check_claim(pin_out)?;
+        if let Some(physical) = self.pins().get(pin_out as usize) {
+            physical.validate_write(output)?;
+        }
+        if analog::needs_extended(pin_out, output) {
+            self.require(Feature::ExtendedAnalog)?;
+        }
+        self.send(AnalogWrite(pin_out, output)).await?;
+        Ok(())
+    }
+
+    /// Writes several analog or PWM outputs in one burst, e.g. the channels of an RGB
+    /// LED, so they change together. Nothing is written if any value is invalid.
+    /// # Errors
+    /// Returns an error if a value does not fit its pin or the message could not be sent.
+    pub async fn analog_write_group(&mut self, writes: &[(PinId, u16)]) -> Result<()> {
+        let pins = self.pins();
+        let mut group = Vec::with_capacity(writes.len());
+        for (pin, output) in writes {
+            let pin_out = self.convert_pin_id_to_u8(*pin);
+            self.check_claim(pin_out)?;
+            if let Some(physical) = pins.get(pin_out as usize) {
+                physical.validate_write(*output)?;
+            }
+            if analog::needs_extended(pin_out, *output) {
+                self.require(Feature::ExtendedAnalog)?;
+            }
+            group.push((pin_out, *output));
+        }
+        self.send(AnalogWriteGroup(group)).await?;
         Ok(())
     }
 
+    /// Writes a duty cycle between 0.0 and 1.0, scaled to the pin's PWM resolution.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the fraction is outside of 0.0 to 1.0 or
+    /// [`FirmataError::WrongType`] if the pin does not support PWM.
+    pub async fn set_duty_fraction(&mut self, pin: PinId, fraction: f64) -> Result<()> {
+        let pin_out = self.convert_pin_id_to_u8(pin);
+        let value = self
+            .pins()
+            .get(pin_out as usize)
+            .ok_or(FirmataError::OutOfRange(
+                "tried to address pin a pin that exceeded the max pin index",
+            ))?
+            .duty_to_value(fraction)?;
+        self.analog_write(pin, value).await
+    }
+
+    /// A handle to a PWM capable pin, e.g. to ramp its duty cycle.
+    /// # Errors
+    /// Returns [`FirmataError::WrongType`] if the pin does not support PWM or
+    /// [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn pwm_output(&self, pin: PinId) -> Result<PwmOutput> {
+        self.pin(pin)?.max_pwm_value()?;
+        Ok(PwmOutput::new(self.clone(), pin))
+    }
+
+    /// Switches a pin to a digital output and hands it over as a typed handle.
+    /// # Errors
+    /// Returns [`FirmataError::WrongType`] if the pin does not support the mode,
+    /// [`FirmataError::NotFoundError`] if the board has no such pin or an error if the
+    /// message could not be sent.
+    pub async fn into_output(mut self, pin: PinId) -> Result<OutputPin> {
+        pins::configure(&mut self, pin, PinMode::Output).await?;
+        Ok(OutputPin::new(self, pin))
+    }
+
+    /// Like [`Board::into_output`] for a digital input.
+    /// # Errors
+    /// See [`Board::into_output`].
+    pub async fn into_input(mut self, pin: PinId) -> Result<InputPin> {
+        pins::configure(&mut self, pin, PinMode::Input).await?;
+        Ok(InputPin::new(self, pin))
+    }
+
+    /// Like [`Board::into_output`] for a digital input with the pullup enabled.
+    /// # Errors
+    /// See [`Board::into_output`], [`FirmataError::UnsupportedByFirmware`] if the
+    /// firmware is too old for the pullup mode.
+    pub async fn into_input_pullup(mut self, pin: PinId) -> Result<InputPin> {
+        pins::configure(&mut self, pin, PinMode::Pullup).await?;
+        Ok(InputPin::new(self, pin))
+    }
+
+    /// Like [`Board::into_output`] for an analog input.
+    /// # Errors
+    /// See [`Board::into_output`].
+    pub async fn into_analog(mut self, pin: PinId) -> Result<AnalogPin> {
+        pins::configure(&mut self, pin, PinMode::Analog).await?;
+        Ok(AnalogPin::new(self, pin))
+    }
+
+    /// Like [`Board::into_output`] for a PWM output.
+    /// # Errors
+    /// See [`Board::into_output`].
+    pub async fn into_pwm(mut self, pin: PinId) -> Result<PwmPin> {
+        pins::configure(&mut self, pin, PinMode::Pwm).await?;
+        PwmPin::new(self, pin)
+    }
+
+    /// Like [`Board::into_output`] for a servo.
+    /// # Errors
+    /// See [`Board::into_output`].
+    pub async fn into_servo(mut self, pin: PinId) -> Result<ServoPin> {
+        pins::configure(&mut self, pin, PinMode::Servo).await?;
+        Ok(ServoPin::new(self, pin))
+    }
+
+    /// Changes the PWM frequency of a pin on firmwares that support it.
+    /// # Errors
+    /// Returns [`FirmataError::WrongType`] if the pin does not support PWM or
+    /// [`FirmataError::OutOfRange`] if the frequency can not be sent.
+    pub async fn set_pwm_frequency(&mut self, pin: PinId, hz: u32) -> Result<()> {
+        let pin_out = self.convert_pin_id_to_u8(pin);
+        self.check_claim(pin_out)?;
+        let physical = self.pin(pin)?;
+        pwm::validate_frequency(&physical, hz)?;
+        self.send(PwmFrequency(pin_out, hz)).await?;
+        Ok(())
+    }
+
+    /// See [`crate::standard::board::Board::servo_config`].
+    /// # Errors
+    /// Returns [`FirmataError::WrongType`] if the pin does not support servos,
+    /// [`FirmataError::OutOfRange`] if the pulse range is invalid or an error if the
+    /// message could not be sent.
+    pub async fn servo_config(&mut self, pin: PinId, min_pulse: u16, max_pulse: u16) -> Result<()> {
+        let pin_out = self.convert_pin_id_to_u8(pin);
+        self.check_claim(pin_out)?;
+        servo::validate_config(&self.pin(pin)?, min_pulse, max_pulse)?;
+        self.send(ServoConfig(pin_out, min_pulse, max_pulse))
+            .await?;
+        Ok(())
+    }
+
+    /// Sets a single output pin, firmwares older than protocol 2.5 are sent the whole
+    /// port with the other pins kept at their level in [`State::output_latches`].
     pub async fn digital_write(&mut self, pin: PinId, output: bool) -> Result<()> {
         let pin_out = self.convert_pin_id_to_u8(pin);
-        self.tx.send(DigitalWrite(pin_out, output)).await?;
+        self.check_claim(pin_out)?;
+        if self.require(Feature::SetDigitalPinValue).is_err() {
+            let value = if output { 0xFF } else { 0 };
+            self.send(DigitalPortWrite(
+                digital::port_of(pin_out),
+                digital::mask_of(pin_out),
+                value,
+            ))
+            .await?;
+            return Ok(());
+        }
+        self.send(DigitalWrite(pin_out, output)).await?;
         Ok(())
     }
 
+    /// Sets the pins of a port selected by the mask to their bits in the value with one
+    /// message, the other pins keep their level in [`State::output_latches`] as of when
+    /// the board io sends it.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the port can not be addressed,
+    /// [`FirmataError::PinBusy`] if another handle claimed a pin of the mask or an
+    /// error if the message could not be sent.
+    pub async fn digital_port_write(&mut self, port: u8, mask: u8, value: u8) -> Result<()> {
+        digital::validate_port(port)?;
+        for bit in 0..8 {
+            if mask & 1 << bit != 0 {
+                self.check_claim(port * 8 + bit)?;
+            }
+        }
+        self.send(DigitalPortWrite(port, mask, value)).await?;
+        Ok(())
+    }
+
+    /// A handle that sends text and receives the text the firmware sends.
+    #[must_use]
+    pub fn text_channel(&self) -> TextChannel {
+        TextChannel::new(self.clone())
+    }
+
+    /// Sends text to the firmware, split into messages of at most
+    /// [`MAX_STRING_CHUNK`](crate::text::MAX_STRING_CHUNK) bytes.
+    /// # Errors
+    /// Returns an error if the connection is closed.
     pub async fn string_write(&mut self, string: &str) -> Result<()> {
-        self.tx.send(StringWrite(string.to_string())).await?;
+        self.send(StringWrite(string.to_string())).await?;
         Ok(())
     }
 
     pub async fn set_pin_mode(&mut self, pin: PinId, mode: PinMode) -> Result<()> {
+        if mode == PinMode::Pullup {
+            self.require(Feature::PullupMode)?;
+        }
         let pin_out = self.convert_pin_id_to_u8(pin);
-        self.tx.send(PinMode(pin_out, mode)).await?;
+        self.check_claim(pin_out)?;
+        self.send(PinMode(pin_out, mode)).await?;
+        self.auto_report(pin_out, mode).await
+    }
+
+    /// Enables the report a pin needs in its new mode if automatic reporting is on.
+    async fn auto_report(&self, pin_out: u8, mode: PinMode) -> Result<()> {
+        if self.shared.auto_report.load(Ordering::Relaxed) {
+            match mode {
+                PinMode::Input | PinMode::Pullup => {
+                    self.send(ReportDigital(pin_out / 8, true)).await?;
+                }
+                PinMode::Analog => {
+                    let channel = self
+                        .state
+                        .borrow()
+                        .pin_state
+                        .analog_channel(PinId::Pin(pin_out))?;
+                    self.send(ReportAnalog(channel, true)).await?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Asks the firmware for the mode and state of a pin. The answer arrives as
+    /// [`BoardEvent::PinState`] and updates the mode and, for outputs, the value in the
+    /// state, e.g. to reconcile the state with the firmware after a reconnect.
+    /// # Errors
+    /// Returns [`FirmataError::UnsupportedByFirmware`] if the firmware is too old for
+    /// pin state queries or an error if the message could not be sent.
+    pub async fn query_pin_state(&mut self, pin: PinId) -> Result<()> {
+        self.require(Feature::PinStateQuery)?;
+        let pin_out = self.convert_pin_id_to_u8(pin);
+        self.send(PinStateQuery(pin_out)).await?;
+        Ok(())
+    }
+
+    /// Sets the modes of several pins in one burst, e.g. the setup section of a
+    /// program. Every entry is checked against the capabilities of its pin first, only
+    /// the valid ones are sent. With a timeout every sent mode is verified with a pin
+    /// state query. The report holds the outcome of every entry.
+    /// # Errors
+    /// Returns [`FirmataError::UnsupportedByFirmware`] if verification was requested
+    /// from a firmware without pin state queries or an error if a message could not be
+    /// sent.
+    pub async fn configure(
+        &mut self,
+        pins: &[(PinId, PinMode)],
+        verify: Option<Duration>,
+    ) -> Result<PinConfigReport> {
+        if verify.is_some() {
+            self.require(Feature::PinStateQuery)?;
+        }
+        let pin_state = self.get_state().pin_state;
+        let mut report = PinConfigReport::default();
+        let mut group = vec![];
+        for (pin, mode) in pins {
+            let index = pin_state.pin_id_to_u8(*pin);
+            let status = match self.check_pin_mode(&pin_state, *pin, index, *mode) {
+                Ok(()) => {
+                    group.push((index, *mode));
+                    PinConfigStatus::Sent
+                }
+                Err(e) => PinConfigStatus::Rejected(e.to_string()),
+            };
+            report.results.push(PinConfigResult {
+                pin: *pin,
+                index,
+                mode: *mode,
+                status,
+            });
+        }
+        if group.is_empty() {
+            return Ok(report);
+        }
+        let mut events = self.events.resubscribe();
+        self.send(PinModeGroup(group.clone())).await?;
+        for (index, mode) in &group {
+            self.auto_report(*index, *mode).await?;
+        }
+        let Some(timeout) = verify else {
+            return Ok(report);
+        };
+        let mut pending: Vec<u8> = group.iter().map(|(index, _)| *index).collect();
+        pending.sort_unstable();
+        pending.dedup();
+        for index in &pending {
+            self.send(PinStateQuery(*index)).await?;
+        }
+        let mut reported = BTreeMap::new();
+        let _ = tokio::time::timeout(timeout, async {
+            while reported.len() < pending.len() {
+                match events.recv().await {
+                    Ok(BoardEvent::PinState(v)) if pending.contains(&v.pin) => {
+                        reported.insert(v.pin, v);
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+        .await;
+        for result in &mut report.results {
+            if result.status != PinConfigStatus::Sent {
+                continue;
+            }
+            result.status = match reported.get(&result.index) {
+                Some(v) if v.pin_mode() == Some(result.mode) => PinConfigStatus::Verified,
+                Some(v) => PinConfigStatus::Mismatch(v.mode),
+                None => PinConfigStatus::Unverified,
+            };
+        }
+        Ok(report)
+    }
+
+    /// Checks an entry of [`Board::configure`] against the capabilities of its pin.
+    fn check_pin_mode(
+        &self,
+        pin_state: &PinStates,
+        pin: PinId,
+        index: u8,
+        mode: PinMode,
+    ) -> Result<()> {
+        let physical = pin_state.get(pin)?;
+        if physical.modes.is_empty() {
+            return Err(FirmataError::UninitializedError(
+                "pin capabilities have not been queried",
+            ));
+        }
+        if physical.resolution(mode).is_none() {
+            return Err(FirmataError::WrongType("pin does not support the mode"));
+        }
+        if mode == PinMode::Pullup {
+            self.require(Feature::PullupMode)?;
+        }
+        self.check_claim(index)
+    }
+
+    /// Configures an AccelStepper device.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+    pub async fn stepper_config(&mut self, config: StepperConfig) -> Result<()> {
+        if config.device >= MAX_STEPPERS {
+            return Err(FirmataError::OutOfRange("stepper device number exceeds 9"));
+        }
+        self.send(MessageOut::StepperConfig(config)).await?;
+        Ok(())
+    }
+
+    /// See [`crate::standard::board::Board::stepper_zero`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+    pub async fn stepper_zero(&mut self, device: u8) -> Result<()> {
+        stepper::validate_device(device)?;
+        self.send(StepperZero(device)).await?;
+        Ok(())
+    }
+
+    /// See [`crate::standard::board::Board::stepper_step`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+    pub async fn stepper_step(&mut self, device: u8, steps: i32) -> Result<()> {
+        stepper::validate_device(device)?;
+        self.send(StepperStep(device, steps)).await?;
+        Ok(())
+    }
+
+    /// See [`crate::standard::board::Board::stepper_to`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+    pub async fn stepper_to(&mut self, device: u8, position: i32) -> Result<()> {
+        stepper::validate_device(device)?;
+        self.send(StepperTo(device, position)).await?;
+        Ok(())
+    }
+
+    /// See [`crate::standard::board::Board::stepper_enable`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+    pub async fn stepper_enable(&mut self, device: u8, enable: bool) -> Result<()> {
+        stepper::validate_device(device)?;
+        self.send(StepperEnable(device, enable)).await?;
+        Ok(())
+    }
+
+    /// See [`crate::standard::board::Board::stepper_stop`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+    pub async fn stepper_stop(&mut self, device: u8) -> Result<()> {
+        stepper::validate_device(device)?;
+        self.send(StepperStop(device)).await?;
         Ok(())
     }
 
+    /// Asks for the position of a device, the answer arrives as
+    /// [`BoardEvent::StepperPosition`] and updates [`Board::stepper_position`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+    pub async fn report_stepper_position(&mut self, device: u8) -> Result<()> {
+        stepper::validate_device(device)?;
+        self.send(StepperReportPosition(device)).await?;
+        Ok(())
+    }
+
+    /// The last position a device reported, `None` before its first report.
+    pub fn stepper_position(&self, device: u8) -> Option<i32> {
+        self.state
+            .borrow()
+            .steppers
+            .get(&device)
+            .map(|v| v.position)
+    }
+
+    /// See [`crate::standard::board::Board::set_stepper_speed`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number or speed is not supported.
+    pub async fn set_stepper_speed(&mut self, device: u8, speed: f32) -> Result<()> {
+        stepper::validate_device(device)?;
+        stepper::validate_float(speed)?;
+        self.send(StepperSpeed(device, speed)).await?;
+        Ok(())
+    }
+
+    /// See [`crate::standard::board::Board::set_stepper_acceleration`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number or acceleration is not
+    /// supported.
+    pub async fn set_stepper_acceleration(&mut self, device: u8, acceleration: f32) -> Result<()> {
+        stepper::validate_device(device)?;
+        stepper::validate_float(acceleration)?;
+        self.send(StepperAcceleration(device, acceleration)).await?;
+        Ok(())
+    }
+
+    /// Moves a device to an absolute position, resolving with the reported position
+    /// once it got there or was stopped.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the move did not complete within the timeout
+    /// or [`FirmataError::StateError`] if the board io stopped first.
+    pub async fn move_stepper(
+        &mut self,
+        device: u8,
+        position: i32,
+        timeout: Duration,
+    ) -> Result<i32> {
+        let moves = |state: &State| state.steppers.get(&device).map_or(0, |v| v.moves);
+        let before = moves(&self.get_state());
+        self.stepper_to(device, position).await?;
+        let state = self
+            .wait_for_timeout(|state| moves(state) > before, timeout)
+            .await?;
+        Ok(state.steppers.get(&device).map_or(position, |v| v.position))
+    }
+
+    /// Groups configured stepper devices so they can be moved together with [`Board::move_all`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the group or a device number is not supported.
+    pub async fn multi_stepper_config(&mut self, group: u8, devices: &[u8]) -> Result<()> {
+        if group >= MAX_STEPPER_GROUPS {
+            return Err(FirmataError::OutOfRange("stepper group number exceeds 4"));
+        }
+        if devices.iter().any(|device| *device >= MAX_STEPPERS) {
+            return Err(FirmataError::OutOfRange("stepper device number exceeds 9"));
+        }
+        self.tx
+            .send(MultiStepperConfig(group, devices.to_vec()))
+            .await?;
+        Ok(())
+    }
+
+    /// Starts moving every member of a group to its absolute position without waiting.
+    pub async fn multi_stepper_to(&mut self, group: u8, positions: &[i32]) -> Result<()> {
+        self.tx
+            .send(MultiStepperTo(group, positions.to_vec()))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn multi_stepper_stop(&mut self, group: u8) -> Result<()> {
+        self.send(MultiStepperStop(group)).await?;
+        Ok(())
+    }
+
+    /// Moves every member of a group to its absolute position, resolving once all of
+    /// them finished together.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the move did not complete within the timeout
+    /// or [`FirmataError::StateError`] if the board io stopped first.
+    pub async fn move_all(
+        &mut self,
+        group: u8,
+        positions: &[i32],
+        timeout: Duration,
+    ) -> Result<()> {
+        let completed = |state: &State| state.stepper_group_moves.get(&group).copied();
+        let before = completed(&self.get_state()).unwrap_or(0);
+        self.multi_stepper_to(group, positions).await?;
+        self.wait_for_timeout(|state| completed(state).unwrap_or(0) > before, timeout)
+            .await?;
+        Ok(())
+    }
+
+    /// See [`crate::standard::board::Board::onewire_config`].
+    /// # Errors
+    /// Returns [`FirmataError::WrongType`] if the pin does not support OneWire or an
+    /// error if the message could not be sent.
+    pub async fn onewire_config(&mut self, pin: PinId, parasitic_power: bool) -> Result<()> {
+        let pin_out = self.convert_pin_id_to_u8(pin);
+        self.check_claim(pin_out)?;
+        onewire::validate_pin(&self.pin(pin)?)?;
+        self.send(OneWireConfig(pin_out, parasitic_power)).await?;
+        Ok(())
+    }
+
+    /// Searches the bus of a pin and waits for the addresses of every device on it.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the search did not finish in time.
+    pub async fn onewire_search(
+        &mut self,
+        pin: PinId,
+        timeout: Duration,
+    ) -> Result<Vec<OneWireAddress>> {
+        self.search_onewire(pin, false, timeout).await
+    }
+
+    /// Like [`Board::onewire_search`], only devices with an alarm set answer.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the search did not finish in time.
+    pub async fn onewire_search_alarms(
+        &mut self,
+        pin: PinId,
+        timeout: Duration,
+    ) -> Result<Vec<OneWireAddress>> {
+        self.search_onewire(pin, true, timeout).await
+    }
+
+    async fn search_onewire(
+        &mut self,
+        pin: PinId,
+        alarms: bool,
+        timeout: Duration,
+    ) -> Result<Vec<OneWireAddress>> {
+        let pin_out = self.convert_pin_id_to_u8(pin);
+        let events = self.events.resubscribe();
+        self.send(OneWireSearch(pin_out, alarms)).await?;
+        Self::wait_for_onewire(events, timeout, |reply| match reply {
+            OneWireReply::Search { pin, addresses } if !alarms && pin == pin_out => Some(addresses),
+            OneWireReply::SearchAlarms { pin, addresses } if alarms && pin == pin_out => {
+                Some(addresses)
+            }
+            _ => None,
+        })
+        .await
+    }
+
+    /// The devices found by the last search of a pin.
+    pub fn onewire_devices(&self, pin: PinId) -> Vec<OneWireAddress> {
+        let pin = self.convert_pin_id_to_u8(pin);
+        self.state
+            .borrow()
+            .onewire_devices
+            .get(&pin)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Runs a bus transaction without waiting for it, read bytes arrive as
+    /// [`BoardEvent::OneWire`].
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub async fn onewire_send(&mut self, pin: PinId, request: OneWireRequest) -> Result<()> {
+        let pin_out = self.convert_pin_id_to_u8(pin);
+        self.check_claim(pin_out)?;
+        self.send(MessageOut::OneWireRequest(pin_out, request))
+            .await?;
+        Ok(())
+    }
+
+    /// Runs a bus transaction and, if it reads, waits for the bytes. The correlation id
+    /// of the request is replaced with one unique to the board.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the read bytes did not arrive in time.
+    pub async fn onewire_transfer(
+        &mut self,
+        pin: PinId,
+        request: OneWireRequest,
+        timeout: Duration,
+    ) -> Result<Vec<u8>> {
+        let pin_out = self.convert_pin_id_to_u8(pin);
+        let id = self.shared.next_onewire_id.fetch_add(1, Ordering::Relaxed);
+        let read = request.read;
+        let request = request.read(read, id);
+        let events = self.events.resubscribe();
+        self.onewire_send(pin, request).await?;
+        if read == 0 {
+            return Ok(vec![]);
+        }
+        Self::wait_for_onewire(events, timeout, |reply| match reply {
+            OneWireReply::Read {
+                pin,
+                correlation_id,
+                data,
+            } if pin == pin_out && correlation_id == id => Some(data),
+            _ => None,
+        })
+        .await
+    }
+
+    /// See [`crate::standard::board::Board::read_ds18b20`].
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the sensor did not answer in time or
+    /// [`FirmataError::ConversionFailure`] if the reading was corrupted.
+    pub async fn read_ds18b20(
+        &mut self,
+        pin: PinId,
+        address: Option<OneWireAddress>,
+        timeout: Duration,
+    ) -> Result<f64> {
+        self.onewire_send(pin, OneWireRequest::ds18b20_convert(address))
+            .await?;
+        // The firmware only runs the read once the conversion delay has passed.
+        let scratchpad = self
+            .onewire_transfer(
+                pin,
+                OneWireRequest::ds18b20_read(address, 0),
+                timeout + onewire::DS18B20_CONVERSION_TIME,
+            )
+            .await?;
+        onewire::ds18b20_temperature(&scratchpad)
+    }
+
+    async fn wait_for_onewire<T, F: FnMut(OneWireReply) -> Option<T>>(
+        mut events: broadcast::Receiver<BoardEvent>,
+        timeout: Duration,
+        mut matcher: F,
+    ) -> Result<T> {
+        tokio::time::timeout(timeout, async {
+            loop {
+                match events.recv().await {
+                    Ok(BoardEvent::OneWire(reply)) => {
+                        if let Some(value) = matcher(reply) {
+                            return Ok(value);
+                        }
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => {
+                        return Err(FirmataError::StateError("the board io task has stopped"))
+                    }
+                }
+            }
+        })
+        .await
+        .map_err(|_| FirmataError::Timeout(format!("{}", timeout.as_millis())))?
+    }
+
+    /// See [`crate::standard::board::Board::create_task`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the task can not be stored or an error if
+    /// a message could not be sent.
+    pub async fn create_task(&mut self, task: u8, data: &[u8]) -> Result<()> {
+        for request in scheduler::store_task(task, data)? {
+            self.send(Scheduler(request)).await?;
+        }
+        Ok(())
+    }
+
+    /// Runs a stored task once the delay passed.
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub async fn schedule_task(&mut self, task: u8, delay: Duration) -> Result<()> {
+        self.send(Scheduler(SchedulerRequest::Schedule { task, delay }))
+            .await?;
+        Ok(())
+    }
+
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub async fn delete_task(&mut self, task: u8) -> Result<()> {
+        self.send(Scheduler(SchedulerRequest::Delete(task))).await?;
+        Ok(())
+    }
+
+    /// Deletes every task.
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub async fn reset_tasks(&mut self) -> Result<()> {
+        self.send(Scheduler(SchedulerRequest::Reset)).await?;
+        Ok(())
+    }
+
+    /// Waits for the ids of the stored tasks, failed tasks are reported as
+    /// [`BoardEvent::Scheduler`].
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the firmware did not answer in time, e.g.
+    /// because it has no scheduler.
+    pub async fn query_tasks(&mut self, timeout: Duration) -> Result<Vec<u8>> {
+        let events = self.events.resubscribe();
+        self.send(Scheduler(SchedulerRequest::QueryAll)).await?;
+        Self::wait_for_scheduler(events, timeout, |reply| match reply {
+            SchedulerReply::Tasks(tasks) => Some(tasks),
+            _ => None,
+        })
+        .await
+    }
+
+    /// Waits for the state of a task, `None` if the firmware has no such task.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the firmware did not answer in time.
+    pub async fn query_task(&mut self, task: u8, timeout: Duration) -> Result<Option<TaskState>> {
+        let events = self.events.resubscribe();
+        self.send(Scheduler(SchedulerRequest::Query(task))).await?;
+        Self::wait_for_scheduler(events, timeout, |reply| match reply {
+            SchedulerReply::Task(id, state) if id == task => Some(state),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn wait_for_scheduler<T, F: FnMut(SchedulerReply) -> Option<T>>(
+        mut events: broadcast::Receiver<BoardEvent>,
+        timeout: Duration,
+        mut matcher: F,
+    ) -> Result<T> {
+        tokio::time::timeout(timeout, async {
+            loop {
+                match events.recv().await {
+                    Ok(BoardEvent::Scheduler(reply)) => {
+                        if let Some(value) = matcher(reply) {
+                            return Ok(value);
+                        }
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => {
+                        return Err(FirmataError::StateError("the board io task has stopped"))
+                    }
+                }
+            }
+        })
+        .await
+        .map_err(|_| FirmataError::Timeout(format!("{}", timeout.as_millis())))?
+    }
+
+    /// See [`crate::standard::board::Board::serial_config`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] for an invalid port or baud rate,
+    /// [`FirmataError::UnsupportedByFirmware`] if the firmware is older than protocol
+    /// 2.5 or an error if the message could not be sent.
+    pub async fn serial_config(
+        &mut self,
+        port: u8,
+        baud: u32,
+        pins: Option<(u8, u8)>,
+    ) -> Result<()> {
+        self.require(Feature::SerialData)?;
+        uart::validate_config(port, baud, pins)?;
+        self.send(Serial(SerialRequest::Config { port, baud, pins }))
+            .await?;
+        Ok(())
+    }
+
+    /// Opens a serial port, has the firmware report what it receives and returns a
+    /// stream of the port, see [`SerialStream`].
+    /// # Errors
+    /// See [`Board::serial_config`].
+    pub async fn serial_open(
+        &mut self,
+        port: u8,
+        baud: u32,
+        pins: Option<(u8, u8)>,
+    ) -> Result<SerialStream> {
+        // Subscribed first so no reply is missed.
+        let stream = self.serial_stream(port)?;
+        self.serial_config(port, baud, pins).await?;
+        self.serial_start_reading(port, None).await?;
+        Ok(stream)
+    }
+
+    /// A stream of a port that is already open, it receives the bytes reported from now
+    /// on.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] for an invalid port.
+    pub fn serial_stream(&self, port: u8) -> Result<SerialStream> {
+        uart::validate_port(port)?;
+        Ok(SerialStream::new(self.clone(), port))
+    }
+
+    /// See [`crate::standard::board::Board::serial_write`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] for an invalid port or an error if a message
+    /// could not be sent.
+    pub async fn serial_write(&mut self, port: u8, data: &[u8]) -> Result<()> {
+        uart::validate_port(port)?;
+        for request in uart::write_requests(port, data) {
+            self.send(Serial(request)).await?;
+        }
+        Ok(())
+    }
+
+    /// Has the firmware report what a port receives as [`BoardEvent::SerialData`], at
+    /// most `max_bytes` per message if given.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] for an invalid port or an error if the
+    /// message could not be sent.
+    pub async fn serial_start_reading(&mut self, port: u8, max_bytes: Option<u16>) -> Result<()> {
+        uart::validate_port(port)?;
+        self.send(Serial(SerialRequest::Read {
+            port,
+            mode: SerialReadMode::Continuous,
+            max_bytes,
+        }))
+        .await?;
+        Ok(())
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] for an invalid port or an error if the
+    /// message could not be sent.
+    pub async fn serial_stop_reading(&mut self, port: u8) -> Result<()> {
+        uart::validate_port(port)?;
+        self.send(Serial(SerialRequest::Read {
+            port,
+            mode: SerialReadMode::Stop,
+            max_bytes: None,
+        }))
+        .await?;
+        Ok(())
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] for an invalid port or an error if the
+    /// message could not be sent.
+    pub async fn serial_close(&mut self, port: u8) -> Result<()> {
+        uart::validate_port(port)?;
+        self.send(Serial(SerialRequest::Close(port))).await?;
+        Ok(())
+    }
+
+    /// See [`crate::standard::board::Board::serial_flush`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] for an invalid port or an error if the
+    /// message could not be sent.
+    pub async fn serial_flush(&mut self, port: u8) -> Result<()> {
+        uart::validate_port(port)?;
+        self.send(Serial(SerialRequest::Flush(port))).await?;
+        Ok(())
+    }
+
+    /// See [`crate::standard::board::Board::serial_listen`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the port is not a software port or an
+    /// error if the message could not be sent.
+    pub async fn serial_listen(&mut self, port: u8) -> Result<()> {
+        if !uart::is_software_port(port) {
+            return Err(FirmataError::OutOfRange(
+                "only software serial ports listen",
+            ));
+        }
+        self.send(Serial(SerialRequest::Listen(port))).await?;
+        Ok(())
+    }
+
+    /// Sends an optional trigger pulse and measures the length of the following pulse.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the firmware did not see a pulse in time or
+    /// did not answer within [`pulse::REPLY_MARGIN`] beyond the request timeout.
+    pub async fn pulse_in(&mut self, request: PulseRequest) -> Result<Duration> {
+        let replies = |state: &State| state.pulses.get(&request.pin).map_or(0, |v| v.replies);
+        let before = replies(&self.get_state());
+        self.send(PulseIn(request)).await?;
+        // Give the firmware a moment beyond its own timeout to report back.
+        let wait = request.timeout + pulse::REPLY_MARGIN;
+        let state = self
+            .wait_for_timeout(|state| replies(state) > before, wait)
+            .await?;
+        let duration = state
+            .pulses
+            .get(&request.pin)
+            .map(|v| v.duration)
+            .unwrap_or_default();
+        if duration.is_zero() {
+            return Err(FirmataError::Timeout(format!(
+                "{}",
+                request.timeout.as_millis()
+            )));
+        }
+        Ok(duration)
+    }
+
+    /// Sets how often the firmware reports analog and I2C values. The interval is
+    /// clamped to what the firmware accepts, see [`sampling::clamp_interval`].
+    /// # Errors
+    /// Returns an error if the firmware lacks the feature or the message was not sent.
     pub async fn sampling_interval(&mut self, duration: std::time::Duration) -> Result<()> {
-        self.tx.send(SampleingInterval(duration)).await?;
+        self.require(Feature::SamplingInterval)?;
+        self.send(SampleingInterval(sampling::clamp_interval(duration)))
+            .await?;
+        Ok(())
+    }
+
+    /// The sampling interval last sent to the firmware, `None` while the firmware
+    /// still uses its own default.
+    pub fn active_sampling_interval(&self) -> Option<Duration> {
+        self.state.borrow().sampling_interval
+    }
+
+    /// Applies the sampling interval, enables or disables reporting of every listed
+    /// channel and sets up the client-side division of its samples.
+    /// # Errors
+    /// Returns an error if the messages could not be sent.
+    pub async fn configure_sampling(&mut self, config: &SamplingConfig) -> Result<()> {
+        if let Some(interval) = config.interval {
+            self.sampling_interval(interval).await?;
+        }
+        for (channel, every) in &config.channels {
+            let pin = self.convert_pin_id_to_u8(PinId::Analog(*channel));
+            if let Ok(mut sampling) = self.shared.sampling.write() {
+                sampling.set(pin, *every);
+            }
+            self.report_analog(PinId::Analog(*channel), *every > 0)
+                .await?;
+        }
         Ok(())
     }
 }
@@ -1,31 +1,106 @@
-use super::board::Board;
+use super::board::{Board, Shared};
+use super::capture::CaptureRecorder;
+use super::debounce::Debouncer;
+use super::events::{BoardEvent, EVENT_CAPACITY};
+use super::frames::FrameCollector;
+use super::interceptor::{Interceptor, Interceptors};
 use super::network::FirmataCodec;
-use crate::message::{MessageIn, System};
-use crate::{message, FirmataError, PinMode, PinStates, Result};
-use futures::SinkExt;
+use super::observer::BoardObserver;
+use super::oversample::Oversampler;
+use super::sync_adapter::{self, SyncReader, SyncWriter};
+use super::watchdog::{Report, Watchdog, WatchdogConfig, WatchdogStatus};
+use crate::capability_cache::{CacheKey, CapabilityCache};
+use crate::digital::OutputLatches;
+use crate::i2c::{I2cConfig, I2cReadOptions};
+use crate::journal::{Journal, JournalEntry};
+use crate::message::{MessageIn, OneWireReply, StepperReply, System};
+use crate::onewire::{OneWireAddress, OneWireRequest};
+use crate::profile::BoardProfile;
+use crate::pulse::{PulseMeasurement, PulseRequest};
+use crate::recovery::RecoveryPolicy;
+use crate::sampling;
+use crate::scheduler::SchedulerRequest;
+use crate::stepper::{StepperConfig, StepperStatus};
+use crate::transport::{BoxedAsyncRead, BoxedAsyncWrite, ReconnectPolicy, Transport};
+use crate::uart::SerialRequest;
+use crate::{message, FirmataError, PinId, PinMode, PinStates, Result, Stats};
+use futures::{FutureExt, SinkExt};
 use message::ReportFirmware;
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
 use std::marker::{Send, Unpin};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::sync::mpsc;
-use tokio::sync::watch;
+use tokio::sync::{broadcast, watch};
 use tokio_stream::StreamExt;
 use tokio_util::codec::{FramedRead, FramedWrite};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum MessageOut {
     AnalogMappingQuery,
     CapabilityQuery,
     ReportFirmware,
-    I2cConfig(u16),
-    I2cRead(u8, u16),
+    I2cConfig(I2cConfig),
+    I2cRead(u8, I2cReadOptions),
     I2cWrite(u8, Vec<u8>),
     ReportDigital(u8, bool),
+    /// Enables or disables the reports of an analog channel, not a pin index.
     ReportAnalog(u8, bool),
     AnalogWrite(u8, u16),
+    /// Several analog writes encoded back to back so they reach the board in one burst.
+    AnalogWriteGroup(Vec<(u8, u16)>),
     DigitalWrite(u8, bool),
     StringWrite(String),
     PinMode(u8, PinMode),
     SampleingInterval(std::time::Duration),
+    StepperConfig(StepperConfig),
+    MultiStepperConfig(u8, Vec<u8>),
+    StepperZero(u8),
+    StepperStep(u8, i32),
+    StepperTo(u8, i32),
+    StepperEnable(u8, bool),
+    StepperStop(u8),
+    StepperReportPosition(u8),
+    /// The maximum speed of a device in steps per second.
+    StepperSpeed(u8, f32),
+    /// The acceleration of a device in steps per second squared.
+    StepperAcceleration(u8, f32),
+    MultiStepperTo(u8, Vec<i32>),
+    MultiStepperStop(u8),
+    PulseIn(PulseRequest),
+    SystemReset,
+    PwmFrequency(u8, u32),
+    ProtocolVersionQuery,
+    /// Writes the output pins of a port selected by the mask to their bits in the value,
+    /// bit 0 being the first pin of the port. The board io merges the other pins from
+    /// its output latches when it sends the message, encoded on its own they are low.
+    DigitalPortWrite(u8, u8, u8),
+    /// The protocol version, capability, analog mapping and firmware queries written
+    /// together in one flush.
+    QueryAll,
+    /// A sysex command the crate does not model and its payload of 7 bit bytes.
+    Sysex(u8, Vec<u8>),
+    /// A command for the task scheduler of the firmware, see [`crate::scheduler`].
+    Scheduler(SchedulerRequest),
+    /// A command for a serial port of the board, see [`crate::uart`].
+    Serial(SerialRequest),
+    /// Several pin modes encoded back to back so they reach the board in one burst.
+    PinModeGroup(Vec<(u8, PinMode)>),
+    PinStateQuery(u8),
+    /// The pulse range of a servo in microseconds, also switches the pin to servo mode.
+    ServoConfig(u8, u16, u16),
+    /// Sets up a OneWire bus on a pin, with or without parasitic power.
+    OneWireConfig(u8, bool),
+    /// Searches the bus of a pin for devices, or only those with an alarm set.
+    OneWireSearch(u8, bool),
+    OneWireRequest(u8, OneWireRequest),
+    /// Replaces the pins of the board state, e.g. with those of a [`BoardProfile`].
+    /// Handled by the board io, nothing is sent to the board.
+    #[cfg_attr(feature = "arbitrary", arbitrary(skip))]
+    AssumePins(PinStates),
 }
 
 #[derive(Debug, Clone, Default)]
@@ -34,6 +109,40 @@ pub struct State {
     pub firmware_name: String,
     pub firmware_version: String,
     pub protocol_version: String,
+    pub stats: Stats,
+    pub watchdog: WatchdogStatus,
+    /// Number of completed moves per multi stepper group.
+    pub stepper_group_moves: BTreeMap<u8, u64>,
+    /// Position and completed moves per stepper device.
+    pub steppers: BTreeMap<u8, StepperStatus>,
+    /// Undebounced values of every pin with debouncing enabled.
+    pub raw_values: BTreeMap<u8, u16>,
+    pub pulses: BTreeMap<u8, PulseMeasurement>,
+    /// The sampling interval last sent, `None` while the firmware uses its default.
+    pub sampling_interval: Option<Duration>,
+    /// The I2C configuration last sent, `None` until one was sent.
+    pub i2c_config: Option<I2cConfig>,
+    /// Level of every pin with a comparator that has reported a value.
+    pub comparator_levels: BTreeMap<u8, bool>,
+    /// The devices found by the last search per OneWire pin.
+    pub onewire_devices: BTreeMap<u8, Vec<OneWireAddress>>,
+    /// The levels written to the outputs of every port, apart from reported inputs.
+    pub output_latches: OutputLatches,
+}
+
+/// The most frames handled per wakeup of [`BoardIo::poll`] before outgoing messages
+/// get a turn, the state is published once for all of them.
+pub const MAX_FRAMES_PER_POLL: usize = 64;
+
+/// What [`BoardIo::shutdown`] does with messages the handles queued but were not sent yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShutdownPolicy {
+    /// Discards the queued messages.
+    #[default]
+    Immediate,
+    /// Sends the queued messages until the queue is empty or the deadline passed, so
+    /// final commands such as turning every output off reach the board.
+    Drain(Duration),
 }
 
 #[derive(Debug)]
@@ -43,8 +152,31 @@ pub struct BoardIo<T: AsyncReadExt, U: AsyncWriteExt> {
     board_state: State,
     state_tx: watch::Sender<State>,
     state_rx: watch::Receiver<State>,
+    events: broadcast::Sender<BoardEvent>,
     message_tx: mpsc::Sender<MessageOut>,
     message_rx: mpsc::Receiver<MessageOut>,
+    pending_firmware_queries: VecDeque<Instant>,
+    watchdog: Watchdog,
+    shared: Arc<Shared>,
+    debouncer: Debouncer,
+    oversampler: Oversampler,
+    frames: FrameCollector,
+    captures: CaptureRecorder,
+    deadbands: BTreeMap<u8, u16>,
+    interceptors: Interceptors,
+    /// Set when a message did not change the state, so it is not published again.
+    state_unchanged: bool,
+    /// Set when a corrupted frame asks for a SYSTEM_RESET under the recovery policy.
+    reset_requested: bool,
+}
+
+impl<T: AsyncReadExt, U: AsyncWriteExt> Drop for BoardIo<T, U> {
+    /// Ends the per pin subscriptions, the shared settings outlive the board io.
+    fn drop(&mut self) {
+        if let Ok(mut watchers) = self.shared.pin_watchers.write() {
+            watchers.clear();
+        }
+    }
 }
 
 impl<T: AsyncReadExt + Unpin + Send, U: AsyncWriteExt + Unpin + Send> BoardIo<T, U> {
@@ -54,52 +186,489 @@ impl<T: AsyncReadExt + Unpin + Send, U: AsyncWriteExt + Unpin + Send> BoardIo<T,
         let board_state = State::default();
         let (state_tx, state_rx) = watch::channel(State::default());
         let (message_tx, message_rx) = mpsc::channel::<MessageOut>(50);
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
         Self {
             conn_read,
             conn_write,
             board_state,
             state_tx,
             state_rx,
+            events,
             message_tx,
             message_rx,
+            pending_firmware_queries: VecDeque::new(),
+            watchdog: Watchdog::default(),
+            shared: Arc::default(),
+            debouncer: Debouncer::default(),
+            oversampler: Oversampler::default(),
+            frames: FrameCollector::default(),
+            captures: CaptureRecorder::default(),
+            deadbands: BTreeMap::new(),
+            interceptors: Interceptors::default(),
+            state_unchanged: false,
+            reset_requested: false,
         }
     }
 
+    /// Swaps the underlying connection for a new one, e.g. after the device was
+    /// reconnected. Any [`Board`] handles remain valid.
+    pub fn replace_transport(&mut self, conn_read: T, conn_write: U) {
+        let mut codec = self.conn_read.decoder().clone();
+        codec.reset();
+        self.conn_read = FramedRead::new(conn_read, codec);
+        self.conn_write = FramedWrite::new(conn_write, FirmataCodec::default());
+        self.pending_firmware_queries.clear();
+    }
+
+    /// Re-issues every configuration command recorded in the journal, used to bring a
+    /// freshly reconnected board back to the configured state.
+    /// # Errors
+    /// Returns an error if writing to the connection failed.
+    pub async fn restore_configuration(&mut self) -> Result<()> {
+        let entries = self.journal();
+        for entry in entries.entries() {
+            if let Some(mut message) = self.interceptors.outbound(entry.clone().into()) {
+                self.update_local(&mut message);
+                self.conn_write.feed(message).await?;
+            }
+        }
+        self.conn_write.flush().await?;
+        self.state_tx.send(self.board_state.clone())?;
+        Ok(())
+    }
+
+    /// The configuration commands issued through this [`BoardIo`] and its boards.
+    pub fn journal(&self) -> Journal {
+        self.shared
+            .journal
+            .read()
+            .map(|journal| journal.clone())
+            .unwrap_or_default()
+    }
+
     pub fn get_board(&self) -> Board {
-        Board::create(self.state_rx.clone(), self.message_tx.clone())
+        Board::create_shared(
+            self.state_rx.clone(),
+            self.message_tx.clone(),
+            self.shared.clone(),
+            self.events.subscribe(),
+        )
+    }
+
+    /// A read-only handle, see [`BoardObserver`].
+    pub fn get_observer(&self) -> BoardObserver {
+        BoardObserver::new(
+            self.state_rx.clone(),
+            self.shared.clone(),
+            self.events.subscribe(),
+        )
+    }
+
+    /// Publishes an event to every [`Board`] handle, it is fine if nobody is listening.
+    pub(crate) fn publish(&self, event: BoardEvent) {
+        let _ = self.events.send(event);
+    }
+
+    fn set_pin_value(&mut self, pin: u8, value: u16) {
+        if let Some(v) = self.board_state.pin_state.pins.get_mut(pin as usize) {
+            let old = v.value;
+            v.value = value;
+            if old != value {
+                self.publish(BoardEvent::PinChanged {
+                    pin,
+                    old,
+                    new: value,
+                });
+                self.notify_pin(pin, value);
+            }
+            self.update_comparator(pin, value);
+        }
+    }
+
+    /// Feeds the subscriptions of a pin, dropping its channel once nobody listens.
+    fn notify_pin(&self, pin: u8, value: u16) {
+        let delivered = match self.shared.pin_watchers.read() {
+            Ok(watchers) => watchers.get(&pin).map(|sender| sender.send(value).is_ok()),
+            Err(_) => return,
+        };
+        if delivered == Some(false) {
+            if let Ok(mut watchers) = self.shared.pin_watchers.write() {
+                watchers.retain(|_, sender| !sender.is_closed());
+            }
+        }
+    }
+
+    /// Moves the comparator of a pin, publishing an edge when its level changes.
+    fn update_comparator(&mut self, pin: u8, value: u16) {
+        let comparator = self
+            .shared
+            .comparators
+            .read()
+            .ok()
+            .and_then(|comparators| comparators.get(&pin).copied());
+        let Some(comparator) = comparator else {
+            self.board_state.comparator_levels.remove(&pin);
+            return;
+        };
+        let previous = self.board_state.comparator_levels.get(&pin).copied();
+        let level = comparator.level(previous, value);
+        self.board_state.comparator_levels.insert(pin, level);
+        if previous != Some(level) {
+            self.publish(BoardEvent::ComparatorEdge { pin, level });
+        }
+    }
+
+    /// Makes unknown sysex commands show up as [`MessageIn::UnknownSysex`] and count
+    /// towards [`crate::Stats::unknown_sysex`] instead of failing [`BoardIo::poll`].
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.conn_read.decoder_mut().set_lenient(lenient);
+    }
+
+    /// Sets how corrupted frames are handled, see [`RecoveryPolicy`]. The policy is
+    /// shown in [`Stats::recovery_policy`].
+    pub fn set_recovery_policy(&mut self, policy: RecoveryPolicy) {
+        self.conn_read.decoder_mut().set_recovery(policy);
+        self.board_state.stats.recovery_policy = policy;
+        let _ = self.state_tx.send(self.board_state.clone());
+    }
+
+    /// Sets the longest frame accepted from the firmware, see
+    /// [`FirmataCodec::set_max_frame_size`].
+    pub fn set_max_frame_size(&mut self, size: usize) {
+        self.conn_read.decoder_mut().set_max_frame_size(size);
+    }
+
+    /// Publishes every received frame with the bytes it was decoded from as
+    /// [`BoardEvent::RawFrame`], for sniffers, bridges and bug reports. Off by default.
+    pub fn set_retain_raw(&mut self, retain: bool) {
+        self.conn_read.decoder_mut().set_retain_raw(retain);
+    }
+
+    /// Averages every `samples` readings of an analog pin and only publishes the average,
+    /// smoothing noise and reducing state updates. One or zero samples turns it off.
+    /// Analog pin ids are only resolved correctly after [`BoardIo::generate_board_state`].
+    pub fn set_oversampling(&mut self, pin: PinId, samples: u32) {
+        let pin = self.board_state.pin_state.pin_id_to_u8(pin);
+        self.oversampler.set(pin, samples);
+    }
+
+    /// Groups analog pins so their raw samples of each sampling interval are also
+    /// published together as one [`BoardEvent::SampleFrame`], for processing that needs
+    /// the samples of several sensors from the same instant. Individual pin updates are
+    /// not affected. An empty list removes the group. Analog pin ids are only resolved
+    /// correctly after [`BoardIo::generate_board_state`].
+    pub fn set_sample_group(&mut self, group: u8, pins: &[PinId]) {
+        let pins = pins
+            .iter()
+            .map(|pin| self.board_state.pin_state.pin_id_to_u8(*pin))
+            .collect();
+        self.frames.set(group, pins);
+    }
+
+    /// Only publishes analog values of a pin that differ from the published value by
+    /// more than the threshold, so ADC jitter does not wake every subscriber. A threshold
+    /// of zero turns it off. Analog pin ids are only resolved correctly after
+    /// [`BoardIo::generate_board_state`].
+    pub fn set_deadband(&mut self, pin: PinId, threshold: u16) {
+        let pin = self.board_state.pin_state.pin_id_to_u8(pin);
+        if threshold == 0 {
+            self.deadbands.remove(&pin);
+        } else {
+            self.deadbands.insert(pin, threshold);
+        }
+    }
+
+    /// Whether a new analog value is within the deadband of the published value.
+    fn within_deadband(&self, pin: u8, value: u16) -> bool {
+        self.deadbands.get(&pin).is_some_and(|threshold| {
+            let current = self.board_state.pin_state.pins[usize::from(pin)].value;
+            current.abs_diff(value) <= *threshold
+        })
     }
 
-    fn update_local(&mut self, message: &MessageOut) {
+    /// Adds an interceptor behind the ones added before, see [`Interceptor`].
+    pub fn add_interceptor<I: Interceptor + 'static>(&mut self, interceptor: I) {
+        self.interceptors.push(Box::new(interceptor));
+    }
+
+    /// Gives up on a write to the transport that does not complete within the timeout,
+    /// e.g. when a USB serial buffer is full or a TCP peer died, failing
+    /// [`BoardIo::poll`] with [`FirmataError::WriteTimeout`]. [`Board`] handles waiting
+    /// for room in the outgoing queue fail with the same error. `None`, the default,
+    /// waits forever.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        if let Ok(mut write_timeout) = self.shared.write_timeout.write() {
+            *write_timeout = timeout;
+        }
+    }
+
+    /// Writes a message, applying the write timeout.
+    async fn write(&mut self, message: MessageOut) -> Result<()> {
+        let timeout = self.shared.write_timeout.read().ok().and_then(|v| *v);
+        let Some(timeout) = timeout else {
+            return self.conn_write.send(message).await;
+        };
+        // The codec takes the message, a copy is kept to describe a timed out write.
+        match tokio::time::timeout(timeout, self.conn_write.send(message.clone())).await {
+            Ok(written) => written,
+            Err(_) => Err(FirmataError::WriteTimeout(timeout, format!("{message:?}"))),
+        }
+    }
+
+    /// Writes a bootstrap message unless an interceptor drops it.
+    async fn feed(&mut self, message: MessageOut) -> Result<()> {
+        if let Some(message) = self.interceptors.outbound(message) {
+            self.conn_write.feed(message).await?;
+        }
+        Ok(())
+    }
+
+    /// Configures the watchdog, this needs to be done prior to calling [`BoardIo::poll`].
+    pub fn set_watchdog(&mut self, config: WatchdogConfig) {
+        self.watchdog.configure(config);
+    }
+
+    fn check_watchdog(&mut self) -> Result<()> {
+        let status = self.watchdog.evaluate();
+        let link_stale = status.link_stale;
+        if status != self.board_state.watchdog {
+            self.board_state.watchdog = status;
+            self.state_tx.send(self.board_state.clone())?;
+        }
+        if link_stale && self.watchdog.config().fail_on_link_timeout {
+            return Err(FirmataError::Timeout(format!(
+                "no traffic from board within {}",
+                self.watchdog
+                    .config()
+                    .link_timeout
+                    .unwrap_or_default()
+                    .as_millis()
+            )));
+        }
+        Ok(())
+    }
+
+    fn update_digital(&mut self, pin: u8, raw: u16) {
+        let window = self
+            .shared
+            .debounce
+            .read()
+            .ok()
+            .and_then(|debounce| debounce.get(&pin).copied());
+        match window {
+            Some(window) => {
+                self.board_state.raw_values.insert(pin, raw);
+                let stable = self.board_state.pin_state.pins[pin as usize].value;
+                self.debouncer.observe(pin, raw, stable, window);
+            }
+            None => {
+                self.board_state.raw_values.remove(&pin);
+                self.debouncer.forget(pin);
+                self.set_pin_value(pin, raw);
+            }
+        }
+    }
+
+    /// Feeds the capture armed on a pin, publishing it once it is complete.
+    fn record_capture(&mut self, pin: u8, value: u16) {
+        let armed = self
+            .shared
+            .captures
+            .read()
+            .ok()
+            .and_then(|captures| captures.get(&pin).copied());
+        let Some(capture) = self.captures.push(pin, value, armed) else {
+            return;
+        };
+        // Captures are one shot, unless the pin was armed again in the meantime.
+        if let Ok(mut captures) = self.shared.captures.write() {
+            if captures.get(&pin) == Some(&capture.request) {
+                captures.remove(&pin);
+            }
+        }
+        self.publish(BoardEvent::Capture(capture));
+    }
+
+    fn accept_sample(&self, pin: u8) -> bool {
+        self.shared
+            .sampling
+            .write()
+            .map_or(true, |mut sampling| sampling.accept(pin))
+    }
+
+    fn commit_debounced(&mut self) -> Result<()> {
+        let matured = self.debouncer.take_matured();
+        if matured.is_empty() {
+            return Ok(());
+        }
+        for (pin, value) in matured {
+            self.set_pin_value(pin, value);
+        }
+        self.state_tx.send(self.board_state.clone())?;
+        Ok(())
+    }
+
+    /// Applies the bus defaults of the active I2C configuration to a read. This happens
+    /// here rather than in the handles so a read queued right after a configuration
+    /// change already uses it.
+    fn apply_i2c_config(&self, message: MessageOut) -> MessageOut {
+        match (message, self.board_state.i2c_config) {
+            (MessageOut::I2cRead(address, options), Some(config)) => {
+                MessageOut::I2cRead(address, config.apply(options))
+            }
+            (message, _) => message,
+        }
+    }
+
+    fn update_local(&mut self, message: &mut MessageOut) {
+        // Merged here rather than by the handle so writes queued back to back build on
+        // each other.
+        if let MessageOut::DigitalPortWrite(port, mask, value) = message {
+            *value = self
+                .board_state
+                .output_latches
+                .write_port(*port, *mask, *value);
+            *mask = 0xFF;
+        }
+        let entries = JournalEntry::entries_from_message(message);
+        if !entries.is_empty() {
+            if let Ok(mut journal) = self.shared.journal.write() {
+                for entry in entries {
+                    journal.record(entry);
+                }
+            }
+        }
         match message {
+            MessageOut::AssumePins(pins) => {
+                self.board_state.pin_state = pins.clone();
+                self.publish(BoardEvent::PinsUpdated);
+            }
             MessageOut::AnalogWrite(pin, value) => {
                 let index: usize = *pin as usize;
                 if self.board_state.pin_state.pins.len() > index {
                     self.board_state.pin_state.pins[index].value = *value;
                 }
             }
+            MessageOut::AnalogWriteGroup(writes) => {
+                for (pin, value) in writes {
+                    if let Some(physical) = self.board_state.pin_state.pins.get_mut(*pin as usize) {
+                        physical.value = *value;
+                    }
+                }
+            }
             MessageOut::DigitalWrite(pin, value) => {
                 let index: usize = *pin as usize;
                 if self.board_state.pin_state.pins.len() > index {
                     self.board_state.pin_state.pins[index].value = *value as u16;
                 }
+                self.board_state.output_latches.write_pin(*pin, *value);
             }
+            MessageOut::DigitalPortWrite(port, _, value) => {
+                for bit in 0..8 {
+                    let index = usize::from(*port) * 8 + bit;
+                    if let Some(pin) = self.board_state.pin_state.pins.get_mut(index) {
+                        if pin.mode == PinMode::Output {
+                            pin.value = u16::from(*value >> bit & 1);
+                        }
+                    }
+                }
+            }
+            MessageOut::SampleingInterval(interval) => {
+                self.board_state.sampling_interval = Some(sampling::clamp_interval(*interval));
+            }
+            MessageOut::I2cConfig(config) => self.board_state.i2c_config = Some(*config),
             MessageOut::PinMode(pin, mode) => {
                 let index: usize = *pin as usize;
                 if self.board_state.pin_state.pins.len() > index {
                     self.board_state.pin_state.pins[index].mode = *mode;
                 }
             }
+            MessageOut::PinModeGroup(modes) => {
+                for (pin, mode) in modes {
+                    if let Some(physical) = self.board_state.pin_state.pins.get_mut(*pin as usize) {
+                        physical.mode = *mode;
+                    }
+                }
+            }
+            MessageOut::ServoConfig(pin, _, _) => {
+                if let Some(physical) = self.board_state.pin_state.pins.get_mut(*pin as usize) {
+                    physical.mode = PinMode::Servo;
+                }
+            }
+            MessageOut::OneWireConfig(pin, _) => {
+                if let Some(physical) = self.board_state.pin_state.pins.get_mut(*pin as usize) {
+                    physical.mode = PinMode::Onewire;
+                }
+            }
+            MessageOut::ReportFirmware | MessageOut::QueryAll => {
+                self.pending_firmware_queries.push_back(Instant::now());
+            }
+            MessageOut::ReportAnalog(channel, enable) => {
+                if let Some(pin) = self.board_state.pin_state.pin_for_channel(*channel) {
+                    self.watchdog.subscribe(Report::Analog(pin), *enable);
+                }
+            }
+            MessageOut::ReportDigital(port, enable) => {
+                self.watchdog.subscribe(Report::Digital(*port), *enable);
+            }
+            MessageOut::SystemReset => {
+                self.board_state.output_latches.clear();
+                // The firmware forgets its configuration so there is nothing to replay.
+                let entries = self.journal();
+                for entry in entries.entries() {
+                    match entry {
+                        JournalEntry::ReportAnalog(channel, _) => {
+                            if let Some(pin) = self.board_state.pin_state.pin_for_channel(*channel)
+                            {
+                                self.watchdog.subscribe(Report::Analog(pin), false);
+                            }
+                        }
+                        JournalEntry::ReportDigital(port, _) => {
+                            self.watchdog.subscribe(Report::Digital(*port), false);
+                        }
+                        _ => {}
+                    }
+                }
+                if let Ok(mut journal) = self.shared.journal.write() {
+                    journal.clear();
+                }
+            }
             _ => {}
         }
     }
 
+    /// Appends a received message to the event log, if one is set.
+    fn log_message(&self, message: &MessageIn) {
+        if let Ok(mut log) = self.shared.event_log.write() {
+            if let Some(log) = log.as_mut() {
+                log.record(message);
+            }
+        }
+    }
+
     fn handle_message(&mut self, message: MessageIn) -> Result<()> {
+        self.log_message(&message);
         match message {
             message::MessageIn::Analog(v) => {
                 if !self.board_state.pin_state.pins.is_empty() {
                     let pin: usize = self.board_state.pin_state.pin_id_to_u8(v.pin) as usize;
+                    self.watchdog.observe_report(Report::Analog(pin as u8));
                     if self.board_state.pin_state.pins[pin].analog {
-                        self.board_state.pin_state.pins[pin].value = v.value;
+                        self.record_capture(pin as u8, v.value);
+                        for frame in self.frames.push(pin as u8, v.value) {
+                            self.publish(BoardEvent::SampleFrame(frame));
+                        }
+                        let value = if self.accept_sample(pin as u8) {
+                            self.oversampler.push(pin as u8, v.value)
+                        } else {
+                            None
+                        };
+                        match value {
+                            Some(value) if !self.within_deadband(pin as u8, value) => {
+                                self.set_pin_value(pin as u8, value);
+                            }
+                            _ => self.state_unchanged = true,
+                        }
                         Ok(())
                     } else {
                         Err(FirmataError::UninitializedError(
@@ -113,15 +682,20 @@ impl<T: AsyncReadExt + Unpin + Send, U: AsyncWriteExt + Unpin + Send> BoardIo<T,
                 }
             }
             message::MessageIn::Digital(v) => {
+                self.watchdog.observe_report(Report::Digital(v.port));
                 if !self.board_state.pin_state.pins.is_empty() {
                     for i in 0..8 {
                         let pin = (8 * v.port) + i;
 
                         if self.board_state.pin_state.pins.len() > pin as usize
-                            && self.board_state.pin_state.pins[pin as usize].mode == PinMode::Input
+                            && matches!(
+                                self.board_state.pin_state.pins[pin as usize].mode,
+                                PinMode::Input | PinMode::Pullup
+                            )
                         {
-                            self.board_state.pin_state.pins[pin as usize].value =
-                                (v.value >> (i & 0x07)) & 0x01;
+                            let raw = (v.value >> (i & 0x07)) & 0x01;
+                            self.record_capture(pin, raw);
+                            self.update_digital(pin, raw);
                         }
                     }
                     Ok(())
@@ -134,9 +708,8 @@ impl<T: AsyncReadExt + Unpin + Send, U: AsyncWriteExt + Unpin + Send> BoardIo<T,
             message::MessageIn::System(v) => match v {
                 message::System::AnalogMappingResponse(v) => {
                     if !self.board_state.pin_state.pins.is_empty() {
-                        for id in v.supported_analog_pins {
-                            self.board_state.pin_state.pins[id].analog = true;
-                        }
+                        self.board_state.pin_state.map_analog_channels(v.channels)?;
+                        self.publish(BoardEvent::PinsUpdated);
                         Ok(())
                     } else {
                         Err(FirmataError::UninitializedError(
@@ -146,96 +719,390 @@ impl<T: AsyncReadExt + Unpin + Send, U: AsyncWriteExt + Unpin + Send> BoardIo<T,
                 }
                 message::System::CapabilityResponseMessage(v) => {
                     self.board_state.pin_state.pins = v.pins;
+                    self.publish(BoardEvent::PinsUpdated);
                     Ok(())
                 }
                 message::System::ReportFirmwareMessage(v) => {
+                    if let Some(sent) = self.pending_firmware_queries.pop_front() {
+                        self.board_state.stats.record_latency(sent.elapsed());
+                    }
+                    self.publish(BoardEvent::Firmware {
+                        name: v.name.clone(),
+                        version: v.version.clone(),
+                    });
                     self.board_state.firmware_name = v.name;
                     self.board_state.firmware_version = v.version;
                     Ok(())
                 }
-                message::System::I2cReplyMessage(_v) => {
+                message::System::I2cReplyMessage(v) => {
                     //mutex.i2c_data.push(v.reply);
+                    self.publish(BoardEvent::I2cReply(v.reply));
+                    Ok(())
+                }
+                message::System::StringDataMessage(v) => {
+                    self.publish(BoardEvent::StringData(v.text));
+                    Ok(())
+                }
+                message::System::PinStateResponseMessage(v) => {
+                    if let Some(pin) = self.board_state.pin_state.pins.get_mut(usize::from(v.pin)) {
+                        v.apply(pin);
+                    }
+                    self.publish(BoardEvent::PinState(v));
+                    Ok(())
+                }
+                message::System::PulseReplyMessage(v) => {
+                    let pulse = self.board_state.pulses.entry(v.pin).or_default();
+                    pulse.record(v.duration_us);
+                    let duration = pulse.duration;
+                    self.publish(BoardEvent::Pulse {
+                        pin: v.pin,
+                        duration,
+                    });
+                    Ok(())
+                }
+                message::System::OneWireReplyMessage(v) => {
+                    if let OneWireReply::Search { pin, addresses } = &v {
+                        self.board_state
+                            .onewire_devices
+                            .insert(*pin, addresses.clone());
+                    }
+                    self.publish(BoardEvent::OneWire(v));
+                    Ok(())
+                }
+                message::System::SchedulerReplyMessage(v) => {
+                    self.publish(BoardEvent::Scheduler(v));
+                    Ok(())
+                }
+                message::System::SerialReply { port, data } => {
+                    self.publish(BoardEvent::SerialData { port, data });
+                    Ok(())
+                }
+                message::System::StepperReplyMessage(v) => {
+                    match v {
+                        StepperReply::MoveComplete { device, position } => {
+                            self.board_state
+                                .steppers
+                                .entry(device)
+                                .or_default()
+                                .record_move(position);
+                            self.publish(BoardEvent::StepperComplete { device, position });
+                        }
+                        StepperReply::Position { device, position } => {
+                            self.board_state
+                                .steppers
+                                .entry(device)
+                                .or_default()
+                                .position = position;
+                            self.publish(BoardEvent::StepperPosition { device, position });
+                        }
+                        StepperReply::MultiMoveComplete { group } => {
+                            *self
+                                .board_state
+                                .stepper_group_moves
+                                .entry(group)
+                                .or_default() += 1;
+                            self.publish(BoardEvent::StepperMoveComplete { group });
+                        }
+                    }
                     Ok(())
                 }
             },
+            message::MessageIn::UnknownSysex { command, payload } => {
+                self.board_state.stats.unknown_sysex += 1;
+                self.publish(BoardEvent::UnknownSysex { command, payload });
+                Ok(())
+            }
+            message::MessageIn::FrameError(v) => {
+                self.board_state.stats.record_frame_error(&v);
+                match self.board_state.stats.recovery_policy {
+                    RecoveryPolicy::ResetDecoder => self.board_state.stats.decoder_resets += 1,
+                    RecoveryPolicy::SystemReset => self.reset_requested = true,
+                    RecoveryPolicy::SkipFrame | RecoveryPolicy::Bail => {}
+                }
+                self.publish(BoardEvent::FrameError(v));
+                Ok(())
+            }
             message::MessageIn::ProtocolVersion(v) => {
+                self.publish(BoardEvent::ProtocolVersion(v.clone()));
                 self.board_state.protocol_version = v;
                 Ok(())
             }
         }
     }
 
+    /// Handles a frame and every further frame that can be decoded without waiting,
+    /// then publishes the state once if any of them changed it.
+    fn receive_batch(&mut self, first: Result<MessageIn>) -> Result<()> {
+        let mut changed = false;
+        let mut next = Some(first);
+        let mut handled = 0;
+        let result = loop {
+            let Some(frame) = next else { break Ok(()) };
+            match frame.and_then(|v| self.receive(v)) {
+                Ok(v) => changed |= v,
+                Err(e) => break Err(e),
+            }
+            handled += 1;
+            if handled == MAX_FRAMES_PER_POLL {
+                break Ok(());
+            }
+            next = self.conn_read.next().now_or_never().flatten();
+        };
+        if changed {
+            self.state_tx.send(self.board_state.clone())?;
+        }
+        result
+    }
+
+    /// Handles a single frame, returns whether it changed the state.
+    fn receive(&mut self, message: MessageIn) -> Result<bool> {
+        self.watchdog.observe_traffic();
+        if let Some(raw) = self.conn_read.decoder_mut().take_raw() {
+            self.publish(BoardEvent::RawFrame {
+                raw,
+                message: message.clone(),
+            });
+        }
+        match self.interceptors.inbound(message) {
+            Some(message) => {
+                self.handle_message(message)?;
+                Ok(!std::mem::take(&mut self.state_unchanged))
+            }
+            None => Ok(false),
+        }
+    }
+
     pub async fn poll(&mut self) -> Result<()> {
+        let mut watchdog_tick = tokio::time::interval(self.watchdog.check_period());
         loop {
+            let debounce_deadline = self.debouncer.next_deadline();
             tokio::select! {
                     val = self.conn_read.next() => {
-                        if let Some(v) = val {
-                            self.handle_message(v?)?;
-                            self.state_tx.send(self.board_state.clone())?;
+                        match val {
+                            Some(v) => {
+                                self.receive_batch(v)?;
+                                if std::mem::take(&mut self.reset_requested) {
+                                    self.reset_firmware().await?;
+                                }
+                            }
+                            None => {
+                                return Err(FirmataError::IoError(std::io::Error::new(
+                                    std::io::ErrorKind::UnexpectedEof,
+                                    "connection to the board was closed",
+                                )));
+                            }
                         }
                     }
                     val = self.message_rx.recv() => {
-                        if let Some(v) = val {
-                            self.update_local(&v);
-                            self.conn_write.send(v).await?;
+                        let val = val.map(|v| self.apply_i2c_config(v));
+                        if let Some(mut v) = val.and_then(|v| self.interceptors.outbound(v)) {
+                            self.update_local(&mut v);
+                            self.write(v).await?;
                             self.state_tx.send(self.board_state.clone())?
                         }
+                    }
+                    _ = watchdog_tick.tick(), if self.watchdog.is_enabled() => {
+                        self.check_watchdog()?;
+                    }
+                    _ = tokio::time::sleep_until(
+                        debounce_deadline.unwrap_or_else(Instant::now).into()
+                    ), if debounce_deadline.is_some() => {
+                        self.commit_debounced()?;
+                    }
+            }
+        }
+    }
+
+    /// Polls until the shutdown future resolves and then shuts down with the policy it
+    /// resolved to, see [`BoardIo::shutdown`].
+    /// # Errors
+    /// Returns the error that stopped polling or an error raised while shutting down.
+    pub async fn poll_until<F: Future<Output = ShutdownPolicy>>(
+        &mut self,
+        shutdown: F,
+    ) -> Result<()> {
+        let policy = tokio::select! {
+            result = self.poll() => return result,
+            policy = shutdown => policy,
+        };
+        self.shutdown(policy).await?;
+        Ok(())
+    }
+
+    /// Stops accepting messages from the handles, handles the queued messages according
+    /// to the policy and closes the transport. Returns the number of queued messages
+    /// that were sent.
+    /// # Errors
+    /// Returns an error if a message could not be written or the transport not closed.
+    pub async fn shutdown(&mut self, policy: ShutdownPolicy) -> Result<usize> {
+        self.message_rx.close();
+        let mut sent = 0;
+        if let ShutdownPolicy::Drain(timeout) = policy {
+            let deadline = Instant::now() + timeout;
+            let drain = async {
+                while let Ok(message) = self.message_rx.try_recv() {
+                    let message = self.apply_i2c_config(message);
+                    if let Some(mut message) = self.interceptors.outbound(message) {
+                        self.update_local(&mut message);
+                        self.conn_write.feed(message).await?;
+                        sent += 1;
+                    }
                 }
+                self.conn_write.flush().await?;
+                Ok::<_, FirmataError>(())
+            };
+            if let Ok(result) = tokio::time::timeout_at(deadline.into(), drain).await {
+                result?;
             }
         }
+        self.conn_write.close().await?;
+        Ok(sent)
+    }
+
+    /// Resets the firmware after a corrupted frame and restores the configuration.
+    async fn reset_firmware(&mut self) -> Result<()> {
+        self.write(MessageOut::SystemReset).await?;
+        self.board_state.stats.system_resets += 1;
+        self.restore_configuration().await
     }
 
     /// Populates the state of the board, used for quick look ups
     /// # Errors
     /// Can return several firmata errors depeneding on the state that failed.
     pub async fn generate_board_state(&mut self) -> Result<()> {
-        self.conn_write.feed(MessageOut::ReportFirmware).await?;
-        self.conn_write.feed(MessageOut::CapabilityQuery).await?;
-        self.conn_write.feed(MessageOut::AnalogMappingQuery).await?;
+        let (firmware, pin_state) = self.bootstrap(true, true).await?;
+        let firmware = firmware.ok_or(FirmataError::WrongType("expected firmware found none"))?;
+        let pin_state =
+            pin_state.ok_or(FirmataError::WrongType("expected pinstates found none"))?;
+        self.apply_bootstrap(firmware, pin_state)
+    }
+
+    /// Like [`BoardIo::generate_board_state`] but only queries the firmware if the pins
+    /// of the board are in the cache, otherwise the pins are queried and cached.
+    /// # Errors
+    /// Can return several firmata errors depeneding on the state that failed.
+    pub async fn generate_board_state_cached(
+        &mut self,
+        cache: &mut CapabilityCache,
+        serial_number: Option<&str>,
+    ) -> Result<()> {
+        let (firmware, _) = self.bootstrap(true, false).await?;
+        let firmware = firmware.ok_or(FirmataError::WrongType("expected firmware found none"))?;
+        let key = CacheKey::new(&firmware.name, &firmware.version, serial_number);
+        let pin_state = match cache.get(&key) {
+            Some(pin_state) => pin_state.clone(),
+            None => {
+                let (_, pin_state) = self.bootstrap(false, true).await?;
+                let pin_state =
+                    pin_state.ok_or(FirmataError::WrongType("expected pinstates found none"))?;
+                cache.insert(key, pin_state.clone());
+                pin_state
+            }
+        };
+        self.apply_bootstrap(firmware, pin_state)
+    }
+
+    /// Takes the pins from a profile instead of querying the board, for firmwares that
+    /// do not answer the capability or analog mapping queries.
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the profile is inconsistent.
+    pub fn assume_profile(&mut self, profile: &BoardProfile) -> Result<()> {
+        self.board_state.pin_state = profile.pin_states()?;
+        self.state_tx.send(self.board_state.clone())?;
+        Ok(())
+    }
+
+    /// Sends the bootstrap queries and waits for every requested answer.
+    async fn bootstrap(
+        &mut self,
+        query_firmware: bool,
+        query_pins: bool,
+    ) -> Result<(Option<ReportFirmware>, Option<PinStates>)> {
+        // Not waited for, the version is picked up if it arrives during the bootstrap.
+        self.feed(MessageOut::ProtocolVersionQuery).await?;
+        if query_firmware {
+            self.feed(MessageOut::ReportFirmware).await?;
+        }
+        if query_pins {
+            self.feed(MessageOut::CapabilityQuery).await?;
+            self.feed(MessageOut::AnalogMappingQuery).await?;
+        }
         self.conn_write.flush().await?;
-        let mut analog_pins: Option<Vec<usize>> = None;
+        let mut analog_pins: Option<BTreeMap<u8, u8>> = None;
         let mut firmware: Option<ReportFirmware> = None;
         let mut pins: Option<PinStates> = None;
         loop {
-            if analog_pins.is_some() && firmware.is_some() && pins.is_some() {
+            if (!query_pins || (analog_pins.is_some() && pins.is_some()))
+                && (!query_firmware || firmware.is_some())
+            {
                 break;
             }
             let resp = self.conn_read.next().await;
             match resp {
-                Some(v) => match v {
-                    Ok(msg) => match msg {
-                        MessageIn::System(sys_msg) => match sys_msg {
-                            System::AnalogMappingResponse(analog_msg) => {
-                                analog_pins = Some(analog_msg.supported_analog_pins);
-                            }
-                            System::CapabilityResponseMessage(cap_msg) => {
-                                pins = Some(PinStates::create(cap_msg.pins));
-                            }
-                            System::ReportFirmwareMessage(firm_msg) => {
-                                firmware = Some(firm_msg);
+                Some(v) => match v.map(|msg| self.interceptors.inbound(msg)) {
+                    Ok(None) => continue,
+                    Ok(Some(msg)) => {
+                        self.log_message(&msg);
+                        match msg {
+                            MessageIn::System(sys_msg) => match sys_msg {
+                                System::AnalogMappingResponse(analog_msg) => {
+                                    analog_pins = Some(analog_msg.channels);
+                                }
+                                System::CapabilityResponseMessage(cap_msg) => {
+                                    pins = Some(PinStates::create(cap_msg.pins));
+                                }
+                                System::ReportFirmwareMessage(firm_msg) => {
+                                    firmware = Some(firm_msg);
+                                }
+                                _ => continue,
+                            },
+                            MessageIn::ProtocolVersion(version) => {
+                                self.board_state.protocol_version = version;
                             }
                             _ => continue,
-                        },
-                        MessageIn::ProtocolVersion(_prot_msg) => continue,
-                        _ => continue,
-                    },
+                        }
+                    }
                     Err(e) => return Err(e),
                 },
-                None => continue,
+                None => {
+                    return Err(FirmataError::IoError(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "connection to the board was closed",
+                    )))
+                }
             }
         }
 
-        let mut pin_state = pins.ok_or(FirmataError::WrongType("expected pinstates found none"))?;
-        pin_state.map_analog_pins(
-            analog_pins.ok_or(FirmataError::WrongType("expected analog pins found none"))?,
-        )?;
-        let firmware = firmware.ok_or(FirmataError::WrongType("expected firmware found none"))?;
+        let pin_state = match pins {
+            Some(mut pin_state) => {
+                pin_state.map_analog_channels(
+                    analog_pins
+                        .ok_or(FirmataError::WrongType("expected analog pins found none"))?,
+                )?;
+                Some(pin_state)
+            }
+            None => None,
+        };
+        Ok((firmware, pin_state))
+    }
 
+    fn apply_bootstrap(&mut self, firmware: ReportFirmware, pin_state: PinStates) -> Result<()> {
         let new_state = State {
             pin_state,
             firmware_name: firmware.name,
             firmware_version: firmware.version,
-            protocol_version: String::default(),
+            protocol_version: self.board_state.protocol_version.clone(),
+            stats: self.board_state.stats.clone(),
+            watchdog: self.board_state.watchdog.clone(),
+            stepper_group_moves: self.board_state.stepper_group_moves.clone(),
+            steppers: self.board_state.steppers.clone(),
+            raw_values: BTreeMap::new(),
+            pulses: BTreeMap::new(),
+            sampling_interval: None,
+            i2c_config: None,
+            comparator_levels: BTreeMap::new(),
+            onewire_devices: BTreeMap::new(),
+            output_latches: OutputLatches::default(),
         };
 
         self.board_state = new_state;
@@ -243,3 +1110,84 @@ impl<T: AsyncReadExt + Unpin + Send, U: AsyncWriteExt + Unpin + Send> BoardIo<T,
         Ok(())
     }
 }
+
+impl BoardIo<SyncReader, SyncWriter> {
+    /// Creates a [`BoardIo`] over a blocking reader and writer, see [`sync_adapter::from_sync`].
+    pub fn create_from_sync<R, W>(reader: R, writer: W) -> Self
+    where
+        R: std::io::Read + Send + 'static,
+        W: std::io::Write + Send + 'static,
+    {
+        let (conn_read, conn_write) = sync_adapter::from_sync(reader, writer);
+        Self::create(conn_read, conn_write)
+    }
+}
+
+/// A [`BoardIo`] over any [`Transport`].
+pub type TransportBoardIo = BoardIo<BoxedAsyncRead, BoxedAsyncWrite>;
+
+impl TransportBoardIo {
+    /// Connects over the transport, the board state still has to be generated.
+    /// # Errors
+    /// Returns an error if the transport could not be opened.
+    pub async fn open(transport: &dyn Transport) -> Result<Self> {
+        let (conn_read, conn_write) = transport.open_async().await?;
+        Ok(Self::create(conn_read, conn_write))
+    }
+
+    /// Opens the transport again, regenerates the board state and replays the journal.
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the transport can not be reopened or an
+    /// error if connecting or setting up the board failed.
+    pub async fn reconnect(&mut self, transport: &dyn Transport) -> Result<()> {
+        if !transport.info().reconnectable {
+            return Err(FirmataError::StateError(
+                "the transport can not be reopened",
+            ));
+        }
+        let (conn_read, conn_write) = transport.open_async().await?;
+        self.replace_transport(conn_read, conn_write);
+        self.generate_board_state().await?;
+        self.restore_configuration().await
+    }
+
+    /// Polls the board and reconnects over the transport whenever the connection drops,
+    /// waiting between attempts as the policy says. After a reconnect the board info is
+    /// queried again and the journal replayed, restoring pin modes and reports, while
+    /// every [`Board`] handle and observer stays valid.
+    /// # Errors
+    /// Returns any error that is not caused by the connection going away, or the last
+    /// reconnect error once the policy gives up.
+    pub async fn poll_with_transport(
+        &mut self,
+        transport: &dyn Transport,
+        policy: &ReconnectPolicy,
+    ) -> Result<()> {
+        loop {
+            let error = match self.poll().await {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            if !error.is_disconnect() || !transport.info().reconnectable {
+                return Err(error);
+            }
+            self.publish(BoardEvent::Disconnected);
+            let mut failed = 0;
+            loop {
+                match self.reconnect(transport).await {
+                    Ok(()) => break,
+                    // The board is not back yet, e.g. the port is missing or refuses.
+                    Err(e @ (FirmataError::IoError(_) | FirmataError::NotFoundError(_))) => {
+                        if !policy.allows(failed + 1) {
+                            return Err(e);
+                        }
+                        tokio::time::sleep(policy.delay(failed)).await;
+                        failed += 1;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            self.publish(BoardEvent::Reconnected);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::capture::{Capture, CaptureRequest};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Instant;
+
+#[derive(Debug)]
+struct Recording {
+    request: CaptureRequest,
+    previous: Option<u16>,
+    history: VecDeque<(Instant, u16)>,
+    /// Empty until the trigger fired.
+    samples: Vec<(Instant, u16)>,
+}
+
+impl Recording {
+    fn new(request: CaptureRequest) -> Self {
+        Self {
+            request,
+            previous: None,
+            history: VecDeque::with_capacity(request.pre_trigger),
+            samples: vec![],
+        }
+    }
+}
+
+/// Records the captures armed through the board handles, one per pin.
+#[derive(Debug, Default)]
+pub(crate) struct CaptureRecorder {
+    recordings: BTreeMap<u8, Recording>,
+}
+
+impl CaptureRecorder {
+    /// Adds a sample of a pin with the capture currently armed on it, returns the
+    /// capture once it is complete. A changed request starts the recording over.
+    pub fn push(&mut self, pin: u8, value: u16, armed: Option<CaptureRequest>) -> Option<Capture> {
+        let Some(request) = armed else {
+            self.recordings.remove(&pin);
+            return None;
+        };
+        let recording = self
+            .recordings
+            .entry(pin)
+            .or_insert_with(|| Recording::new(request));
+        if recording.request != request {
+            *recording = Recording::new(request);
+        }
+        let sample = (Instant::now(), value);
+        if recording.samples.is_empty() && !request.trigger.fires(recording.previous, value) {
+            recording.previous = Some(value);
+            if request.pre_trigger > 0 {
+                if recording.history.len() == request.pre_trigger {
+                    recording.history.pop_front();
+                }
+                recording.history.push_back(sample);
+            }
+            return None;
+        }
+        recording.samples.push(sample);
+        if recording.samples.len() < request.samples {
+            return None;
+        }
+        let recording = self.recordings.remove(&pin)?;
+        Some(Capture {
+            pin,
+            request,
+            pre_trigger: recording.history.into(),
+            samples: recording.samples,
+        })
+    }
+}
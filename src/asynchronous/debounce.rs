@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Tracks digital values that changed but have not been stable long enough yet.
+#[derive(Debug, Default)]
+pub(crate) struct Debouncer {
+    pending: BTreeMap<u8, (u16, Instant)>,
+}
+
+impl Debouncer {
+    /// Records a raw value for a pin whose last stable value is `stable`. The raw value
+    /// is only accepted once it has been held for the whole window.
+    pub fn observe(&mut self, pin: u8, raw: u16, stable: u16, window: Duration) {
+        if raw == stable {
+            self.pending.remove(&pin);
+            return;
+        }
+        match self.pending.get(&pin) {
+            Some((value, _)) if *value == raw => {}
+            _ => {
+                self.pending.insert(pin, (raw, Instant::now() + window));
+            }
+        }
+    }
+
+    pub fn forget(&mut self, pin: u8) {
+        self.pending.remove(&pin);
+    }
+
+    /// The next time a pending value matures.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values().map(|(_, deadline)| *deadline).min()
+    }
+
+    /// Removes and returns every value that has been stable for its window.
+    pub fn take_matured(&mut self) -> Vec<(u8, u16)> {
+        let now = Instant::now();
+        let matured: Vec<(u8, u16)> = self
+            .pending
+            .iter()
+            .filter(|(_, (_, deadline))| *deadline <= now)
+            .map(|(pin, (value, _))| (*pin, *value))
+            .collect();
+        for (pin, _) in &matured {
+            self.pending.remove(pin);
+        }
+        matured
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LONG: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn a_value_held_for_the_window_matures() {
+        let mut debouncer = Debouncer::default();
+        debouncer.observe(2, 1, 0, Duration::ZERO);
+        assert_eq!(debouncer.take_matured(), vec![(2, 1)]);
+        assert!(debouncer.take_matured().is_empty());
+        assert_eq!(debouncer.next_deadline(), None);
+    }
+
+    #[test]
+    fn a_value_within_the_window_is_held_back() {
+        let mut debouncer = Debouncer::default();
+        debouncer.observe(2, 1, 0, LONG);
+        assert!(debouncer.take_matured().is_empty());
+        assert!(debouncer.next_deadline().is_some());
+    }
+
+    #[test]
+    fn bouncing_back_to_the_stable_value_cancels_the_change() {
+        let mut debouncer = Debouncer::default();
+        debouncer.observe(2, 1, 0, Duration::ZERO);
+        debouncer.observe(2, 0, 0, Duration::ZERO);
+        assert!(debouncer.take_matured().is_empty());
+        assert_eq!(debouncer.next_deadline(), None);
+    }
+
+    #[test]
+    fn repeating_the_pending_value_keeps_its_deadline() {
+        let mut debouncer = Debouncer::default();
+        debouncer.observe(2, 1, 0, LONG);
+        let deadline = debouncer.next_deadline();
+        debouncer.observe(2, 1, 0, LONG);
+        assert_eq!(debouncer.next_deadline(), deadline);
+        debouncer.forget(2);
+        assert_eq!(debouncer.next_deadline(), None);
+    }
+}
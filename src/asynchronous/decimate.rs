@@ -0,0 +1,105 @@
+use super::events::BoardEvent;
+use crate::{FirmataError, Result};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::Instant;
+
+/// Limits how often a subscriber sees [`BoardEvent::PinChanged`] for each pin. Changes
+/// inside of the interval are merged and delivered once it has passed, so the latest
+/// value always arrives. Every other event is passed through.
+#[derive(Debug, Clone)]
+pub(crate) struct Decimator {
+    interval: Duration,
+    delivered: BTreeMap<u8, Instant>,
+    pending: BTreeMap<u8, (u16, u16)>,
+}
+
+impl Decimator {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            delivered: BTreeMap::new(),
+            pending: BTreeMap::new(),
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the event if it can be delivered now, otherwise holds on to it.
+    fn offer(&mut self, event: BoardEvent) -> Option<BoardEvent> {
+        let BoardEvent::PinChanged { pin, old, new } = event else {
+            return Some(event);
+        };
+        let now = Instant::now();
+        if self
+            .delivered
+            .get(&pin)
+            .is_some_and(|last| now < *last + self.interval)
+        {
+            let first_old = self.pending.get(&pin).map_or(old, |(old, _)| *old);
+            self.pending.insert(pin, (first_old, new));
+            return None;
+        }
+        self.delivered.insert(pin, now);
+        let old = self.pending.remove(&pin).map_or(old, |(old, _)| old);
+        Some(BoardEvent::PinChanged { pin, old, new })
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.pending
+            .keys()
+            .filter_map(|pin| self.delivered.get(pin))
+            .map(|last| *last + self.interval)
+            .min()
+    }
+
+    /// A held change whose interval has passed.
+    fn take_due(&mut self) -> Option<BoardEvent> {
+        let now = Instant::now();
+        let pin = *self.pending.keys().find(|pin| {
+            self.delivered
+                .get(pin)
+                .is_none_or(|last| *last + self.interval <= now)
+        })?;
+        let (old, new) = self.pending.remove(&pin)?;
+        self.delivered.insert(pin, now);
+        Some(BoardEvent::PinChanged { pin, old, new })
+    }
+}
+
+/// Receives the next event for a handle, applying its decimation if it has one.
+pub(crate) async fn next_event(
+    events: &mut broadcast::Receiver<BoardEvent>,
+    decimator: &mut Option<Decimator>,
+) -> Result<BoardEvent> {
+    loop {
+        if let Some(event) = decimator.as_mut().and_then(Decimator::take_due) {
+            return Ok(event);
+        }
+        let deadline = decimator.as_ref().and_then(Decimator::next_deadline);
+        let received = match deadline {
+            Some(deadline) => tokio::select! {
+                received = events.recv() => received,
+                () = tokio::time::sleep_until(deadline) => continue,
+            },
+            None => events.recv().await,
+        };
+        match received {
+            Ok(event) => match decimator {
+                Some(decimator) => {
+                    if let Some(event) = decimator.offer(event) {
+                        return Ok(event);
+                    }
+                }
+                None => return Ok(event),
+            },
+            Err(RecvError::Lagged(missed)) => return Ok(BoardEvent::Lagged(missed)),
+            Err(RecvError::Closed) => {
+                return Err(FirmataError::StateError("the board io task has stopped"))
+            }
+        }
+    }
+}
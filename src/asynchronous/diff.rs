@@ -0,0 +1,134 @@
+use super::boardio::State;
+use crate::i2c::I2cConfig;
+use crate::PinMode;
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+/// A difference between two [`State`] snapshots, see [`State::diff`]. Statistics and
+/// the watchdog status are not compared, they change with every frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateChange {
+    PinValue {
+        pin: u8,
+        old: u16,
+        new: u16,
+    },
+    PinMode {
+        pin: u8,
+        old: PinMode,
+        new: PinMode,
+    },
+    /// The pins differ in number, supported modes or analog mapping, e.g. after a new
+    /// capability response. Values and modes are only compared for pins in both.
+    PinLayout,
+    FirmwareName {
+        old: String,
+        new: String,
+    },
+    FirmwareVersion {
+        old: String,
+        new: String,
+    },
+    ProtocolVersion {
+        old: String,
+        new: String,
+    },
+    SamplingInterval {
+        old: Option<Duration>,
+        new: Option<Duration>,
+    },
+    I2cConfig {
+        old: Option<I2cConfig>,
+        new: Option<I2cConfig>,
+    },
+    /// A comparator level appeared, changed or was dropped.
+    ComparatorLevel {
+        pin: u8,
+        old: Option<bool>,
+        new: Option<bool>,
+    },
+}
+
+impl State {
+    /// Lists what changed from this snapshot to a later one, pins and comparators in
+    /// ascending order followed by the board metadata. Empty if nothing of interest changed.
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> Vec<StateChange> {
+        let mut changes = vec![];
+        let (old_pins, new_pins) = (&self.pin_state, &other.pin_state);
+        if old_pins.pins.len() != new_pins.pins.len()
+            || old_pins.analog_channels != new_pins.analog_channels
+            || old_pins
+                .pins
+                .iter()
+                .zip(&new_pins.pins)
+                .any(|(old, new)| old.modes != new.modes || old.analog != new.analog)
+        {
+            changes.push(StateChange::PinLayout);
+        }
+        for (index, (old, new)) in old_pins.pins.iter().zip(&new_pins.pins).enumerate() {
+            let pin = index as u8;
+            if old.mode != new.mode {
+                changes.push(StateChange::PinMode {
+                    pin,
+                    old: old.mode,
+                    new: new.mode,
+                });
+            }
+            if old.value != new.value {
+                changes.push(StateChange::PinValue {
+                    pin,
+                    old: old.value,
+                    new: new.value,
+                });
+            }
+        }
+        let pins = self
+            .comparator_levels
+            .keys()
+            .chain(other.comparator_levels.keys())
+            .collect::<BTreeSet<_>>();
+        for pin in pins {
+            let old = self.comparator_levels.get(pin).copied();
+            let new = other.comparator_levels.get(pin).copied();
+            if old != new {
+                changes.push(StateChange::ComparatorLevel {
+                    pin: *pin,
+                    old,
+                    new,
+                });
+            }
+        }
+        if self.firmware_name != other.firmware_name {
+            changes.push(StateChange::FirmwareName {
+                old: self.firmware_name.clone(),
+                new: other.firmware_name.clone(),
+            });
+        }
+        if self.firmware_version != other.firmware_version {
+            changes.push(StateChange::FirmwareVersion {
+                old: self.firmware_version.clone(),
+                new: other.firmware_version.clone(),
+            });
+        }
+        if self.protocol_version != other.protocol_version {
+            changes.push(StateChange::ProtocolVersion {
+                old: self.protocol_version.clone(),
+                new: other.protocol_version.clone(),
+            });
+        }
+        if self.sampling_interval != other.sampling_interval {
+            changes.push(StateChange::SamplingInterval {
+                old: self.sampling_interval,
+                new: other.sampling_interval,
+            });
+        }
+        if self.i2c_config != other.i2c_config {
+            changes.push(StateChange::I2cConfig {
+                old: self.i2c_config,
+                new: other.i2c_config,
+            });
+        }
+        changes
+    }
+}
@@ -0,0 +1,103 @@
+use crate::capture::Capture;
+use crate::message::{FrameError, MessageIn, OneWireReply, PinStateResponse, SchedulerReply};
+use crate::I2CReply;
+use std::time::{Duration, Instant};
+
+/// How many events are buffered per [`Board`](super::board::Board) handle before the
+/// oldest ones are dropped and the handle receives [`BoardEvent::Lagged`].
+pub const EVENT_CAPACITY: usize = 256;
+
+/// One sample of every pin of a group from the same sampling interval, see
+/// [`BoardIo::set_sample_group`](super::boardio::BoardIo::set_sample_group).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleFrame {
+    pub group: u8,
+    /// When the first sample of the frame arrived.
+    pub timestamp: Instant,
+    /// Pin index and raw value, in the order the group was configured with.
+    pub values: Vec<(u8, u16)>,
+}
+
+/// Something that happened on the board, published by [`BoardIo`](super::boardio::BoardIo)
+/// to every [`Board`](super::board::Board) handle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoardEvent {
+    /// The value of a pin changed, digital values are only reported after debouncing.
+    PinChanged {
+        pin: u8,
+        old: u16,
+        new: u16,
+    },
+    Firmware {
+        name: String,
+        version: String,
+    },
+    ProtocolVersion(String),
+    /// The capability or analog mapping response arrived and the pins were updated.
+    PinsUpdated,
+    I2cReply(I2CReply),
+    /// The answer to a pin state query, see
+    /// [`Board::query_pin_state`](super::board::Board::query_pin_state).
+    PinState(PinStateResponse),
+    /// An analog pin with a comparator crossed a threshold, see
+    /// [`Board::set_comparator`](super::board::Board::set_comparator).
+    ComparatorEdge {
+        pin: u8,
+        level: bool,
+    },
+    /// Every pin of a sample group reported within one sampling interval.
+    SampleFrame(SampleFrame),
+    /// An armed capture recorded all of its samples, see
+    /// [`Board::arm_capture`](super::board::Board::arm_capture).
+    Capture(Capture),
+    /// A pulse was measured, zero if the firmware timed out.
+    Pulse {
+        pin: u8,
+        duration: Duration,
+    },
+    /// Every stepper of a multi stepper group reached its target.
+    StepperMoveComplete {
+        group: u8,
+    },
+    /// A stepper device reached its target or was stopped.
+    StepperComplete {
+        device: u8,
+        position: i32,
+    },
+    /// The answer to a stepper position request.
+    StepperPosition {
+        device: u8,
+        position: i32,
+    },
+    /// A OneWire search result or the bytes read by a request.
+    OneWire(OneWireReply),
+    /// The stored tasks, a single task or a failed task reported by the scheduler.
+    Scheduler(SchedulerReply),
+    /// Bytes received by a serial port of the board, see [`crate::uart`].
+    SerialData {
+        port: u8,
+        data: Vec<u8>,
+    },
+    /// A sysex message the crate does not understand, only sent in lenient mode.
+    UnknownSysex {
+        command: u8,
+        payload: Vec<u8>,
+    },
+    /// Text sent by the firmware, one event per STRING_DATA message.
+    StringData(String),
+    /// The decoder discarded corrupted bytes to get back in sync.
+    FrameError(FrameError),
+    /// A frame as received, before interceptors, only published while raw frame
+    /// retention is enabled with [`BoardIo::set_retain_raw`](super::boardio::BoardIo::set_retain_raw).
+    RawFrame {
+        raw: Vec<u8>,
+        message: MessageIn,
+    },
+    /// This handle fell behind and the given number of events were dropped, state
+    /// derived from events may have to be refreshed from the board state.
+    Lagged(u64),
+    /// The connection went away and a reconnecting poll loop is waiting for it.
+    Disconnected,
+    /// The connection is back, the board state was regenerated and the journal replayed.
+    Reconnected,
+}
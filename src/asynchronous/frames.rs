@@ -0,0 +1,65 @@
+use super::events::SampleFrame;
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+#[derive(Debug, Default)]
+struct Group {
+    pins: Vec<u8>,
+    pending: BTreeMap<u8, u16>,
+    started: Option<Instant>,
+}
+
+/// Collects the samples of grouped analog pins until every pin of a group reported once,
+/// the firmware sends all channels of a sampling interval back to back.
+#[derive(Debug, Default)]
+pub(crate) struct FrameCollector {
+    groups: BTreeMap<u8, Group>,
+}
+
+impl FrameCollector {
+    pub fn set(&mut self, group: u8, pins: Vec<u8>) {
+        if pins.is_empty() {
+            self.groups.remove(&group);
+        } else {
+            self.groups.insert(
+                group,
+                Group {
+                    pins,
+                    ..Group::default()
+                },
+            );
+        }
+    }
+
+    /// Adds a sample, returns the frames it completed. A pin reporting twice before its
+    /// group is complete means a sample went missing, the incomplete frame is dropped.
+    pub fn push(&mut self, pin: u8, value: u16) -> Vec<SampleFrame> {
+        let mut frames = vec![];
+        for (id, group) in &mut self.groups {
+            if !group.pins.contains(&pin) {
+                continue;
+            }
+            if group.pending.contains_key(&pin) {
+                group.pending.clear();
+                group.started = None;
+            }
+            let started = *group.started.get_or_insert_with(Instant::now);
+            group.pending.insert(pin, value);
+            if group.pending.len() == group.pins.len() {
+                let values = group
+                    .pins
+                    .iter()
+                    .map(|pin| (*pin, group.pending[pin]))
+                    .collect();
+                group.pending.clear();
+                group.started = None;
+                frames.push(SampleFrame {
+                    group: *id,
+                    timestamp: started,
+                    values,
+                });
+            }
+        }
+        frames
+    }
+}
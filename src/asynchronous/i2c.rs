@@ -0,0 +1,64 @@
+use super::board::Board;
+use crate::i2c::{I2cConfig, I2cReadOptions, I2cRetryPolicy};
+use crate::{I2CReply, Result};
+use std::time::Duration;
+
+/// I2C access to a board that reported I2C support, created by [`Board::i2c`].
+#[derive(Debug, Clone)]
+pub struct I2c {
+    board: Board,
+}
+
+impl I2c {
+    pub(crate) fn new(board: Board) -> Self {
+        Self { board }
+    }
+
+    /// See [`Board::i2c_config`].
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub async fn config(&mut self, config: I2cConfig) -> Result<()> {
+        self.board.i2c_config(config).await
+    }
+
+    /// See [`Board::i2c_read_with`].
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub async fn read(&mut self, address: u8, options: I2cReadOptions) -> Result<()> {
+        self.board.i2c_read_with(address, options).await
+    }
+
+    /// See [`Board::i2c_write`].
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub async fn write(&mut self, address: u8, data: &[u8]) -> Result<()> {
+        self.board.i2c_write(address, data).await
+    }
+
+    /// See [`Board::i2c_read_await`].
+    /// # Errors
+    /// Returns [`crate::FirmataError::I2cTimeout`] if no reply arrived in time.
+    pub async fn read_register(
+        &mut self,
+        address: u8,
+        register: u16,
+        size: u16,
+        timeout: Duration,
+    ) -> Result<I2CReply> {
+        self.board
+            .i2c_read_await(address, register, size, timeout)
+            .await
+    }
+
+    /// See [`Board::i2c_read_reply`].
+    /// # Errors
+    /// Returns [`crate::FirmataError::I2cTimeout`] if every attempt timed out.
+    pub async fn read_reply(
+        &mut self,
+        address: u8,
+        options: I2cReadOptions,
+        policy: I2cRetryPolicy,
+    ) -> Result<I2CReply> {
+        self.board.i2c_read_reply(address, options, policy).await
+    }
+}
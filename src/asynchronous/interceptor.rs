@@ -0,0 +1,50 @@
+use super::boardio::MessageOut;
+use crate::message::MessageIn;
+
+/// Sees every message passing through a [`BoardIo`](super::boardio::BoardIo) and may
+/// change or drop it. Inbound messages are intercepted before they update the state and
+/// outbound messages before they are journaled and written. Returning `None` drops the
+/// message, the default implementations pass everything through.
+pub trait Interceptor: Send {
+    fn inbound(&mut self, message: MessageIn) -> Option<MessageIn> {
+        Some(message)
+    }
+
+    fn outbound(&mut self, message: MessageOut) -> Option<MessageOut> {
+        Some(message)
+    }
+}
+
+/// Interceptors in the order they were added, each one sees the output of the previous.
+#[derive(Default)]
+pub(crate) struct Interceptors {
+    chain: Vec<Box<dyn Interceptor>>,
+}
+
+impl std::fmt::Debug for Interceptors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Interceptors")
+            .field("len", &self.chain.len())
+            .finish()
+    }
+}
+
+impl Interceptors {
+    pub fn push(&mut self, interceptor: Box<dyn Interceptor>) {
+        self.chain.push(interceptor);
+    }
+
+    pub fn inbound(&mut self, message: MessageIn) -> Option<MessageIn> {
+        self.chain
+            .iter_mut()
+            .try_fold(message, |message, interceptor| interceptor.inbound(message))
+    }
+
+    pub fn outbound(&mut self, message: MessageOut) -> Option<MessageOut> {
+        self.chain
+            .iter_mut()
+            .try_fold(message, |message, interceptor| {
+                interceptor.outbound(message)
+            })
+    }
+}
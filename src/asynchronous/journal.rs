@@ -0,0 +1,65 @@
+//! Conversions between the async [`MessageOut`] and the [`JournalEntry`] it records.
+use super::boardio::MessageOut;
+use crate::journal::JournalEntry;
+
+impl JournalEntry {
+    /// Returns the entry for a configuration message, `None` for every other message.
+    #[must_use]
+    pub fn from_message(message: &MessageOut) -> Option<Self> {
+        Some(match message {
+            MessageOut::PinMode(pin, mode) => Self::PinMode(*pin, *mode),
+            MessageOut::ReportAnalog(channel, enable) => Self::ReportAnalog(*channel, *enable),
+            MessageOut::ReportDigital(port, enable) => Self::ReportDigital(*port, *enable),
+            MessageOut::SampleingInterval(interval) => Self::SamplingInterval(*interval),
+            MessageOut::I2cConfig(config) => Self::I2cConfig(*config),
+            MessageOut::PwmFrequency(pin, hz) => Self::PwmFrequency(*pin, *hz),
+            MessageOut::StepperConfig(config) => Self::StepperConfig(*config),
+            MessageOut::MultiStepperConfig(group, devices) => {
+                Self::MultiStepperConfig(*group, devices.clone())
+            }
+            MessageOut::StepperSpeed(device, speed) => Self::StepperSpeed(*device, *speed),
+            MessageOut::StepperAcceleration(device, acceleration) => {
+                Self::StepperAcceleration(*device, *acceleration)
+            }
+            MessageOut::ServoConfig(pin, min, max) => Self::ServoConfig(*pin, *min, *max),
+            MessageOut::OneWireConfig(pin, power) => Self::OneWireConfig(*pin, *power),
+            _ => return None,
+        })
+    }
+
+    /// Like [`JournalEntry::from_message`], with a message configuring several pins
+    /// split into one entry per pin.
+    #[must_use]
+    pub fn entries_from_message(message: &MessageOut) -> Vec<Self> {
+        match message {
+            MessageOut::PinModeGroup(modes) => modes
+                .iter()
+                .map(|(pin, mode)| Self::PinMode(*pin, *mode))
+                .collect(),
+            message => Self::from_message(message).into_iter().collect(),
+        }
+    }
+}
+
+impl From<JournalEntry> for MessageOut {
+    fn from(entry: JournalEntry) -> Self {
+        match entry {
+            JournalEntry::PinMode(pin, mode) => Self::PinMode(pin, mode),
+            JournalEntry::ReportAnalog(channel, enable) => Self::ReportAnalog(channel, enable),
+            JournalEntry::ReportDigital(port, enable) => Self::ReportDigital(port, enable),
+            JournalEntry::SamplingInterval(interval) => Self::SampleingInterval(interval),
+            JournalEntry::I2cConfig(config) => Self::I2cConfig(config),
+            JournalEntry::PwmFrequency(pin, hz) => Self::PwmFrequency(pin, hz),
+            JournalEntry::StepperConfig(config) => Self::StepperConfig(config),
+            JournalEntry::MultiStepperConfig(group, devices) => {
+                Self::MultiStepperConfig(group, devices)
+            }
+            JournalEntry::StepperSpeed(device, speed) => Self::StepperSpeed(device, speed),
+            JournalEntry::StepperAcceleration(device, acceleration) => {
+                Self::StepperAcceleration(device, acceleration)
+            }
+            JournalEntry::ServoConfig(pin, min, max) => Self::ServoConfig(pin, min, max),
+            JournalEntry::OneWireConfig(pin, power) => Self::OneWireConfig(pin, power),
+        }
+    }
+}
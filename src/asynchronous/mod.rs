@@ -1,4 +1,29 @@
+pub mod blocking;
 pub mod board;
 pub mod boardio;
+mod capture;
+mod debounce;
+mod decimate;
+pub mod diff;
+pub mod events;
+mod frames;
+pub mod i2c;
+pub mod interceptor;
+mod journal;
 pub mod network;
-mod parser;
+pub mod observer;
+mod oversample;
+pub(crate) mod parser;
+pub mod pins;
+pub mod pwm;
+pub mod scene;
+pub mod scheduler;
+pub mod state_machine;
+pub mod stdio;
+pub mod supervisor;
+pub mod sync_adapter;
+pub mod text;
+pub mod uart;
+#[cfg(unix)]
+pub mod unix;
+pub mod watchdog;
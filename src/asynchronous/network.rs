@@ -1,27 +1,125 @@
-use crate::protocol_constants::{
-    is_id, ANALOG_MAPPING_QUERY, ANALOG_MESSAGE, ANALOG_MESSAGE_END, CAPABILITY_QUERY,
-    DIGITAL_MESSAGE, DIGITAL_MESSAGE_END, DIGITAL_PIN_WRITE, END_SYSEX, I2C_CONFIG, I2C_MODE_READ,
-    I2C_MODE_WRITE, I2C_REQUEST, PIN_MODE, PROTOCOL_VERSION, REPORT_ANALOG, REPORT_DIGITAL,
-    REPORT_FIRMWARE, SAMPLEING_INTERVAL, START_SYSEX, STRING_DATA,
-};
+use crate::protocol::{MidiCommand, SysexCommand, END_SYSEX, START_SYSEX};
 
 use super::boardio::MessageOut;
 use super::parser::parse_data;
-use crate::message::MessageIn;
+use crate::message::{get_header_type, FrameError, MessageIn};
+use crate::recovery::RecoveryPolicy;
+use crate::{analog, digital, i2c, onewire, pulse, pwm, sampling, servo, stepper, text};
 use crate::{FirmataError, Result};
-use byteorder::{LittleEndian, WriteBytesExt};
 use bytes::BytesMut;
 use tokio_util::codec::{Decoder, Encoder};
 
-const BUFFER_SIZE: usize = 1000;
+/// The longest frame the codec accepts unless configured otherwise, enough for the
+/// capability response of a board with several hundred pins.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 8192;
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
-pub struct FirmataCodec(());
+/// Where the decoder is within the frame at the start of the buffer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+enum DecodeState {
+    /// Looking for the command byte of the next frame.
+    #[default]
+    Header,
+    /// Waiting for the two data bytes of a three byte message.
+    Fixed,
+    /// Collecting a sysex message, the bytes before `scanned` are known to be data.
+    Sysex { scanned: usize },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FirmataCodec {
+    lenient: bool,
+    retain_raw: bool,
+    raw: Option<Vec<u8>>,
+    recovery: RecoveryPolicy,
+    max_frame_size: usize,
+    state: DecodeState,
+}
+
+impl Default for FirmataCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl FirmataCodec {
     #[must_use]
     pub const fn new() -> Self {
-        Self(())
+        Self {
+            lenient: false,
+            retain_raw: false,
+            raw: None,
+            recovery: RecoveryPolicy::SkipFrame,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            state: DecodeState::Header,
+        }
+    }
+
+    /// A codec that decodes unknown sysex commands to [`MessageIn::UnknownSysex`]
+    /// instead of failing.
+    #[must_use]
+    pub const fn lenient() -> Self {
+        Self {
+            lenient: true,
+            retain_raw: false,
+            raw: None,
+            recovery: RecoveryPolicy::SkipFrame,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            state: DecodeState::Header,
+        }
+    }
+
+    #[must_use]
+    pub const fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Keeps the bytes of the last decoded frame for [`FirmataCodec::take_raw`].
+    pub fn set_retain_raw(&mut self, retain: bool) {
+        self.retain_raw = retain;
+        self.raw = None;
+    }
+
+    #[must_use]
+    pub const fn retains_raw(&self) -> bool {
+        self.retain_raw
+    }
+
+    /// Sets how corrupted frames are handled, see [`RecoveryPolicy`].
+    pub fn set_recovery(&mut self, recovery: RecoveryPolicy) {
+        self.recovery = recovery;
+    }
+
+    #[must_use]
+    pub const fn recovery(&self) -> RecoveryPolicy {
+        self.recovery
+    }
+
+    /// Sets the longest frame the codec buffers, longer sysex messages fail the decode
+    /// with [`FirmataError::ParseError`] instead of being buffered without bound.
+    pub fn set_max_frame_size(&mut self, size: usize) {
+        self.max_frame_size = size;
+    }
+
+    #[must_use]
+    pub const fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    /// Forgets a partially received frame, e.g. when the connection is replaced and the
+    /// buffered bytes are gone.
+    pub fn reset(&mut self) {
+        self.state = DecodeState::Header;
+        self.raw = None;
+    }
+
+    /// The bytes the last decoded message came from, or the discarded bytes of a
+    /// [`MessageIn::FrameError`].
+    pub fn take_raw(&mut self) -> Option<Vec<u8>> {
+        self.raw.take()
     }
 }
 
@@ -31,141 +129,306 @@ impl Encoder<MessageOut> for FirmataCodec {
     fn encode(&mut self, item: MessageOut, dst: &mut BytesMut) -> Result<()> {
         match item {
             MessageOut::AnalogMappingQuery => {
-                dst.extend_from_slice(&[START_SYSEX, ANALOG_MAPPING_QUERY, END_SYSEX]);
+                dst.extend_from_slice(&SysexCommand::AnalogMappingQuery.frame(&[]));
             }
             MessageOut::CapabilityQuery => {
-                dst.extend_from_slice(&[START_SYSEX, CAPABILITY_QUERY, END_SYSEX]);
+                dst.extend_from_slice(&SysexCommand::CapabilityQuery.frame(&[]));
             }
             MessageOut::ReportFirmware => {
-                dst.extend_from_slice(&[START_SYSEX, REPORT_FIRMWARE, END_SYSEX]);
+                dst.extend_from_slice(&SysexCommand::ReportFirmware.frame(&[]));
+            }
+            MessageOut::I2cConfig(config) => {
+                dst.extend_from_slice(&config.encode());
             }
-            MessageOut::I2cConfig(delay) => {
-                let bytes_out = delay.to_le_bytes();
+            MessageOut::I2cRead(addr, options) => {
+                dst.extend_from_slice(&i2c::encode_read(addr, &options));
+            }
+            MessageOut::I2cWrite(addr, data) => {
+                dst.extend_from_slice(&i2c::encode_write(addr, &data));
+            }
+            MessageOut::ReportDigital(pin, enable) => {
                 dst.extend_from_slice(&[
-                    START_SYSEX,
-                    I2C_CONFIG,
-                    bytes_out[0],
-                    bytes_out[1],
-                    END_SYSEX,
+                    MidiCommand::ReportDigital.with_channel(pin),
+                    enable as u8,
                 ]);
             }
-            MessageOut::I2cRead(addr, size) => {
-                let bytes_out = size.to_le_bytes();
+            MessageOut::ReportAnalog(channel, enable) => {
                 dst.extend_from_slice(&[
-                    START_SYSEX,
-                    I2C_REQUEST,
-                    addr,
-                    (I2C_MODE_READ << 3),
-                    bytes_out[0],
-                    bytes_out[1],
-                    END_SYSEX,
+                    MidiCommand::ReportAnalog.with_channel(channel),
+                    enable as u8,
                 ]);
             }
-            // This method is not fully implemented and requires data to be added after the write.
-            MessageOut::I2cWrite(addr, _data) => dst.extend_from_slice(&[
-                START_SYSEX,
-                I2C_REQUEST,
-                addr,
-                I2C_MODE_WRITE << 3,
-                END_SYSEX,
-            ]),
-            MessageOut::ReportDigital(pin, enable) => {
-                dst.extend_from_slice(&[REPORT_DIGITAL | pin, enable as u8]);
-            }
-            MessageOut::ReportAnalog(pin, enable) => {
-                dst.extend_from_slice(&[REPORT_ANALOG | (pin + 1), enable as u8]);
-            }
             MessageOut::AnalogWrite(pin, output) => {
-                let bytes_out = output.to_le_bytes();
-                dst.extend_from_slice(&[ANALOG_MESSAGE | pin, bytes_out[0], bytes_out[1]]);
+                dst.extend_from_slice(&analog::encode_write(pin, output)?);
             }
-            MessageOut::DigitalWrite(port, output) => {
-                dst.extend_from_slice(&[DIGITAL_PIN_WRITE, port, output as u8]);
+            MessageOut::AnalogWriteGroup(writes) => {
+                for (pin, output) in writes {
+                    dst.extend_from_slice(&analog::encode_write(pin, output)?);
+                }
+            }
+            MessageOut::DigitalWrite(pin, output) => {
+                dst.extend_from_slice(&digital::encode_pin_write(pin, output));
             }
             MessageOut::StringWrite(string_out) => {
-                let mut buf: Vec<u8> = vec![START_SYSEX, STRING_DATA];
-                for x in string_out.as_bytes().iter() {
-                    let double_byte: u16 = (*x).into();
-                    buf.write_u16::<LittleEndian>(double_byte)?;
+                dst.extend_from_slice(&text::encode(&string_out));
+            }
+            MessageOut::PinMode(pin, mode) => {
+                dst.extend_from_slice(&[MidiCommand::SetPinMode.to_u8(), pin, mode.to_u8()])
+            }
+            MessageOut::PinModeGroup(modes) => {
+                for (pin, mode) in modes {
+                    dst.extend_from_slice(&[MidiCommand::SetPinMode.to_u8(), pin, mode.to_u8()]);
                 }
-                buf.push(END_SYSEX);
-                dst.extend_from_slice(buf.as_slice());
             }
-            MessageOut::PinMode(pin, mode) => dst.extend_from_slice(&[PIN_MODE, pin, mode.to_u8()]),
+            MessageOut::PinStateQuery(pin) => {
+                dst.extend_from_slice(&SysexCommand::PinStateQuery.frame(&[pin]));
+            }
             MessageOut::SampleingInterval(duration) => {
-                let dur_in_ms: u16 = duration.as_millis() as u16;
-                let bytes = dur_in_ms.to_le_bytes();
-                dst.extend_from_slice(&[
-                    START_SYSEX,
-                    SAMPLEING_INTERVAL,
-                    bytes[0],
-                    bytes[1],
-                    END_SYSEX,
-                ]);
+                dst.extend_from_slice(&sampling::encode_interval(duration));
+            }
+            MessageOut::StepperConfig(config) => {
+                dst.extend_from_slice(&stepper::encode_config(&config));
+            }
+            MessageOut::MultiStepperConfig(group, devices) => {
+                dst.extend_from_slice(&stepper::encode_multi_config(group, &devices));
+            }
+            MessageOut::StepperZero(device) => {
+                dst.extend_from_slice(&stepper::encode_zero(device));
+            }
+            MessageOut::StepperStep(device, steps) => {
+                dst.extend_from_slice(&stepper::encode_step(device, steps));
+            }
+            MessageOut::StepperTo(device, position) => {
+                dst.extend_from_slice(&stepper::encode_to(device, position));
+            }
+            MessageOut::StepperEnable(device, enable) => {
+                dst.extend_from_slice(&stepper::encode_enable(device, enable));
+            }
+            MessageOut::StepperStop(device) => dst.extend_from_slice(&stepper::encode_stop(device)),
+            MessageOut::StepperReportPosition(device) => {
+                dst.extend_from_slice(&stepper::encode_report_position(device));
+            }
+            MessageOut::StepperSpeed(device, speed) => {
+                dst.extend_from_slice(&stepper::encode_speed(device, speed));
+            }
+            MessageOut::StepperAcceleration(device, acceleration) => {
+                dst.extend_from_slice(&stepper::encode_acceleration(device, acceleration));
+            }
+            MessageOut::MultiStepperTo(group, positions) => {
+                dst.extend_from_slice(&stepper::encode_multi_to(group, &positions));
+            }
+            MessageOut::MultiStepperStop(group) => {
+                dst.extend_from_slice(&stepper::encode_multi_stop(group));
             }
+            MessageOut::PulseIn(request) => {
+                dst.extend_from_slice(&pulse::encode_request(&request));
+            }
+            MessageOut::SystemReset => dst.extend_from_slice(&[MidiCommand::SystemReset.to_u8()]),
+            MessageOut::AssumePins(_) => {}
+            MessageOut::PwmFrequency(pin, hz) => {
+                dst.extend_from_slice(&pwm::encode_frequency(pin, hz));
+            }
+            MessageOut::ServoConfig(pin, min, max) => {
+                dst.extend_from_slice(&servo::encode_config(pin, min, max));
+            }
+            MessageOut::OneWireConfig(pin, power) => {
+                dst.extend_from_slice(&onewire::encode_config(pin, power));
+            }
+            MessageOut::OneWireSearch(pin, alarms) => {
+                dst.extend_from_slice(&onewire::encode_search(pin, alarms));
+            }
+            MessageOut::OneWireRequest(pin, request) => {
+                dst.extend_from_slice(&onewire::encode_request(pin, &request));
+            }
+            MessageOut::ProtocolVersionQuery => {
+                dst.extend_from_slice(&[MidiCommand::ProtocolVersion.to_u8()])
+            }
+            MessageOut::Sysex(command, payload) => {
+                dst.extend_from_slice(&[START_SYSEX, command]);
+                dst.extend_from_slice(&payload);
+                dst.extend_from_slice(&[END_SYSEX]);
+            }
+            MessageOut::QueryAll => {
+                // The firmware answers in order, so the firmware reply arrives last.
+                dst.extend_from_slice(&[MidiCommand::ProtocolVersion.to_u8()]);
+                dst.extend_from_slice(&SysexCommand::CapabilityQuery.frame(&[]));
+                dst.extend_from_slice(&SysexCommand::AnalogMappingQuery.frame(&[]));
+                dst.extend_from_slice(&SysexCommand::ReportFirmware.frame(&[]));
+            }
+            MessageOut::DigitalPortWrite(port, mask, value) => {
+                dst.extend_from_slice(&digital::encode_port_write(port, value & mask));
+            }
+            MessageOut::Scheduler(request) => dst.extend_from_slice(&request.encode()),
+            MessageOut::Serial(request) => dst.extend_from_slice(&request.encode()),
         }
         Ok(())
     }
 }
 
-fn remove_section(buffer: &mut BytesMut, start_index: usize, end_index: usize) -> BytesMut {
-    let mut new_buffer = buffer.split_off(start_index);
-    buffer.unsplit(new_buffer.split_off(end_index + 1));
-    new_buffer
+/// Bytes with the high bit set start a command, data bytes are always 7 bit.
+const fn is_command(byte: u8) -> bool {
+    byte & 0x80 != 0
+}
+
+fn frame_error(reason: &str, discarded: &[u8]) -> MessageIn {
+    MessageIn::FrameError(FrameError {
+        reason: reason.to_string(),
+        discarded: discarded.to_vec(),
+    })
+}
+
+/// The outcome of looking for a frame at the start of the buffer.
+enum Next {
+    Frame(BytesMut),
+    Corrupted(MessageIn),
+    Pending,
+}
+
+impl FirmataCodec {
+    /// Splits the next complete frame off the buffer. Corrupted input is split off up to
+    /// the next plausible header and returned as [`Next::Corrupted`]. Bytes already
+    /// looked at are not scanned again when more arrive.
+    /// # Errors
+    /// Returns [`FirmataError::ParseError`] with the buffered bytes if a frame grows
+    /// beyond the maximum frame size.
+    fn next_frame(&mut self, src: &mut BytesMut) -> Result<Next> {
+        loop {
+            match self.state {
+                DecodeState::Header => match src.iter().position(|x| get_header_type(*x).is_ok()) {
+                    None if src.is_empty() => return Ok(Next::Pending),
+                    None => {
+                        let discarded = src.split();
+                        return Ok(Next::Corrupted(frame_error(
+                            "no message header found",
+                            &discarded,
+                        )));
+                    }
+                    Some(v) if v > 0 => {
+                        let discarded = src.split_to(v);
+                        return Ok(Next::Corrupted(frame_error(
+                            "discarded bytes before a message header",
+                            &discarded,
+                        )));
+                    }
+                    Some(_) if src[0] == START_SYSEX => {
+                        self.state = DecodeState::Sysex { scanned: 1 };
+                    }
+                    Some(_) => self.state = DecodeState::Fixed,
+                },
+                DecodeState::Fixed => {
+                    if let Some(v) = src.iter().take(3).skip(1).position(|x| is_command(*x)) {
+                        self.state = DecodeState::Header;
+                        let discarded = src.split_to(v + 1);
+                        return Ok(Next::Corrupted(frame_error(
+                            "message interrupted by another header",
+                            &discarded,
+                        )));
+                    }
+                    if src.len() < 3 {
+                        return Ok(Next::Pending);
+                    }
+                    self.state = DecodeState::Header;
+                    return Ok(Next::Frame(src.split_to(3)));
+                }
+                DecodeState::Sysex { scanned } => {
+                    let scanned = scanned.min(src.len());
+                    let end = src[scanned..]
+                        .iter()
+                        .position(|x| is_command(*x))
+                        .map(|v| v + scanned);
+                    if end.unwrap_or(src.len()) + 1 > self.max_frame_size {
+                        self.state = DecodeState::Header;
+                        let discarded = match end {
+                            Some(end) if src[end] == END_SYSEX => src.split_to(end + 1),
+                            Some(end) => src.split_to(end),
+                            None => src.split(),
+                        };
+                        return Err(FirmataError::ParseError(
+                            "system message exceeded the maximum frame size",
+                            discarded.to_vec(),
+                        ));
+                    }
+                    match end {
+                        // A sysex without a command byte.
+                        Some(1) if src[1] == END_SYSEX => {
+                            self.state = DecodeState::Header;
+                            let discarded = src.split_to(2);
+                            return Ok(Next::Corrupted(frame_error(
+                                "system message without a command",
+                                &discarded,
+                            )));
+                        }
+                        Some(end) if src[end] == END_SYSEX => {
+                            self.state = DecodeState::Header;
+                            return Ok(Next::Frame(src.split_to(end + 1)));
+                        }
+                        Some(end) => {
+                            self.state = DecodeState::Header;
+                            let discarded = src.split_to(end);
+                            return Ok(Next::Corrupted(frame_error(
+                                "system message interrupted by another header",
+                                &discarded,
+                            )));
+                        }
+                        None => {
+                            self.state = DecodeState::Sysex { scanned: src.len() };
+                            return Ok(Next::Pending);
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl Decoder for FirmataCodec {
     type Item = MessageIn;
     type Error = FirmataError;
 
+    /// Decodes the next message. Corrupted frames are handled according to the
+    /// [`RecoveryPolicy`], unless it is [`RecoveryPolicy::Bail`] they do not fail the
+    /// stream but are reported as [`MessageIn::FrameError`] so the link can be monitored.
+    /// A sysex message longer than the maximum frame size fails the stream with
+    /// [`FirmataError::ParseError`] under every policy. A complete frame that does not
+    /// parse, e.g. an unknown sysex command, fails the stream too unless the codec is
+    /// lenient, then a sysex becomes [`MessageIn::UnknownSysex`].
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>> {
-        let start = src.iter().position(|x| {
-            *x == START_SYSEX
-                || is_id(*x, DIGITAL_MESSAGE..DIGITAL_MESSAGE_END)
-                || is_id(*x, ANALOG_MESSAGE..ANALOG_MESSAGE_END)
-                || *x == PROTOCOL_VERSION
-        });
-
-        // Tries to get an entire message from the buffer
-        let bytes: Option<BytesMut> = match start {
-            Some(v) => {
-                if src[v] == START_SYSEX {
-                    let end = src.iter().position(|y| *y == END_SYSEX);
-                    end.map(|end_val| remove_section(src, v, end_val))
-                } else if is_id(src[v], ANALOG_MESSAGE..ANALOG_MESSAGE_END)
-                    || is_id(src[v], DIGITAL_MESSAGE..DIGITAL_MESSAGE_END)
-                    || src[v] == PROTOCOL_VERSION
-                {
-                    if src.len() > (v + 2) {
-                        Some(remove_section(src, v, v + 2))
-                    } else {
-                        // this should grow because we are missing two bytes
-                        None
+        let mut error = match self.next_frame(src)? {
+            Next::Frame(mut data) => {
+                let message = match parse_data(&mut data, self.lenient) {
+                    Ok(message) => message,
+                    // A well framed sysex with a payload the crate can not parse.
+                    Err(_) if self.lenient && data.len() > 2 && data[0] == START_SYSEX => {
+                        MessageIn::UnknownSysex {
+                            command: data[1],
+                            payload: data[2..data.len() - 1].to_vec(),
+                        }
                     }
-                } else {
-                    None
+                    Err(e) => return Err(e),
+                };
+                if self.retain_raw {
+                    self.raw = Some(data.to_vec());
                 }
+                return Ok(Some(message));
             }
-            None => None,
+            Next::Corrupted(error) => error,
+            Next::Pending => return Ok(None),
         };
-
-        if src.len() > (BUFFER_SIZE as f64 * 0.7) as usize {
-            if let Some(value) = src.iter().position(|x| {
-                *x == START_SYSEX
-                    || *x == ANALOG_MESSAGE
-                    || *x == DIGITAL_MESSAGE
-                    || *x == PROTOCOL_VERSION
-            }) {
-                if value > (BUFFER_SIZE as f64 * 0.3) as usize {
-                    let _x = src.split_to(value);
-                }
+        if let MessageIn::FrameError(v) = &mut error {
+            if self.recovery == RecoveryPolicy::ResetDecoder {
+                v.discarded.extend_from_slice(&src.split());
+            }
+            if self.retain_raw {
+                self.raw = Some(v.discarded.clone());
+            }
+            if self.recovery == RecoveryPolicy::Bail {
+                return Err(FirmataError::ParseError(
+                    "corrupted frame",
+                    std::mem::take(&mut v.discarded),
+                ));
             }
         }
-
-        match bytes {
-            Some(mut data) => Ok(Some(parse_data(&mut data)?)),
-            None => Ok(None),
-        }
+        Ok(Some(error))
     }
 
     fn framed<T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Sized>(
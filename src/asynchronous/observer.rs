@@ -0,0 +1,350 @@
+use super::board::{self, Shared};
+use super::boardio::State;
+use super::decimate::{self, Decimator};
+use super::events::BoardEvent;
+use super::watchdog::WatchdogStatus;
+use crate::board_info::BoardInfo;
+use crate::calibration::CalibrationTable;
+use crate::capability_report::CapabilityReport;
+use crate::event_log::LogEntry;
+use crate::features::FeatureSet;
+use crate::journal::Journal;
+use crate::reservation::ReservationRegistry;
+use crate::session::Session;
+use crate::{FirmataError, Pin, PinId, Result, Stats};
+use futures::{Stream, StreamExt};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::watch;
+
+/// A read-only handle to a board, created by [`super::board::Board::observer`] or
+/// [`super::boardio::BoardIo::get_observer`]. It sees the same state and events as a
+/// [`super::board::Board`] but can not send anything, so it can be handed to monitoring
+/// code without the risk of it actuating outputs.
+#[derive(Debug)]
+pub struct BoardObserver {
+    state: watch::Receiver<State>,
+    shared: Arc<Shared>,
+    events: broadcast::Receiver<BoardEvent>,
+    decimator: Option<Decimator>,
+}
+
+impl Clone for BoardObserver {
+    /// The clone only receives events published after it was created.
+    fn clone(&self) -> Self {
+        let mut observer = Self::new(
+            self.state.clone(),
+            self.shared.clone(),
+            self.events.resubscribe(),
+        );
+        observer.set_report_interval(self.report_interval());
+        observer
+    }
+}
+
+impl BoardObserver {
+    pub(crate) fn new(
+        state: watch::Receiver<State>,
+        shared: Arc<Shared>,
+        events: broadcast::Receiver<BoardEvent>,
+    ) -> Self {
+        Self {
+            state,
+            shared,
+            events,
+            decimator: None,
+        }
+    }
+
+    /// A snapshot of the whole board state.
+    pub fn state(&self) -> State {
+        self.state.borrow().clone()
+    }
+
+    /// Every state published from now on, the stream ends once the board io has stopped.
+    pub fn states(&self) -> impl Stream<Item = State> {
+        futures::stream::unfold(self.state.clone(), |mut state| async move {
+            state.changed().await.ok()?;
+            let current = state.borrow_and_update().clone();
+            Some((current, state))
+        })
+    }
+
+    /// See [`super::board::Board::next_event`].
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] once the [`super::boardio::BoardIo`] is gone.
+    pub async fn next_event(&mut self) -> Result<BoardEvent> {
+        decimate::next_event(&mut self.events, &mut self.decimator).await
+    }
+
+    /// Limits [`BoardEvent::PinChanged`] events for this handle to one per pin and
+    /// interval, e.g. 100 ms for a display refreshing at 10 Hz. Changes in between are
+    /// merged, other handles and the firmware sampling interval are not affected.
+    /// `None` delivers every change.
+    pub fn set_report_interval(&mut self, interval: Option<Duration>) {
+        self.decimator = interval.map(Decimator::new);
+    }
+
+    pub fn report_interval(&self) -> Option<Duration> {
+        self.decimator.as_ref().map(Decimator::interval)
+    }
+
+    /// The events published from now on, the stream ends once the board io has stopped.
+    pub fn events(self) -> impl Stream<Item = BoardEvent> {
+        futures::stream::unfold(self, |mut observer| async move {
+            let event = observer.next_event().await.ok()?;
+            Some((event, observer))
+        })
+    }
+
+    /// The STRING_DATA text the firmware sends from now on, e.g. debug output, one item
+    /// per message. The stream ends once the board io has stopped.
+    pub fn strings(self) -> impl Stream<Item = String> {
+        self.events().filter_map(|event| async move {
+            match event {
+                BoardEvent::StringData(text) => Some(text),
+                _ => None,
+            }
+        })
+    }
+
+    /// See [`super::board::Board::wait_for`].
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the board io stopped first.
+    pub async fn wait_for<F: FnMut(&State) -> bool>(&self, predicate: F) -> Result<State> {
+        let mut state = self.state.clone();
+        let matched = state
+            .wait_for(predicate)
+            .await
+            .map_err(|_| FirmataError::StateError("board io has stopped"))?;
+        Ok(matched.clone())
+    }
+
+    /// See [`super::board::Board::wait_for_timeout`].
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the predicate was not satisfied in time or
+    /// [`FirmataError::StateError`] if the board io stopped first.
+    pub async fn wait_for_timeout<F: FnMut(&State) -> bool>(
+        &self,
+        predicate: F,
+        timeout: Duration,
+    ) -> Result<State> {
+        tokio::time::timeout(timeout, self.wait_for(predicate))
+            .await
+            .map_err(|_| FirmataError::Timeout(format!("{}", timeout.as_millis())))?
+    }
+
+    /// The pin index a pin id resolves to, as used in [`BoardEvent::PinChanged`].
+    pub fn pin_index(&self, pin: PinId) -> u8 {
+        self.state.borrow().pin_state.pin_id_to_u8(pin)
+    }
+
+    pub fn pins(&self) -> Vec<Pin> {
+        self.state.borrow().pin_state.pins.clone()
+    }
+
+    /// Returns the pin an id resolves to through the analog mapping.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn pin(&self, pin: PinId) -> Result<Pin> {
+        self.state.borrow().pin_state.get(pin).cloned()
+    }
+
+    /// Returns the current value of a pin.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn try_value(&self, pin: PinId) -> Result<u16> {
+        self.state.borrow().pin_state.get(pin).map(|v| v.value)
+    }
+
+    /// Returns the last reported value of a pin, ignoring debouncing.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the pin does not exist.
+    pub fn raw_pin_value(&self, pin: PinId) -> Result<u16> {
+        let pin_id = self.pin_index(pin);
+        match self.state.borrow().raw_values.get(&pin_id) {
+            Some(value) => Ok(*value),
+            None => self.try_value(pin),
+        }
+    }
+
+    pub fn calibration_table(&self) -> CalibrationTable {
+        self.shared
+            .calibration
+            .read()
+            .map(|table| table.clone())
+            .unwrap_or_default()
+    }
+
+    /// See [`super::board::Board::read_calibrated`].
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the pin does not exist.
+    pub fn read_calibrated(&self, pin: PinId) -> Result<f64> {
+        let physical = self.pin(pin)?;
+        Ok(self
+            .calibration_table()
+            .convert(self.pin_index(pin), &physical))
+    }
+
+    /// See [`super::board::Board::read_engineering`].
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the pin does not exist.
+    pub fn read_engineering(&self, pin: PinId) -> Result<f64> {
+        let physical = self.pin(pin)?;
+        let pin_id = self.pin_index(pin);
+        let converted = self
+            .shared
+            .transforms
+            .read()
+            .ok()
+            .and_then(|table| table.convert(pin_id, &physical));
+        match converted {
+            Some(value) => Ok(value),
+            None => Ok(self.calibration_table().convert(pin_id, &physical)),
+        }
+    }
+
+    pub fn label(&self, pin: PinId) -> Option<String> {
+        let pin = self.pin_index(pin);
+        self.shared
+            .labels
+            .read()
+            .ok()
+            .and_then(|labels| labels.get(&pin).cloned())
+    }
+
+    /// See [`super::board::Board::event_log_entries`].
+    /// # Errors
+    /// Returns an error if the storage of the log could not be read.
+    pub fn event_log_entries(&self, window: Duration) -> Result<Vec<LogEntry>> {
+        match self.shared.event_log.read().as_deref() {
+            Ok(Some(log)) => log.last(window),
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// See [`super::board::Board::subscribe_analog`].
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn subscribe_analog(&self, pin: PinId) -> Result<impl Stream<Item = u16>> {
+        let current = self.pin(pin)?.value;
+        let pin = self.pin_index(pin);
+        Ok(board::pin_changes(self.shared.watch_pin(pin, current)))
+    }
+
+    /// See [`super::board::Board::subscribe_digital`].
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn subscribe_digital(&self, pin: PinId) -> Result<impl Stream<Item = bool>> {
+        Ok(self.subscribe_analog(pin)?.map(|value| value != 0))
+    }
+
+    /// See [`super::board::Board::comparator_level`].
+    pub fn comparator_level(&self, pin: PinId) -> Option<bool> {
+        let pin = self.pin_index(pin);
+        self.state.borrow().comparator_levels.get(&pin).copied()
+    }
+
+    /// Whether a pin is claimed by a [`super::board::Board`] handle.
+    pub fn is_claimed(&self, pin: PinId) -> bool {
+        let pin = self.pin_index(pin);
+        self.shared
+            .claims
+            .read()
+            .is_ok_and(|claims| claims.contains_key(&pin))
+    }
+
+    /// Every reservation made through any handle of the board.
+    pub fn reservations(&self) -> ReservationRegistry {
+        self.shared
+            .reservations
+            .read()
+            .map(|registry| registry.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn protocol_version(&self) -> String {
+        self.state.borrow().protocol_version.clone()
+    }
+
+    pub fn firmware_name(&self) -> String {
+        self.state.borrow().firmware_name.clone()
+    }
+
+    pub fn firmware_version(&self) -> String {
+        self.state.borrow().firmware_version.clone()
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.state.borrow().stats.clone()
+    }
+
+    pub fn watchdog_status(&self) -> WatchdogStatus {
+        self.state.borrow().watchdog.clone()
+    }
+
+    /// The firmware, protocol version and size of the board in one snapshot.
+    pub fn info(&self) -> BoardInfo {
+        let state = self.state.borrow();
+        BoardInfo::new(
+            &state.firmware_name,
+            &state.firmware_version,
+            &state.protocol_version,
+            &state.pin_state,
+        )
+    }
+
+    /// A summary of every pin for display, see [`CapabilityReport`].
+    pub fn capability_report(&self) -> CapabilityReport {
+        let labels = self
+            .shared
+            .labels
+            .read()
+            .map(|labels| labels.clone())
+            .unwrap_or_default();
+        let state = self.state.borrow();
+        CapabilityReport::new(
+            &state.firmware_name,
+            &state.firmware_version,
+            &state.protocol_version,
+            &state.pin_state,
+            &labels,
+        )
+    }
+
+    /// The optional features detected from the capabilities and protocol version.
+    pub fn features(&self) -> FeatureSet {
+        let state = self.state.borrow();
+        FeatureSet::detect(&state.pin_state, &state.protocol_version)
+    }
+
+    /// The configuration commands issued through every handle of the board, in order.
+    pub fn journal(&self) -> Journal {
+        self.shared
+            .journal
+            .read()
+            .map(|journal| journal.clone())
+            .unwrap_or_default()
+    }
+
+    /// The configuration, labels and calibration of the board.
+    pub fn session(&self) -> Session {
+        Session {
+            journal: self.journal(),
+            labels: self
+                .shared
+                .labels
+                .read()
+                .map(|labels| labels.clone())
+                .unwrap_or_default(),
+            calibration: self.calibration_table(),
+        }
+    }
+}
+
+// Observers are handed to monitoring tasks, like board handles.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync + Clone>() {}
+    assert_send_sync::<BoardObserver>();
+};
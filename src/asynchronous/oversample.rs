@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+
+#[derive(Debug, Default)]
+struct Accumulator {
+    samples: u32,
+    sum: u32,
+    count: u32,
+}
+
+/// Averages every `samples` raw readings of a pin into a single value.
+#[derive(Debug, Default)]
+pub(crate) struct Oversampler {
+    pins: BTreeMap<u8, Accumulator>,
+}
+
+impl Oversampler {
+    pub fn set(&mut self, pin: u8, samples: u32) {
+        if samples > 1 {
+            self.pins.insert(
+                pin,
+                Accumulator {
+                    samples,
+                    ..Accumulator::default()
+                },
+            );
+        } else {
+            self.pins.remove(&pin);
+        }
+    }
+
+    /// Adds a reading, returns the average once enough readings were collected and the
+    /// reading itself for pins without oversampling.
+    pub fn push(&mut self, pin: u8, value: u16) -> Option<u16> {
+        let Some(accumulator) = self.pins.get_mut(&pin) else {
+            return Some(value);
+        };
+        accumulator.sum += u32::from(value);
+        accumulator.count += 1;
+        if accumulator.count < accumulator.samples {
+            return None;
+        }
+        let average = accumulator.sum / accumulator.count;
+        accumulator.sum = 0;
+        accumulator.count = 0;
+        Some(average as u16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_average_is_returned_on_the_nth_sample() {
+        let mut oversampler = Oversampler::default();
+        oversampler.set(14, 4);
+        assert_eq!(oversampler.push(14, 100), None);
+        assert_eq!(oversampler.push(14, 200), None);
+        assert_eq!(oversampler.push(14, 300), None);
+        assert_eq!(oversampler.push(14, 401), Some(250));
+        // The next window starts empty.
+        assert_eq!(oversampler.push(14, 10), None);
+    }
+
+    #[test]
+    fn pins_without_oversampling_pass_through() {
+        let mut oversampler = Oversampler::default();
+        oversampler.set(14, 4);
+        oversampler.set(15, 1);
+        assert_eq!(oversampler.push(15, 7), Some(7));
+        assert_eq!(oversampler.push(16, 8), Some(8));
+        oversampler.set(14, 0);
+        assert_eq!(oversampler.push(14, 9), Some(9));
+    }
+
+    #[test]
+    fn full_scale_readings_do_not_overflow() {
+        let mut oversampler = Oversampler::default();
+        oversampler.set(14, 64);
+        let averages: Vec<u16> = (0..64)
+            .filter_map(|_| oversampler.push(14, 0x3FFF))
+            .collect();
+        assert_eq!(averages, [0x3FFF]);
+    }
+}
@@ -1,52 +1,101 @@
-use byteorder::{ByteOrder, LittleEndian};
 use bytes::BytesMut;
 
 use crate::message::{
-    get_header_type, Analog, AnalogMappingResponse, CapabilityResponse, Digital, Header, I2cReply,
-    MessageIn, ReportFirmware, System,
-};
-use crate::protocol_constants::{
-    ANALOG_MAPPING_RESPONSE, CAPABILITY_RESPONSE, I2C_MODE_READ, REPORT_FIRMWARE,
+    decode_u14, get_header_type, Analog, AnalogMappingResponse, CapabilityResponse, Digital,
+    Header, I2cReply, MessageIn, OneWireReply, PinStateResponse, PulseReply, ReportFirmware,
+    SchedulerReply, StepperReply, StringData, System,
 };
+use crate::protocol::SysexCommand;
+use crate::uart;
 use crate::{FirmataError, PinId, Result};
 
-fn parse_system_message(buf: &[u8]) -> Result<System> {
-    match buf[0] {
-        ANALOG_MAPPING_RESPONSE => {
+fn parse_system_message(buf: &[u8], lenient: bool) -> Result<MessageIn> {
+    let command = *buf.first().ok_or(FirmataError::ParseError(
+        "system message without a command",
+        vec![],
+    ))?;
+    let system = match SysexCommand::try_from(command) {
+        Ok(SysexCommand::AnalogMappingResponse) => {
             let message_out = AnalogMappingResponse::deserialize(&buf[1..]);
-            Ok(System::AnalogMappingResponse(message_out))
+            System::AnalogMappingResponse(message_out)
         }
 
-        CAPABILITY_RESPONSE => {
+        Ok(SysexCommand::CapabilityResponse) => {
             let message_out = CapabilityResponse::deserialize(&buf[1..])?;
-            Ok(System::CapabilityResponseMessage(message_out))
+            System::CapabilityResponseMessage(message_out)
         }
-        I2C_MODE_READ => {
-            let message_out = I2cReply::deserialize(&buf[1..]);
-            Ok(System::I2cReplyMessage(message_out))
+        Ok(SysexCommand::I2cReply) => {
+            let message_out = I2cReply::deserialize(&buf[1..])?;
+            System::I2cReplyMessage(message_out)
         }
-        REPORT_FIRMWARE => {
+        Ok(SysexCommand::ReportFirmware) => {
             let message_out = ReportFirmware::deserialize(&buf[1..])?;
-            Ok(System::ReportFirmwareMessage(message_out))
+            System::ReportFirmwareMessage(message_out)
         }
-        _ => Err(FirmataError::ParseError(
-            "did not find an expected system message",
-            buf.to_vec(),
-        )),
-    }
+        Ok(SysexCommand::AccelStepperData) => {
+            let message_out = StepperReply::deserialize(&buf[1..])?;
+            System::StepperReplyMessage(message_out)
+        }
+        Ok(SysexCommand::PingRead) => {
+            let message_out = PulseReply::deserialize(&buf[1..])?;
+            System::PulseReplyMessage(message_out)
+        }
+        Ok(SysexCommand::StringData) => {
+            System::StringDataMessage(StringData::deserialize(&buf[1..]))
+        }
+        Ok(SysexCommand::PinStateResponse) => {
+            System::PinStateResponseMessage(PinStateResponse::deserialize(&buf[1..])?)
+        }
+        Ok(SysexCommand::OnewireData) => {
+            System::OneWireReplyMessage(OneWireReply::deserialize(&buf[1..])?)
+        }
+        Ok(SysexCommand::ExtendedAnalog) => {
+            return Ok(MessageIn::Analog(Analog::deserialize_extended(&buf[1..])?))
+        }
+        Ok(SysexCommand::SchedulerData) => {
+            System::SchedulerReplyMessage(SchedulerReply::deserialize(&buf[1..])?)
+        }
+        Ok(SysexCommand::SerialData) => {
+            let (port, data) = uart::decode_reply(&buf[1..])?;
+            System::SerialReply { port, data }
+        }
+        _ if lenient => {
+            return Ok(MessageIn::UnknownSysex {
+                command,
+                payload: buf[1..].to_vec(),
+            })
+        }
+        _ => {
+            return Err(FirmataError::ParseError(
+                "did not find an expected system message",
+                buf.to_vec(),
+            ))
+        }
+    };
+    Ok(MessageIn::System(system))
 }
 
-pub fn parse_data(buf: &mut BytesMut) -> Result<MessageIn> {
-    let header = get_header_type(buf[0])?;
+/// Parses a single frame, with `lenient` unknown sysex commands become
+/// [`MessageIn::UnknownSysex`] instead of an error.
+pub fn parse_data(buf: &mut BytesMut, lenient: bool) -> Result<MessageIn> {
+    let first = *buf
+        .first()
+        .ok_or(FirmataError::ParseError("empty frame", vec![]))?;
+    let header = get_header_type(first)?;
+    let min_len = match header {
+        Header::System => 2,
+        _ => 3,
+    };
+    if buf.len() < min_len {
+        return Err(FirmataError::ParseError("truncated frame", buf.to_vec()));
+    }
     match header {
         // Prune the sysex messages out and pass in for deserialization
-        Header::System => Ok(MessageIn::System(parse_system_message(
-            &buf[1..buf.len() - 1],
-        )?)),
+        Header::System => parse_system_message(&buf[1..buf.len() - 1], lenient),
         Header::AnalogMessage => {
-            let value: u16 = LittleEndian::read_u16(&buf[1..3]);
-            // Analog message can only do a range between 0..15, if you need to address
-            // greater then 15 you need to use ANALOG_EXTENDED.
+            let value = decode_u14(buf[1], buf[2]);
+            // Analog message can only do a range between 0..15, channels above are
+            // reported with EXTENDED_ANALOG.
             let pin = buf[0] & 0x0F;
             let analog_message = Analog {
                 pin: PinId::Analog(pin),
@@ -56,12 +105,12 @@ pub fn parse_data(buf: &mut BytesMut) -> Result<MessageIn> {
         }
         Header::DigitalMessage => {
             let port = buf[0] & 0x0F;
-            let value: u16 = LittleEndian::read_u16(&buf[1..3]);
+            let value = decode_u14(buf[1], buf[2]);
             let digital_message = Digital { port, value };
             Ok(MessageIn::Digital(digital_message))
         }
         Header::ProtocolVersion => {
-            let protocol_version = format!("{:o}.{:o}", buf[0], buf[1]);
+            let protocol_version = format!("{}.{}", buf[1], buf[2]);
             Ok(MessageIn::ProtocolVersion(protocol_version))
         }
     }
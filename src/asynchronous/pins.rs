@@ -0,0 +1,253 @@
+//! Handles to a single pin in one mode, created by [`Board::into_output`] and its
+//! siblings. A handle sets the mode when it is created and only offers what the mode
+//! allows, so e.g. writing a level to an analog input does not compile.
+use super::board::Board;
+use super::pwm::{PwmOutput, RampTask};
+use crate::pwm::RampCurve;
+use crate::{FirmataError, PinId, PinMode, Result};
+use futures::Stream;
+use std::time::Duration;
+
+/// Checks the capabilities of the pin and switches it to the mode.
+pub(crate) async fn configure(board: &mut Board, pin: PinId, mode: PinMode) -> Result<()> {
+    let physical = board.pin(pin)?;
+    if physical.modes.is_empty() {
+        return Err(FirmataError::UninitializedError(
+            "pin capabilities have not been queried",
+        ));
+    }
+    if physical.resolution(mode).is_none() {
+        return Err(FirmataError::WrongType("pin does not support the mode"));
+    }
+    board.set_pin_mode(pin, mode).await
+}
+
+/// A digital output, created by [`Board::into_output`].
+#[derive(Debug, Clone)]
+pub struct OutputPin {
+    board: Board,
+    pin: PinId,
+}
+
+impl OutputPin {
+    pub(crate) const fn new(board: Board, pin: PinId) -> Self {
+        Self { board, pin }
+    }
+
+    pub const fn pin(&self) -> PinId {
+        self.pin
+    }
+
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub async fn set(&mut self, high: bool) -> Result<()> {
+        self.board.digital_write(self.pin, high).await
+    }
+
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub async fn set_high(&mut self) -> Result<()> {
+        self.set(true).await
+    }
+
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub async fn set_low(&mut self) -> Result<()> {
+        self.set(false).await
+    }
+
+    /// Inverts the last written level.
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub async fn toggle(&mut self) -> Result<()> {
+        let high = self.is_set_high()?;
+        self.set(!high).await
+    }
+
+    /// The last written level.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn is_set_high(&self) -> Result<bool> {
+        Ok(self.board.try_value(self.pin)? != 0)
+    }
+}
+
+/// A digital input, created by [`Board::into_input`] or [`Board::into_input_pullup`].
+#[derive(Debug, Clone)]
+pub struct InputPin {
+    board: Board,
+    pin: PinId,
+}
+
+impl InputPin {
+    pub(crate) const fn new(board: Board, pin: PinId) -> Self {
+        Self { board, pin }
+    }
+
+    pub const fn pin(&self) -> PinId {
+        self.pin
+    }
+
+    /// The last reported level, after debouncing. The port of the pin has to be
+    /// reported, see [`Board::report_digital_pin`].
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn is_high(&self) -> Result<bool> {
+        Ok(self.board.try_value(self.pin)? != 0)
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn is_low(&self) -> Result<bool> {
+        Ok(!self.is_high()?)
+    }
+
+    /// See [`Board::subscribe_digital`].
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn changes(&self) -> Result<impl Stream<Item = bool>> {
+        self.board.subscribe_digital(self.pin)
+    }
+}
+
+/// An analog input, created by [`Board::into_analog`].
+#[derive(Debug, Clone)]
+pub struct AnalogPin {
+    board: Board,
+    pin: PinId,
+}
+
+impl AnalogPin {
+    pub(crate) const fn new(board: Board, pin: PinId) -> Self {
+        Self { board, pin }
+    }
+
+    pub const fn pin(&self) -> PinId {
+        self.pin
+    }
+
+    /// The last reported value. The pin has to be reported, see
+    /// [`Board::report_analog`].
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn value(&self) -> Result<u16> {
+        self.board.try_value(self.pin)
+    }
+
+    /// See [`Board::read_calibrated`].
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn read_calibrated(&self) -> Result<f64> {
+        self.board.read_calibrated(self.pin)
+    }
+
+    /// See [`Board::subscribe_analog`].
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn changes(&self) -> Result<impl Stream<Item = u16>> {
+        self.board.subscribe_analog(self.pin)
+    }
+}
+
+/// A PWM output, created by [`Board::into_pwm`].
+#[derive(Debug, Clone)]
+pub struct PwmPin {
+    output: PwmOutput,
+    board: Board,
+    max_value: u16,
+}
+
+impl PwmPin {
+    pub(crate) fn new(board: Board, pin: PinId) -> Result<Self> {
+        Ok(Self {
+            output: board.pwm_output(pin)?,
+            max_value: board.pin(pin)?.max_pwm_value()?,
+            board,
+        })
+    }
+
+    pub fn pin(&self) -> PinId {
+        self.output.pin()
+    }
+
+    /// The value of a duty cycle of 1.0.
+    pub const fn max_value(&self) -> u16 {
+        self.max_value
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the value exceeds the resolution of the
+    /// pin or an error if the message could not be sent.
+    pub async fn set_value(&mut self, value: u16) -> Result<()> {
+        if value > self.max_value {
+            return Err(FirmataError::OutOfRange(
+                "value exceeds the pwm resolution of the pin",
+            ));
+        }
+        self.board.analog_write(self.pin(), value).await
+    }
+
+    /// See [`PwmOutput::set_duty`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the fraction is outside of 0.0 to 1.0 or
+    /// an error if the message could not be sent.
+    pub async fn set_duty(&mut self, fraction: f64) -> Result<()> {
+        self.output.set_duty(fraction).await
+    }
+
+    /// See [`PwmOutput::set_frequency`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the frequency can not be sent or an
+    /// error if the message could not be sent.
+    pub async fn set_frequency(&mut self, hz: u32) -> Result<()> {
+        self.output.set_frequency(hz).await
+    }
+
+    /// See [`PwmOutput::ramp`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if a fraction is outside of 0.0 to 1.0.
+    pub fn ramp(
+        &self,
+        from: f64,
+        to: f64,
+        duration: Duration,
+        curve: RampCurve,
+    ) -> Result<RampTask> {
+        self.output.ramp(from, to, duration, curve)
+    }
+}
+
+/// A servo output, created by [`Board::into_servo`].
+#[derive(Debug, Clone)]
+pub struct ServoPin {
+    board: Board,
+    pin: PinId,
+}
+
+impl ServoPin {
+    pub(crate) const fn new(board: Board, pin: PinId) -> Self {
+        Self { board, pin }
+    }
+
+    pub const fn pin(&self) -> PinId {
+        self.pin
+    }
+
+    /// Turns the servo to an angle in degrees, values from 544 up are taken as pulse
+    /// widths in microseconds by StandardFirmata.
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub async fn write(&mut self, angle: u16) -> Result<()> {
+        self.board.analog_write(self.pin, angle).await
+    }
+
+    /// See [`Board::servo_config`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the pulse range is invalid or an error if
+    /// the message could not be sent.
+    pub async fn set_pulse_range(&mut self, min_pulse: u16, max_pulse: u16) -> Result<()> {
+        self.board
+            .servo_config(self.pin, min_pulse, max_pulse)
+            .await
+    }
+}
@@ -0,0 +1,120 @@
+use super::board::Board;
+use crate::pwm::{Ramp, RampCurve, MIN_RAMP_STEP};
+use crate::{PinId, Result};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// A PWM capable pin of a board, created by [`Board::pwm_output`].
+#[derive(Debug, Clone)]
+pub struct PwmOutput {
+    board: Board,
+    pin: PinId,
+}
+
+impl PwmOutput {
+    pub(crate) fn new(board: Board, pin: PinId) -> Self {
+        Self { board, pin }
+    }
+
+    pub fn pin(&self) -> PinId {
+        self.pin
+    }
+
+    /// See [`Board::set_duty_fraction`].
+    /// # Errors
+    /// Returns [`crate::FirmataError::OutOfRange`] if the fraction is outside of 0.0 to
+    /// 1.0 or an error if the message could not be sent.
+    pub async fn set_duty(&mut self, fraction: f64) -> Result<()> {
+        self.board.set_duty_fraction(self.pin, fraction).await
+    }
+
+    /// See [`Board::set_pwm_frequency`].
+    /// # Errors
+    /// Returns [`crate::FirmataError::OutOfRange`] if the frequency can not be sent or
+    /// an error if the message could not be sent.
+    pub async fn set_frequency(&mut self, hz: u32) -> Result<()> {
+        self.board.set_pwm_frequency(self.pin, hz).await
+    }
+
+    /// Moves the duty cycle from one fraction to another over the duration in a
+    /// background task. The steps are spaced by [`MIN_RAMP_STEP`] or the average round
+    /// trip of the link if that is slower, and never finer than the PWM resolution.
+    /// # Errors
+    /// Returns [`crate::FirmataError::OutOfRange`] if a fraction is outside of 0.0 to
+    /// 1.0 or [`crate::FirmataError::WrongType`] if the pin does not support PWM.
+    pub fn ramp(
+        &self,
+        from: f64,
+        to: f64,
+        duration: Duration,
+        curve: RampCurve,
+    ) -> Result<RampTask> {
+        let pin = self.board.pin(self.pin)?;
+        let ramp = Ramp::new(
+            pin.duty_to_value(from)?,
+            pin.duty_to_value(to)?,
+            duration,
+            curve,
+        );
+        let interval = self
+            .board
+            .stats()
+            .average_latency
+            .map_or(MIN_RAMP_STEP, |latency| latency.max(MIN_RAMP_STEP));
+        Ok(RampTask::spawn(
+            self.board.clone(),
+            self.pin,
+            ramp,
+            interval,
+        ))
+    }
+}
+
+/// A running ramp, dropping the handle cancels the ramp and leaves the output at the
+/// last written value.
+#[derive(Debug)]
+#[must_use = "dropping the handle cancels the ramp"]
+pub struct RampTask {
+    task: JoinHandle<Result<()>>,
+}
+
+impl RampTask {
+    fn spawn(mut board: Board, pin: PinId, ramp: Ramp, interval: Duration) -> Self {
+        let task = tokio::spawn(async move {
+            let start = tokio::time::Instant::now();
+            for (at, value) in ramp.steps(interval) {
+                tokio::time::sleep_until(start + at).await;
+                board.analog_write(pin, value).await?;
+            }
+            Ok(())
+        });
+        Self { task }
+    }
+
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+
+    /// Returns true if the ramp is done, cancelled or stopped by a failed write.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Waits for the ramp to reach its end value.
+    /// # Errors
+    /// Returns an error if a write failed or [`crate::FirmataError::IoError`] if the
+    /// ramp was cancelled.
+    pub async fn finish(mut self) -> Result<()> {
+        match (&mut self.task).await {
+            Ok(result) => result,
+            Err(e) => Err(std::io::Error::other(e).into()),
+        }
+    }
+}
+
+impl Drop for RampTask {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
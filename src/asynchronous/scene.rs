@@ -0,0 +1,88 @@
+//! Named sequences of pin actions, stored on the board handles and run by name.
+use super::board::Board;
+use super::scheduler::Action;
+use crate::{FirmataError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// One step of a [`Scene`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Action(Action),
+    Wait(Duration),
+}
+
+/// Steps run one after another.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scene {
+    pub steps: Vec<Step>,
+}
+
+impl Scene {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn then(mut self, action: Action) -> Self {
+        self.steps.push(Step::Action(action));
+        self
+    }
+
+    #[must_use]
+    pub fn wait(mut self, duration: Duration) -> Self {
+        self.steps.push(Step::Wait(duration));
+        self
+    }
+
+    /// Runs every step in order.
+    /// # Errors
+    /// Returns an error if a write could not be sent, later steps are not run.
+    pub async fn run(&self, board: &mut Board) -> Result<()> {
+        for step in &self.steps {
+            match step {
+                Step::Action(action) => action.run(board).await?,
+                Step::Wait(duration) => tokio::time::sleep(*duration).await,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Scenes keyed by name.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SceneBook {
+    pub scenes: BTreeMap<String, Scene>,
+}
+
+impl SceneBook {
+    /// # Errors
+    /// Returns [`FirmataError::ConversionFailure`] if the scenes could not be serialized.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|_| FirmataError::ConversionFailure("failed to serialize scenes"))
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::ConversionFailure`] if the json does not hold valid scenes.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|_| FirmataError::ConversionFailure("failed to deserialize scenes"))
+    }
+
+    /// # Errors
+    /// Returns an error if the file could not be written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// # Errors
+    /// Returns an error if the file could not be read or parsed.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
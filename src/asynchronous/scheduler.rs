@@ -0,0 +1,148 @@
+//! Time based actions run by background tasks on a [`Board`] handle.
+use super::board::Board;
+use crate::{FirmataError, PinId, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// What a scheduled task does each time it fires.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    DigitalWrite(PinId, bool),
+    AnalogWrite(PinId, u16),
+    /// Writes the level, waits for the duration and writes the opposite level.
+    Pulse {
+        pin: PinId,
+        level: bool,
+        duration: Duration,
+    },
+}
+
+impl Action {
+    /// Performs the action once.
+    /// # Errors
+    /// Returns an error if a write could not be sent.
+    pub async fn run(&self, board: &mut Board) -> Result<()> {
+        match *self {
+            Self::DigitalWrite(pin, level) => board.digital_write(pin, level).await,
+            Self::AnalogWrite(pin, value) => board.analog_write(pin, value).await,
+            Self::Pulse {
+                pin,
+                level,
+                duration,
+            } => {
+                board.digital_write(pin, level).await?;
+                tokio::time::sleep(duration).await;
+                board.digital_write(pin, !level).await
+            }
+        }
+    }
+}
+
+/// A time of day in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeOfDay {
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+impl TimeOfDay {
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if a field is outside of a day.
+    pub fn new(hour: u8, minute: u8, second: u8) -> Result<Self> {
+        if hour > 23 || minute > 59 || second > 59 {
+            return Err(FirmataError::OutOfRange("time of day is outside of a day"));
+        }
+        Ok(Self {
+            hour,
+            minute,
+            second,
+        })
+    }
+
+    fn since_midnight(self) -> Duration {
+        Duration::from_secs(
+            u64::from(self.hour) * 3600 + u64::from(self.minute) * 60 + u64::from(self.second),
+        )
+    }
+
+    /// Time left until the next occurrence, a full day if it is right now.
+    fn until_next(self) -> Duration {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let today = Duration::from_nanos((now.as_nanos() % DAY.as_nanos()) as u64);
+        let target = self.since_midnight();
+        if target > today {
+            target - today
+        } else {
+            DAY - (today - target)
+        }
+    }
+}
+
+/// When a scheduled task fires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    /// Once after the delay.
+    After(Duration),
+    /// Repeatedly, the first time after one period.
+    Every(Duration),
+    /// Every day at the time.
+    Daily(TimeOfDay),
+}
+
+/// A running scheduled task, dropping the handle cancels the task.
+#[derive(Debug)]
+pub struct ScheduledTask {
+    task: JoinHandle<Result<()>>,
+}
+
+impl ScheduledTask {
+    pub(crate) fn spawn(board: Board, schedule: Schedule, action: Action) -> Self {
+        Self {
+            task: tokio::spawn(run(board, schedule, action)),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+
+    /// Returns true if the task is done, cancelled or stopped by a failed write.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl Drop for ScheduledTask {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn run(mut board: Board, schedule: Schedule, action: Action) -> Result<()> {
+    match schedule {
+        Schedule::After(delay) => {
+            tokio::time::sleep(delay).await;
+            action.run(&mut board).await
+        }
+        Schedule::Every(period) => {
+            let mut interval =
+                tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                action.run(&mut board).await?;
+            }
+        }
+        Schedule::Daily(time) => loop {
+            tokio::time::sleep(time.until_next()).await;
+            action.run(&mut board).await?;
+        },
+    }
+}
@@ -0,0 +1,171 @@
+//! Declarative state machines driven by pin events and timers, e.g. for a garage door
+//! or a traffic light.
+use super::board::Board;
+use super::events::BoardEvent;
+use super::scheduler::Action;
+use crate::{PinId, Result};
+use std::fmt::Debug;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// What causes a transition out of a state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    PinHigh(PinId),
+    PinLow(PinId),
+    PinChanged(PinId),
+    /// The machine has been in the state for the duration.
+    After(Duration),
+}
+
+#[derive(Debug, Clone)]
+struct Transition<S> {
+    from: S,
+    trigger: Trigger,
+    to: S,
+}
+
+/// Builds a [`StateMachine`], transitions are checked in the order they were added.
+#[derive(Debug, Clone)]
+pub struct StateMachineBuilder<S> {
+    initial: S,
+    entry_actions: Vec<(S, Action)>,
+    transitions: Vec<Transition<S>>,
+}
+
+impl<S: Clone + PartialEq + Debug + Send + Sync + 'static> StateMachineBuilder<S> {
+    #[must_use]
+    pub fn new(initial: S) -> Self {
+        Self {
+            initial,
+            entry_actions: vec![],
+            transitions: vec![],
+        }
+    }
+
+    /// Adds an action run every time the state is entered, including the initial state.
+    #[must_use]
+    pub fn on_entry(mut self, state: S, action: Action) -> Self {
+        self.entry_actions.push((state, action));
+        self
+    }
+
+    #[must_use]
+    pub fn transition(mut self, from: S, trigger: Trigger, to: S) -> Self {
+        self.transitions.push(Transition { from, trigger, to });
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> StateMachine<S> {
+        StateMachine { definition: self }
+    }
+}
+
+/// A state machine ready to run on a board, see [`StateMachineBuilder`].
+#[derive(Debug, Clone)]
+pub struct StateMachine<S> {
+    definition: StateMachineBuilder<S>,
+}
+
+impl<S: Clone + PartialEq + Debug + Send + Sync + 'static> StateMachine<S> {
+    /// Enters the initial state and keeps following transitions in a background task.
+    #[must_use]
+    pub fn start(self, board: Board) -> StateMachineHandle<S> {
+        let (state_tx, state) = watch::channel(self.definition.initial.clone());
+        let task = tokio::spawn(run(board, self.definition, state_tx));
+        StateMachineHandle { state, task }
+    }
+}
+
+/// A running state machine, dropping the handle stops it.
+#[derive(Debug)]
+pub struct StateMachineHandle<S> {
+    state: watch::Receiver<S>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl<S: Clone> StateMachineHandle<S> {
+    /// The state the machine is in.
+    #[must_use]
+    pub fn current(&self) -> S {
+        self.state.borrow().clone()
+    }
+
+    /// A receiver that is notified of every state change.
+    #[must_use]
+    pub fn subscribe(&self) -> watch::Receiver<S> {
+        self.state.clone()
+    }
+
+    pub fn stop(&self) {
+        self.task.abort();
+    }
+
+    /// Returns true if the machine stopped, e.g. because the board went away.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl<S> Drop for StateMachineHandle<S> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Whether a pin change fires a trigger, pins are compared by index.
+fn fires(trigger: Trigger, board: &Board, pin: u8, new: u16) -> bool {
+    let matches = |id: PinId| board.pin_index(id) == pin;
+    match trigger {
+        Trigger::PinHigh(id) => matches(id) && new != 0,
+        Trigger::PinLow(id) => matches(id) && new == 0,
+        Trigger::PinChanged(id) => matches(id),
+        Trigger::After(_) => false,
+    }
+}
+
+async fn run<S: Clone + PartialEq + Debug + Send + Sync>(
+    mut board: Board,
+    definition: StateMachineBuilder<S>,
+    state_tx: watch::Sender<S>,
+) -> Result<()> {
+    let mut state = definition.initial.clone();
+    loop {
+        for (_, action) in definition.entry_actions.iter().filter(|v| v.0 == state) {
+            action.run(&mut board).await?;
+        }
+        let entered = Instant::now();
+        let timer = definition
+            .transitions
+            .iter()
+            .filter(|v| v.from == state)
+            .find_map(|v| match v.trigger {
+                Trigger::After(duration) => Some((entered + duration, v.to.clone())),
+                _ => None,
+            });
+        let next = loop {
+            tokio::select! {
+                event = board.next_event() => {
+                    if let BoardEvent::PinChanged { pin, new, .. } = event? {
+                        let found = definition.transitions.iter().find(|v| {
+                            v.from == state && fires(v.trigger, &board, pin, new)
+                        });
+                        if let Some(transition) = found {
+                            break transition.to.clone();
+                        }
+                    }
+                }
+                _ = tokio::time::sleep_until(timer.as_ref().map_or(entered, |v| v.0)),
+                    if timer.is_some() => {
+                    break timer.as_ref().map_or(state.clone(), |v| v.1.clone());
+                }
+            }
+        };
+        state = next;
+        state_tx.send_replace(state.clone());
+    }
+}
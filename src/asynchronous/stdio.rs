@@ -0,0 +1,37 @@
+//! Transports over standard streams, for Firmata endpoints exposed as subprocesses such
+//! as `socat`, a serial port forwarded over `ssh` or an emulator.
+use super::boardio::BoardIo;
+use crate::{FirmataError, Result};
+use std::process::Stdio;
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+pub type StdioBoardIo = BoardIo<tokio::io::Stdin, tokio::io::Stdout>;
+pub type ProcessBoardIo = BoardIo<ChildStdout, ChildStdin>;
+
+/// Talks Firmata over the stdin and stdout of this process, for when this program is
+/// itself started by the tool that owns the connection.
+#[must_use]
+pub fn stdio() -> StdioBoardIo {
+    BoardIo::create(tokio::io::stdin(), tokio::io::stdout())
+}
+
+/// Spawns the command and talks Firmata over its stdin and stdout. The child is killed
+/// when the returned handle is dropped, its stderr is inherited.
+/// # Errors
+/// Returns an error if the command could not be started.
+pub fn spawn(command: &mut Command) -> Result<(ProcessBoardIo, Child)> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let stdin = child
+        .stdin
+        .take()
+        .ok_or(FirmataError::StateError("child process has no stdin"))?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or(FirmataError::StateError("child process has no stdout"))?;
+    Ok((BoardIo::create(stdout, stdin), child))
+}
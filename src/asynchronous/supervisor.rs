@@ -0,0 +1,198 @@
+//! Named jobs tied to a board that are stopped when the connection drops and started
+//! again once a reconnecting poll loop reports [`BoardEvent::Reconnected`].
+use super::board::Board;
+use super::events::BoardEvent;
+use crate::Result;
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::{Id, JoinHandle, JoinSet};
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+type JobFn = Arc<dyn Fn(Board) -> JobFuture + Send + Sync>;
+
+/// What happens to a job after it stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// The job runs once and is not started again after a reconnect.
+    Never,
+    /// The job is started again after a reconnect.
+    OnReconnect,
+    /// Like [`RestartPolicy::OnReconnect`], a job that ends on its own is also started
+    /// again after the restart delay.
+    Always,
+}
+
+struct Job {
+    name: String,
+    policy: RestartPolicy,
+    run: JobFn,
+}
+
+/// Collects the jobs of a board before [`Supervisor::start`].
+pub struct Supervisor {
+    board: Board,
+    jobs: Vec<Job>,
+    restart_delay: Duration,
+}
+
+impl std::fmt::Debug for Supervisor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Supervisor")
+            .field(
+                "jobs",
+                &self.jobs.iter().map(|v| &v.name).collect::<Vec<_>>(),
+            )
+            .field("restart_delay", &self.restart_delay)
+            .finish()
+    }
+}
+
+impl Supervisor {
+    #[must_use]
+    pub fn new(board: Board) -> Self {
+        Self {
+            board,
+            jobs: vec![],
+            restart_delay: Duration::from_secs(1),
+        }
+    }
+
+    /// How long a job with [`RestartPolicy::Always`] waits before it is started again.
+    #[must_use]
+    pub fn restart_delay(mut self, delay: Duration) -> Self {
+        self.restart_delay = delay;
+        self
+    }
+
+    /// Adds a job, it is given its own board handle every time it is started.
+    #[must_use]
+    pub fn job<F, Fut>(mut self, name: &str, policy: RestartPolicy, job: F) -> Self
+    where
+        F: Fn(Board) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.jobs.push(Job {
+            name: name.to_string(),
+            policy,
+            run: Arc::new(move |board| Box::pin(job(board))),
+        });
+        self
+    }
+
+    /// Starts every job and supervises them in a background task.
+    #[must_use]
+    pub fn start(self) -> SupervisorHandle {
+        let (running_tx, running) = watch::channel(BTreeSet::new());
+        let task = tokio::spawn(supervise(self, running_tx));
+        SupervisorHandle { running, task }
+    }
+}
+
+/// A running supervisor, dropping the handle stops every job.
+#[derive(Debug)]
+pub struct SupervisorHandle {
+    running: watch::Receiver<BTreeSet<String>>,
+    task: JoinHandle<()>,
+}
+
+impl SupervisorHandle {
+    /// The names of the jobs that are currently running.
+    #[must_use]
+    pub fn running(&self) -> BTreeSet<String> {
+        self.running.borrow().clone()
+    }
+
+    pub fn stop(&self) {
+        self.task.abort();
+    }
+
+    /// Returns true if the supervisor stopped, e.g. because the board io stopped.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl Drop for SupervisorHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// The running jobs, keyed by task id to find a job again once it ends.
+struct Running {
+    set: JoinSet<Result<()>>,
+    names: HashMap<Id, usize>,
+}
+
+impl Running {
+    fn spawn(&mut self, supervisor: &Supervisor, index: usize) {
+        let job = &supervisor.jobs[index];
+        let handle = self.set.spawn((job.run)(supervisor.board.clone()));
+        self.names.insert(handle.id(), index);
+    }
+
+    fn names(&self, supervisor: &Supervisor) -> BTreeSet<String> {
+        self.names
+            .values()
+            .map(|index| supervisor.jobs[*index].name.clone())
+            .collect()
+    }
+}
+
+async fn supervise(supervisor: Supervisor, running_tx: watch::Sender<BTreeSet<String>>) {
+    let mut running = Running {
+        set: JoinSet::new(),
+        names: HashMap::new(),
+    };
+    for index in 0..supervisor.jobs.len() {
+        running.spawn(&supervisor, index);
+    }
+    let mut events = supervisor.board.clone();
+    let mut delayed: JoinSet<usize> = JoinSet::new();
+    loop {
+        running_tx.send_replace(running.names(&supervisor));
+        tokio::select! {
+            event = events.next_event() => match event {
+                Ok(BoardEvent::Disconnected) => {
+                    running.set.abort_all();
+                    delayed.abort_all();
+                }
+                Ok(BoardEvent::Reconnected) => {
+                    let active: BTreeSet<usize> = running.names.values().copied().collect();
+                    for index in 0..supervisor.jobs.len() {
+                        if supervisor.jobs[index].policy != RestartPolicy::Never
+                            && !active.contains(&index)
+                        {
+                            running.spawn(&supervisor, index);
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(_) => return,
+            },
+            Some(finished) = running.set.join_next_with_id() => {
+                let id = match &finished {
+                    Ok((id, _)) => *id,
+                    Err(e) => e.id(),
+                };
+                if let Some(index) = running.names.remove(&id) {
+                    // Aborted jobs were stopped by a disconnect and wait for the reconnect.
+                    let aborted = finished.as_ref().is_err_and(|e| e.is_cancelled());
+                    if !aborted && supervisor.jobs[index].policy == RestartPolicy::Always {
+                        let delay = supervisor.restart_delay;
+                        delayed.spawn(async move {
+                            tokio::time::sleep(delay).await;
+                            index
+                        });
+                    }
+                }
+            }
+            Some(Ok(index)) = delayed.join_next() => running.spawn(&supervisor, index),
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use futures::future::BoxFuture;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use std::thread;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::mpsc::error::SendError;
+use tokio::sync::{mpsc, oneshot};
+
+const READ_CHUNK_SIZE: usize = 256;
+const READ_QUEUE_SIZE: usize = 64;
+/// Writes queued for the write thread before [`SyncWriter`] waits for it.
+const WRITE_QUEUE_SIZE: usize = 64;
+
+/// Async read half produced by [`from_sync`], fed by a dedicated blocking read thread.
+#[derive(Debug)]
+pub struct SyncReader {
+    rx: mpsc::Receiver<io::Result<Vec<u8>>>,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+enum Command {
+    Write(Vec<u8>),
+    /// Flushes the writer and acknowledges once everything queued before was written.
+    Flush(oneshot::Sender<io::Result<()>>),
+}
+
+/// Async write half produced by [`from_sync`], drained by a dedicated blocking write thread.
+/// Writes wait once 64 of them are queued and a flush waits until the
+/// thread flushed the writer. A failed write is returned by the next write or flush.
+pub struct SyncWriter {
+    tx: mpsc::Sender<Command>,
+    /// A command the queue had no room for yet.
+    sending: Option<BoxFuture<'static, Result<(), SendError<Command>>>>,
+    flushed: Option<oneshot::Receiver<io::Result<()>>>,
+    /// The error the write thread stopped on.
+    error: Arc<Mutex<Option<io::Error>>>,
+}
+
+impl std::fmt::Debug for SyncWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SyncWriter")
+            .field("sending", &self.sending.is_some())
+            .field("flushing", &self.flushed.is_some())
+            .finish()
+    }
+}
+
+/// Wraps a blocking [`std::io::Read`] and [`std::io::Write`] pair into an
+/// [`AsyncRead`]/[`AsyncWrite`] pair that can be handed to
+/// [`BoardIo`](super::boardio::BoardIo). Each half is serviced by its own OS thread
+/// so a blocking read never stalls outgoing messages.
+///
+/// Reads that return [`io::ErrorKind::TimedOut`] or [`io::ErrorKind::WouldBlock`] are
+/// retried, which suits serial libraries configured with a read timeout. Once the
+/// [`SyncReader`] is dropped the read thread stops after its current read returns, so a
+/// reader without a timeout keeps its thread until data arrives.
+pub fn from_sync<R, W>(reader: R, writer: W) -> (SyncReader, SyncWriter)
+where
+    R: io::Read + Send + 'static,
+    W: io::Write + Send + 'static,
+{
+    let (read_tx, read_rx) = mpsc::channel(READ_QUEUE_SIZE);
+    let (write_tx, write_rx) = mpsc::channel(WRITE_QUEUE_SIZE);
+    let error = Arc::new(Mutex::new(None));
+    let write_error = error.clone();
+    thread::spawn(move || read_loop(reader, read_tx));
+    thread::spawn(move || write_loop(writer, write_rx, write_error));
+    (
+        SyncReader {
+            rx: read_rx,
+            buffer: vec![],
+            position: 0,
+        },
+        SyncWriter {
+            tx: write_tx,
+            sending: None,
+            flushed: None,
+            error,
+        },
+    )
+}
+
+fn read_loop<R: io::Read>(mut reader: R, tx: mpsc::Sender<io::Result<Vec<u8>>>) {
+    let mut buf = [0_u8; READ_CHUNK_SIZE];
+    // Stops once the SyncReader was dropped.
+    while !tx.is_closed() {
+        match reader.read(&mut buf) {
+            Ok(0) => return,
+            Ok(n) => {
+                if tx.blocking_send(Ok(buf[..n].to_vec())).is_err() {
+                    return;
+                }
+            }
+            Err(e) => match e.kind() {
+                io::ErrorKind::Interrupted
+                | io::ErrorKind::TimedOut
+                | io::ErrorKind::WouldBlock => continue,
+                _ => {
+                    let _ = tx.blocking_send(Err(e));
+                    return;
+                }
+            },
+        }
+    }
+}
+
+fn write_loop<W: io::Write>(
+    mut writer: W,
+    mut rx: mpsc::Receiver<Command>,
+    error: Arc<Mutex<Option<io::Error>>>,
+) {
+    while let Some(command) = rx.blocking_recv() {
+        match command {
+            Command::Write(data) => {
+                if let Err(e) = writer.write_all(&data) {
+                    if let Ok(mut error) = error.lock() {
+                        *error = Some(e);
+                    }
+                    return;
+                }
+            }
+            Command::Flush(ack) => {
+                let result = writer.flush();
+                let failed = result.is_err();
+                let _ = ack.send(result);
+                if failed {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl AsyncRead for SyncReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if self.position >= self.buffer.len() {
+            match self.rx.poll_recv(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    self.buffer = chunk;
+                    self.position = 0;
+                }
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Err(e)),
+                // The read thread has exited, report end of stream.
+                Poll::Ready(None) => return Poll::Ready(Ok(())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        let remaining = &self.buffer[self.position..];
+        let amount = remaining.len().min(buf.remaining());
+        buf.put_slice(&remaining[..amount]);
+        self.position += amount;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl SyncWriter {
+    /// The error the write thread stopped on, or that it stopped.
+    fn stopped(&self) -> io::Error {
+        self.error
+            .lock()
+            .ok()
+            .and_then(|mut error| error.take())
+            .unwrap_or_else(|| {
+                io::Error::new(io::ErrorKind::BrokenPipe, "sync write thread has stopped")
+            })
+    }
+
+    /// Completes queueing the command in flight.
+    fn poll_sending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let Some(sending) = self.sending.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+        let result = ready!(sending.as_mut().poll(cx));
+        self.sending = None;
+        Poll::Ready(result.map_err(|_| self.stopped()))
+    }
+
+    fn start_sending(&mut self, command: Command) {
+        let tx = self.tx.clone();
+        self.sending = Some(Box::pin(async move { tx.send(command).await }));
+    }
+}
+
+impl AsyncWrite for SyncWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_sending(cx))?;
+        if let Some(e) = this.error.lock().ok().and_then(|mut error| error.take()) {
+            return Poll::Ready(Err(e));
+        }
+        this.start_sending(Command::Write(buf.to_vec()));
+        // Queued right away unless the queue is full, then the next call waits for it.
+        match this.poll_sending(cx) {
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            _ => Poll::Ready(Ok(buf.len())),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            ready!(this.poll_sending(cx))?;
+            if let Some(flushed) = this.flushed.as_mut() {
+                let result = ready!(Pin::new(flushed).poll(cx));
+                this.flushed = None;
+                return Poll::Ready(result.unwrap_or_else(|_| Err(this.stopped())));
+            }
+            let (ack, flushed) = oneshot::channel();
+            this.start_sending(Command::Flush(ack));
+            this.flushed = Some(flushed);
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::io::AsyncWriteExt;
+
+    /// Records what was written after a delay, failing once the limit was reached.
+    struct SlowWriter {
+        written: Arc<Mutex<Vec<u8>>>,
+        limit: usize,
+    }
+
+    impl io::Write for SlowWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            thread::sleep(Duration::from_millis(10));
+            let mut written = self.written.lock().unwrap();
+            if written.len() + buf.len() > self.limit {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "limit reached"));
+            }
+            written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn adapter(limit: usize) -> (SyncWriter, Arc<Mutex<Vec<u8>>>) {
+        let written = Arc::new(Mutex::new(vec![]));
+        let writer = SlowWriter {
+            written: written.clone(),
+            limit,
+        };
+        let (_, writer) = from_sync(io::empty(), writer);
+        (writer, written)
+    }
+
+    #[tokio::test]
+    async fn flush_waits_for_the_write_thread() {
+        let (mut writer, written) = adapter(usize::MAX);
+        for byte in 0..10_u8 {
+            writer.write_all(&[byte]).await.unwrap();
+        }
+        writer.flush().await.unwrap();
+        assert_eq!(*written.lock().unwrap(), (0..10).collect::<Vec<u8>>());
+    }
+
+    #[tokio::test]
+    async fn a_failed_write_is_reported_by_the_next_call() {
+        let (mut writer, _) = adapter(2);
+        writer.write_all(&[1, 2, 3]).await.unwrap();
+        let error = writer.flush().await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(writer.write_all(&[4]).await.is_err());
+    }
+}
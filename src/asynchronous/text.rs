@@ -0,0 +1,78 @@
+use super::board::Board;
+use super::events::BoardEvent;
+use crate::Result;
+use futures::Stream;
+use std::collections::VecDeque;
+
+/// Text sent to and received from the firmware over STRING_DATA, created by
+/// [`Board::text_channel`]. Only text that arrives after the channel was created is
+/// received.
+#[derive(Debug, Clone)]
+pub struct TextChannel {
+    board: Board,
+    newline_delimited: bool,
+    partial: String,
+    lines: VecDeque<String>,
+}
+
+impl TextChannel {
+    pub(crate) fn new(board: Board) -> Self {
+        Self {
+            board,
+            newline_delimited: false,
+            partial: String::new(),
+            lines: VecDeque::new(),
+        }
+    }
+
+    /// Joins received messages and splits them on newlines, for firmwares that print a
+    /// line in several messages. By default every message is a line.
+    #[must_use]
+    pub fn newline_delimited(mut self) -> Self {
+        self.newline_delimited = true;
+        self
+    }
+
+    /// See [`Board::string_write`].
+    /// # Errors
+    /// Returns an error if the connection is closed.
+    pub async fn send(&mut self, text: &str) -> Result<()> {
+        self.board.string_write(text).await
+    }
+
+    /// Waits for the next line, without the line ending.
+    /// # Errors
+    /// Returns [`crate::FirmataError::StateError`] once the board io task has stopped.
+    pub async fn next_line(&mut self) -> Result<String> {
+        loop {
+            if let Some(line) = self.lines.pop_front() {
+                return Ok(line);
+            }
+            if let BoardEvent::StringData(text) = self.board.next_event().await? {
+                self.receive(&text);
+            }
+        }
+    }
+
+    /// The received lines as a stream, which ends once the board io task has stopped.
+    pub fn lines(self) -> impl Stream<Item = String> {
+        futures::stream::unfold(self, |mut channel| async move {
+            let line = channel.next_line().await.ok()?;
+            Some((line, channel))
+        })
+    }
+
+    fn receive(&mut self, text: &str) {
+        if !self.newline_delimited {
+            self.lines
+                .push_back(text.trim_end_matches(['\r', '\n']).to_string());
+            return;
+        }
+        self.partial.push_str(text);
+        while let Some(end) = self.partial.find('\n') {
+            let line: String = self.partial.drain(..=end).collect();
+            self.lines
+                .push_back(line.trim_end_matches(['\r', '\n']).to_string());
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use super::board::Board;
+use super::events::BoardEvent;
+use crate::uart::WRITE_CHUNK_SIZE;
+use crate::Result;
+use bytes::{Buf, BytesMut};
+use futures::{Future, Stream, StreamExt};
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+type Received = Pin<Box<dyn Stream<Item = io::Result<Vec<u8>>> + Send>>;
+type Sending = Pin<Box<dyn Future<Output = (Board, Result<()>)> + Send>>;
+
+/// A serial port of the board as a local [`AsyncRead`] and [`AsyncWrite`], created by
+/// [`Board::serial_open`] or [`Board::serial_stream`]. Only bytes reported after the
+/// stream was created are read, reading ends once the board io task has stopped.
+///
+/// Received bytes arrive as [`BoardEvent::SerialData`], so bytes are lost if the stream
+/// is not read while more than [`EVENT_CAPACITY`](super::events::EVENT_CAPACITY) events
+/// are published, the next read then fails. Shutting the stream down closes the port.
+pub struct SerialStream {
+    port: u8,
+    received: Received,
+    buffered: BytesMut,
+    /// The handle writes are sent with, taken while a write is in flight.
+    board: Option<Board>,
+    sending: Option<Sending>,
+    closed: bool,
+}
+
+impl SerialStream {
+    pub(crate) fn new(board: Board, port: u8) -> Self {
+        let received = futures::stream::unfold(board.clone(), move |mut board| async move {
+            loop {
+                match board.next_event().await.ok()? {
+                    BoardEvent::SerialData { port: from, data } if from == port => {
+                        return Some((Ok(data), board));
+                    }
+                    BoardEvent::Lagged(_) => {
+                        let lost = io::Error::other("serial data lost");
+                        return Some((Err(lost), board));
+                    }
+                    _ => {}
+                }
+            }
+        });
+        Self {
+            port,
+            received: received.boxed(),
+            buffered: BytesMut::new(),
+            board: Some(board),
+            sending: None,
+            closed: false,
+        }
+    }
+
+    pub const fn port(&self) -> u8 {
+        self.port
+    }
+
+    /// Completes the message in flight.
+    fn poll_sending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let Some(sending) = self.sending.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+        let (board, result) = ready!(sending.as_mut().poll(cx));
+        self.sending = None;
+        self.board = Some(board);
+        Poll::Ready(result.map_err(io::Error::other))
+    }
+
+    /// Starts sending with the idle handle, the handle is returned once it was sent.
+    fn start_sending<F, Fut>(&mut self, cx: &mut Context<'_>, send: F) -> Poll<io::Result<()>>
+    where
+        F: FnOnce(Board) -> Fut,
+        Fut: Future<Output = (Board, Result<()>)> + Send + 'static,
+    {
+        if let Some(board) = self.board.take() {
+            self.sending = Some(Box::pin(send(board)));
+        }
+        self.poll_sending(cx)
+    }
+}
+
+impl fmt::Debug for SerialStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SerialStream")
+            .field("port", &self.port)
+            .field("buffered", &self.buffered.len())
+            .field("sending", &self.sending.is_some())
+            .field("closed", &self.closed)
+            .finish()
+    }
+}
+
+impl AsyncRead for SerialStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        while this.buffered.is_empty() {
+            match ready!(this.received.as_mut().poll_next(cx)) {
+                Some(data) => this.buffered.extend_from_slice(&data?),
+                // The board io task has stopped, reading ends.
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+        let length = buf.remaining().min(this.buffered.len());
+        buf.put_slice(&this.buffered[..length]);
+        this.buffered.advance(length);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for SerialStream {
+    /// Accepts up to [`WRITE_CHUNK_SIZE`] bytes, one SERIAL_WRITE message.
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if data.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        ready!(this.poll_sending(cx))?;
+        let port = this.port;
+        let chunk = data[..data.len().min(WRITE_CHUNK_SIZE)].to_vec();
+        let length = chunk.len();
+        let started = this.start_sending(cx, move |mut board| async move {
+            let result = board.serial_write(port, &chunk).await;
+            (board, result)
+        });
+        // An error surfaces now if it is immediate, otherwise on the next call.
+        match started {
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            _ => Poll::Ready(Ok(length)),
+        }
+    }
+
+    /// Waits until the written bytes reached the board io, the firmware may still be
+    /// transmitting them.
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_sending(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_sending(cx))?;
+        if this.closed {
+            return Poll::Ready(Ok(()));
+        }
+        this.closed = true;
+        let port = this.port;
+        this.start_sending(cx, move |mut board| async move {
+            let result = board.serial_close(port).await;
+            (board, result)
+        })
+    }
+}
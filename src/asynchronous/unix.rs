@@ -0,0 +1,34 @@
+//! Unix domain socket transport, for setups where `ser2net` or `socat` exposes the
+//! board on a local socket.
+use super::boardio::TransportBoardIo;
+use crate::transport::{ReconnectPolicy, UnixTransport};
+use crate::Result;
+use std::path::Path;
+use std::time::Duration;
+
+pub type UnixBoardIo = TransportBoardIo;
+
+impl UnixBoardIo {
+    /// Connects to the socket, the board state still has to be generated.
+    /// # Errors
+    /// Returns an error if the socket could not be connected.
+    pub async fn connect_unix<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open(&UnixTransport::new(path)).await
+    }
+
+    /// Polls the board and reconnects to the socket whenever the connection drops,
+    /// regenerating the board state and replaying the journal afterwards.
+    /// # Errors
+    /// Returns any error that is not caused by the connection going away.
+    pub async fn poll_with_reconnect<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        retry_interval: Duration,
+    ) -> Result<()> {
+        self.poll_with_transport(
+            &UnixTransport::new(path),
+            &ReconnectPolicy::fixed(retry_interval),
+        )
+        .await
+    }
+}
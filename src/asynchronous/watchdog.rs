@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// The default period used to evaluate the watchdog when no timeout is configured.
+const DEFAULT_CHECK_PERIOD: Duration = Duration::from_secs(1);
+
+/// A report stream the board has been asked to send.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Report {
+    /// Analog reports keyed by pin index.
+    Analog(u8),
+    /// Digital reports keyed by port.
+    Digital(u8),
+}
+
+/// Configuration of the [`BoardIo`](super::boardio::BoardIo) watchdog.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WatchdogConfig {
+    /// Flags the link as stale if no message at all arrived within this window.
+    pub link_timeout: Option<Duration>,
+    /// Flags a subscribed report as stale if it has not been seen within this window.
+    pub report_timeout: Option<Duration>,
+    /// Makes `poll` return [`crate::FirmataError::Timeout`] once the link goes stale.
+    pub fail_on_link_timeout: bool,
+}
+
+/// The last evaluation of the watchdog, published as part of the board state.
+///
+/// A stale report on a live link means the sensor stopped reporting (digital reports
+/// only arrive on change), while a stale link means nothing is arriving at all.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchdogStatus {
+    pub link_stale: bool,
+    pub stale_reports: Vec<Report>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Watchdog {
+    config: WatchdogConfig,
+    last_traffic: Instant,
+    reports: HashMap<Report, Instant>,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self {
+            config: WatchdogConfig::default(),
+            last_traffic: Instant::now(),
+            reports: HashMap::new(),
+        }
+    }
+}
+
+impl Watchdog {
+    pub fn configure(&mut self, config: WatchdogConfig) {
+        self.config = config;
+        self.last_traffic = Instant::now();
+    }
+
+    pub fn config(&self) -> &WatchdogConfig {
+        &self.config
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.link_timeout.is_some() || self.config.report_timeout.is_some()
+    }
+
+    /// How often the watchdog should be evaluated to catch a timeout reasonably quickly.
+    pub fn check_period(&self) -> Duration {
+        [self.config.link_timeout, self.config.report_timeout]
+            .iter()
+            .flatten()
+            .min()
+            .map_or(DEFAULT_CHECK_PERIOD, |v| {
+                (*v / 2).max(Duration::from_millis(1))
+            })
+    }
+
+    pub fn subscribe(&mut self, report: Report, enable: bool) {
+        if enable {
+            self.reports.entry(report).or_insert_with(Instant::now);
+        } else {
+            self.reports.remove(&report);
+        }
+    }
+
+    pub fn observe_traffic(&mut self) {
+        self.last_traffic = Instant::now();
+    }
+
+    pub fn observe_report(&mut self, report: Report) {
+        if let Some(last_seen) = self.reports.get_mut(&report) {
+            *last_seen = Instant::now();
+        }
+    }
+
+    pub fn evaluate(&self) -> WatchdogStatus {
+        let link_stale = self
+            .config
+            .link_timeout
+            .is_some_and(|timeout| self.last_traffic.elapsed() > timeout);
+        let mut stale_reports: Vec<Report> = match self.config.report_timeout {
+            Some(timeout) => self
+                .reports
+                .iter()
+                .filter(|(_, last_seen)| last_seen.elapsed() > timeout)
+                .map(|(report, _)| *report)
+                .collect(),
+            None => vec![],
+        };
+        stale_reports.sort_by_key(|report| match report {
+            Report::Analog(v) => (0, *v),
+            Report::Digital(v) => (1, *v),
+        });
+        WatchdogStatus {
+            link_stale,
+            stale_reports,
+        }
+    }
+}
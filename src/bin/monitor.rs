@@ -0,0 +1,227 @@
+//! Live terminal monitor for a Firmata board.
+//!
+//! `firmata-monitor <serial port | host:port> [baud]`, the arrow keys select a pin,
+//! space toggles a digital output, `p` pings the board and `q` quits.
+use firmata::asynchronous::board::Board;
+use firmata::asynchronous::boardio::BoardIo;
+use firmata::asynchronous::events::BoardEvent;
+use firmata::serial::{self, PortSelector};
+use firmata::{FirmataError, PinId, PinMode, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+const DEFAULT_BAUD: u32 = 57_600;
+const LOG_LINES: usize = 200;
+const REFRESH: Duration = Duration::from_millis(50);
+
+struct Monitor {
+    board: Board,
+    selected: TableState,
+    log: VecDeque<String>,
+}
+
+impl Monitor {
+    fn record(&mut self, line: String) {
+        if self.log.len() == LOG_LINES {
+            self.log.pop_front();
+        }
+        self.log.push_back(line);
+    }
+
+    fn record_event(&mut self, event: BoardEvent) {
+        match event {
+            BoardEvent::I2cReply(reply) => self.record(format!(
+                "i2c {:#04x} reg {} {:02x?}",
+                reply.address, reply.register, reply.data
+            )),
+            BoardEvent::FrameError(error) => self.record(format!(
+                "frame error: {} ({} bytes)",
+                error.reason,
+                error.discarded.len()
+            )),
+            BoardEvent::Firmware { name, version } => {
+                self.record(format!("firmware {name} {version}"));
+            }
+            BoardEvent::UnknownSysex { command, payload } => {
+                self.record(format!("sysex {command:#04x} {payload:02x?}"));
+            }
+            BoardEvent::Lagged(missed) => self.record(format!("missed {missed} events")),
+            // Pin changes are visible in the table.
+            _ => {}
+        }
+    }
+
+    /// Handles a key press, returns false to quit.
+    async fn key(&mut self, code: KeyCode) -> bool {
+        let pins = self.board.pins().len();
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Down => self.selected.select(Some(
+                self.selected
+                    .selected()
+                    .map_or(0, |v| (v + 1).min(pins.saturating_sub(1))),
+            )),
+            KeyCode::Up => self.selected.select(Some(
+                self.selected.selected().map_or(0, |v| v.saturating_sub(1)),
+            )),
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                if let Err(e) = self.toggle().await {
+                    self.record(format!("toggle failed: {e}"));
+                }
+            }
+            KeyCode::Char('p') => match self.board.ping(Duration::from_secs(1)).await {
+                Ok(latency) => self.record(format!("ping {latency:?}")),
+                Err(e) => self.record(format!("ping failed: {e}")),
+            },
+            _ => {}
+        }
+        true
+    }
+
+    async fn toggle(&mut self) -> Result<()> {
+        let index = self.selected.selected().unwrap_or(0) as u8;
+        let pin = self.board.pin(PinId::Pin(index))?;
+        if pin.mode != PinMode::Output {
+            return Err(FirmataError::WrongType("only output pins can be toggled"));
+        }
+        self.board
+            .digital_write(PinId::Pin(index), pin.value == 0)
+            .await
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [top, bottom] =
+            Layout::vertical([Constraint::Min(8), Constraint::Length(10)]).areas(frame.area());
+        let [pins_area, stats_area] =
+            Layout::horizontal([Constraint::Min(40), Constraint::Length(36)]).areas(top);
+
+        let rows = self
+            .board
+            .pins()
+            .into_iter()
+            .enumerate()
+            .map(|(index, pin)| {
+                let label = self
+                    .board
+                    .label(PinId::Pin(index as u8))
+                    .unwrap_or_default();
+                Row::new(vec![
+                    index.to_string(),
+                    format!("{:?}", pin.mode),
+                    pin.value.to_string(),
+                    label,
+                ])
+            });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(4),
+                Constraint::Length(8),
+                Constraint::Length(7),
+                Constraint::Min(10),
+            ],
+        )
+        .header(Row::new(vec!["pin", "mode", "value", "label"]))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::bordered().title("pins"));
+        frame.render_stateful_widget(table, pins_area, &mut self.selected);
+
+        let stats = self.board.stats();
+        let watchdog = self.board.watchdog_status();
+        let info = self.board.info();
+        let lines = vec![
+            Line::from(format!(
+                "firmware {} {}",
+                info.firmware_name, info.firmware_version
+            )),
+            Line::from(format!("protocol {}", info.protocol_version)),
+            Line::from(format!("round trips {}", stats.round_trips)),
+            Line::from(format!("last latency {:?}", stats.last_latency)),
+            Line::from(format!("average latency {:?}", stats.average_latency)),
+            Line::from(format!(
+                "frame errors {} ({} bytes)",
+                stats.frame_errors, stats.discarded_bytes
+            )),
+            Line::from(format!("unknown sysex {}", stats.unknown_sysex)),
+            Line::from(format!("link stale {}", watchdog.link_stale)),
+        ];
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title("link")),
+            stats_area,
+        );
+
+        let visible = usize::from(bottom.height.saturating_sub(2));
+        let log: Vec<String> = self.log.iter().rev().take(visible).rev().cloned().collect();
+        frame.render_widget(
+            List::new(log).block(Block::bordered().title("traffic")),
+            bottom,
+        );
+    }
+}
+
+async fn connect(target: &str, baud: u32) -> Result<Board> {
+    let board = if target.contains(':') {
+        let (r, w) = tokio::net::TcpStream::connect(target).await?.into_split();
+        let mut board_io = BoardIo::create(r, w);
+        board_io.generate_board_state().await?;
+        let board = board_io.get_board();
+        tokio::spawn(async move { board_io.poll().await });
+        board
+    } else {
+        let mut board_io =
+            serial::open_board_io(&PortSelector::Path(target.to_string()), baud).await?;
+        board_io.generate_board_state().await?;
+        let board = board_io.get_board();
+        tokio::spawn(async move { board_io.poll().await });
+        board
+    };
+    Ok(board)
+}
+
+async fn run(terminal: &mut DefaultTerminal, board: Board) -> Result<()> {
+    let mut monitor = Monitor {
+        board,
+        selected: TableState::default().with_selected(Some(0)),
+        log: VecDeque::new(),
+    };
+    let mut events = monitor.board.clone();
+    let mut refresh = tokio::time::interval(REFRESH);
+    loop {
+        terminal.draw(|frame| monitor.draw(frame))?;
+        tokio::select! {
+            event = events.next_event() => monitor.record_event(event?),
+            _ = refresh.tick() => {}
+        }
+        while event::poll(Duration::ZERO)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !monitor.key(key.code).await {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let Some(target) = args.next() else {
+        eprintln!("usage: firmata-monitor <serial port | host:port> [baud]");
+        std::process::exit(2);
+    };
+    let baud = args
+        .next()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_BAUD);
+    let board = connect(&target, baud).await?;
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, board).await;
+    ratatui::restore();
+    result
+}
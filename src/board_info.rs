@@ -0,0 +1,49 @@
+//! Identity and size of a board in one value, for logging and status displays.
+use crate::features::FeatureSet;
+use crate::PinStates;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BoardInfo {
+    pub firmware_name: String,
+    pub firmware_version: String,
+    pub protocol_version: String,
+    pub pin_count: usize,
+    pub analog_channels: usize,
+    pub features: FeatureSet,
+}
+
+impl BoardInfo {
+    #[must_use]
+    pub fn new(
+        firmware_name: &str,
+        firmware_version: &str,
+        protocol_version: &str,
+        pin_state: &PinStates,
+    ) -> Self {
+        Self {
+            firmware_name: firmware_name.to_string(),
+            firmware_version: firmware_version.to_string(),
+            protocol_version: protocol_version.to_string(),
+            pin_count: pin_state.pins.len(),
+            analog_channels: pin_state.analog_channels.len(),
+            features: FeatureSet::detect(pin_state, protocol_version),
+        }
+    }
+}
+
+/// One line, e.g. `StandardFirmata.ino 2.5, protocol 2.6, 20 pins (6 analog)`.
+impl fmt::Display for BoardInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}, protocol {}, {} pins ({} analog)",
+            self.firmware_name,
+            self.firmware_version,
+            self.protocol_version,
+            self.pin_count,
+            self.analog_channels
+        )
+    }
+}
@@ -0,0 +1,150 @@
+//! Buffers that stop growing at a capacity, for data that accumulates until the
+//! application collects it.
+use serde::{Deserialize, Serialize};
+use std::collections::vec_deque::{self, VecDeque};
+
+/// What happens to a value pushed into a full [`BoundedBuffer`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Eviction {
+    /// The oldest value is dropped to make room, ring buffer semantics.
+    #[default]
+    DropOldest,
+    /// The new value is dropped.
+    DropNewest,
+}
+
+/// A queue with an optional capacity, counting every value dropped because it was full.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BoundedBuffer<T> {
+    values: VecDeque<T>,
+    capacity: Option<usize>,
+    eviction: Eviction,
+    overflows: u64,
+}
+
+impl<T> Default for BoundedBuffer<T> {
+    fn default() -> Self {
+        Self::unbounded()
+    }
+}
+
+impl<T> BoundedBuffer<T> {
+    #[must_use]
+    pub fn unbounded() -> Self {
+        Self {
+            values: VecDeque::new(),
+            capacity: None,
+            eviction: Eviction::default(),
+            overflows: 0,
+        }
+    }
+
+    /// A buffer holding at most `capacity` values, a capacity of zero drops everything.
+    #[must_use]
+    pub fn with_capacity(capacity: usize, eviction: Eviction) -> Self {
+        let mut buffer = Self::unbounded();
+        buffer.set_capacity(Some(capacity), eviction);
+        buffer
+    }
+
+    /// Changes the capacity, values beyond a smaller capacity are evicted right away.
+    pub fn set_capacity(&mut self, capacity: Option<usize>, eviction: Eviction) {
+        self.capacity = capacity;
+        self.eviction = eviction;
+        if let Some(capacity) = capacity {
+            while self.values.len() > capacity {
+                match eviction {
+                    Eviction::DropOldest => self.values.pop_front(),
+                    Eviction::DropNewest => self.values.pop_back(),
+                };
+                self.overflows += 1;
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    #[must_use]
+    pub fn eviction(&self) -> Eviction {
+        self.eviction
+    }
+
+    /// Appends a value, returns false if a value had to be dropped.
+    pub fn push(&mut self, value: T) -> bool {
+        match self.capacity {
+            Some(capacity) if self.values.len() >= capacity => {
+                self.overflows += 1;
+                if self.eviction == Eviction::DropOldest && capacity > 0 {
+                    self.values.pop_front();
+                    self.values.push_back(value);
+                }
+                false
+            }
+            _ => {
+                self.values.push_back(value);
+                true
+            }
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.values.pop_front()
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        self.values.remove(index)
+    }
+
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.values.retain(f);
+    }
+
+    pub fn drain(&mut self) -> vec_deque::Drain<'_, T> {
+        self.values.drain(..)
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.values.get(index)
+    }
+
+    pub fn iter(&self) -> vec_deque::Iter<'_, T> {
+        self.values.iter()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// How many values were dropped because the buffer was full.
+    #[must_use]
+    pub fn overflows(&self) -> u64 {
+        self.overflows
+    }
+
+    pub fn reset_overflows(&mut self) {
+        self.overflows = 0;
+    }
+}
+
+impl<'a, T> IntoIterator for &'a BoundedBuffer<T> {
+    type Item = &'a T;
+    type IntoIter = vec_deque::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.iter()
+    }
+}
@@ -0,0 +1,148 @@
+//! Conversion of raw analog readings into engineering units.
+use crate::{FirmataError, Pin, PinMode, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The resolution assumed when a pin does not report its analog resolution.
+pub const DEFAULT_ANALOG_RESOLUTION: u8 = 10;
+
+/// Maps two raw readings to two known values, readings are interpolated between them.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct TwoPoint {
+    pub raw_low: f64,
+    pub value_low: f64,
+    pub raw_high: f64,
+    pub value_high: f64,
+}
+
+/// Calibration of a single analog pin.
+///
+/// Without a two point calibration a reading is converted to volts using the reference
+/// voltage and then to `volts * scale + offset`. A two point calibration maps the raw
+/// reading directly and ignores the other fields.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    pub reference_voltage: f64,
+    pub offset: f64,
+    pub scale: f64,
+    pub two_point: Option<TwoPoint>,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            reference_voltage: 5.0,
+            offset: 0.0,
+            scale: 1.0,
+            two_point: None,
+        }
+    }
+}
+
+impl Calibration {
+    /// A calibration reporting volts for the given reference voltage.
+    #[must_use]
+    pub fn voltage(reference_voltage: f64) -> Self {
+        Self {
+            reference_voltage,
+            ..Self::default()
+        }
+    }
+
+    /// A calibration that linearly maps two raw readings to two known values.
+    #[must_use]
+    pub fn two_point(raw_low: f64, value_low: f64, raw_high: f64, value_high: f64) -> Self {
+        Self {
+            two_point: Some(TwoPoint {
+                raw_low,
+                value_low,
+                raw_high,
+                value_high,
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Converts a raw reading taken at the given resolution in bits.
+    #[must_use]
+    pub fn apply(&self, raw: u16, resolution: u8) -> f64 {
+        let raw = f64::from(raw);
+        if let Some(points) = self.two_point {
+            let span = points.raw_high - points.raw_low;
+            if span == 0.0 {
+                return points.value_low;
+            }
+            return points.value_low
+                + (raw - points.raw_low) * (points.value_high - points.value_low) / span;
+        }
+        let max = f64::from((1_u32 << resolution.min(31)) - 1).max(1.0);
+        let volts = raw / max * self.reference_voltage;
+        volts * self.scale + self.offset
+    }
+}
+
+/// Returns the analog resolution reported for a pin in its capabilities.
+#[must_use]
+pub fn analog_resolution(pin: &Pin) -> u8 {
+    pin.resolution(PinMode::Analog)
+        .unwrap_or(DEFAULT_ANALOG_RESOLUTION)
+}
+
+/// Calibrations keyed by pin index.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CalibrationTable {
+    pub pins: BTreeMap<u8, Calibration>,
+}
+
+impl CalibrationTable {
+    pub fn set(&mut self, pin: u8, calibration: Calibration) {
+        self.pins.insert(pin, calibration);
+    }
+
+    pub fn remove(&mut self, pin: u8) -> Option<Calibration> {
+        self.pins.remove(&pin)
+    }
+
+    #[must_use]
+    pub fn get(&self, pin: u8) -> Option<&Calibration> {
+        self.pins.get(&pin)
+    }
+
+    /// Converts a pin's current value, pins without a calibration report volts
+    /// using the default calibration.
+    #[must_use]
+    pub fn convert(&self, index: u8, pin: &Pin) -> f64 {
+        self.get(index)
+            .copied()
+            .unwrap_or_default()
+            .apply(pin.value, analog_resolution(pin))
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::ConversionFailure`] if the table could not be serialized.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|_| FirmataError::ConversionFailure("failed to serialize calibration"))
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::ConversionFailure`] if the json is not a valid table.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|_| FirmataError::ConversionFailure("failed to deserialize calibration"))
+    }
+
+    /// # Errors
+    /// Returns an error if the file could not be written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// # Errors
+    /// Returns an error if the file could not be read or parsed.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
@@ -0,0 +1,99 @@
+//! Persisted capability and analog mapping results, used to skip the slow part of the
+//! bootstrap handshake for boards that were seen before.
+use crate::{FirmataError, PinStates, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Identifies a board, the serial number tells apart boards running the same firmware.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CacheKey {
+    pub firmware_name: String,
+    pub firmware_version: String,
+    pub serial_number: Option<String>,
+}
+
+impl CacheKey {
+    #[must_use]
+    pub fn new(firmware_name: &str, firmware_version: &str, serial_number: Option<&str>) -> Self {
+        Self {
+            firmware_name: firmware_name.to_string(),
+            firmware_version: firmware_version.to_string(),
+            serial_number: serial_number.map(str::to_string),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CacheEntry {
+    key: CacheKey,
+    pins: PinStates,
+}
+
+/// Pin capabilities and analog mappings keyed by the board they were read from.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CapabilityCache {
+    entries: Vec<CacheEntry>,
+}
+
+impl CapabilityCache {
+    #[must_use]
+    pub fn get(&self, key: &CacheKey) -> Option<&PinStates> {
+        self.entries
+            .iter()
+            .find(|entry| entry.key == *key)
+            .map(|entry| &entry.pins)
+    }
+
+    /// Stores the pins of a board, replacing an earlier entry for the same board.
+    pub fn insert(&mut self, key: CacheKey, pins: PinStates) {
+        self.remove(&key);
+        self.entries.push(CacheEntry { key, pins });
+    }
+
+    pub fn remove(&mut self, key: &CacheKey) -> Option<PinStates> {
+        let index = self.entries.iter().position(|entry| entry.key == *key)?;
+        Some(self.entries.remove(index).pins)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::ConversionFailure`] if the cache could not be serialized.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|_| FirmataError::ConversionFailure("failed to serialize capability cache"))
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::ConversionFailure`] if the json is not a valid cache.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|_| FirmataError::ConversionFailure("failed to deserialize capability cache"))
+    }
+
+    /// # Errors
+    /// Returns an error if the file could not be written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// Loads a cache, a missing file yields an empty cache.
+    /// # Errors
+    /// Returns an error if the file could not be read or parsed.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(json) => Self::from_json(&json),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! A summary of what every pin of a board can do and is doing, for `info` style output
+//! and support requests.
+use crate::{Mode, PinMode, PinStates};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PinReport {
+    pub pin: u8,
+    pub analog_channel: Option<u8>,
+    pub label: Option<String>,
+    pub modes: Vec<Mode>,
+    pub mode: PinMode,
+    pub value: u16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityReport {
+    pub firmware_name: String,
+    pub firmware_version: String,
+    pub protocol_version: String,
+    pub pins: Vec<PinReport>,
+}
+
+impl CapabilityReport {
+    #[must_use]
+    pub fn new(
+        firmware_name: &str,
+        firmware_version: &str,
+        protocol_version: &str,
+        pin_state: &PinStates,
+        labels: &BTreeMap<u8, String>,
+    ) -> Self {
+        let pins = pin_state
+            .pins
+            .iter()
+            .enumerate()
+            .map(|(index, pin)| {
+                let index = index as u8;
+                PinReport {
+                    pin: index,
+                    analog_channel: pin_state.channel_for_pin(index),
+                    label: labels.get(&index).cloned(),
+                    modes: pin.modes.clone(),
+                    mode: pin.mode,
+                    value: pin.value,
+                }
+            })
+            .collect();
+        Self {
+            firmware_name: firmware_name.to_string(),
+            firmware_version: firmware_version.to_string(),
+            protocol_version: protocol_version.to_string(),
+            pins,
+        }
+    }
+
+    /// The pins that support a mode, e.g. every PWM capable pin.
+    pub fn pins_supporting(&self, mode: PinMode) -> impl Iterator<Item = &PinReport> {
+        self.pins
+            .iter()
+            .filter(move |pin| pin.modes.iter().any(|v| v.mode == mode))
+    }
+}
+
+/// Renders one line per pin with its supported modes and their resolutions.
+impl fmt::Display for CapabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "firmware {} {}, protocol {}",
+            self.firmware_name, self.firmware_version, self.protocol_version
+        )?;
+        writeln!(
+            f,
+            "{:>4}  {:<6}  {:<8}  {:>5}  supported modes",
+            "pin", "analog", "mode", "value"
+        )?;
+        for pin in &self.pins {
+            let channel = pin
+                .analog_channel
+                .map_or_else(|| "-".to_string(), |v| format!("A{v}"));
+            let modes: Vec<String> = pin
+                .modes
+                .iter()
+                .map(|v| format!("{:?}({})", v.mode, v.resolution))
+                .collect();
+            write!(
+                f,
+                "{:>4}  {:<6}  {:<8}  {:>5}  {}",
+                pin.pin,
+                channel,
+                format!("{:?}", pin.mode),
+                pin.value,
+                modes.join(", ")
+            )?;
+            if let Some(label) = &pin.label {
+                write!(f, "  # {label}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
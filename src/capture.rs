@@ -0,0 +1,114 @@
+//! Oscilloscope style captures of a single pin: once armed, the samples around a trigger
+//! are recorded inside of the board io and delivered together, so short events are not
+//! lost to per sample streaming.
+use crate::{FirmataError, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// The most samples a capture holds, history included.
+pub const MAX_CAPTURE_SAMPLES: usize = 65_536;
+
+/// When an armed capture starts recording, compared against the previous sample since
+/// arming, so a pin that already is above a threshold does not trigger.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureTrigger {
+    /// The first sample.
+    Immediate,
+    /// The value rises from below the threshold to or above it.
+    Above(u16),
+    /// The value falls from above the threshold to or below it.
+    Below(u16),
+    /// A digital pin goes from low to high.
+    RisingEdge,
+    /// A digital pin goes from high to low.
+    FallingEdge,
+    /// A digital pin changes its level.
+    AnyEdge,
+}
+
+impl CaptureTrigger {
+    #[must_use]
+    pub fn fires(self, previous: Option<u16>, value: u16) -> bool {
+        match (self, previous) {
+            (Self::Immediate, _) => true,
+            (_, None) => false,
+            (Self::Above(threshold), Some(previous)) => previous < threshold && value >= threshold,
+            (Self::Below(threshold), Some(previous)) => previous > threshold && value <= threshold,
+            (Self::RisingEdge, Some(previous)) => previous == 0 && value != 0,
+            (Self::FallingEdge, Some(previous)) => previous != 0 && value == 0,
+            (Self::AnyEdge, Some(previous)) => (previous == 0) != (value == 0),
+        }
+    }
+}
+
+/// What to record once a capture is armed on a pin.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureRequest {
+    pub trigger: CaptureTrigger,
+    /// Samples recorded from the trigger on, the triggering sample being the first.
+    pub samples: usize,
+    /// Samples kept from before the trigger.
+    pub pre_trigger: usize,
+}
+
+impl CaptureRequest {
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if no samples are requested or more than
+    /// [`MAX_CAPTURE_SAMPLES`].
+    pub fn new(trigger: CaptureTrigger, samples: usize) -> Result<Self> {
+        Self {
+            trigger,
+            samples,
+            pre_trigger: 0,
+        }
+        .validate()
+    }
+
+    /// Also keeps the given number of samples from before the trigger.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the capture holds more than
+    /// [`MAX_CAPTURE_SAMPLES`].
+    pub fn pre_trigger(mut self, samples: usize) -> Result<Self> {
+        self.pre_trigger = samples;
+        self.validate()
+    }
+
+    fn validate(self) -> Result<Self> {
+        if self.samples == 0 {
+            return Err(FirmataError::OutOfRange(
+                "a capture needs at least one sample",
+            ));
+        }
+        if self.samples.saturating_add(self.pre_trigger) > MAX_CAPTURE_SAMPLES {
+            return Err(FirmataError::OutOfRange(
+                "a capture holds at most 65536 samples",
+            ));
+        }
+        Ok(self)
+    }
+}
+
+/// A completed capture, every sample with the time it arrived.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    pub pin: u8,
+    pub request: CaptureRequest,
+    /// The samples before the trigger, oldest first, fewer than requested if the
+    /// trigger fired early.
+    pub pre_trigger: Vec<(Instant, u16)>,
+    /// The triggering sample and the ones after it.
+    pub samples: Vec<(Instant, u16)>,
+}
+
+impl Capture {
+    /// When the triggering sample arrived.
+    #[must_use]
+    pub fn triggered_at(&self) -> Option<Instant> {
+        self.samples.first().map(|(at, _)| *at)
+    }
+
+    /// Every sample, the history followed by the samples from the trigger on.
+    pub fn all_samples(&self) -> impl Iterator<Item = &(Instant, u16)> {
+        self.pre_trigger.iter().chain(&self.samples)
+    }
+}
@@ -0,0 +1,38 @@
+//! A digital view of an analog pin, for light sensors, battery levels and other inputs
+//! where only crossing a threshold matters.
+use crate::{FirmataError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Thresholds with hysteresis, the level goes high at or above `high` and low again at
+/// or below `low`, so noise around a single threshold does not make it flicker.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comparator {
+    pub low: u16,
+    pub high: u16,
+}
+
+impl Comparator {
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if `low` is above `high`.
+    pub fn new(low: u16, high: u16) -> Result<Self> {
+        if low > high {
+            return Err(FirmataError::OutOfRange(
+                "comparator low threshold is above the high threshold",
+            ));
+        }
+        Ok(Self { low, high })
+    }
+
+    /// The level after a new value. Between the thresholds the previous level is kept,
+    /// a first value between them counts as low.
+    #[must_use]
+    pub fn level(&self, previous: Option<bool>, value: u16) -> bool {
+        if value >= self.high {
+            true
+        } else if value <= self.low {
+            false
+        } else {
+            previous.unwrap_or(false)
+        }
+    }
+}
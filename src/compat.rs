@@ -0,0 +1,96 @@
+//! Adapts the client to the protocol version reported by the firmware, following the
+//! changelog at <https://github.com/firmata/protocol/blob/master/protocol.md>.
+use crate::{FirmataError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A `major.minor` protocol version.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl Version {
+    #[must_use]
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    /// Parses a version such as `2.5`, returns `None` for an empty or malformed string.
+    #[must_use]
+    pub fn parse(version: &str) -> Option<Self> {
+        let (major, minor) = version.trim().split_once('.')?;
+        Some(Self {
+            major: major.parse().ok()?,
+            minor: minor.parse().ok()?,
+        })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Protocol features that only exist from a certain version on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    SamplingInterval,
+    I2c,
+    CapabilityQuery,
+    AnalogMappingQuery,
+    PinStateQuery,
+    ExtendedAnalog,
+    /// The `SET_DIGITAL_PIN_VALUE` message, older firmwares only take whole ports.
+    SetDigitalPinValue,
+    PullupMode,
+    SerialData,
+}
+
+impl Feature {
+    #[must_use]
+    pub const fn min_version(self) -> Version {
+        match self {
+            Self::SamplingInterval | Self::I2c => Version::new(2, 1),
+            Self::CapabilityQuery
+            | Self::AnalogMappingQuery
+            | Self::PinStateQuery
+            | Self::ExtendedAnalog => Version::new(2, 2),
+            Self::SetDigitalPinValue | Self::PullupMode | Self::SerialData => Version::new(2, 5),
+        }
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::SamplingInterval => "the firmware does not support setting the sampling interval",
+            Self::I2c => "the firmware does not support i2c",
+            Self::CapabilityQuery => "the firmware does not support capability queries",
+            Self::AnalogMappingQuery => "the firmware does not support analog mapping queries",
+            Self::PinStateQuery => "the firmware does not support pin state queries",
+            Self::ExtendedAnalog => "the firmware does not support extended analog",
+            Self::SetDigitalPinValue => "the firmware does not support setting single pins",
+            Self::PullupMode => "the firmware does not support the pullup pin mode",
+            Self::SerialData => "the firmware does not support serial data",
+        }
+    }
+}
+
+/// Returns true if a firmware with the reported protocol version supports the feature.
+/// An unknown version is assumed to support everything, so nothing is held back before
+/// the version arrived.
+#[must_use]
+pub fn supports(protocol_version: &str, feature: Feature) -> bool {
+    Version::parse(protocol_version).is_none_or(|version| version >= feature.min_version())
+}
+
+/// # Errors
+/// Returns [`FirmataError::UnsupportedByFirmware`] if the firmware is too old for the feature.
+pub fn require(protocol_version: &str, feature: Feature) -> Result<()> {
+    if supports(protocol_version, feature) {
+        Ok(())
+    } else {
+        Err(FirmataError::UnsupportedByFirmware(feature.name()))
+    }
+}
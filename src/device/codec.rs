@@ -0,0 +1,39 @@
+use super::message::{DeviceMessage, HostMessage};
+use crate::{FirmataError, Result};
+use bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// The longest sysex a board buffers, longer ones are dropped unread.
+const BUFFER_SIZE: usize = 1000;
+
+/// Frames the board side of a connection, commands that are not understood are
+/// skipped rather than failing the stream.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct DeviceCodec;
+
+impl Decoder for DeviceCodec {
+    type Item = HostMessage;
+    type Error = FirmataError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<HostMessage>> {
+        while let Some(length) = HostMessage::frame_length(src) {
+            let frame = src.split_to(length);
+            if let Some(message) = HostMessage::decode(&frame) {
+                return Ok(Some(message));
+            }
+        }
+        if src.len() > BUFFER_SIZE {
+            src.clear();
+        }
+        Ok(None)
+    }
+}
+
+impl Encoder<DeviceMessage> for DeviceCodec {
+    type Error = FirmataError;
+
+    fn encode(&mut self, item: DeviceMessage, dst: &mut BytesMut) -> Result<()> {
+        dst.extend_from_slice(&item.encode());
+        Ok(())
+    }
+}
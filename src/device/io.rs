@@ -0,0 +1,155 @@
+use super::codec::DeviceCodec;
+use super::message::{DeviceMessage, HostMessage};
+use super::state::DeviceState;
+use crate::{FirmataError, Result};
+use futures::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::{mpsc, watch};
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+/// Answers the commands the built-in handling of [`DeviceState::apply`] leaves open,
+/// e.g. I2C requests or the sysex commands of an extension. Every handler sees every
+/// command after the state was updated and returns the messages to send back.
+pub trait DeviceHandler: Send {
+    fn handle(&mut self, message: &HostMessage, state: &DeviceState) -> Vec<DeviceMessage>;
+}
+
+impl<F> DeviceHandler for F
+where
+    F: FnMut(&HostMessage, &DeviceState) -> Vec<DeviceMessage> + Send,
+{
+    fn handle(&mut self, message: &HostMessage, state: &DeviceState) -> Vec<DeviceMessage> {
+        self(message, state)
+    }
+}
+
+/// Pushes messages the host did not ask for, such as the analog and digital reports
+/// of a sampling loop, into a running [`DeviceIo`]. Reports of channels and ports the
+/// host did not enable are dropped.
+#[derive(Debug, Clone)]
+pub struct DeviceReporter {
+    message_tx: mpsc::Sender<DeviceMessage>,
+    state_rx: watch::Receiver<DeviceState>,
+}
+
+impl DeviceReporter {
+    /// The state as of the last command the host sent, e.g. for the sampling interval.
+    #[must_use]
+    pub fn state(&self) -> DeviceState {
+        self.state_rx.borrow().clone()
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the device io has stopped.
+    pub async fn send(&self, message: DeviceMessage) -> Result<()> {
+        self.message_tx
+            .send(message)
+            .await
+            .map_err(|_| FirmataError::StateError("the device io task has stopped"))
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the device io has stopped.
+    pub async fn analog(&self, channel: u8, value: u16) -> Result<()> {
+        self.send(DeviceMessage::Analog(channel, value)).await
+    }
+
+    /// Reports the levels of the eight pins of a port, bit 0 being the first pin.
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the device io has stopped.
+    pub async fn digital_port(&self, port: u8, value: u8) -> Result<()> {
+        self.send(DeviceMessage::DigitalPort(port, value)).await
+    }
+}
+
+/// Serves the board side of the protocol over a connection, the counterpart of a
+/// [`crate::asynchronous::boardio::BoardIo`]. Queries, pin modes, writes and
+/// reporting are handled by the [`DeviceState`], anything else by the registered
+/// [`DeviceHandler`]s.
+pub struct DeviceIo<R: AsyncRead, W: AsyncWrite> {
+    conn_read: FramedRead<R, DeviceCodec>,
+    conn_write: FramedWrite<W, DeviceCodec>,
+    state: DeviceState,
+    state_tx: watch::Sender<DeviceState>,
+    state_rx: watch::Receiver<DeviceState>,
+    message_tx: mpsc::Sender<DeviceMessage>,
+    message_rx: mpsc::Receiver<DeviceMessage>,
+    handlers: Vec<Box<dyn DeviceHandler>>,
+}
+
+impl<R: AsyncRead, W: AsyncWrite> std::fmt::Debug for DeviceIo<R, W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceIo")
+            .field("state", &self.state)
+            .field("handlers", &self.handlers.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R: AsyncRead + Unpin + Send, W: AsyncWrite + Unpin + Send> DeviceIo<R, W> {
+    pub fn create(conn_read: R, conn_write: W, state: DeviceState) -> Self {
+        let (state_tx, state_rx) = watch::channel(state.clone());
+        let (message_tx, message_rx) = mpsc::channel(50);
+        Self {
+            conn_read: FramedRead::new(conn_read, DeviceCodec),
+            conn_write: FramedWrite::new(conn_write, DeviceCodec),
+            state,
+            state_tx,
+            state_rx,
+            message_tx,
+            message_rx,
+            handlers: vec![],
+        }
+    }
+
+    /// Handlers run in the order they were added.
+    pub fn add_handler<H: DeviceHandler + 'static>(&mut self, handler: H) {
+        self.handlers.push(Box::new(handler));
+    }
+
+    #[must_use]
+    pub fn reporter(&self) -> DeviceReporter {
+        DeviceReporter {
+            message_tx: self.message_tx.clone(),
+            state_rx: self.state_rx.clone(),
+        }
+    }
+
+    #[must_use]
+    pub const fn state(&self) -> &DeviceState {
+        &self.state
+    }
+
+    /// Serves the connection until the host closes it.
+    /// # Errors
+    /// Returns an error if reading or writing the connection failed.
+    pub async fn run(&mut self) -> Result<()> {
+        loop {
+            tokio::select! {
+                val = self.conn_read.next() => {
+                    let Some(message) = val.transpose()? else {
+                        return Ok(());
+                    };
+                    for reply in self.handle(&message) {
+                        self.conn_write.feed(reply).await?;
+                    }
+                    self.conn_write.flush().await?;
+                    self.state_tx.send_replace(self.state.clone());
+                }
+                Some(message) = self.message_rx.recv() => {
+                    if self.state.is_reported(&message) {
+                        self.conn_write.send(message).await?;
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle(&mut self, message: &HostMessage) -> Vec<DeviceMessage> {
+        let mut replies = self.state.apply(message);
+        for handler in &mut self.handlers {
+            replies.extend(handler.handle(message, &self.state));
+        }
+        replies
+    }
+}
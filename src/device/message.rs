@@ -0,0 +1,282 @@
+use crate::protocol::{I2cMode, MidiCommand, SysexCommand, END_SYSEX, START_SYSEX};
+use crate::{digital, message, text, Pin, PinMode};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// What a client sends to a board.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum HostMessage {
+    ProtocolVersionQuery,
+    FirmwareQuery,
+    CapabilityQuery,
+    AnalogMappingQuery,
+    PinStateQuery(u8),
+    SystemReset,
+    SetPinMode(u8, PinMode),
+    /// The levels of the eight pins of a port, bit 0 being the first pin.
+    DigitalPortWrite(u8, u8),
+    DigitalPinWrite(u8, bool),
+    /// A PWM or servo value for a pin, from ANALOG_MESSAGE or EXTENDED_ANALOG.
+    AnalogWrite(u8, u16),
+    /// Enables or disables the reports of an analog channel.
+    ReportAnalog(u8, bool),
+    /// Enables or disables the reports of a digital port.
+    ReportDigital(u8, bool),
+    SamplingInterval(Duration),
+    /// The delay in microseconds between writing a register and reading.
+    I2cConfig(u16),
+    I2cRequest(I2cRequest),
+    /// A pin and its servo pulse range in microseconds.
+    ServoConfig(u8, u16, u16),
+    StringData(String),
+    /// Any other sysex command with its payload, e.g. of an extension.
+    Sysex(u8, Vec<u8>),
+}
+
+/// An I2C_REQUEST as sent by a client.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct I2cRequest {
+    /// A 7 bit address, or 10 bit if `ten_bit` is set.
+    pub address: u16,
+    pub ten_bit: bool,
+    /// 0 write, 1 read, 2 read continuously, 3 stop reading, see [`I2cMode`].
+    pub mode: u8,
+    pub restart: bool,
+    /// The 14 bit values following the header, the bytes of a write or the optional
+    /// register and the size of a read.
+    pub data: Vec<u16>,
+}
+
+impl I2cRequest {
+    #[must_use]
+    pub const fn is_write(&self) -> bool {
+        self.mode == I2cMode::Write.to_u8()
+    }
+
+    /// Whether the request reads once or continuously.
+    #[must_use]
+    pub const fn is_read(&self) -> bool {
+        self.mode == I2cMode::Read.to_u8() || self.mode == I2cMode::ReadContinuously.to_u8()
+    }
+
+    /// The bytes of a write.
+    #[must_use]
+    pub fn bytes(&self) -> Vec<u8> {
+        self.data.iter().map(|v| *v as u8).collect()
+    }
+
+    /// The register a read starts at, if the client sent one.
+    #[must_use]
+    pub fn register(&self) -> Option<u16> {
+        match *self.data.as_slice() {
+            [register, _] => Some(register),
+            _ => None,
+        }
+    }
+
+    /// The number of bytes to read.
+    #[must_use]
+    pub fn read_size(&self) -> Option<u16> {
+        self.data.last().copied()
+    }
+
+    fn deserialize(payload: &[u8]) -> Option<Self> {
+        let [address, mode, ref data @ ..] = *payload else {
+            return None;
+        };
+        let ten_bit = mode & 0x20 != 0;
+        let address = if ten_bit {
+            u16::from(address) | u16::from(mode & 0x07) << 7
+        } else {
+            u16::from(address)
+        };
+        Some(Self {
+            address,
+            ten_bit,
+            mode: (mode >> 3) & 0x03,
+            restart: mode & 0x40 != 0,
+            data: data
+                .chunks_exact(2)
+                .map(|pair| message::decode_u14(pair[0], pair[1]))
+                .collect(),
+        })
+    }
+}
+
+impl HostMessage {
+    /// The length of the command at the start of the input, `None` until all of it
+    /// arrived. A stray data byte counts as a command of its own, [`HostMessage::decode`]
+    /// drops it.
+    #[must_use]
+    pub fn frame_length(input: &[u8]) -> Option<usize> {
+        let length = match *input.first()? {
+            START_SYSEX => input.iter().position(|v| *v == END_SYSEX)? + 1,
+            0x90..=0x9F | 0xE0..=0xEF | 0xF4 | 0xF5 => 3,
+            0xC0..=0xDF => 2,
+            _ => 1,
+        };
+        (input.len() >= length).then_some(length)
+    }
+
+    /// Decodes a complete command as found by [`HostMessage::frame_length`], `None` for
+    /// bytes that are not a command a board understands.
+    #[must_use]
+    pub fn decode(frame: &[u8]) -> Option<Self> {
+        Some(match *frame {
+            [START_SYSEX, command, ref payload @ .., END_SYSEX] => {
+                Self::decode_sysex(command, payload)
+            }
+            [0xF9] => Self::ProtocolVersionQuery,
+            [0xFF] => Self::SystemReset,
+            [command @ 0xC0..=0xCF, enable] => Self::ReportAnalog(command & 0x0F, enable != 0),
+            [command @ 0xD0..=0xDF, enable] => Self::ReportDigital(command & 0x0F, enable != 0),
+            [0xF4, pin, mode] => Self::SetPinMode(pin, PinMode::from_u8(mode).ok()?),
+            [0xF5, pin, level] => Self::DigitalPinWrite(pin, level != 0),
+            [command @ 0x90..=0x9F, lsb, msb] => {
+                Self::DigitalPortWrite(command & 0x0F, message::decode_u14(lsb, msb) as u8)
+            }
+            [command @ 0xE0..=0xEF, lsb, msb] => {
+                Self::AnalogWrite(command & 0x0F, message::decode_u14(lsb, msb))
+            }
+            _ => return None,
+        })
+    }
+
+    fn decode_sysex(command: u8, payload: &[u8]) -> Self {
+        let sysex = || Self::Sysex(command, payload.to_vec());
+        match (SysexCommand::try_from(command), payload) {
+            (Ok(SysexCommand::ReportFirmware), []) => Self::FirmwareQuery,
+            (Ok(SysexCommand::CapabilityQuery), []) => Self::CapabilityQuery,
+            (Ok(SysexCommand::AnalogMappingQuery), []) => Self::AnalogMappingQuery,
+            (Ok(SysexCommand::PinStateQuery), [pin]) => Self::PinStateQuery(*pin),
+            (Ok(SysexCommand::SamplingInterval), [lsb, msb]) => Self::SamplingInterval(
+                Duration::from_millis(u64::from(message::decode_u14(*lsb, *msb))),
+            ),
+            (Ok(SysexCommand::I2cConfig), [lsb, msb, ..]) => {
+                Self::I2cConfig(message::decode_u14(*lsb, *msb))
+            }
+            (Ok(SysexCommand::I2cRequest), _) => {
+                I2cRequest::deserialize(payload).map_or_else(sysex, Self::I2cRequest)
+            }
+            (Ok(SysexCommand::ExtendedAnalog), [pin, value @ ..]) if !value.is_empty() => {
+                let value = value
+                    .iter()
+                    .take(3)
+                    .rev()
+                    .fold(0_u32, |value, byte| value << 7 | u32::from(byte & 0x7F));
+                Self::AnalogWrite(*pin, value.min(u32::from(u16::MAX)) as u16)
+            }
+            (Ok(SysexCommand::ServoConfig), [pin, min_lsb, min_msb, max_lsb, max_msb]) => {
+                Self::ServoConfig(
+                    *pin,
+                    message::decode_u14(*min_lsb, *min_msb),
+                    message::decode_u14(*max_lsb, *max_msb),
+                )
+            }
+            (Ok(SysexCommand::StringData), _) => Self::StringData(text::decode(payload)),
+            _ => sysex(),
+        }
+    }
+}
+
+/// What a board sends to a client.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum DeviceMessage {
+    /// The major and minor protocol version.
+    ProtocolVersion(u8, u8),
+    Firmware {
+        name: String,
+        major: u8,
+        minor: u8,
+    },
+    /// The modes of every pin.
+    Capabilities(Vec<Pin>),
+    /// The analog channel of every pin, `None` for digital pins.
+    AnalogMapping(Vec<Option<u8>>),
+    PinState {
+        pin: u8,
+        mode: PinMode,
+        value: u16,
+    },
+    /// The 14 bit value of an analog channel.
+    Analog(u8, u16),
+    /// A port and the levels of its eight pins.
+    DigitalPort(u8, u8),
+    I2cReply {
+        address: u16,
+        register: u16,
+        data: Vec<u8>,
+    },
+    StringData(String),
+    /// Any other sysex command with its payload of 7 bit bytes.
+    Sysex(u8, Vec<u8>),
+}
+
+impl DeviceMessage {
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::ProtocolVersion(major, minor) => {
+                vec![MidiCommand::ProtocolVersion.to_u8(), *major, *minor]
+            }
+            Self::Firmware { name, major, minor } => {
+                let mut body = vec![*major, *minor];
+                for byte in name.bytes() {
+                    body.extend_from_slice(&[byte & 0x7F, byte >> 7]);
+                }
+                SysexCommand::ReportFirmware.frame(&body)
+            }
+            Self::Capabilities(pins) => {
+                let mut body = vec![];
+                for pin in pins {
+                    for mode in &pin.modes {
+                        body.extend_from_slice(&[mode.mode.to_u8(), mode.resolution]);
+                    }
+                    body.push(0x7F);
+                }
+                SysexCommand::CapabilityResponse.frame(&body)
+            }
+            Self::AnalogMapping(channels) => {
+                let body: Vec<u8> = channels.iter().map(|v| v.unwrap_or(0x7F)).collect();
+                SysexCommand::AnalogMappingResponse.frame(&body)
+            }
+            Self::PinState { pin, mode, value } => {
+                let mut body = vec![*pin, mode.to_u8()];
+                let mut value = *value;
+                loop {
+                    body.push((value & 0x7F) as u8);
+                    value >>= 7;
+                    if value == 0 {
+                        break;
+                    }
+                }
+                SysexCommand::PinStateResponse.frame(&body)
+            }
+            Self::Analog(channel, value) => {
+                let [lsb, msb] = message::encode_u14(*value);
+                vec![MidiCommand::AnalogMessage.with_channel(*channel), lsb, msb]
+            }
+            Self::DigitalPort(port, value) => digital::encode_port_write(*port, *value).to_vec(),
+            Self::I2cReply {
+                address,
+                register,
+                data,
+            } => {
+                let mut body = vec![];
+                body.extend_from_slice(&message::encode_u14(*address));
+                body.extend_from_slice(&message::encode_u14(*register));
+                for byte in data {
+                    body.extend_from_slice(&message::encode_u14(u16::from(*byte)));
+                }
+                SysexCommand::I2cReply.frame(&body)
+            }
+            Self::StringData(string) => text::encode(string),
+            Self::Sysex(command, payload) => {
+                let mut buf = vec![START_SYSEX, *command & 0x7F];
+                buf.extend(payload.iter().map(|v| v & 0x7F));
+                buf.push(END_SYSEX);
+                buf
+            }
+        }
+    }
+}
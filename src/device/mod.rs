@@ -0,0 +1,11 @@
+//! The board side of the protocol, for firmwares written in Rust, bridges and test
+//! fixtures that answer a host instead of talking to a board.
+pub mod codec;
+pub mod io;
+pub mod message;
+pub mod state;
+
+pub use codec::DeviceCodec;
+pub use io::{DeviceHandler, DeviceIo, DeviceReporter};
+pub use message::{DeviceMessage, HostMessage, I2cRequest};
+pub use state::DeviceState;
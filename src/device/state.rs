@@ -0,0 +1,174 @@
+use super::message::{DeviceMessage, HostMessage};
+use crate::digital;
+use crate::profile::BoardProfile;
+use crate::{PinMode, PinStates, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+/// The sampling interval of StandardFirmata until the host sets one.
+pub const DEFAULT_SAMPLING_INTERVAL: Duration = Duration::from_millis(19);
+
+/// What a board knows about itself and what the host asked of it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeviceState {
+    pub name: String,
+    /// Major and minor version reported with the name.
+    pub version: (u8, u8),
+    /// Major and minor protocol version answered to a version query.
+    pub protocol: (u8, u8),
+    /// The pins with the modes and values the host set.
+    pub pins: PinStates,
+    pub reported_channels: BTreeSet<u8>,
+    pub reported_ports: BTreeSet<u8>,
+    pub sampling_interval: Duration,
+    /// The delay in microseconds the host asked for between an I2C write and read.
+    pub i2c_read_delay: u16,
+}
+
+impl DeviceState {
+    #[must_use]
+    pub fn new(name: &str, pins: PinStates) -> Self {
+        Self {
+            name: name.to_string(),
+            version: (2, 5),
+            protocol: (2, 6),
+            pins,
+            reported_channels: BTreeSet::new(),
+            reported_ports: BTreeSet::new(),
+            sampling_interval: DEFAULT_SAMPLING_INTERVAL,
+            i2c_read_delay: 0,
+        }
+    }
+
+    /// # Errors
+    /// Returns [`crate::FirmataError::StateError`] if the profile refers to a pin it
+    /// does not have.
+    pub fn from_profile(name: &str, profile: &BoardProfile) -> Result<Self> {
+        Ok(Self::new(name, profile.pin_states()?))
+    }
+
+    /// Whether the host enabled the reports of the channel or port of an analog or
+    /// digital report, other messages are always sent.
+    #[must_use]
+    pub fn is_reported(&self, message: &DeviceMessage) -> bool {
+        match message {
+            DeviceMessage::Analog(channel, _) => self.reported_channels.contains(channel),
+            DeviceMessage::DigitalPort(port, _) => self.reported_ports.contains(port),
+            _ => true,
+        }
+    }
+
+    /// The levels of the eight pins of a port as last written, bit 0 being the first pin.
+    #[must_use]
+    pub fn port_value(&self, port: u8) -> u8 {
+        (0..8).fold(0_u8, |value, bit| {
+            let level = self
+                .pins
+                .pins
+                .get(usize::from(port * 8 + bit))
+                .is_some_and(|v| v.value != 0);
+            value | u8::from(level) << bit
+        })
+    }
+
+    /// Applies a command the way StandardFirmata does and returns the answers to
+    /// queries. Commands it does not know, such as I2C requests, are left to the
+    /// handlers of a [`super::DeviceIo`].
+    pub fn apply(&mut self, message: &HostMessage) -> Vec<DeviceMessage> {
+        match message {
+            HostMessage::ProtocolVersionQuery => {
+                let (major, minor) = self.protocol;
+                return vec![DeviceMessage::ProtocolVersion(major, minor)];
+            }
+            HostMessage::FirmwareQuery => {
+                let (major, minor) = self.version;
+                return vec![DeviceMessage::Firmware {
+                    name: self.name.clone(),
+                    major,
+                    minor,
+                }];
+            }
+            HostMessage::CapabilityQuery => {
+                return vec![DeviceMessage::Capabilities(self.pins.pins.clone())];
+            }
+            HostMessage::AnalogMappingQuery => {
+                let channels = (0..self.pins.pins.len() as u8)
+                    .map(|pin| self.pins.channel_for_pin(pin))
+                    .collect();
+                return vec![DeviceMessage::AnalogMapping(channels)];
+            }
+            HostMessage::PinStateQuery(pin) => {
+                if let Some(physical) = self.pins.pins.get(usize::from(*pin)) {
+                    return vec![DeviceMessage::PinState {
+                        pin: *pin,
+                        mode: physical.mode,
+                        value: physical.value,
+                    }];
+                }
+            }
+            HostMessage::SystemReset => self.reset(),
+            HostMessage::SetPinMode(pin, mode) => {
+                if let Some(pin) = self.pins.pins.get_mut(usize::from(*pin)) {
+                    pin.mode = *mode;
+                }
+            }
+            HostMessage::DigitalPortWrite(port, value) => {
+                for bit in 0..8 {
+                    let pin = port * 8 + bit;
+                    if self
+                        .pins
+                        .pins
+                        .get(usize::from(pin))
+                        .is_some_and(|v| v.mode == PinMode::Output)
+                    {
+                        self.write(pin, u16::from(*value & digital::mask_of(pin) != 0));
+                    }
+                }
+            }
+            HostMessage::DigitalPinWrite(pin, level) => self.write(*pin, u16::from(*level)),
+            HostMessage::AnalogWrite(pin, value) => self.write(*pin, *value),
+            HostMessage::ReportAnalog(channel, enable) => {
+                if !enable {
+                    self.reported_channels.remove(channel);
+                } else if self.pins.pin_for_channel(*channel).is_some() {
+                    self.reported_channels.insert(*channel);
+                }
+            }
+            HostMessage::ReportDigital(port, enable) => {
+                if *enable {
+                    self.reported_ports.insert(*port);
+                } else {
+                    self.reported_ports.remove(port);
+                }
+            }
+            HostMessage::SamplingInterval(interval) => {
+                self.sampling_interval = (*interval).max(Duration::from_millis(1));
+            }
+            HostMessage::I2cConfig(delay) => self.i2c_read_delay = *delay,
+            HostMessage::I2cRequest(_)
+            | HostMessage::ServoConfig(..)
+            | HostMessage::StringData(_)
+            | HostMessage::Sysex(..) => {}
+        }
+        vec![]
+    }
+
+    fn write(&mut self, pin: u8, value: u16) {
+        if let Some(pin) = self.pins.pins.get_mut(usize::from(pin)) {
+            pin.value = value;
+        }
+    }
+
+    /// Back to the power on state, the pins keep their capabilities.
+    pub fn reset(&mut self) {
+        for pin in &mut self.pins.pins {
+            pin.mode = PinMode::Input;
+            pin.value = 0;
+        }
+        self.sampling_interval = DEFAULT_SAMPLING_INTERVAL;
+        self.reported_channels.clear();
+        self.reported_ports.clear();
+        self.i2c_read_delay = 0;
+    }
+}
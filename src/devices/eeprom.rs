@@ -0,0 +1,152 @@
+use crate::standard::board::Board;
+use crate::{FirmataError, Result};
+use std::io;
+use std::time::Duration;
+
+/// Describes the geometry of a 24Cxx style I2C EEPROM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EepromConfig {
+    /// The 7 bit bus address of the chip, `0x50` when all address pins are low.
+    pub address: u8,
+    /// Total size in bytes.
+    pub capacity: usize,
+    /// Writes may not cross a page boundary.
+    pub page_size: usize,
+    /// The number of memory address bytes, small chips use one byte and put the
+    /// remaining address bits into the bus address.
+    pub address_bytes: u8,
+    /// Time the chip needs to commit a page before it responds again.
+    pub write_cycle: Duration,
+    /// Largest transfer the firmware's I2C buffer can carry in a single request.
+    pub max_transfer: usize,
+    /// How long to wait for a read reply.
+    pub timeout: Duration,
+}
+
+impl EepromConfig {
+    const fn new(address: u8, capacity: usize, page_size: usize, address_bytes: u8) -> Self {
+        Self {
+            address,
+            capacity,
+            page_size,
+            address_bytes,
+            write_cycle: Duration::from_millis(5),
+            max_transfer: 16,
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    #[must_use]
+    pub const fn at24c02(address: u8) -> Self {
+        Self::new(address, 256, 8, 1)
+    }
+
+    #[must_use]
+    pub const fn at24c16(address: u8) -> Self {
+        Self::new(address, 2048, 16, 1)
+    }
+
+    #[must_use]
+    pub const fn at24c32(address: u8) -> Self {
+        Self::new(address, 4096, 32, 2)
+    }
+
+    #[must_use]
+    pub const fn at24c256(address: u8) -> Self {
+        Self::new(address, 32768, 64, 2)
+    }
+
+    /// Returns the bus address and memory address bytes used to reach a location.
+    fn locate(&self, location: usize) -> (u8, Vec<u8>) {
+        if self.address_bytes == 1 {
+            let block = ((location >> 8) & 0x07) as u8;
+            (self.address | block, vec![(location & 0xFF) as u8])
+        } else {
+            (
+                self.address,
+                vec![((location >> 8) & 0xFF) as u8, (location & 0xFF) as u8],
+            )
+        }
+    }
+}
+
+/// A 24Cxx EEPROM attached to a [`Board`]'s I2C bus.
+#[derive(Debug)]
+pub struct Eeprom<'a, T: io::Read + io::Write> {
+    board: &'a mut Board<T>,
+    config: EepromConfig,
+}
+
+impl<'a, T: io::Read + io::Write> Eeprom<'a, T> {
+    pub fn new(board: &'a mut Board<T>, config: EepromConfig) -> Self {
+        Self { board, config }
+    }
+
+    #[must_use]
+    pub const fn config(&self) -> &EepromConfig {
+        &self.config
+    }
+
+    fn check_range(&self, location: usize, len: usize) -> Result<()> {
+        if location + len > self.config.capacity {
+            return Err(FirmataError::OutOfRange(
+                "eeprom access exceeds the capacity of the chip",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reads `len` bytes starting at `location`, split into transfers the firmware can carry.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the range exceeds the chip or
+    /// [`FirmataError::Timeout`] if the chip stopped answering.
+    pub fn read(&mut self, location: usize, len: usize) -> Result<Vec<u8>> {
+        self.check_range(location, len)?;
+        let mut data = Vec::with_capacity(len);
+        while data.len() < len {
+            let current = location + data.len();
+            // Single address byte chips change bus address every 256 bytes.
+            let block_remaining = if self.config.address_bytes == 1 {
+                256 - (current & 0xFF)
+            } else {
+                usize::MAX
+            };
+            let chunk = (len - data.len())
+                .min(self.config.max_transfer)
+                .min(block_remaining);
+            let (bus_address, memory_address) = self.config.locate(current);
+            self.board.i2c_write(bus_address, &memory_address)?;
+            self.board.i2c_read(bus_address, chunk as u16)?;
+            let reply = self
+                .board
+                .wait_for_i2c_reply(bus_address, self.config.timeout)?;
+            data.extend(reply.data.into_iter().take(chunk));
+        }
+        Ok(data)
+    }
+
+    /// Writes the bytes starting at `location`, split on page boundaries with a write
+    /// cycle delay after every page.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the range exceeds the chip.
+    pub fn write(&mut self, location: usize, bytes: &[u8]) -> Result<()> {
+        self.check_range(location, bytes.len())?;
+        let max_data = self
+            .config
+            .max_transfer
+            .saturating_sub(self.config.address_bytes as usize)
+            .max(1);
+        let mut written = 0;
+        while written < bytes.len() {
+            let current = location + written;
+            let page_remaining = self.config.page_size - (current % self.config.page_size);
+            let chunk = (bytes.len() - written).min(page_remaining).min(max_data);
+            let (bus_address, mut payload) = self.config.locate(current);
+            payload.extend_from_slice(&bytes[written..written + chunk]);
+            self.board.i2c_write(bus_address, &payload)?;
+            std::thread::sleep(self.config.write_cycle);
+            written += chunk;
+        }
+        Ok(())
+    }
+}
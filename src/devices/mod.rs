@@ -0,0 +1,4 @@
+//! Drivers for common peripherals built on top of the board APIs.
+pub mod eeprom;
+pub mod seven_segment;
+pub mod soft_pwm;
@@ -0,0 +1,225 @@
+use crate::asynchronous::board::Board;
+use crate::{FirmataError, PinId, PinMode, Result};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Segment bit used for the decimal point, bits 0 to 6 are segments a to g.
+pub const SEGMENT_DP: u8 = 0x80;
+
+/// Segment patterns for the digits 0 to 9.
+pub const DIGIT_SEGMENTS: [u8; 10] = [0x3F, 0x06, 0x5B, 0x4F, 0x66, 0x6D, 0x7D, 0x07, 0x7F, 0x6F];
+
+/// Returns the segment pattern for a character, unsupported characters are blank.
+#[must_use]
+pub fn encode_char(c: char) -> u8 {
+    match c.to_ascii_uppercase() {
+        d @ '0'..='9' => DIGIT_SEGMENTS[d as usize - '0' as usize],
+        'A' => 0x77,
+        'B' => 0x7C,
+        'C' => 0x39,
+        'D' => 0x5E,
+        'E' => 0x79,
+        'F' => 0x71,
+        'G' => 0x3D,
+        'H' => 0x76,
+        'I' => 0x30,
+        'J' => 0x1E,
+        'L' => 0x38,
+        'N' => 0x54,
+        'O' => 0x5C,
+        'P' => 0x73,
+        'R' => 0x50,
+        'S' => 0x6D,
+        'T' => 0x78,
+        'U' => 0x3E,
+        'Y' => 0x6E,
+        '-' => 0x40,
+        '_' => 0x08,
+        _ => 0x00,
+    }
+}
+
+/// How the segments are connected to the board.
+#[derive(Debug, Clone)]
+pub enum Wiring {
+    /// Segments a to g and the decimal point wired to individual pins.
+    Direct { segments: [PinId; 8] },
+    /// Segments driven by a 74HC595 style shift register, bit 7 is shifted first.
+    ShiftRegister {
+        data: PinId,
+        clock: PinId,
+        latch: PinId,
+    },
+}
+
+/// Configuration of a [`SevenSegment`] display.
+#[derive(Debug, Clone)]
+pub struct SevenSegmentConfig {
+    pub wiring: Wiring,
+    /// Digit select pins from left to right, empty for a single always on digit.
+    pub digits: Vec<PinId>,
+    pub common_anode: bool,
+    /// How long each digit is lit before moving to the next one.
+    pub refresh: Duration,
+}
+
+impl SevenSegmentConfig {
+    #[must_use]
+    pub fn new(wiring: Wiring, digits: Vec<PinId>) -> Self {
+        Self {
+            wiring,
+            digits,
+            common_anode: false,
+            refresh: Duration::from_millis(4),
+        }
+    }
+}
+
+/// A multi digit 7-segment display refreshed by a background task.
+#[derive(Debug)]
+pub struct SevenSegment {
+    buffer: watch::Sender<Vec<u8>>,
+    digits: usize,
+    task: JoinHandle<Result<()>>,
+}
+
+impl SevenSegment {
+    /// Configures every pin as an output and starts multiplexing the digits.
+    /// # Errors
+    /// Returns an error if the pin modes could not be sent.
+    pub async fn start(mut board: Board, config: SevenSegmentConfig) -> Result<Self> {
+        let mut outputs: Vec<PinId> = match &config.wiring {
+            Wiring::Direct { segments } => segments.to_vec(),
+            Wiring::ShiftRegister { data, clock, latch } => vec![*data, *clock, *latch],
+        };
+        outputs.extend(config.digits.iter().copied());
+        for pin in outputs {
+            board.set_pin_mode(pin, PinMode::Output).await?;
+        }
+        let digits = config.digits.len().max(1);
+        let (buffer, rx) = watch::channel(vec![0; digits]);
+        let task = tokio::spawn(multiplex(board, config, rx));
+        Ok(Self {
+            buffer,
+            digits,
+            task,
+        })
+    }
+
+    #[must_use]
+    pub const fn digits(&self) -> usize {
+        self.digits
+    }
+
+    /// Sets the raw segment pattern of every digit from left to right.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if more patterns than digits are given.
+    pub fn set_segments(&self, segments: &[u8]) -> Result<()> {
+        if segments.len() > self.digits {
+            return Err(FirmataError::OutOfRange(
+                "more segment patterns than digits on the display",
+            ));
+        }
+        let mut buffer = vec![0; self.digits - segments.len()];
+        buffer.extend_from_slice(segments);
+        self.buffer.send_replace(buffer);
+        Ok(())
+    }
+
+    /// Shows a right aligned number.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the number does not fit on the display.
+    pub fn display_number(&self, value: i64) -> Result<()> {
+        self.display_text(&value.to_string())
+    }
+
+    /// Shows right aligned text, a `.` lights the decimal point of the previous character.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the text does not fit on the display.
+    pub fn display_text(&self, text: &str) -> Result<()> {
+        let mut segments: Vec<u8> = vec![];
+        for c in text.chars() {
+            match (c, segments.last_mut()) {
+                ('.', Some(last)) if *last & SEGMENT_DP == 0 => *last |= SEGMENT_DP,
+                ('.', _) => segments.push(SEGMENT_DP),
+                _ => segments.push(encode_char(c)),
+            }
+        }
+        self.set_segments(&segments)
+    }
+
+    pub fn clear(&self) {
+        self.buffer.send_replace(vec![0; self.digits]);
+    }
+
+    /// Returns true if the refresh task stopped, e.g. because the board went away.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl Drop for SevenSegment {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn write_segments(
+    board: &mut Board,
+    wiring: &Wiring,
+    pattern: u8,
+    common_anode: bool,
+) -> Result<()> {
+    let pattern = if common_anode { !pattern } else { pattern };
+    match wiring {
+        Wiring::Direct { segments } => {
+            for (bit, pin) in segments.iter().enumerate() {
+                board.digital_write(*pin, pattern & (1 << bit) != 0).await?;
+            }
+        }
+        Wiring::ShiftRegister { data, clock, latch } => {
+            board.digital_write(*latch, false).await?;
+            for bit in (0..8).rev() {
+                board
+                    .digital_write(*data, pattern & (1 << bit) != 0)
+                    .await?;
+                board.digital_write(*clock, true).await?;
+                board.digital_write(*clock, false).await?;
+            }
+            board.digital_write(*latch, true).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn multiplex(
+    mut board: Board,
+    config: SevenSegmentConfig,
+    mut buffer: watch::Receiver<Vec<u8>>,
+) -> Result<()> {
+    // Common cathode digits are enabled by pulling them low.
+    let digit_on = config.common_anode;
+    if config.digits.is_empty() {
+        loop {
+            let pattern = buffer.borrow_and_update().first().copied().unwrap_or(0);
+            write_segments(&mut board, &config.wiring, pattern, config.common_anode).await?;
+            if buffer.changed().await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+    for pin in &config.digits {
+        board.digital_write(*pin, !digit_on).await?;
+    }
+    loop {
+        for (index, pin) in config.digits.iter().enumerate() {
+            let pattern = buffer.borrow().get(index).copied().unwrap_or(0);
+            write_segments(&mut board, &config.wiring, pattern, config.common_anode).await?;
+            board.digital_write(*pin, digit_on).await?;
+            tokio::time::sleep(config.refresh).await;
+            board.digital_write(*pin, !digit_on).await?;
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use crate::asynchronous::board::Board;
+use crate::{FirmataError, PinId, PinMode, Result};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// The fastest period that is toggled, each edge is a separate message to the board.
+pub const MIN_SOFT_PWM_PERIOD: Duration = Duration::from_millis(10);
+
+/// A PWM signal on a plain digital output, toggled by a background task. Only suited to
+/// slow loads such as heaters or slow dimming since the edges jitter with the link.
+#[derive(Debug)]
+pub struct SoftPwm {
+    board: Board,
+    pin: PinId,
+    duty: watch::Sender<f64>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl SoftPwm {
+    /// Configures the pin as an output and starts toggling it with a duty of zero.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the period is shorter than
+    /// [`MIN_SOFT_PWM_PERIOD`] or an error if the pin mode could not be sent.
+    pub async fn start(mut board: Board, pin: PinId, period: Duration) -> Result<Self> {
+        if period < MIN_SOFT_PWM_PERIOD {
+            return Err(FirmataError::OutOfRange(
+                "soft pwm period is shorter than 10 ms",
+            ));
+        }
+        board.set_pin_mode(pin, PinMode::Output).await?;
+        board.digital_write(pin, false).await?;
+        let (duty, rx) = watch::channel(0.0);
+        let task = tokio::spawn(toggle(board.clone(), pin, period, rx));
+        Ok(Self {
+            board,
+            pin,
+            duty,
+            task,
+        })
+    }
+
+    /// Sets the fraction of the period the pin is high.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the duty is outside of 0.0 to 1.0.
+    pub fn set_duty(&self, duty: f64) -> Result<()> {
+        if !(0.0..=1.0).contains(&duty) {
+            return Err(FirmataError::OutOfRange(
+                "duty cycle must be between 0.0 and 1.0",
+            ));
+        }
+        self.duty.send_replace(duty);
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn duty(&self) -> f64 {
+        *self.duty.borrow()
+    }
+
+    /// Returns true if the toggle task stopped, e.g. because the board went away.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Stops toggling and leaves the pin low.
+    /// # Errors
+    /// Returns an error if the pin could not be written.
+    pub async fn stop(mut self) -> Result<()> {
+        self.task.abort();
+        self.board.digital_write(self.pin, false).await
+    }
+}
+
+impl Drop for SoftPwm {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn toggle(
+    mut board: Board,
+    pin: PinId,
+    period: Duration,
+    mut duty: watch::Receiver<f64>,
+) -> Result<()> {
+    let mut level = false;
+    loop {
+        let current = *duty.borrow_and_update();
+        let high = period.mul_f64(current);
+        // Fully off or on only needs a write when the level changes.
+        if high.is_zero() || high >= period {
+            let wanted = !high.is_zero();
+            if wanted != level {
+                board.digital_write(pin, wanted).await?;
+                level = wanted;
+            }
+            if duty.changed().await.is_err() {
+                return Ok(());
+            }
+            continue;
+        }
+        board.digital_write(pin, true).await?;
+        tokio::time::sleep(high).await;
+        board.digital_write(pin, false).await?;
+        level = false;
+        tokio::time::sleep(period - high).await;
+    }
+}
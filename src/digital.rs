@@ -0,0 +1,115 @@
+//! Encoding of digital writes and the output latches they are computed from.
+//! DIGITAL_MESSAGE sets all eight pins of a port at once, so the levels written to
+//! every port are kept apart from the levels the firmware reports for inputs.
+use crate::message;
+use crate::protocol::MidiCommand;
+use crate::{FirmataError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The highest port DIGITAL_MESSAGE can address, it is sent in the command nibble.
+pub const MAX_PORT: u8 = 0x0F;
+
+/// The port a pin belongs to.
+#[must_use]
+pub const fn port_of(pin: u8) -> u8 {
+    pin / 8
+}
+
+/// The bit of a pin within its port.
+#[must_use]
+pub const fn mask_of(pin: u8) -> u8 {
+    1 << (pin % 8)
+}
+
+/// The levels last written to the outputs of every port, ports that were never
+/// written are low.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputLatches {
+    ports: BTreeMap<u8, u8>,
+}
+
+impl OutputLatches {
+    #[must_use]
+    pub fn port(&self, port: u8) -> u8 {
+        self.ports.get(&port).copied().unwrap_or(0)
+    }
+
+    #[must_use]
+    pub fn level(&self, pin: u8) -> bool {
+        self.port(port_of(pin)) & mask_of(pin) != 0
+    }
+
+    /// Sets the pins of the mask to their bits in the value and returns the new value
+    /// of the whole port.
+    pub fn write_port(&mut self, port: u8, mask: u8, value: u8) -> u8 {
+        let latch = self.ports.entry(port).or_insert(0);
+        *latch = (*latch & !mask) | (value & mask);
+        *latch
+    }
+
+    /// Sets one pin and returns the new value of its port.
+    pub fn write_pin(&mut self, pin: u8, level: bool) -> u8 {
+        let value = if level { 0xFF } else { 0 };
+        self.write_port(port_of(pin), mask_of(pin), value)
+    }
+
+    /// Forgets every written level, e.g. after the firmware was reset.
+    pub fn clear(&mut self) {
+        self.ports.clear();
+    }
+}
+
+/// Checks that a port can be written.
+/// # Errors
+/// Returns [`FirmataError::OutOfRange`] if the port exceeds [`MAX_PORT`].
+pub const fn validate_port(port: u8) -> Result<()> {
+    if port > MAX_PORT {
+        return Err(FirmataError::OutOfRange("digital port exceeds 15"));
+    }
+    Ok(())
+}
+
+/// Encodes the levels of a whole port as DIGITAL_MESSAGE.
+#[must_use]
+pub fn encode_port_write(port: u8, value: u8) -> [u8; 3] {
+    let [lsb, msb] = message::encode_u14(u16::from(value));
+    [MidiCommand::DigitalMessage.with_channel(port), lsb, msb]
+}
+
+/// Encodes the level of a single pin as SET_DIGITAL_PIN_VALUE, supported from protocol
+/// version 2.5.
+#[must_use]
+pub fn encode_pin_write(pin: u8, level: bool) -> [u8; 3] {
+    [
+        MidiCommand::SetDigitalPinValue.to_u8(),
+        pin & 0x7F,
+        u8::from(level),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masked_writes_keep_the_other_pins() {
+        let mut latches = OutputLatches::default();
+        assert_eq!(latches.write_port(1, 0x0F, 0xFF), 0x0F);
+        assert_eq!(latches.write_port(1, 0x81, 0x80), 0x8E);
+        assert_eq!(latches.port(1), 0x8E);
+        assert_eq!(latches.port(0), 0);
+    }
+
+    #[test]
+    fn pin_writes_set_their_bit() {
+        let mut latches = OutputLatches::default();
+        assert_eq!(latches.write_pin(9, true), 0x02);
+        assert_eq!(latches.write_pin(10, true), 0x06);
+        assert_eq!(latches.write_pin(9, false), 0x04);
+        assert!(latches.level(10));
+        assert!(!latches.level(9));
+        latches.clear();
+        assert!(!latches.level(10));
+    }
+}
@@ -0,0 +1,267 @@
+//! An append-only log of every message received from a board, for post-mortem debugging
+//! and for test fixtures recorded from real sessions. [`State::replay`] rebuilds the
+//! board state from a log.
+use crate::asynchronous::boardio::State;
+use crate::bounded::{BoundedBuffer, Eviction};
+use crate::message::{MessageIn, OneWireReply, StepperReply, System};
+use crate::{FirmataError, PinMode, PinStates, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// A received message and when it arrived, relative to the creation of the log.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub elapsed: Duration,
+    pub message: MessageIn,
+}
+
+/// Where an [`EventLog`] keeps its entries.
+pub trait LogStorage: Send + Sync + fmt::Debug {
+    /// # Errors
+    /// Returns an error if the entry could not be stored.
+    fn append(&mut self, entry: &LogEntry) -> Result<()>;
+
+    /// Every stored entry, oldest first.
+    /// # Errors
+    /// Returns an error if the entries could not be read back.
+    fn entries(&self) -> Result<Vec<LogEntry>>;
+}
+
+/// Keeps entries in memory, optionally only the most recent ones.
+#[derive(Debug, Clone)]
+pub struct MemoryStorage {
+    entries: BoundedBuffer<LogEntry>,
+}
+
+impl MemoryStorage {
+    #[must_use]
+    pub fn unbounded() -> Self {
+        Self {
+            entries: BoundedBuffer::unbounded(),
+        }
+    }
+
+    /// Keeps the most recent `capacity` entries, older ones are dropped.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: BoundedBuffer::with_capacity(capacity, Eviction::DropOldest),
+        }
+    }
+}
+
+impl LogStorage for MemoryStorage {
+    fn append(&mut self, entry: &LogEntry) -> Result<()> {
+        self.entries.push(entry.clone());
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<LogEntry>> {
+        Ok(self.entries.iter().cloned().collect())
+    }
+}
+
+/// Appends entries to a file as one JSON object per line, every entry is flushed so the
+/// log survives a crash of the application.
+#[derive(Debug)]
+pub struct FileStorage {
+    path: PathBuf,
+    file: File,
+}
+
+impl FileStorage {
+    /// Opens the file for appending, creating it if it does not exist.
+    /// # Errors
+    /// Returns an error if the file could not be opened.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self { path, file })
+    }
+
+    /// Reads the entries of a log file, e.g. to replay a recorded session in a test.
+    /// # Errors
+    /// Returns an error if the file could not be read or a line is not an entry.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Vec<LogEntry>> {
+        let mut entries = vec![];
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            entries.push(serde_json::from_str(&line).map_err(|_| {
+                FirmataError::ConversionFailure("failed to deserialize event log entry")
+            })?);
+        }
+        Ok(entries)
+    }
+}
+
+impl LogStorage for FileStorage {
+    fn append(&mut self, entry: &LogEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)
+            .map_err(|_| FirmataError::ConversionFailure("failed to serialize event log entry"))?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<LogEntry>> {
+        Self::read(&self.path)
+    }
+}
+
+/// Records received messages into a [`LogStorage`]. A failing storage does not stop
+/// the board, the failures are counted in [`EventLog::write_errors`].
+#[derive(Debug)]
+pub struct EventLog {
+    started: Instant,
+    storage: Box<dyn LogStorage>,
+    write_errors: u64,
+}
+
+impl EventLog {
+    pub fn new<S: LogStorage + 'static>(storage: S) -> Self {
+        Self {
+            started: Instant::now(),
+            storage: Box::new(storage),
+            write_errors: 0,
+        }
+    }
+
+    /// A log of the most recent `capacity` messages kept in memory.
+    #[must_use]
+    pub fn in_memory(capacity: usize) -> Self {
+        Self::new(MemoryStorage::with_capacity(capacity))
+    }
+
+    pub fn record(&mut self, message: &MessageIn) {
+        let entry = LogEntry {
+            elapsed: self.started.elapsed(),
+            message: message.clone(),
+        };
+        if self.storage.append(&entry).is_err() {
+            self.write_errors += 1;
+        }
+    }
+
+    /// # Errors
+    /// Returns an error if the storage could not be read.
+    pub fn entries(&self) -> Result<Vec<LogEntry>> {
+        self.storage.entries()
+    }
+
+    /// The entries received within `window` before the newest one, e.g. the minute
+    /// before a fault.
+    /// # Errors
+    /// Returns an error if the storage could not be read.
+    pub fn last(&self, window: Duration) -> Result<Vec<LogEntry>> {
+        let mut entries = self.entries()?;
+        if let Some(newest) = entries.last().map(|v| v.elapsed) {
+            let start = newest.saturating_sub(window);
+            entries.retain(|v| v.elapsed >= start);
+        }
+        Ok(entries)
+    }
+
+    pub const fn write_errors(&self) -> u64 {
+        self.write_errors
+    }
+}
+
+impl State {
+    /// Rebuilds the state a board io would have after receiving the logged messages,
+    /// starting from an empty state. Only what the firmware reported is covered, pin
+    /// modes set by the application and client side filters such as debouncing or
+    /// deadbands are not part of the log.
+    #[must_use]
+    pub fn replay(entries: &[LogEntry]) -> Self {
+        let mut state = Self::default();
+        for entry in entries {
+            state.apply(&entry.message);
+        }
+        state
+    }
+
+    /// Applies a single received message, messages that do not fit the state, e.g. a
+    /// value for a pin that does not exist, are skipped.
+    pub fn apply(&mut self, message: &MessageIn) {
+        match message {
+            MessageIn::Analog(v) => {
+                let pin = self.pin_state.pin_id_to_u8(v.pin);
+                if let Some(pin) = self.pin_state.pins.get_mut(usize::from(pin)) {
+                    pin.value = v.value;
+                }
+            }
+            MessageIn::Digital(v) => {
+                for bit in 0..8 {
+                    let index = usize::from(v.port) * 8 + bit;
+                    if let Some(pin) = self.pin_state.pins.get_mut(index) {
+                        if pin.mode == PinMode::Input {
+                            pin.value = (v.value >> bit) & 0x01;
+                        }
+                    }
+                }
+            }
+            MessageIn::System(System::AnalogMappingResponse(v)) => {
+                let _ = self.pin_state.map_analog_channels(v.channels.clone());
+            }
+            MessageIn::System(System::CapabilityResponseMessage(v)) => {
+                self.pin_state = PinStates::create(v.pins.clone());
+            }
+            MessageIn::System(System::ReportFirmwareMessage(v)) => {
+                self.firmware_name.clone_from(&v.name);
+                self.firmware_version.clone_from(&v.version);
+            }
+            MessageIn::System(System::PinStateResponseMessage(v)) => {
+                if let Some(pin) = self.pin_state.pins.get_mut(usize::from(v.pin)) {
+                    v.apply(pin);
+                }
+            }
+            MessageIn::System(System::PulseReplyMessage(v)) => {
+                self.pulses.entry(v.pin).or_default().record(v.duration_us);
+            }
+            MessageIn::System(System::StepperReplyMessage(StepperReply::MultiMoveComplete {
+                group,
+            })) => {
+                *self.stepper_group_moves.entry(*group).or_default() += 1;
+            }
+            MessageIn::System(System::StepperReplyMessage(StepperReply::MoveComplete {
+                device,
+                position,
+            })) => {
+                self.steppers
+                    .entry(*device)
+                    .or_default()
+                    .record_move(*position);
+            }
+            MessageIn::System(System::StepperReplyMessage(StepperReply::Position {
+                device,
+                position,
+            })) => {
+                self.steppers.entry(*device).or_default().position = *position;
+            }
+            MessageIn::System(System::OneWireReplyMessage(OneWireReply::Search {
+                pin,
+                addresses,
+            })) => {
+                self.onewire_devices.insert(*pin, addresses.clone());
+            }
+            MessageIn::System(
+                System::I2cReplyMessage(_)
+                | System::StringDataMessage(_)
+                | System::OneWireReplyMessage(_)
+                | System::SchedulerReplyMessage(_)
+                | System::SerialReply { .. },
+            ) => {}
+            MessageIn::UnknownSysex { .. } => self.stats.unknown_sysex += 1,
+            MessageIn::FrameError(v) => self.stats.record_frame_error(v),
+            MessageIn::ProtocolVersion(v) => self.protocol_version.clone_from(v),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Detects which optional features a firmware offers, from its capability response and
+//! protocol version. Handles for a feature can only be created if it was detected, so
+//! using an absent feature fails when the handle is created instead of being ignored by
+//! the firmware.
+use crate::compat::{self, Feature};
+use crate::{FirmataError, PinMode, PinStates, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// The optional features of a board, pins are listed by index.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureSet {
+    pub i2c_pins: Vec<u8>,
+    pub servo_pins: Vec<u8>,
+    pub pwm_pins: Vec<u8>,
+    pub stepper_pins: Vec<u8>,
+    pub encoder_pins: Vec<u8>,
+    pub onewire_pins: Vec<u8>,
+    /// Serial ports with an RX or TX pin, hardware ports are 0 to 3.
+    pub serial_ports: BTreeSet<u8>,
+}
+
+impl FeatureSet {
+    /// Builds the feature set from the pins of a capability response, a feature the
+    /// protocol version is too old for is left out.
+    #[must_use]
+    pub fn detect(pin_state: &PinStates, protocol_version: &str) -> Self {
+        let pins_with = |mode: PinMode| -> Vec<u8> {
+            pin_state
+                .pins
+                .iter()
+                .enumerate()
+                .filter(|(_, pin)| pin.resolution(mode).is_some())
+                .map(|(index, _)| index as u8)
+                .collect()
+        };
+        let mut features = Self {
+            i2c_pins: pins_with(PinMode::I2c),
+            servo_pins: pins_with(PinMode::Servo),
+            pwm_pins: pins_with(PinMode::Pwm),
+            stepper_pins: pins_with(PinMode::Stepper),
+            encoder_pins: pins_with(PinMode::Encoder),
+            onewire_pins: pins_with(PinMode::Onewire),
+            // The resolution of a serial pin names its port, RX and TX alternating.
+            serial_ports: pin_state
+                .pins
+                .iter()
+                .filter_map(|pin| pin.resolution(PinMode::Serial))
+                .map(|resolution| resolution / 2)
+                .collect(),
+        };
+        if !compat::supports(protocol_version, Feature::I2c) {
+            features.i2c_pins.clear();
+        }
+        if !compat::supports(protocol_version, Feature::SerialData) {
+            features.serial_ports.clear();
+        }
+        features
+    }
+
+    #[must_use]
+    pub fn has_i2c(&self) -> bool {
+        !self.i2c_pins.is_empty()
+    }
+
+    #[must_use]
+    pub fn has_stepper(&self) -> bool {
+        !self.stepper_pins.is_empty()
+    }
+
+    #[must_use]
+    pub fn has_serial_port(&self, port: u8) -> bool {
+        self.serial_ports.contains(&port)
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::UnsupportedByFirmware`] if no pin supports I2C.
+    pub fn require_i2c(&self) -> Result<()> {
+        if self.has_i2c() {
+            Ok(())
+        } else {
+            Err(FirmataError::UnsupportedByFirmware(
+                "no pin of the board supports i2c",
+            ))
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use super::{Decoded, FrameFormat};
+use bytes::{Buf, BytesMut};
+
+/// Ends every frame, it never occurs inside an encoded frame.
+pub const DELIMITER: u8 = 0x00;
+/// Unterminated frames longer than this are dropped.
+pub const MAX_FRAME: usize = 1024;
+
+/// Consistent Overhead Byte Stuffing, removes every zero byte from the payload at a cost
+/// of at most one byte per 254 so the zero can delimit frames.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CobsFraming;
+
+/// Appends the COBS encoding of `payload` to `out`, without the delimiter.
+pub fn encode(payload: &[u8], out: &mut Vec<u8>) {
+    let mut code_index = out.len();
+    let mut code: u8 = 1;
+    out.push(0);
+    for byte in payload {
+        if *byte == 0 {
+            out[code_index] = code;
+            code_index = out.len();
+            code = 1;
+            out.push(0);
+            continue;
+        }
+        out.push(*byte);
+        code += 1;
+        if code == 0xFF {
+            out[code_index] = code;
+            code_index = out.len();
+            code = 1;
+            out.push(0);
+        }
+    }
+    out[code_index] = code;
+}
+
+/// Decodes a COBS encoded frame without the delimiter, `None` if it is malformed.
+#[must_use]
+pub fn decode(frame: &[u8]) -> Option<Vec<u8>> {
+    let mut payload = Vec::with_capacity(frame.len());
+    let mut index = 0;
+    while index < frame.len() {
+        let code = usize::from(frame[index]);
+        if code == 0 || index + code > frame.len() {
+            return None;
+        }
+        let end = index + code;
+        payload.extend_from_slice(&frame[index + 1..end]);
+        index = end;
+        if code < 0xFF && index < frame.len() {
+            payload.push(0);
+        }
+    }
+    Some(payload)
+}
+
+impl FrameFormat for CobsFraming {
+    fn encode(&self, payload: &[u8], out: &mut Vec<u8>) {
+        encode(payload, out);
+        out.push(DELIMITER);
+    }
+
+    fn decode(&self, buf: &mut BytesMut) -> Option<Decoded> {
+        let leading = buf.iter().take_while(|v| **v == DELIMITER).count();
+        buf.advance(leading);
+        let Some(len) = buf.iter().position(|v| *v == DELIMITER) else {
+            if buf.len() > MAX_FRAME {
+                let skip = buf.len();
+                buf.clear();
+                return Some(Decoded::Corrupted(skip));
+            }
+            return None;
+        };
+        let frame = buf.split_to(len + 1);
+        Some(match decode(&frame[..len]) {
+            Some(payload) => Decoded::Frame(payload),
+            None => Decoded::Corrupted(len + 1),
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use super::{Decoded, FrameFormat};
+use bytes::{Buf, BytesMut};
+
+/// First byte of every frame.
+pub const FRAME_START: u8 = 0xA5;
+/// The largest payload of a frame, longer claims are treated as corruption.
+pub const MAX_PAYLOAD: usize = 1024;
+
+/// CRC-16/CCITT-FALSE.
+#[must_use]
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for byte in data {
+        crc ^= u16::from(*byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 == 0 {
+                crc << 1
+            } else {
+                (crc << 1) ^ 0x1021
+            };
+        }
+    }
+    crc
+}
+
+/// Frames of a start byte, a little endian payload length, the payload and a little
+/// endian CRC-16 of the length and payload. After a bad frame the receiver looks for the
+/// next start byte.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CrcFraming;
+
+impl FrameFormat for CrcFraming {
+    fn encode(&self, payload: &[u8], out: &mut Vec<u8>) {
+        for chunk in payload.chunks(MAX_PAYLOAD) {
+            let start = out.len();
+            out.push(FRAME_START);
+            out.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+            out.extend_from_slice(chunk);
+            let crc = crc16(&out[start + 1..]);
+            out.extend_from_slice(&crc.to_le_bytes());
+        }
+    }
+
+    fn decode(&self, buf: &mut BytesMut) -> Option<Decoded> {
+        match buf.iter().position(|v| *v == FRAME_START) {
+            Some(0) => {}
+            Some(skip) => {
+                buf.advance(skip);
+                return Some(Decoded::Corrupted(skip));
+            }
+            None if buf.is_empty() => return None,
+            None => {
+                let skip = buf.len();
+                buf.clear();
+                return Some(Decoded::Corrupted(skip));
+            }
+        }
+        if buf.len() < 3 {
+            return None;
+        }
+        let len = usize::from(u16::from_le_bytes([buf[1], buf[2]]));
+        if len > MAX_PAYLOAD {
+            buf.advance(1);
+            return Some(Decoded::Corrupted(1));
+        }
+        if buf.len() < len + 5 {
+            return None;
+        }
+        let crc = u16::from_le_bytes([buf[len + 3], buf[len + 4]]);
+        if crc16(&buf[1..len + 3]) != crc {
+            buf.advance(1);
+            return Some(Decoded::Corrupted(1));
+        }
+        let frame = buf.split_to(len + 5);
+        Some(Decoded::Frame(frame[3..len + 3].to_vec()))
+    }
+}
@@ -0,0 +1,138 @@
+//! Framing around the Firmata byte stream for links that corrupt data, such as long
+//! RS-485 runs, or that carry packets rather than a stream, such as radio serial bridges.
+//! Both ends have to use the same format, the Firmata decoder sees only the payload of
+//! intact frames.
+use bytes::BytesMut;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream};
+
+pub mod cobs;
+pub mod crc;
+pub mod slip;
+
+const CHUNK_SIZE: usize = 256;
+
+/// Result of looking for a frame at the start of the buffered bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decoded {
+    /// The payload of an intact frame.
+    Frame(Vec<u8>),
+    /// Bytes that were dropped because they did not form an intact frame.
+    Corrupted(usize),
+}
+
+/// Encodes payloads into frames and finds frames in received bytes.
+pub trait FrameFormat: Send + 'static {
+    fn encode(&self, payload: &[u8], out: &mut Vec<u8>);
+
+    /// Removes the next frame or the corrupted bytes in front of it from the buffer,
+    /// `None` if more bytes are needed.
+    fn decode(&self, buf: &mut BytesMut) -> Option<Decoded>;
+}
+
+/// Counters of a framed link, shared with the tasks servicing it.
+#[derive(Debug, Default)]
+pub struct FramingStats {
+    frames: AtomicU64,
+    corrupted: AtomicU64,
+    discarded_bytes: AtomicU64,
+}
+
+impl FramingStats {
+    /// Intact frames received.
+    pub fn frames(&self) -> u64 {
+        self.frames.load(Ordering::Relaxed)
+    }
+
+    /// Corrupted frames dropped.
+    pub fn corrupted(&self) -> u64 {
+        self.corrupted.load(Ordering::Relaxed)
+    }
+
+    pub fn discarded_bytes(&self) -> u64 {
+        self.discarded_bytes.load(Ordering::Relaxed)
+    }
+}
+
+/// Wraps a transport so writes to the returned stream are sent as frames and the
+/// payload of every intact received frame can be read from it, e.g. to pass to
+/// [`BoardIo::create`](crate::asynchronous::boardio::BoardIo::create) after
+/// [`tokio::io::split`].
+///
+/// Must be called from within a tokio runtime, the link is serviced by two tasks that
+/// stop once either end is closed.
+pub fn framed<T, F>(transport: T, format: F) -> (DuplexStream, Arc<FramingStats>)
+where
+    T: AsyncRead + AsyncWrite + Send + 'static,
+    F: FrameFormat + Sync,
+{
+    let (stream, inner) = tokio::io::duplex(CHUNK_SIZE * 16);
+    let (inner_read, inner_write) = tokio::io::split(inner);
+    let (transport_read, transport_write) = tokio::io::split(transport);
+    let stats = Arc::new(FramingStats::default());
+    let format = Arc::new(format);
+    tokio::spawn(send_frames(inner_read, transport_write, format.clone()));
+    tokio::spawn(receive_frames(
+        transport_read,
+        inner_write,
+        format,
+        stats.clone(),
+    ));
+    (stream, stats)
+}
+
+async fn send_frames<R, W, F>(mut reader: R, mut writer: W, format: Arc<F>) -> io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+    F: FrameFormat,
+{
+    let mut buf = [0_u8; CHUNK_SIZE];
+    let mut frame = Vec::with_capacity(CHUNK_SIZE * 2);
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            return writer.shutdown().await;
+        }
+        frame.clear();
+        format.encode(&buf[..n], &mut frame);
+        writer.write_all(&frame).await?;
+        writer.flush().await?;
+    }
+}
+
+async fn receive_frames<R, W, F>(
+    mut reader: R,
+    mut writer: W,
+    format: Arc<F>,
+    stats: Arc<FramingStats>,
+) -> io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+    F: FrameFormat,
+{
+    let mut buf = BytesMut::with_capacity(CHUNK_SIZE * 4);
+    loop {
+        if reader.read_buf(&mut buf).await? == 0 {
+            return writer.shutdown().await;
+        }
+        while let Some(decoded) = format.decode(&mut buf) {
+            match decoded {
+                Decoded::Frame(payload) => {
+                    stats.frames.fetch_add(1, Ordering::Relaxed);
+                    writer.write_all(&payload).await?;
+                }
+                Decoded::Corrupted(bytes) => {
+                    stats.corrupted.fetch_add(1, Ordering::Relaxed);
+                    stats
+                        .discarded_bytes
+                        .fetch_add(bytes as u64, Ordering::Relaxed);
+                }
+            }
+        }
+        writer.flush().await?;
+    }
+}
@@ -0,0 +1,55 @@
+use super::{Decoded, FrameFormat};
+use bytes::{Buf, BytesMut};
+
+pub const END: u8 = 0xC0;
+pub const ESC: u8 = 0xDB;
+pub const ESC_END: u8 = 0xDC;
+pub const ESC_ESC: u8 = 0xDD;
+/// Unterminated frames longer than this are dropped.
+pub const MAX_FRAME: usize = 1024;
+
+/// SLIP (RFC 1055) framing, every frame is enclosed in `END` bytes so a receiver can
+/// join the stream at any point.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SlipFraming;
+
+impl FrameFormat for SlipFraming {
+    fn encode(&self, payload: &[u8], out: &mut Vec<u8>) {
+        out.push(END);
+        for byte in payload {
+            match *byte {
+                END => out.extend_from_slice(&[ESC, ESC_END]),
+                ESC => out.extend_from_slice(&[ESC, ESC_ESC]),
+                v => out.push(v),
+            }
+        }
+        out.push(END);
+    }
+
+    fn decode(&self, buf: &mut BytesMut) -> Option<Decoded> {
+        let leading = buf.iter().take_while(|v| **v == END).count();
+        buf.advance(leading);
+        let Some(len) = buf.iter().position(|v| *v == END) else {
+            if buf.len() > MAX_FRAME {
+                let skip = buf.len();
+                buf.clear();
+                return Some(Decoded::Corrupted(skip));
+            }
+            return None;
+        };
+        let frame = buf.split_to(len + 1);
+        let mut payload = Vec::with_capacity(len);
+        let mut bytes = frame[..len].iter();
+        while let Some(byte) = bytes.next() {
+            payload.push(match *byte {
+                ESC => match bytes.next() {
+                    Some(&ESC_END) => END,
+                    Some(&ESC_ESC) => ESC,
+                    _ => return Some(Decoded::Corrupted(len + 1)),
+                },
+                v => v,
+            });
+        }
+        Some(Decoded::Frame(payload))
+    }
+}
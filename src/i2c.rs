@@ -0,0 +1,197 @@
+//! I2C request options beyond the plain read and write of the core API.
+use crate::protocol::{I2cMode, SysexCommand, END_SYSEX, START_SYSEX};
+use crate::{message, I2CReply};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Bit 6 of the mode byte, the firmware ends the write of the register with a
+/// repeated start instead of a stop.
+const I2C_RESTART: u8 = 0x40;
+/// Bit 5 of the mode byte, the address continues in bits 0 to 2 of the mode byte.
+const I2C_TEN_BIT: u8 = 0x20;
+/// The longest read delay, it is sent as a 14 bit number of microseconds.
+pub const MAX_I2C_READ_DELAY: Duration = Duration::from_micros(0x3FFF);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum I2cAddressSize {
+    #[default]
+    SevenBit,
+    TenBit,
+}
+
+/// Settings of the I2C bus, sent with I2C_CONFIG and kept by the boards so they can be
+/// sent again after a reconnect. The address size and restart are applied to reads that
+/// go through the board.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct I2cConfig {
+    /// Microseconds the firmware waits between writing a register and reading, for
+    /// devices that need time to prepare the data.
+    pub read_delay_us: u16,
+    pub address_size: I2cAddressSize,
+    /// Sends a repeated start instead of a stop between register write and read.
+    pub restart: bool,
+}
+
+impl I2cConfig {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the read delay, clamped to [`MAX_I2C_READ_DELAY`].
+    #[must_use]
+    pub fn read_delay(mut self, delay: Duration) -> Self {
+        self.read_delay_us = delay.min(MAX_I2C_READ_DELAY).as_micros() as u16;
+        self
+    }
+
+    #[must_use]
+    pub fn address_size(mut self, address_size: I2cAddressSize) -> Self {
+        self.address_size = address_size;
+        self
+    }
+
+    #[must_use]
+    pub fn restart(mut self, restart: bool) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    /// Takes the bus defaults over into the options of a read, options set on the read
+    /// itself are kept.
+    #[must_use]
+    pub fn apply(&self, options: I2cReadOptions) -> I2cReadOptions {
+        I2cReadOptions {
+            restart: options.restart || self.restart,
+            address_size: match options.address_size {
+                I2cAddressSize::SevenBit => self.address_size,
+                I2cAddressSize::TenBit => I2cAddressSize::TenBit,
+            },
+            ..options
+        }
+    }
+
+    /// Encodes the I2C_CONFIG message, only the read delay goes to the firmware.
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let delay = self
+            .read_delay_us
+            .min(MAX_I2C_READ_DELAY.as_micros() as u16);
+        SysexCommand::I2cConfig.frame(&message::encode_u14(delay))
+    }
+}
+
+/// How a read request is sent, the default matches [`Board::i2c_read`](crate::standard::board::Board::i2c_read).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct I2cReadOptions {
+    pub size: u16,
+    /// Register written to the device before the bytes are read.
+    pub register: Option<u16>,
+    /// Sends a repeated start between the register write and the read instead of a
+    /// stop, which many IMUs and EEPROMs require.
+    pub restart: bool,
+    #[serde(default)]
+    pub address_size: I2cAddressSize,
+}
+
+impl I2cReadOptions {
+    #[must_use]
+    pub fn new(size: u16) -> Self {
+        Self {
+            size,
+            register: None,
+            restart: false,
+            address_size: I2cAddressSize::SevenBit,
+        }
+    }
+
+    #[must_use]
+    pub fn register(mut self, register: u16) -> Self {
+        self.register = Some(register);
+        self
+    }
+
+    #[must_use]
+    pub fn restart(mut self, restart: bool) -> Self {
+        self.restart = restart;
+        self
+    }
+}
+
+/// How long to wait for the reply of a read and how often to send the request again.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct I2cRetryPolicy {
+    /// Time allowed for each attempt.
+    pub timeout: Duration,
+    /// Attempts made after the first one timed out.
+    pub retries: u32,
+}
+
+impl Default for I2cRetryPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_millis(500),
+            retries: 2,
+        }
+    }
+}
+
+/// Whether a reply answers a read, the register is only compared if one was requested.
+#[must_use]
+pub fn reply_matches(reply: &I2CReply, addr: u8, options: &I2cReadOptions) -> bool {
+    reply.address == i32::from(addr)
+        && options
+            .register
+            .is_none_or(|register| reply.register == i32::from(register))
+}
+
+/// The address and mode bytes that start an I2C request.
+#[must_use]
+pub fn encode_request_header(
+    addr: u8,
+    mode: I2cMode,
+    address_size: I2cAddressSize,
+    restart: bool,
+) -> [u8; 2] {
+    let mut mode = mode.request_bits();
+    if restart {
+        mode |= I2C_RESTART;
+    }
+    if address_size == I2cAddressSize::TenBit {
+        mode |= I2C_TEN_BIT | (addr >> 7);
+    }
+    [addr & 0x7F, mode]
+}
+
+/// Encodes a write of bytes to a device, every byte is split into two 7 bit bytes.
+#[must_use]
+pub fn encode_write(addr: u8, data: &[u8]) -> Vec<u8> {
+    let [address, mode] =
+        encode_request_header(addr, I2cMode::Write, I2cAddressSize::SevenBit, false);
+    let mut buf = Vec::with_capacity(5 + data.len() * 2);
+    buf.extend_from_slice(&[START_SYSEX, SysexCommand::I2cRequest.to_u8(), address, mode]);
+    for byte in data {
+        buf.extend_from_slice(&message::encode_u14(u16::from(*byte)));
+    }
+    buf.push(END_SYSEX);
+    buf
+}
+
+/// Encodes a read request.
+#[must_use]
+pub fn encode_read(addr: u8, options: &I2cReadOptions) -> Vec<u8> {
+    let [address, mode] =
+        encode_request_header(addr, I2cMode::Read, options.address_size, options.restart);
+    let mut buf = vec![START_SYSEX, SysexCommand::I2cRequest.to_u8(), address, mode];
+    if let Some(register) = options.register {
+        buf.push((register & 0x7F) as u8);
+        buf.push(((register >> 7) & 0x7F) as u8);
+    }
+    buf.push((options.size & 0x7F) as u8);
+    buf.push(((options.size >> 7) & 0x7F) as u8);
+    buf.push(END_SYSEX);
+    buf
+}
@@ -0,0 +1,114 @@
+//! An ordered record of the configuration sent to a board, used to bring a board back
+//! to the same setup after a reconnect, on another board or in another session.
+use crate::i2c::I2cConfig;
+use crate::stepper::StepperConfig;
+use crate::PinMode;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A configuration command, as opposed to writes that only change output values.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum JournalEntry {
+    PinMode(u8, PinMode),
+    /// An analog channel, not a pin index.
+    ReportAnalog(u8, bool),
+    ReportDigital(u8, bool),
+    SamplingInterval(Duration),
+    I2cConfig(I2cConfig),
+    PwmFrequency(u8, u32),
+    StepperConfig(StepperConfig),
+    MultiStepperConfig(u8, Vec<u8>),
+    StepperSpeed(u8, f32),
+    StepperAcceleration(u8, f32),
+    ServoConfig(u8, u16, u16),
+    OneWireConfig(u8, bool),
+}
+
+impl JournalEntry {
+    /// Whether a later entry replaces this one, e.g. a new mode for the same pin.
+    fn superseded_by(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::PinMode(a, _), Self::PinMode(b, _))
+            | (Self::ReportAnalog(a, _), Self::ReportAnalog(b, _))
+            | (Self::ReportDigital(a, _), Self::ReportDigital(b, _))
+            | (Self::PwmFrequency(a, _), Self::PwmFrequency(b, _))
+            | (Self::StepperSpeed(a, _), Self::StepperSpeed(b, _))
+            | (Self::StepperAcceleration(a, _), Self::StepperAcceleration(b, _))
+            | (Self::ServoConfig(a, _, _), Self::ServoConfig(b, _, _))
+            | (Self::OneWireConfig(a, _), Self::OneWireConfig(b, _))
+            | (Self::MultiStepperConfig(a, _), Self::MultiStepperConfig(b, _)) => a == b,
+            (Self::StepperConfig(a), Self::StepperConfig(b)) => a.device == b.device,
+            (Self::SamplingInterval(_), Self::SamplingInterval(_))
+            | (Self::I2cConfig(_), Self::I2cConfig(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Configuration entries in the order they were issued. Only the latest entry for the
+/// same target is kept, so replaying the journal reproduces the current configuration.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    pub fn record(&mut self, entry: JournalEntry) {
+        self.entries.retain(|v| !v.superseded_by(&entry));
+        self.entries.push(entry);
+    }
+
+    #[must_use]
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_new_entry_for_the_same_pin_replaces_the_old_one() {
+        let mut journal = Journal::default();
+        journal.record(JournalEntry::PinMode(3, PinMode::Output));
+        journal.record(JournalEntry::ReportAnalog(14, true));
+        journal.record(JournalEntry::PinMode(3, PinMode::Pwm));
+        assert_eq!(
+            journal.entries(),
+            [
+                JournalEntry::ReportAnalog(14, true),
+                JournalEntry::PinMode(3, PinMode::Pwm),
+            ]
+        );
+    }
+
+    #[test]
+    fn entries_for_other_targets_are_kept() {
+        let mut journal = Journal::default();
+        journal.record(JournalEntry::PinMode(3, PinMode::Output));
+        journal.record(JournalEntry::PinMode(4, PinMode::Output));
+        journal.record(JournalEntry::ReportDigital(3, true));
+        assert_eq!(journal.entries().len(), 3);
+    }
+
+    #[test]
+    fn board_wide_settings_keep_only_the_latest() {
+        let mut journal = Journal::default();
+        journal.record(JournalEntry::SamplingInterval(Duration::from_millis(10)));
+        journal.record(JournalEntry::SamplingInterval(Duration::from_millis(50)));
+        assert_eq!(
+            journal.entries(),
+            [JournalEntry::SamplingInterval(Duration::from_millis(50))]
+        );
+    }
+}
@@ -2,28 +2,78 @@
 #![allow(dead_code)]
 //! This module contains a client implementation of the
 //! [Firmata Protocol](https://github.com/firmata/protocol)
+pub mod analog;
 pub mod asynchronous;
+pub mod board_info;
+pub mod bounded;
+pub mod calibration;
+pub mod capability_cache;
+pub mod capability_report;
+pub mod capture;
+pub mod comparator;
+pub mod compat;
+pub mod device;
+pub mod devices;
+pub mod digital;
+pub mod event_log;
+pub mod features;
+pub mod framing;
+pub mod i2c;
+pub mod journal;
 pub mod message;
-mod protocol_constants;
+pub mod onewire;
+pub mod pin_config;
+pub mod profile;
+pub mod protocol;
+pub mod pulse;
+pub mod pwm;
+pub mod recovery;
+pub mod reservation;
+pub mod sampling;
+pub mod scheduler;
+#[cfg(feature = "serial")]
+pub mod serial;
+pub mod servo;
+pub mod session;
+pub mod simulator;
 pub mod standard;
+pub mod stepper;
+pub mod telemetry;
+pub mod testing;
+pub mod text;
+pub mod transform;
+pub mod transport;
+pub mod uart;
+
+/// The private parsers, exposed for the fuzz targets in `fuzz/`.
+#[cfg(fuzzing)]
+#[doc(hidden)]
+pub mod fuzzing {
+    pub use crate::asynchronous::parser::parse_data;
+    pub use crate::standard::parser::read_and_parse_system;
+}
 use asynchronous::boardio::{MessageOut, State};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::iter::Iterator;
 use std::marker::Copy;
 use std::str;
+use std::time::Duration;
 
 pub type AnalogPin = u8;
 pub type DigitalPin = u8;
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum PinId {
     Analog(AnalogPin),
     Digital(DigitalPin),
     Pin(u8),
 }
 
-#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u8)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum PinMode {
     Input = 0,
     Output = 1,
@@ -73,7 +123,7 @@ pub enum FirmataError {
     IoError(#[from] std::io::Error),
     #[error("timeout exceeded `{0}` ms")]
     Timeout(String),
-    #[error("parse error `{0}`: {:?}")]
+    #[error("parse error `{0}`: {1:?}")]
     ParseError(&'static str, Vec<u8>),
     #[error("utf8 parse error occured, `{0}`")]
     Utf8Error(#[from] std::str::Utf8Error),
@@ -89,14 +139,58 @@ pub enum FirmataError {
     StateError(&'static str),
     #[error("Out of range error `{0}`")]
     OutOfRange(&'static str),
+    #[error("Unsupported by firmware `{0}`")]
+    UnsupportedByFirmware(&'static str),
+    /// The pin and who holds it, a reservation owner or another board handle.
+    #[error("pin {0} already reserved by {1}")]
+    PinBusy(u8, String),
+    #[error("pin {pin} already reserved by '{owner}' for {purpose}")]
+    PinReserved {
+        pin: u8,
+        owner: String,
+        purpose: String,
+    },
+    #[error("i2c read from address {0:#04x} register {1:?} timed out")]
+    I2cTimeout(u8, Option<u16>),
+    #[error("writing {1} did not complete within {0:?}")]
+    WriteTimeout(Duration, String),
     #[error("Async State Send Error: `{0}`")]
-    AsyncStateSendError(#[from] tokio::sync::watch::error::SendError<State>),
+    AsyncStateSendError(Box<tokio::sync::watch::error::SendError<State>>),
     #[error("Async MessageOut Send Error: `{0}`")]
     AsyncMessageOutSendError(#[from] tokio::sync::mpsc::error::SendError<MessageOut>),
 }
 
+/// The errno of a serial port whose device was unplugged, "device not configured".
+const ENXIO: i32 = 6;
+
+impl FirmataError {
+    /// Whether the error means the connection to the board went away, the transport
+    /// was closed or reset or the serial device was unplugged.
+    #[must_use]
+    pub fn is_disconnect(&self) -> bool {
+        use std::io::ErrorKind;
+        let Self::IoError(error) = self else {
+            return false;
+        };
+        matches!(
+            error.kind(),
+            ErrorKind::BrokenPipe
+                | ErrorKind::UnexpectedEof
+                | ErrorKind::NotConnected
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+        ) || (cfg!(unix) && error.raw_os_error() == Some(ENXIO))
+    }
+}
+
+impl From<tokio::sync::watch::error::SendError<State>> for FirmataError {
+    fn from(error: tokio::sync::watch::error::SendError<State>) -> Self {
+        Self::AsyncStateSendError(Box::new(error))
+    }
+}
+
 /// A structure representing an I2C reply.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct I2CReply {
     pub address: i32,
     pub register: i32,
@@ -104,14 +198,14 @@ pub struct I2CReply {
 }
 
 /// A structure representing an available pin mode.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Mode {
     pub mode: PinMode,
     pub resolution: u8,
 }
 
 /// A structure representing the current state and configuration of a pin.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Pin {
     pub modes: Vec<Mode>,
     pub analog: bool,
@@ -148,6 +242,62 @@ impl Pin {
             mode: PinMode::Input,
         })
     }
+
+    /// Returns the resolution in bits the pin reported for a mode, if it supports it.
+    #[must_use]
+    pub fn resolution(&self, mode: PinMode) -> Option<u8> {
+        self.modes
+            .iter()
+            .find(|v| v.mode == mode)
+            .map(|v| v.resolution)
+    }
+
+    /// Returns the largest value that can be written in PWM mode.
+    /// # Errors
+    /// Returns [`FirmataError::UninitializedError`] if the capabilities are unknown or
+    /// [`FirmataError::WrongType`] if the pin does not support PWM.
+    pub fn max_pwm_value(&self) -> Result<u16> {
+        if self.modes.is_empty() {
+            return Err(FirmataError::UninitializedError(
+                "pin capabilities have not been queried",
+            ));
+        }
+        let resolution = self
+            .resolution(PinMode::Pwm)
+            .ok_or(FirmataError::WrongType("pin does not support pwm"))?;
+        Ok(((1_u32 << resolution.min(16)) - 1) as u16)
+    }
+
+    /// Checks an analog write against the PWM resolution when the pin is in PWM mode,
+    /// writes in other modes or with unknown capabilities are not checked.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the value exceeds the resolution.
+    pub fn validate_write(&self, value: u16) -> Result<()> {
+        if self.mode == PinMode::Pwm {
+            if let Some(resolution) = self.resolution(PinMode::Pwm) {
+                if u32::from(value) >= 1_u32 << resolution.min(16) {
+                    return Err(FirmataError::OutOfRange(
+                        "value exceeds the pwm resolution of the pin",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts a duty cycle between 0.0 and 1.0 into a PWM value for this pin.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the fraction is outside of 0.0 to 1.0 or
+    /// any error from [`Pin::max_pwm_value`].
+    pub fn duty_to_value(&self, fraction: f64) -> Result<u16> {
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err(FirmataError::OutOfRange(
+                "duty cycle must be between 0.0 and 1.0",
+            ));
+        }
+        let max = self.max_pwm_value()?;
+        Ok((fraction * f64::from(max)).round() as u16)
+    }
 }
 
 /// A structure representing all available pins on a given board.
@@ -155,6 +305,9 @@ impl Pin {
 pub struct PinStates {
     pub pins: Vec<Pin>,
     pub analog_pin_start: u8,
+    /// Analog channel of every analog pin, keyed by pin index.
+    #[serde(default)]
+    pub analog_channels: BTreeMap<u8, u8>,
 }
 
 impl PinStates {
@@ -163,6 +316,7 @@ impl PinStates {
         Self {
             pins,
             analog_pin_start: 0,
+            analog_channels: BTreeMap::new(),
         }
     }
 
@@ -176,9 +330,31 @@ impl PinStates {
                 "more analog pins then pins inside of pinstate",
             ));
         }
-        for id in analog_pins {
-            self.pins[id].analog = true;
+        // Without the channel numbers the firmware's usual sequential order is assumed.
+        let channels = analog_pins
+            .into_iter()
+            .enumerate()
+            .map(|(channel, pin)| (pin as u8, channel as u8))
+            .collect();
+        self.map_analog_channels(channels)
+    }
+
+    /// Marks the pins of an analog mapping response as analog and records their channels.
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the mapping refers to an unknown pin.
+    pub fn map_analog_channels(&mut self, channels: BTreeMap<u8, u8>) -> Result<()> {
+        if channels
+            .keys()
+            .any(|pin| usize::from(*pin) >= self.pins.len())
+        {
+            return Err(FirmataError::StateError(
+                "analog mapping refers to a pin outside of pinstate",
+            ));
+        }
+        for pin in channels.keys() {
+            self.pins[usize::from(*pin)].analog = true;
         }
+        self.analog_channels = channels;
         self.analog_pin_start = self
             .pins
             .iter()
@@ -189,10 +365,99 @@ impl PinStates {
         Ok(())
     }
 
+    /// Returns the analog channel of a pin index.
+    #[must_use]
+    pub fn channel_for_pin(&self, pin: u8) -> Option<u8> {
+        self.analog_channels.get(&pin).copied()
+    }
+
+    /// Returns the analog channel a pin id resolves to, as REPORT_ANALOG addresses it.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin or
+    /// [`FirmataError::OutOfRange`] if the pin has no analog channel REPORT_ANALOG can
+    /// address.
+    pub fn analog_channel(&self, pin_id: PinId) -> Result<u8> {
+        let pin = self.pin_id_to_u8(pin_id);
+        self.get(PinId::Pin(pin))?;
+        match self.channel_for_pin(pin) {
+            Some(channel) if channel <= 0x0F => Ok(channel),
+            _ => Err(FirmataError::OutOfRange(
+                "pin has no analog channel 0 to 15",
+            )),
+        }
+    }
+
+    /// Returns the pin index of an analog channel.
+    #[must_use]
+    pub fn pin_for_channel(&self, channel: u8) -> Option<u8> {
+        self.analog_channels
+            .iter()
+            .find_map(|(pin, v)| (*v == channel).then_some(*pin))
+    }
+
+    /// Returns the pin a pin id resolves to.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn get(&self, pin_id: PinId) -> Result<&Pin> {
+        self.pins
+            .get(usize::from(self.pin_id_to_u8(pin_id)))
+            .ok_or(FirmataError::NotFoundError("pin does not exist"))
+    }
+
+    /// Number of 8 pin digital ports covering the pins, the protocol addresses at most 16.
+    #[must_use]
+    pub fn ports(&self) -> u8 {
+        self.pins.len().div_ceil(8).min(16) as u8
+    }
+
+    /// Resolves a pin id to a pin index, analog channels without a known mapping fall
+    /// back to counting from the first analog pin.
     pub fn pin_id_to_u8(&self, pin_id: PinId) -> u8 {
         match pin_id {
-            PinId::Analog(v) => v + self.analog_pin_start,
+            PinId::Analog(v) => self.pin_for_channel(v).unwrap_or(v + self.analog_pin_start),
             PinId::Digital(v) | PinId::Pin(v) => v,
         }
     }
 }
+
+/// Link statistics gathered while communicating with a board.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    pub round_trips: u64,
+    pub last_latency: Option<Duration>,
+    pub average_latency: Option<Duration>,
+    /// Sysex messages that were skipped because the command is not understood.
+    #[serde(default)]
+    pub unknown_sysex: u64,
+    /// Corrupted frames the decoder skipped and the number of bytes they held.
+    #[serde(default)]
+    pub frame_errors: u64,
+    #[serde(default)]
+    pub discarded_bytes: u64,
+    /// How corrupted frames are handled, see [`RecoveryPolicy`](recovery::RecoveryPolicy).
+    #[serde(default)]
+    pub recovery_policy: recovery::RecoveryPolicy,
+    /// Times the decoder dropped its buffer or the firmware was reset to recover.
+    #[serde(default)]
+    pub decoder_resets: u64,
+    #[serde(default)]
+    pub system_resets: u64,
+}
+
+impl Stats {
+    pub fn record_frame_error(&mut self, error: &message::FrameError) {
+        self.frame_errors += 1;
+        self.discarded_bytes += error.discarded.len() as u64;
+    }
+
+    /// Records a round trip, the average is an exponentially weighted moving average
+    /// that favours the recent history of the link.
+    pub fn record_latency(&mut self, latency: Duration) {
+        self.round_trips += 1;
+        self.last_latency = Some(latency);
+        self.average_latency = Some(match self.average_latency {
+            Some(average) => (average * 7 + latency) / 8,
+            None => latency,
+        });
+    }
+}
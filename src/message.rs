@@ -1,8 +1,10 @@
-use super::protocol_constants::{
-    is_id, ANALOG_MESSAGE, ANALOG_MESSAGE_END, DIGITAL_MESSAGE, DIGITAL_MESSAGE_END,
-    PROTOCOL_VERSION, START_SYSEX,
-};
-use super::{FirmataError, I2CReply, Pin, PinId, Result};
+use super::onewire::{self, OneWireAddress};
+use super::protocol::{AccelStepperCommand, MidiCommand, SchedulerCommand};
+use super::scheduler::TaskState;
+use super::stepper;
+use super::{FirmataError, I2CReply, Pin, PinId, PinMode, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Header {
@@ -17,18 +19,68 @@ pub enum Header {
 /// Returns [`FirmataError::ConversionFailure`] if no header byte
 /// was found.
 pub fn get_header_type(byte: u8) -> Result<Header> {
-    if byte == PROTOCOL_VERSION {
-        return Ok(Header::ProtocolVersion);
-    } else if byte == START_SYSEX {
-        return Ok(Header::System);
-    } else if is_id(byte, ANALOG_MESSAGE..ANALOG_MESSAGE_END) {
-        return Ok(Header::AnalogMessage);
-    } else if is_id(byte, DIGITAL_MESSAGE..DIGITAL_MESSAGE_END) {
-        return Ok(Header::DigitalMessage);
+    match MidiCommand::try_from(byte) {
+        Ok(MidiCommand::ProtocolVersion) => Ok(Header::ProtocolVersion),
+        Ok(MidiCommand::StartSysex) => Ok(Header::System),
+        Ok(MidiCommand::AnalogMessage) => Ok(Header::AnalogMessage),
+        Ok(MidiCommand::DigitalMessage) => Ok(Header::DigitalMessage),
+        _ => Err(FirmataError::ConversionFailure(
+            "failed to convert u8 into message header",
+        )),
     }
-    Err(FirmataError::ConversionFailure(
-        "failed to convert u8 into message header",
-    ))
+}
+
+/// Splits a value into the least and most significant 7 bit bytes used by the protocol,
+/// bits above the 14th are dropped.
+#[must_use]
+pub const fn encode_u14(value: u16) -> [u8; 2] {
+    [(value & 0x7F) as u8, ((value >> 7) & 0x7F) as u8]
+}
+
+/// Joins the least and most significant 7 bit bytes of a value.
+#[must_use]
+pub fn decode_u14(lsb: u8, msb: u8) -> u16 {
+    u16::from(lsb & 0x7F) | u16::from(msb & 0x7F) << 7
+}
+
+/// Packs bytes into a continuous stream of 7 bit bytes, least significant bits first,
+/// as used by the OneWire and scheduler extensions. 7 bytes take 8 bytes on the wire.
+#[must_use]
+pub fn encode_7bit_packed(data: &[u8]) -> Vec<u8> {
+    let mut packed = Vec::with_capacity((data.len() * 8).div_ceil(7));
+    let mut carry = 0_u16;
+    let mut bits = 0;
+    for byte in data {
+        carry |= u16::from(*byte) << bits;
+        bits += 8;
+        while bits >= 7 {
+            packed.push((carry & 0x7F) as u8);
+            carry >>= 7;
+            bits -= 7;
+        }
+    }
+    if bits > 0 {
+        packed.push((carry & 0x7F) as u8);
+    }
+    packed
+}
+
+/// Reverses [`encode_7bit_packed`], trailing bits that do not fill a byte are dropped.
+#[must_use]
+pub fn decode_7bit_packed(packed: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(packed.len() * 7 / 8);
+    let mut carry = 0_u16;
+    let mut bits = 0;
+    for byte in packed {
+        carry |= u16::from(byte & 0x7F) << bits;
+        bits += 7;
+        if bits >= 8 {
+            data.push((carry & 0xFF) as u8);
+            carry >>= 8;
+            bits -= 8;
+        }
+    }
+    data
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -40,12 +92,27 @@ pub enum MessageId {
     Capability = 5,
     I2cReply = 6,
     ReportFirmware = 7,
+    Stepper = 8,
+    Pulse = 9,
+    UnknownSysex = 10,
+    StringData = 11,
+    PinState = 12,
+    OneWire = 13,
+    Scheduler = 14,
+    SerialReply = 15,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum MessageIn {
     Analog(Analog),
     Digital(Digital),
+    /// A sysex message this crate does not understand, only produced by lenient decoding.
+    UnknownSysex {
+        command: u8,
+        payload: Vec<u8>,
+    },
+    /// Bytes the decoder skipped to get back in sync with the stream.
+    FrameError(FrameError),
     System(System),
     ProtocolVersion(String),
 }
@@ -54,31 +121,96 @@ pub enum MessageIn {
 pub struct Message {
     pub message_id: MessageId,
     pub message: MessageIn,
+    /// The bytes the message was decoded from, only kept when raw frame retention is
+    /// enabled on the board.
+    pub raw: Option<Vec<u8>>,
 }
 
-#[derive(Debug, Clone, Copy)]
+/// A corrupted frame that was discarded by the decoder.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FrameError {
+    pub reason: String,
+    pub discarded: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Analog {
     pub pin: PinId,
     pub value: u16,
 }
 
-#[derive(Debug, Clone, Copy)]
+impl Analog {
+    #[must_use]
+    pub const fn into_message(message: Self) -> Message {
+        Message {
+            message_id: MessageId::Analog,
+            message: MessageIn::Analog(message),
+            raw: None,
+        }
+    }
+
+    /// Parses an EXTENDED_ANALOG report, sent by firmwares for channels above 15 or
+    /// values beyond 14 bits. Values wider than 16 bits saturate.
+    /// # Errors
+    /// Returns [`FirmataError::ParseError`] if the report has no channel or value.
+    pub fn deserialize_extended(byte_stream: &[u8]) -> Result<Self> {
+        let [channel, value @ ..] = byte_stream else {
+            return Err(FirmataError::ParseError(
+                "failed to parse extended analog report",
+                byte_stream.to_vec(),
+            ));
+        };
+        if value.is_empty() {
+            return Err(FirmataError::ParseError(
+                "failed to parse extended analog report",
+                byte_stream.to_vec(),
+            ));
+        }
+        // The value is sent in 7 bit bytes, least significant first.
+        let value = value
+            .iter()
+            .take(3)
+            .enumerate()
+            .fold(0_u32, |value, (i, v)| {
+                value | u32::from(v & 0x7F) << (7 * i)
+            });
+        Ok(Self {
+            pin: PinId::Analog(channel & 0x7F),
+            value: u16::try_from(value).unwrap_or(u16::MAX),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Digital {
     pub port: u8,
     pub value: u16,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum System {
     AnalogMappingResponse(AnalogMappingResponse),
     CapabilityResponseMessage(CapabilityResponse),
     ReportFirmwareMessage(ReportFirmware),
     I2cReplyMessage(I2cReply),
+    StepperReplyMessage(StepperReply),
+    PulseReplyMessage(PulseReply),
+    StringDataMessage(StringData),
+    PinStateResponseMessage(PinStateResponse),
+    OneWireReplyMessage(OneWireReply),
+    SchedulerReplyMessage(SchedulerReply),
+    /// Bytes received by a serial port of the board, see [`crate::uart`].
+    SerialReply {
+        port: u8,
+        data: Vec<u8>,
+    },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct AnalogMappingResponse {
     pub supported_analog_pins: Vec<usize>,
+    /// Analog channel of every analog capable pin, keyed by pin index.
+    pub channels: BTreeMap<u8, u8>,
 }
 
 impl AnalogMappingResponse {
@@ -87,25 +219,29 @@ impl AnalogMappingResponse {
         Message {
             message_id: MessageId::AnalogMapping,
             message: MessageIn::System(System::AnalogMappingResponse(message)),
+            raw: None,
         }
     }
 
     #[must_use]
     pub fn deserialize(byte_stream: &[u8]) -> Self {
         let mut supported_analog_pins: Vec<usize> = vec![];
+        let mut channels = BTreeMap::new();
         for (index, value) in byte_stream.iter().enumerate() {
             if *value != 127_u8 {
                 supported_analog_pins.push(index);
+                channels.insert(index as u8, *value);
             }
         }
 
         Self {
             supported_analog_pins,
+            channels,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct CapabilityResponse {
     pub pins: Vec<Pin>,
 }
@@ -116,6 +252,7 @@ impl CapabilityResponse {
         Message {
             message_id: MessageId::Capability,
             message: MessageIn::System(System::CapabilityResponseMessage(message)),
+            raw: None,
         }
     }
 
@@ -141,7 +278,7 @@ impl CapabilityResponse {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct ReportFirmware {
     pub version: String,
     pub name: String,
@@ -153,12 +290,19 @@ impl ReportFirmware {
         Message {
             message_id: MessageId::ReportFirmware,
             message: MessageIn::System(System::ReportFirmwareMessage(message)),
+            raw: None,
         }
     }
 
     /// # Errors
     /// Returns an out of bounds if the message parsed in is not valid.
     pub fn deserialize(byte_stream: &[u8]) -> Result<Self> {
+        if byte_stream.len() < 2 {
+            return Err(FirmataError::ParseError(
+                "failed to parse firmware report",
+                byte_stream.to_vec(),
+            ));
+        }
         let version = format!("{:o}.{:o}", byte_stream[0], byte_stream[1]);
         let name = match String::from_utf8(byte_stream[2..byte_stream.len()].to_vec()) {
             Ok(v) => v.replace('\0', ""),
@@ -173,7 +317,7 @@ impl ReportFirmware {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct I2cReply {
     pub reply: I2CReply,
 }
@@ -184,12 +328,20 @@ impl I2cReply {
         Message {
             message_id: MessageId::I2cReply,
             message: MessageIn::System(System::I2cReplyMessage(message)),
+            raw: None,
         }
     }
 
-    #[must_use]
-    pub fn deserialize(byte_stream: &[u8]) -> Self {
+    /// # Errors
+    /// Returns [`FirmataError::ParseError`] if the reply is too short.
+    pub fn deserialize(byte_stream: &[u8]) -> Result<Self> {
         let len = byte_stream.len();
+        if len < 6 {
+            return Err(FirmataError::ParseError(
+                "failed to parse i2c reply",
+                byte_stream.to_vec(),
+            ));
+        }
         let mut reply = I2CReply {
             address: i32::from(byte_stream[0]) | (i32::from(byte_stream[1]) << 7),
             register: i32::from(byte_stream[2]) | (i32::from(byte_stream[3]) << 7),
@@ -207,6 +359,294 @@ impl I2cReply {
             reply.data.push(byte_stream[i] | byte_stream[i + 1] << 7);
             i += 2;
         }
-        Self { reply }
+        Ok(Self { reply })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepperReply {
+    /// A stepper reached its target, or stopped, at the given position.
+    MoveComplete { device: u8, position: i32 },
+    /// The answer to a position request.
+    Position { device: u8, position: i32 },
+    /// Every stepper in a multi stepper group reached its target.
+    MultiMoveComplete { group: u8 },
+}
+
+impl StepperReply {
+    #[must_use]
+    pub const fn into_message(message: Self) -> Message {
+        Message {
+            message_id: MessageId::Stepper,
+            message: MessageIn::System(System::StepperReplyMessage(message)),
+            raw: None,
+        }
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::ParseError`] if the reply is not a known stepper reply.
+    pub fn deserialize(byte_stream: &[u8]) -> Result<Self> {
+        match (
+            byte_stream
+                .first()
+                .map(|v| AccelStepperCommand::try_from(*v)),
+            byte_stream.get(1),
+        ) {
+            (Some(Ok(AccelStepperCommand::MoveComplete)), Some(device)) => Ok(Self::MoveComplete {
+                device: *device,
+                position: stepper::decode_i32(&byte_stream[2..])?,
+            }),
+            (Some(Ok(AccelStepperCommand::Position)), Some(device)) => Ok(Self::Position {
+                device: *device,
+                position: stepper::decode_i32(&byte_stream[2..])?,
+            }),
+            (Some(Ok(AccelStepperCommand::MultiMoveComplete)), Some(group)) => {
+                Ok(Self::MultiMoveComplete { group: *group })
+            }
+            _ => Err(FirmataError::ParseError(
+                "failed to parse stepper reply",
+                byte_stream.to_vec(),
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PulseReply {
+    pub pin: u8,
+    /// The pulse length in microseconds, zero if the firmware timed out.
+    pub duration_us: u32,
+}
+
+impl PulseReply {
+    #[must_use]
+    pub const fn into_message(message: Self) -> Message {
+        Message {
+            message_id: MessageId::Pulse,
+            message: MessageIn::System(System::PulseReplyMessage(message)),
+            raw: None,
+        }
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::ParseError`] if the reply is too short.
+    pub fn deserialize(byte_stream: &[u8]) -> Result<Self> {
+        if byte_stream.len() < 10 {
+            return Err(FirmataError::ParseError(
+                "failed to parse pulse reply",
+                byte_stream.to_vec(),
+            ));
+        }
+        let pair =
+            |i: usize| u32::from(byte_stream[i] & 0x7F) | u32::from(byte_stream[i + 1] & 0x7F) << 7;
+        let pin = pair(0) as u8;
+        let duration_us = (pair(2) << 24) | (pair(4) << 16) | (pair(6) << 8) | pair(8);
+        Ok(Self { pin, duration_us })
+    }
+}
+
+/// The mode and state of a pin as the firmware sees it, the answer to a pin state query.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PinStateResponse {
+    pub pin: u8,
+    /// The raw mode, firmwares may report modes this crate does not know.
+    pub mode: u8,
+    /// The last written value of an output, for inputs whether the pullup is enabled.
+    pub state: u32,
+}
+
+impl PinStateResponse {
+    #[must_use]
+    pub const fn into_message(message: Self) -> Message {
+        Message {
+            message_id: MessageId::PinState,
+            message: MessageIn::System(System::PinStateResponseMessage(message)),
+            raw: None,
+        }
+    }
+
+    /// The reported mode, `None` for modes this crate does not know.
+    #[must_use]
+    pub fn pin_mode(&self) -> Option<PinMode> {
+        PinMode::from_u8(self.mode).ok()
+    }
+
+    /// Brings a cached pin in line with the response. The state is the last written
+    /// value for output modes and is only taken over for those, for inputs it is the
+    /// pull-up setting rather than a reading.
+    pub fn apply(&self, pin: &mut Pin) {
+        let Some(mode) = self.pin_mode() else {
+            return;
+        };
+        pin.mode = mode;
+        if matches!(mode, PinMode::Output | PinMode::Pwm | PinMode::Servo) {
+            pin.value = u16::try_from(self.state).unwrap_or(u16::MAX);
+        }
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::ParseError`] if the response has no pin or mode.
+    pub fn deserialize(byte_stream: &[u8]) -> Result<Self> {
+        let [pin, mode, state @ ..] = byte_stream else {
+            return Err(FirmataError::ParseError(
+                "failed to parse pin state response",
+                byte_stream.to_vec(),
+            ));
+        };
+        // The state is sent in 7 bit bytes, least significant first.
+        let state = state
+            .iter()
+            .take(4)
+            .enumerate()
+            .fold(0_u32, |state, (i, v)| {
+                state | u32::from(v & 0x7F) << (7 * i)
+            });
+        Ok(Self {
+            pin: *pin,
+            mode: *mode,
+            state,
+        })
+    }
+}
+
+/// A reply of the OneWire extension, see [`crate::onewire`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum OneWireReply {
+    /// The devices found on the bus of a pin.
+    Search {
+        pin: u8,
+        addresses: Vec<OneWireAddress>,
+    },
+    /// The devices with an alarm set.
+    SearchAlarms {
+        pin: u8,
+        addresses: Vec<OneWireAddress>,
+    },
+    /// The bytes read by a request, with the correlation id of the request.
+    Read {
+        pin: u8,
+        correlation_id: u16,
+        data: Vec<u8>,
+    },
+}
+
+impl OneWireReply {
+    #[must_use]
+    pub const fn into_message(message: Self) -> Message {
+        Message {
+            message_id: MessageId::OneWire,
+            message: MessageIn::System(System::OneWireReplyMessage(message)),
+            raw: None,
+        }
+    }
+
+    pub const fn pin(&self) -> u8 {
+        match self {
+            Self::Search { pin, .. } | Self::SearchAlarms { pin, .. } | Self::Read { pin, .. } => {
+                *pin
+            }
+        }
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::ParseError`] if the reply is not a known OneWire reply.
+    pub fn deserialize(byte_stream: &[u8]) -> Result<Self> {
+        let error =
+            || FirmataError::ParseError("failed to parse onewire reply", byte_stream.to_vec());
+        let [subcommand, pin, packed @ ..] = byte_stream else {
+            return Err(error());
+        };
+        let data = decode_7bit_packed(packed);
+        let addresses = || {
+            data.chunks_exact(8)
+                .filter_map(|v| v.try_into().ok().map(OneWireAddress))
+                .collect()
+        };
+        match *subcommand {
+            onewire::SEARCH_REPLY => Ok(Self::Search {
+                pin: *pin,
+                addresses: addresses(),
+            }),
+            onewire::SEARCH_ALARMS_REPLY => Ok(Self::SearchAlarms {
+                pin: *pin,
+                addresses: addresses(),
+            }),
+            onewire::READ_REPLY => match *data.as_slice() {
+                [lsb, msb, ref data @ ..] => Ok(Self::Read {
+                    pin: *pin,
+                    correlation_id: u16::from_le_bytes([lsb, msb]),
+                    data: data.to_vec(),
+                }),
+                _ => Err(error()),
+            },
+            _ => Err(error()),
+        }
+    }
+}
+
+/// A reply of the task scheduler, see [`crate::scheduler`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum SchedulerReply {
+    /// The ids of every stored task.
+    Tasks(Vec<u8>),
+    /// A stored task, `None` if the firmware has no task with the id.
+    Task(u8, Option<TaskState>),
+    /// A task failed, it is reported as it was when the error occurred.
+    Error(u8, Option<TaskState>),
+}
+
+impl SchedulerReply {
+    #[must_use]
+    pub const fn into_message(message: Self) -> Message {
+        Message {
+            message_id: MessageId::Scheduler,
+            message: MessageIn::System(System::SchedulerReplyMessage(message)),
+            raw: None,
+        }
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::ParseError`] if the reply is not a known scheduler reply.
+    pub fn deserialize(byte_stream: &[u8]) -> Result<Self> {
+        let error =
+            || FirmataError::ParseError("failed to parse scheduler reply", byte_stream.to_vec());
+        let [subcommand, ref payload @ ..] = *byte_stream else {
+            return Err(error());
+        };
+        match (SchedulerCommand::try_from(subcommand), payload) {
+            (Ok(SchedulerCommand::QueryAllTasksReply), tasks) => Ok(Self::Tasks(tasks.to_vec())),
+            (Ok(SchedulerCommand::QueryTaskReply), [task, packed @ ..]) => {
+                Ok(Self::Task(*task, TaskState::deserialize(packed)))
+            }
+            (Ok(SchedulerCommand::ErrorTaskReply), [task, packed @ ..]) => {
+                Ok(Self::Error(*task, TaskState::deserialize(packed)))
+            }
+            _ => Err(error()),
+        }
+    }
+}
+
+/// Text sent by the firmware, usually debug or error output.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StringData {
+    pub text: String,
+}
+
+impl StringData {
+    #[must_use]
+    pub const fn into_message(message: Self) -> Message {
+        Message {
+            message_id: MessageId::StringData,
+            message: MessageIn::System(System::StringDataMessage(message)),
+            raw: None,
+        }
+    }
+
+    /// Invalid UTF-8 is replaced rather than rejected.
+    #[must_use]
+    pub fn deserialize(byte_stream: &[u8]) -> Self {
+        Self {
+            text: crate::text::decode(byte_stream),
+        }
     }
 }
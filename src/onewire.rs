@@ -0,0 +1,239 @@
+//! Encoding of the ConfigurableFirmata OneWire extension, e.g. for DS18B20 temperature
+//! sensors. See <https://github.com/firmata/protocol/blob/master/onewire.md>.
+use crate::message;
+use crate::protocol::SysexCommand;
+use crate::{FirmataError, Pin, PinMode, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+const SEARCH_REQUEST: u8 = 0x40;
+const CONFIG_REQUEST: u8 = 0x41;
+pub(crate) const SEARCH_REPLY: u8 = 0x42;
+pub(crate) const READ_REPLY: u8 = 0x43;
+const SEARCH_ALARMS_REQUEST: u8 = 0x44;
+pub(crate) const SEARCH_ALARMS_REPLY: u8 = 0x45;
+
+const RESET_BIT: u8 = 0x01;
+const SKIP_BIT: u8 = 0x02;
+const SELECT_BIT: u8 = 0x04;
+const READ_BIT: u8 = 0x08;
+const DELAY_BIT: u8 = 0x10;
+const WRITE_BIT: u8 = 0x20;
+
+/// The family code of DS18B20 temperature sensors, the first byte of their address.
+pub const DS18B20_FAMILY: u8 = 0x28;
+/// Starts a temperature conversion on the addressed sensors.
+pub const DS18B20_CONVERT: u8 = 0x44;
+/// Reads the 9 byte scratchpad holding the last conversion.
+pub const DS18B20_READ_SCRATCHPAD: u8 = 0xBE;
+/// The longest conversion, at the default 12 bit resolution.
+pub const DS18B20_CONVERSION_TIME: Duration = Duration::from_millis(750);
+
+/// The 64 bit ROM code of a device: family code, serial number and CRC.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct OneWireAddress(pub [u8; 8]);
+
+impl OneWireAddress {
+    #[must_use]
+    pub const fn family(&self) -> u8 {
+        self.0[0]
+    }
+
+    /// Whether the last byte is the CRC of the others, i.e. the address was received
+    /// intact.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        crc8(&self.0[..7]) == self.0[7]
+    }
+}
+
+/// Renders the address as 16 hex digits, family code first.
+impl fmt::Display for OneWireAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The Dallas/Maxim CRC used for addresses and scratchpads.
+#[must_use]
+pub fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0_u8, |mut crc, byte| {
+        let mut byte = *byte;
+        for _ in 0..8 {
+            let mix = (crc ^ byte) & 0x01;
+            crc >>= 1;
+            if mix != 0 {
+                crc ^= 0x8C;
+            }
+            byte >>= 1;
+        }
+        crc
+    })
+}
+
+/// A bus transaction, the firmware runs the parts in the order reset, skip or select,
+/// write, read and delay.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct OneWireRequest {
+    pub reset: bool,
+    /// Addresses every device on the bus, only useful with a single device.
+    pub skip: bool,
+    pub select: Option<OneWireAddress>,
+    pub write: Vec<u8>,
+    /// Number of bytes to read after the write, zero to read nothing.
+    pub read: u16,
+    /// Sent back with the read bytes to match the reply to the request.
+    pub correlation_id: u16,
+    /// How long the firmware waits after the transaction before the next one, e.g. for
+    /// a conversion to finish.
+    pub delay: Duration,
+}
+
+impl OneWireRequest {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resets the bus and addresses a device, or every device if there is no address.
+    #[must_use]
+    pub const fn target(mut self, address: Option<OneWireAddress>) -> Self {
+        self.reset = true;
+        self.skip = address.is_none();
+        self.select = address;
+        self
+    }
+
+    #[must_use]
+    pub fn write(mut self, data: &[u8]) -> Self {
+        self.write = data.to_vec();
+        self
+    }
+
+    #[must_use]
+    pub const fn read(mut self, bytes: u16, correlation_id: u16) -> Self {
+        self.read = bytes;
+        self.correlation_id = correlation_id;
+        self
+    }
+
+    #[must_use]
+    pub const fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Starts a DS18B20 conversion and waits for it to finish.
+    #[must_use]
+    pub fn ds18b20_convert(address: Option<OneWireAddress>) -> Self {
+        Self::new()
+            .target(address)
+            .write(&[DS18B20_CONVERT])
+            .delay(DS18B20_CONVERSION_TIME)
+    }
+
+    /// Reads the scratchpad of a DS18B20, see [`ds18b20_temperature`].
+    #[must_use]
+    pub fn ds18b20_read(address: Option<OneWireAddress>, correlation_id: u16) -> Self {
+        Self::new()
+            .target(address)
+            .write(&[DS18B20_READ_SCRATCHPAD])
+            .read(9, correlation_id)
+    }
+
+    const fn command(&self) -> u8 {
+        let mut command = 0;
+        if self.reset {
+            command |= RESET_BIT;
+        }
+        if self.skip {
+            command |= SKIP_BIT;
+        }
+        if self.select.is_some() {
+            command |= SELECT_BIT;
+        }
+        if self.read > 0 {
+            command |= READ_BIT;
+        }
+        if !self.delay.is_zero() {
+            command |= DELAY_BIT;
+        }
+        if !self.write.is_empty() {
+            command |= WRITE_BIT;
+        }
+        command
+    }
+}
+
+/// Checks that a pin can drive a OneWire bus.
+/// # Errors
+/// Returns [`FirmataError::WrongType`] if the capabilities of the pin are known and
+/// do not include OneWire.
+pub fn validate_pin(pin: &Pin) -> Result<()> {
+    if !pin.modes.is_empty() && pin.resolution(PinMode::Onewire).is_none() {
+        return Err(FirmataError::WrongType("pin does not support onewire"));
+    }
+    Ok(())
+}
+
+/// Encodes the configuration of a bus, with parasitic power the pin is driven high
+/// after writes to power the devices.
+#[must_use]
+pub fn encode_config(pin: u8, parasitic_power: bool) -> Vec<u8> {
+    SysexCommand::OnewireData.frame(&[CONFIG_REQUEST, pin, u8::from(parasitic_power)])
+}
+
+/// Encodes a search for the devices on a bus, or only those with an alarm set.
+#[must_use]
+pub fn encode_search(pin: u8, alarms: bool) -> Vec<u8> {
+    let subcommand = if alarms {
+        SEARCH_ALARMS_REQUEST
+    } else {
+        SEARCH_REQUEST
+    };
+    SysexCommand::OnewireData.frame(&[subcommand, pin])
+}
+
+#[must_use]
+pub fn encode_request(pin: u8, request: &OneWireRequest) -> Vec<u8> {
+    let mut data = vec![];
+    if let Some(address) = request.select {
+        data.extend_from_slice(&address.0);
+    }
+    if request.read > 0 {
+        data.extend_from_slice(&request.read.to_le_bytes());
+        data.extend_from_slice(&request.correlation_id.to_le_bytes());
+    }
+    if !request.delay.is_zero() {
+        let millis = u32::try_from(request.delay.as_millis()).unwrap_or(u32::MAX);
+        data.extend_from_slice(&millis.to_le_bytes());
+    }
+    data.extend_from_slice(&request.write);
+    let mut payload = vec![request.command(), pin];
+    payload.extend_from_slice(&message::encode_7bit_packed(&data));
+    SysexCommand::OnewireData.frame(&payload)
+}
+
+/// Converts a DS18B20 scratchpad to degrees Celsius.
+/// # Errors
+/// Returns [`FirmataError::ConversionFailure`] if the scratchpad is short or its CRC
+/// does not match, e.g. because the sensor is disconnected.
+pub fn ds18b20_temperature(scratchpad: &[u8]) -> Result<f64> {
+    let [lsb, msb, .., crc] = *scratchpad else {
+        return Err(FirmataError::ConversionFailure(
+            "ds18b20 scratchpad is short",
+        ));
+    };
+    if scratchpad.len() != 9 || crc8(&scratchpad[..8]) != crc {
+        return Err(FirmataError::ConversionFailure(
+            "ds18b20 scratchpad crc mismatch",
+        ));
+    }
+    Ok(f64::from(i16::from_le_bytes([lsb, msb])) / 16.0)
+}
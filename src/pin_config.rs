@@ -0,0 +1,82 @@
+//! The outcome of configuring several pins in one call with
+//! [`Board::configure`](crate::asynchronous::board::Board::configure).
+use crate::{PinId, PinMode};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum PinConfigStatus {
+    /// The mode was sent, verification was not requested.
+    Sent,
+    /// The firmware reported the requested mode.
+    Verified,
+    /// The mode was not sent, with the reason.
+    Rejected(String),
+    /// The firmware reported another mode, the raw mode it reported.
+    Mismatch(u8),
+    /// The firmware did not answer the pin state query in time.
+    Unverified,
+}
+
+impl PinConfigStatus {
+    /// Whether the pin is in the requested mode as far as is known.
+    #[must_use]
+    pub const fn is_ok(&self) -> bool {
+        matches!(self, Self::Sent | Self::Verified)
+    }
+}
+
+impl fmt::Display for PinConfigStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sent => write!(f, "sent"),
+            Self::Verified => write!(f, "verified"),
+            Self::Rejected(reason) => write!(f, "rejected: {reason}"),
+            Self::Mismatch(mode) => write!(f, "firmware reported mode {mode}"),
+            Self::Unverified => write!(f, "no pin state reply"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PinConfigResult {
+    pub pin: PinId,
+    /// The pin index the id resolved to.
+    pub index: u8,
+    pub mode: PinMode,
+    pub status: PinConfigStatus,
+}
+
+/// One result per requested pin, in the order they were requested.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PinConfigReport {
+    pub results: Vec<PinConfigResult>,
+}
+
+impl PinConfigReport {
+    /// Whether every pin was configured, and verified if that was requested.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.results.iter().all(|v| v.status.is_ok())
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &PinConfigResult> {
+        self.results.iter().filter(|v| !v.status.is_ok())
+    }
+}
+
+/// Renders one line per pin with its requested mode and status.
+impl fmt::Display for PinConfigReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            writeln!(
+                f,
+                "{:>4}  {:<8}  {}",
+                result.index,
+                format!("{:?}", result.mode),
+                result.status
+            )?;
+        }
+        Ok(())
+    }
+}
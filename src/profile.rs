@@ -0,0 +1,145 @@
+//! Board profiles used instead of the capability and analog mapping queries, for
+//! minimal firmwares that do not implement them.
+use crate::{FirmataError, Mode, Pin, PinMode, PinStates, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Describes the pins of a board, analog channels are numbered in the order of `analog`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BoardProfile {
+    pub name: String,
+    /// Total number of pins, including the analog pins.
+    pub pins: u8,
+    pub analog: Vec<u8>,
+    pub pwm: Vec<u8>,
+    pub i2c: Vec<u8>,
+    pub analog_resolution: u8,
+    pub pwm_resolution: u8,
+}
+
+impl BoardProfile {
+    /// A board with only digital pins, every pin supports input, output and servo.
+    #[must_use]
+    pub fn digital(name: &str, pins: u8) -> Self {
+        Self {
+            name: name.to_string(),
+            pins,
+            analog: vec![],
+            pwm: vec![],
+            i2c: vec![],
+            analog_resolution: 10,
+            pwm_resolution: 8,
+        }
+    }
+
+    #[must_use]
+    pub fn arduino_uno() -> Self {
+        Self {
+            analog: (14..20).collect(),
+            pwm: vec![3, 5, 6, 9, 10, 11],
+            i2c: vec![18, 19],
+            ..Self::digital("Arduino Uno", 20)
+        }
+    }
+
+    #[must_use]
+    pub fn arduino_nano() -> Self {
+        Self {
+            analog: (14..22).collect(),
+            pwm: vec![3, 5, 6, 9, 10, 11],
+            i2c: vec![18, 19],
+            ..Self::digital("Arduino Nano", 22)
+        }
+    }
+
+    #[must_use]
+    pub fn arduino_mega() -> Self {
+        let mut pwm: Vec<u8> = (2..14).collect();
+        pwm.extend(44..47);
+        Self {
+            analog: (54..70).collect(),
+            pwm,
+            i2c: vec![20, 21],
+            ..Self::digital("Arduino Mega 2560", 70)
+        }
+    }
+
+    /// Builds the pin states the capability and analog mapping responses would produce.
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the profile refers to a pin it does not have.
+    pub fn pin_states(&self) -> Result<PinStates> {
+        if self
+            .analog
+            .iter()
+            .chain(&self.pwm)
+            .chain(&self.i2c)
+            .any(|pin| *pin >= self.pins)
+        {
+            return Err(FirmataError::StateError(
+                "board profile refers to a pin outside of the board",
+            ));
+        }
+        let pins = (0..self.pins)
+            .map(|pin| {
+                let mut modes = vec![
+                    Mode {
+                        mode: PinMode::Input,
+                        resolution: 1,
+                    },
+                    Mode {
+                        mode: PinMode::Output,
+                        resolution: 1,
+                    },
+                    Mode {
+                        mode: PinMode::Pullup,
+                        resolution: 1,
+                    },
+                    Mode {
+                        mode: PinMode::Servo,
+                        resolution: 14,
+                    },
+                ];
+                if self.analog.contains(&pin) {
+                    modes.push(Mode {
+                        mode: PinMode::Analog,
+                        resolution: self.analog_resolution,
+                    });
+                }
+                if self.pwm.contains(&pin) {
+                    modes.push(Mode {
+                        mode: PinMode::Pwm,
+                        resolution: self.pwm_resolution,
+                    });
+                }
+                if self.i2c.contains(&pin) {
+                    modes.push(Mode {
+                        mode: PinMode::I2c,
+                        resolution: 1,
+                    });
+                }
+                Pin {
+                    modes,
+                    analog: false,
+                    value: 0,
+                    mode: PinMode::Input,
+                }
+            })
+            .collect();
+        let mut pin_states = PinStates::create(pins);
+        pin_states.map_analog_pins(self.analog.iter().map(|pin| usize::from(*pin)).collect())?;
+        Ok(pin_states)
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::ConversionFailure`] if the json is not a valid profile.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|_| FirmataError::ConversionFailure("failed to deserialize board profile"))
+    }
+
+    /// # Errors
+    /// Returns an error if the file could not be read or parsed.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
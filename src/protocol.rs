@@ -0,0 +1,363 @@
+//! The command bytes of the Firmata protocol as typed enums, for building and matching
+//! messages including custom sysex. See <https://github.com/firmata/protocol>.
+use crate::{FirmataError, Result};
+
+/// First byte of a sysex message.
+pub const START_SYSEX: u8 = MidiCommand::StartSysex.to_u8();
+/// Last byte of a sysex message.
+pub const END_SYSEX: u8 = MidiCommand::EndSysex.to_u8();
+
+/// The MIDI style commands, every byte with the high bit set starts one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum MidiCommand {
+    /// The values of a port of eight digital pins, the port is in the low nibble.
+    DigitalMessage = 0x90,
+    /// Enables reporting of an analog channel, the channel is in the low nibble.
+    ReportAnalog = 0xC0,
+    /// Enables reporting of a digital port, the port is in the low nibble.
+    ReportDigital = 0xD0,
+    /// The value of an analog channel or pin, the pin is in the low nibble.
+    AnalogMessage = 0xE0,
+    StartSysex = 0xF0,
+    SetPinMode = 0xF4,
+    SetDigitalPinValue = 0xF5,
+    EndSysex = 0xF7,
+    ProtocolVersion = 0xF9,
+    SystemReset = 0xFF,
+}
+
+impl MidiCommand {
+    #[must_use]
+    pub const fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Whether the low nibble of the command byte carries a pin, port or channel.
+    #[must_use]
+    pub const fn has_channel(self) -> bool {
+        matches!(
+            self,
+            Self::DigitalMessage | Self::ReportAnalog | Self::ReportDigital | Self::AnalogMessage
+        )
+    }
+
+    /// The command byte with a pin, port or channel in the low nibble, only the low
+    /// nibble of `channel` is used.
+    #[must_use]
+    pub const fn with_channel(self, channel: u8) -> u8 {
+        self.to_u8() | (channel & 0x0F)
+    }
+}
+
+impl TryFrom<u8> for MidiCommand {
+    type Error = FirmataError;
+
+    /// Accepts any channel in the low nibble of the commands that carry one.
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value {
+            0x90..=0x9F => Self::DigitalMessage,
+            0xC0..=0xCF => Self::ReportAnalog,
+            0xD0..=0xDF => Self::ReportDigital,
+            0xE0..=0xEF => Self::AnalogMessage,
+            0xF0 => Self::StartSysex,
+            0xF4 => Self::SetPinMode,
+            0xF5 => Self::SetDigitalPinValue,
+            0xF7 => Self::EndSysex,
+            0xF9 => Self::ProtocolVersion,
+            0xFF => Self::SystemReset,
+            _ => {
+                return Err(FirmataError::ParseError(
+                    "failed to convert u8 to midi command",
+                    vec![value],
+                ))
+            }
+        })
+    }
+}
+
+/// The command byte following [`START_SYSEX`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum SysexCommand {
+    /// Serial ports of the board bridged to the host, see [`crate::uart`].
+    SerialData = 0x60,
+    EncoderData = 0x61,
+    AccelStepperData = 0x62,
+    /// Sets the PWM frequency of a pin on ConfigurableFirmata builds with the analog
+    /// output extension, the frequency follows the pin as four 7 bit bytes, least
+    /// significant first.
+    PwmFrequency = 0x63,
+    AnalogMappingQuery = 0x69,
+    AnalogMappingResponse = 0x6A,
+    CapabilityQuery = 0x6B,
+    CapabilityResponse = 0x6C,
+    PinStateQuery = 0x6D,
+    PinStateResponse = 0x6E,
+    ExtendedAnalog = 0x6F,
+    ServoConfig = 0x70,
+    StringData = 0x71,
+    StepperData = 0x72,
+    OnewireData = 0x73,
+    /// Pulse in, shares its id with shift data, firmwares only implement one of them.
+    PingRead = 0x75,
+    I2cRequest = 0x76,
+    I2cReply = 0x77,
+    I2cConfig = 0x78,
+    ReportFirmware = 0x79,
+    SamplingInterval = 0x7A,
+    SchedulerData = 0x7B,
+    NonRealtime = 0x7E,
+    Realtime = 0x7F,
+}
+
+impl SysexCommand {
+    #[must_use]
+    pub const fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// A complete sysex message with this command and a payload of 7 bit bytes.
+    #[must_use]
+    pub fn frame(self, payload: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(payload.len() + 3);
+        buf.extend_from_slice(&[START_SYSEX, self.to_u8()]);
+        buf.extend_from_slice(payload);
+        buf.push(END_SYSEX);
+        buf
+    }
+}
+
+impl TryFrom<u8> for SysexCommand {
+    type Error = FirmataError;
+
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value {
+            0x60 => Self::SerialData,
+            0x61 => Self::EncoderData,
+            0x62 => Self::AccelStepperData,
+            0x63 => Self::PwmFrequency,
+            0x69 => Self::AnalogMappingQuery,
+            0x6A => Self::AnalogMappingResponse,
+            0x6B => Self::CapabilityQuery,
+            0x6C => Self::CapabilityResponse,
+            0x6D => Self::PinStateQuery,
+            0x6E => Self::PinStateResponse,
+            0x6F => Self::ExtendedAnalog,
+            0x70 => Self::ServoConfig,
+            0x71 => Self::StringData,
+            0x72 => Self::StepperData,
+            0x73 => Self::OnewireData,
+            0x75 => Self::PingRead,
+            0x76 => Self::I2cRequest,
+            0x77 => Self::I2cReply,
+            0x78 => Self::I2cConfig,
+            0x79 => Self::ReportFirmware,
+            0x7A => Self::SamplingInterval,
+            0x7B => Self::SchedulerData,
+            0x7E => Self::NonRealtime,
+            0x7F => Self::Realtime,
+            _ => {
+                return Err(FirmataError::ParseError(
+                    "failed to convert u8 to sysex command",
+                    vec![value],
+                ))
+            }
+        })
+    }
+}
+
+/// Names the command a frame starts with, for error messages about encoded frames.
+#[must_use]
+pub fn describe(frame: &[u8]) -> String {
+    let command = match frame {
+        [START_SYSEX, command, ..] => SysexCommand::try_from(*command)
+            .map_or_else(|_| format!("sysex {command:#04x}"), |v| format!("{v:?}")),
+        [first, ..] => MidiCommand::try_from(*first)
+            .map_or_else(|_| format!("{first:#04x}"), |v| format!("{v:?}")),
+        [] => return "empty frame".to_string(),
+    };
+    format!("{command} ({} bytes)", frame.len())
+}
+
+/// The mode of an I2C request, sent in bits 3 and 4 of the byte after the address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum I2cMode {
+    Write = 0,
+    Read = 1,
+    ReadContinuously = 2,
+    StopReading = 3,
+}
+
+impl I2cMode {
+    #[must_use]
+    pub const fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// The mode shifted into place in the second byte of the request.
+    #[must_use]
+    pub const fn request_bits(self) -> u8 {
+        self.to_u8() << 3
+    }
+}
+
+/// The sub command following [`SysexCommand::AccelStepperData`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum AccelStepperCommand {
+    Config = 0x00,
+    Zero = 0x01,
+    Step = 0x02,
+    To = 0x03,
+    Enable = 0x04,
+    Stop = 0x05,
+    /// Requests the position, the reply uses the same command.
+    Position = 0x06,
+    Acceleration = 0x08,
+    Speed = 0x09,
+    MoveComplete = 0x0A,
+    MultiConfig = 0x20,
+    MultiTo = 0x21,
+    MultiStop = 0x23,
+    MultiMoveComplete = 0x24,
+}
+
+impl AccelStepperCommand {
+    #[must_use]
+    pub const fn to_u8(self) -> u8 {
+        self as u8
+    }
+}
+
+impl TryFrom<u8> for AccelStepperCommand {
+    type Error = FirmataError;
+
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value {
+            0x00 => Self::Config,
+            0x01 => Self::Zero,
+            0x02 => Self::Step,
+            0x03 => Self::To,
+            0x04 => Self::Enable,
+            0x05 => Self::Stop,
+            0x06 => Self::Position,
+            0x08 => Self::Acceleration,
+            0x09 => Self::Speed,
+            0x0A => Self::MoveComplete,
+            0x20 => Self::MultiConfig,
+            0x21 => Self::MultiTo,
+            0x23 => Self::MultiStop,
+            0x24 => Self::MultiMoveComplete,
+            _ => {
+                return Err(FirmataError::ParseError(
+                    "failed to convert u8 to accelstepper command",
+                    vec![value],
+                ))
+            }
+        })
+    }
+}
+
+/// The sub command following [`SysexCommand::SchedulerData`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum SchedulerCommand {
+    CreateTask = 0x00,
+    DeleteTask = 0x01,
+    AddToTask = 0x02,
+    /// Pauses the running task, only valid inside a task.
+    DelayTask = 0x03,
+    ScheduleTask = 0x04,
+    QueryAllTasks = 0x05,
+    QueryTask = 0x06,
+    ResetTasks = 0x07,
+    ErrorTaskReply = 0x08,
+    QueryAllTasksReply = 0x09,
+    QueryTaskReply = 0x0A,
+}
+
+impl SchedulerCommand {
+    #[must_use]
+    pub const fn to_u8(self) -> u8 {
+        self as u8
+    }
+}
+
+impl TryFrom<u8> for SchedulerCommand {
+    type Error = FirmataError;
+
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value {
+            0x00 => Self::CreateTask,
+            0x01 => Self::DeleteTask,
+            0x02 => Self::AddToTask,
+            0x03 => Self::DelayTask,
+            0x04 => Self::ScheduleTask,
+            0x05 => Self::QueryAllTasks,
+            0x06 => Self::QueryTask,
+            0x07 => Self::ResetTasks,
+            0x08 => Self::ErrorTaskReply,
+            0x09 => Self::QueryAllTasksReply,
+            0x0A => Self::QueryTaskReply,
+            _ => {
+                return Err(FirmataError::ParseError(
+                    "failed to convert u8 to scheduler command",
+                    vec![value],
+                ))
+            }
+        })
+    }
+}
+
+/// The sub command following [`SysexCommand::SerialData`], sent in the high nibble with
+/// the port in the low nibble.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum SerialCommand {
+    Config = 0x10,
+    Write = 0x20,
+    Read = 0x30,
+    Reply = 0x40,
+    Close = 0x50,
+    Flush = 0x60,
+    /// Makes a software serial port the one that receives, only one of them can.
+    Listen = 0x70,
+}
+
+impl SerialCommand {
+    #[must_use]
+    pub const fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// The command byte addressing a port.
+    #[must_use]
+    pub const fn with_port(self, port: u8) -> u8 {
+        self.to_u8() | (port & 0x0F)
+    }
+}
+
+impl TryFrom<u8> for SerialCommand {
+    type Error = FirmataError;
+
+    /// Converts the high nibble, the port is ignored.
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value & 0xF0 {
+            0x10 => Self::Config,
+            0x20 => Self::Write,
+            0x30 => Self::Read,
+            0x40 => Self::Reply,
+            0x50 => Self::Close,
+            0x60 => Self::Flush,
+            0x70 => Self::Listen,
+            _ => {
+                return Err(FirmataError::ParseError(
+                    "failed to convert u8 to serial command",
+                    vec![value],
+                ))
+            }
+        })
+    }
+}
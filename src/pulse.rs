@@ -0,0 +1,86 @@
+//! Encoding of the pulse in (ping read) feature used for ultrasonic sensors and RC receivers.
+use crate::protocol::{SysexCommand, END_SYSEX, START_SYSEX};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long a client waits for the reply beyond the timeout of the firmware.
+pub const REPLY_MARGIN: Duration = Duration::from_millis(500);
+
+/// Measures the length of a pulse on a pin, optionally sending a trigger pulse first.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct PulseRequest {
+    pub pin: u8,
+    /// The level of the pulse to measure, the trigger pulse uses the same level.
+    pub value: bool,
+    /// Length of the trigger pulse, zero to skip triggering.
+    pub trigger: Duration,
+    /// How long the firmware waits for the pulse.
+    pub timeout: Duration,
+}
+
+impl PulseRequest {
+    /// A request suitable for HC-SR04 style ultrasonic sensors.
+    #[must_use]
+    pub const fn ping(pin: u8) -> Self {
+        Self {
+            pin,
+            value: true,
+            trigger: Duration::from_micros(5),
+            timeout: Duration::from_millis(1000),
+        }
+    }
+
+    /// A request that only measures a high pulse, e.g. from an RC receiver.
+    #[must_use]
+    pub const fn measure(pin: u8, timeout: Duration) -> Self {
+        Self {
+            pin,
+            value: true,
+            trigger: Duration::ZERO,
+            timeout,
+        }
+    }
+}
+
+/// The last pulse measured on a pin.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PulseMeasurement {
+    /// Number of replies received for the pin, used to detect fresh measurements.
+    pub replies: u64,
+    /// The pulse length, zero if the firmware timed out waiting for it.
+    pub duration: Duration,
+}
+
+impl PulseMeasurement {
+    pub fn record(&mut self, duration_us: u32) {
+        self.replies += 1;
+        self.duration = Duration::from_micros(u64::from(duration_us));
+    }
+}
+
+/// Splits every byte of a big endian u32 into two 7 bit bytes.
+fn encode_u32(value: u32) -> [u8; 8] {
+    let mut out = [0; 8];
+    for (i, byte) in value.to_be_bytes().iter().enumerate() {
+        out[i * 2] = byte & 0x7F;
+        out[i * 2 + 1] = (byte >> 7) & 0x7F;
+    }
+    out
+}
+
+#[must_use]
+pub fn encode_request(request: &PulseRequest) -> Vec<u8> {
+    let trigger = u32::try_from(request.trigger.as_micros()).unwrap_or(u32::MAX);
+    let timeout = u32::try_from(request.timeout.as_micros()).unwrap_or(u32::MAX);
+    let mut buf = vec![
+        START_SYSEX,
+        SysexCommand::PingRead.to_u8(),
+        request.pin,
+        u8::from(request.value),
+    ];
+    buf.extend_from_slice(&encode_u32(trigger));
+    buf.extend_from_slice(&encode_u32(timeout));
+    buf.push(END_SYSEX);
+    buf
+}
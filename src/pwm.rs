@@ -0,0 +1,126 @@
+//! PWM helpers beyond the core protocol.
+use crate::protocol::{SysexCommand, END_SYSEX, START_SYSEX};
+use crate::{FirmataError, Pin, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The largest frequency that fits the four 7 bit bytes of the message.
+pub const MAX_PWM_FREQUENCY: u32 = (1 << 28) - 1;
+
+/// Checks that a pin can take a PWM frequency.
+/// # Errors
+/// Returns [`FirmataError::WrongType`] if the pin does not support PWM or
+/// [`FirmataError::OutOfRange`] if the frequency is zero or too large.
+pub fn validate_frequency(pin: &Pin, hz: u32) -> Result<()> {
+    pin.max_pwm_value()?;
+    if hz == 0 || hz > MAX_PWM_FREQUENCY {
+        return Err(FirmataError::OutOfRange(
+            "pwm frequency must be between 1 hz and 2^28 - 1 hz",
+        ));
+    }
+    Ok(())
+}
+
+/// The shortest time between two writes of a ramp, faster steps are not visible on a
+/// LED or servo and only crowd the link.
+pub const MIN_RAMP_STEP: Duration = Duration::from_millis(10);
+
+/// How a ramp moves from its start to its end value over time.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RampCurve {
+    #[default]
+    Linear,
+    /// Starts slowly and speeds up.
+    EaseIn,
+    /// Starts quickly and slows down towards the end.
+    EaseOut,
+    /// Slow at both ends, e.g. for servos moving a load.
+    EaseInOut,
+    /// Corrects for the eye's non-linear response, so a LED fade looks even.
+    Gamma,
+}
+
+impl RampCurve {
+    /// Maps the progress of a ramp between 0.0 and 1.0 to how far the value has moved.
+    #[must_use]
+    pub fn apply(self, progress: f64) -> f64 {
+        let t = progress.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => t * (2.0 - t),
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Self::Gamma => t.powf(2.2),
+        }
+    }
+}
+
+/// A change of an output from one value to another over a duration, as written by
+/// the ramp helpers of both boards.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ramp {
+    pub from: u16,
+    pub to: u16,
+    pub duration: Duration,
+    pub curve: RampCurve,
+}
+
+impl Ramp {
+    #[must_use]
+    pub fn new(from: u16, to: u16, duration: Duration, curve: RampCurve) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            curve,
+        }
+    }
+
+    #[must_use]
+    pub fn value_at(&self, elapsed: Duration) -> u16 {
+        let progress = if self.duration.is_zero() {
+            1.0
+        } else {
+            elapsed.as_secs_f64() / self.duration.as_secs_f64()
+        };
+        let (from, to) = (f64::from(self.from), f64::from(self.to));
+        (from + (to - from) * self.curve.apply(progress)).round() as u16
+    }
+
+    /// The writes of the ramp and when they are due, at most one per interval. Writes
+    /// that would repeat the previous value are left out, the first write is the start
+    /// value and the last one the end value.
+    #[must_use]
+    pub fn steps(&self, interval: Duration) -> Vec<(Duration, u16)> {
+        let interval = interval.max(Duration::from_millis(1));
+        let count = (self.duration.as_nanos() / interval.as_nanos()).max(1) as u32;
+        let mut steps = vec![(Duration::ZERO, self.from)];
+        for step in 1..=count {
+            let at = self.duration * step / count;
+            let value = if step == count {
+                self.to
+            } else {
+                self.value_at(at)
+            };
+            if steps.last().is_some_and(|(_, last)| *last != value) {
+                steps.push((at, value));
+            }
+        }
+        steps
+    }
+}
+
+/// Encodes a PWM frequency change, firmwares without the extension ignore the message.
+#[must_use]
+pub fn encode_frequency(pin: u8, hz: u32) -> Vec<u8> {
+    vec![
+        START_SYSEX,
+        SysexCommand::PwmFrequency.to_u8(),
+        pin,
+        (hz & 0x7F) as u8,
+        ((hz >> 7) & 0x7F) as u8,
+        ((hz >> 14) & 0x7F) as u8,
+        ((hz >> 21) & 0x7F) as u8,
+        END_SYSEX,
+    ]
+}
@@ -0,0 +1,24 @@
+//! What the boards do when the bytes coming from the firmware can not be decoded.
+use serde::{Deserialize, Serialize};
+
+/// The response to a corrupted or unparsable frame, the frame is recorded in
+/// [`Stats::frame_errors`](crate::Stats::frame_errors) under every policy.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RecoveryPolicy {
+    /// Drops the frame and carries on with the next one, the default of
+    /// [`FirmataCodec`](crate::asynchronous::network::FirmataCodec).
+    #[default]
+    SkipFrame,
+    /// Drops the frame and everything buffered behind it, for links where one error
+    /// usually means the following bytes are garbage too. The sync parser keeps no
+    /// buffer, so on the sync board this behaves like [`RecoveryPolicy::SkipFrame`].
+    ResetDecoder,
+    /// Drops the frame and sends SYSTEM_RESET, bringing the firmware back to its power
+    /// on configuration, the journal is replayed afterwards to restore the setup.
+    SystemReset,
+    /// Fails with the parse error, ending [`BoardIo::poll`] or the sync read, the
+    /// default of the sync board.
+    ///
+    /// [`BoardIo::poll`]: crate::asynchronous::boardio::BoardIo::poll
+    Bail,
+}
@@ -0,0 +1,92 @@
+//! A record of which part of an application uses which pin, so device modules written
+//! independently of each other find out about a conflict when they are set up instead
+//! of by fighting over an output.
+use crate::{FirmataError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Reservation {
+    pub pin: u8,
+    /// The component holding the pin, e.g. `conveyor_motor`.
+    pub owner: String,
+    pub purpose: String,
+}
+
+/// Reservations keyed by pin index, at most one per pin.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReservationRegistry {
+    reservations: BTreeMap<u8, Reservation>,
+}
+
+impl ReservationRegistry {
+    /// Reserves a pin for an owner, reserving a pin again for the same owner updates
+    /// the purpose.
+    /// # Errors
+    /// Returns [`FirmataError::PinReserved`] naming the current owner if another owner
+    /// holds the pin.
+    pub fn reserve(&mut self, pin: u8, owner: &str, purpose: &str) -> Result<()> {
+        if let Some(existing) = self.reservations.get(&pin) {
+            if existing.owner != owner {
+                return Err(FirmataError::PinReserved {
+                    pin,
+                    owner: existing.owner.clone(),
+                    purpose: existing.purpose.clone(),
+                });
+            }
+        }
+        self.reservations.insert(
+            pin,
+            Reservation {
+                pin,
+                owner: owner.to_string(),
+                purpose: purpose.to_string(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Releases a pin if the owner holds it.
+    pub fn release(&mut self, pin: u8, owner: &str) -> Option<Reservation> {
+        if self.reservations.get(&pin)?.owner != owner {
+            return None;
+        }
+        self.reservations.remove(&pin)
+    }
+
+    /// Releases every pin of an owner, e.g. when a device module shuts down.
+    pub fn release_owner(&mut self, owner: &str) -> Vec<Reservation> {
+        let pins: Vec<u8> = self.by_owner(owner).map(|v| v.pin).collect();
+        pins.iter()
+            .filter_map(|pin| self.reservations.remove(pin))
+            .collect()
+    }
+
+    #[must_use]
+    pub fn get(&self, pin: u8) -> Option<&Reservation> {
+        self.reservations.get(&pin)
+    }
+
+    pub fn by_owner<'a>(&'a self, owner: &'a str) -> impl Iterator<Item = &'a Reservation> {
+        self.reservations.values().filter(move |v| v.owner == owner)
+    }
+
+    /// Every reservation, ordered by pin.
+    pub fn iter(&self) -> impl Iterator<Item = &Reservation> {
+        self.reservations.values()
+    }
+
+    pub fn clear(&mut self) {
+        self.reservations.clear();
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.reservations.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.reservations.is_empty()
+    }
+}
@@ -0,0 +1,134 @@
+//! Per-channel analog sampling, combining the firmware's per-channel report enable with
+//! client-side division of the global sampling rate.
+use crate::message;
+use crate::protocol::SysexCommand;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// The shortest sampling interval firmwares accept.
+pub const MIN_SAMPLING_INTERVAL: Duration = Duration::from_millis(1);
+/// The longest sampling interval, the milliseconds are sent as a 14 bit value.
+pub const MAX_SAMPLING_INTERVAL: Duration = Duration::from_millis(0x3FFF);
+
+/// Rounds the interval down to whole milliseconds and clamps it to the range the
+/// firmware can be sent, this is the interval the firmware will actually use.
+#[must_use]
+pub fn clamp_interval(interval: Duration) -> Duration {
+    let millis = interval.as_millis().clamp(
+        MIN_SAMPLING_INTERVAL.as_millis(),
+        MAX_SAMPLING_INTERVAL.as_millis(),
+    );
+    Duration::from_millis(millis as u64)
+}
+
+/// Encodes the SAMPLING_INTERVAL sysex for the clamped interval.
+#[must_use]
+pub fn encode_interval(interval: Duration) -> Vec<u8> {
+    let millis = clamp_interval(interval).as_millis() as u16;
+    SysexCommand::SamplingInterval.frame(&message::encode_u14(millis))
+}
+
+/// Sampling configuration applied with `configure_sampling` on either board.
+///
+/// Every listed channel is reported and only every n-th sample of it is applied, a
+/// divisor of zero turns reporting for the channel off. Unlisted channels are untouched.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SamplingConfig {
+    /// The global sampling interval of the firmware, kept as is when `None`.
+    pub interval: Option<Duration>,
+    /// Divisors keyed by analog channel.
+    pub channels: BTreeMap<u8, u32>,
+}
+
+impl SamplingConfig {
+    #[must_use]
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval: Some(interval),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    /// Reports the channel and applies every n-th of its samples.
+    #[must_use]
+    pub fn channel(mut self, channel: u8, every: u32) -> Self {
+        self.channels.insert(channel, every);
+        self
+    }
+
+    #[must_use]
+    pub fn disable(mut self, channel: u8) -> Self {
+        self.channels.insert(channel, 0);
+        self
+    }
+}
+
+/// Drops the samples of a pin that fall between its divisor.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SampleDivider {
+    divisors: BTreeMap<u8, u32>,
+    counts: BTreeMap<u8, u32>,
+}
+
+impl SampleDivider {
+    /// Sets the divisor of a pin index, one or zero keeps every sample.
+    pub fn set(&mut self, pin: u8, every: u32) {
+        self.counts.remove(&pin);
+        if every > 1 {
+            self.divisors.insert(pin, every);
+        } else {
+            self.divisors.remove(&pin);
+        }
+    }
+
+    /// Counts a sample and returns true if it should be applied.
+    pub fn accept(&mut self, pin: u8) -> bool {
+        let Some(every) = self.divisors.get(&pin).copied() else {
+            return true;
+        };
+        let count = self.counts.entry(pin).or_default();
+        let accept = *count == 0;
+        *count = (*count + 1) % every;
+        accept
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accepted(divider: &mut SampleDivider, pin: u8, samples: usize) -> Vec<bool> {
+        (0..samples).map(|_| divider.accept(pin)).collect()
+    }
+
+    #[test]
+    fn every_nth_sample_is_accepted() {
+        let mut divider = SampleDivider::default();
+        divider.set(3, 3);
+        assert_eq!(
+            accepted(&mut divider, 3, 7),
+            [true, false, false, true, false, false, true]
+        );
+    }
+
+    #[test]
+    fn pins_without_a_divisor_keep_every_sample() {
+        let mut divider = SampleDivider::default();
+        divider.set(3, 3);
+        divider.set(4, 1);
+        assert_eq!(accepted(&mut divider, 4, 3), [true, true, true]);
+        assert_eq!(accepted(&mut divider, 5, 3), [true, true, true]);
+    }
+
+    #[test]
+    fn setting_a_divisor_restarts_the_count() {
+        let mut divider = SampleDivider::default();
+        divider.set(3, 3);
+        assert_eq!(accepted(&mut divider, 3, 2), [true, false]);
+        divider.set(3, 2);
+        assert_eq!(accepted(&mut divider, 3, 3), [true, false, true]);
+        divider.set(3, 0);
+        assert_eq!(accepted(&mut divider, 3, 2), [true, true]);
+    }
+}
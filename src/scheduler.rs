@@ -0,0 +1,214 @@
+//! Encoding of the ConfigurableFirmata scheduler, which stores sequences of commands as
+//! tasks and runs them on the board without the host, e.g. for pulse trains with
+//! precise timing over a slow link. See
+//! <https://github.com/firmata/protocol/blob/master/scheduler.md>.
+//!
+//! Not to be confused with [`crate::asynchronous::scheduler`], which runs actions from
+//! the host.
+use crate::asynchronous::boardio::MessageOut;
+use crate::asynchronous::network::FirmataCodec;
+use crate::message;
+use crate::protocol::{SchedulerCommand, SysexCommand};
+use crate::{FirmataError, Result};
+use bytes::BytesMut;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio_util::codec::Encoder;
+
+/// Task ids are sent as a single 7 bit byte.
+pub const MAX_TASK_ID: u8 = 0x7F;
+/// The longest task, its length is sent as a 14 bit value.
+pub const MAX_TASK_LENGTH: usize = 0x3FFF;
+/// Task bytes sent per ADD_TO_FIRMATA_TASK, packed they fit the 64 byte sysex buffer
+/// of the firmware.
+pub const ADD_CHUNK_SIZE: usize = 49;
+
+/// A command for the scheduler of the firmware.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum SchedulerRequest {
+    /// Reserves a task of the given length in bytes, filled by [`SchedulerRequest::AddTo`].
+    Create {
+        task: u8,
+        length: u16,
+    },
+    /// Appends encoded commands to a task.
+    AddTo {
+        task: u8,
+        data: Vec<u8>,
+    },
+    Delete(u8),
+    /// Pauses the running task, only meaningful as part of a task.
+    Delay(Duration),
+    /// Runs a task once the delay passed, a task scheduling itself repeats.
+    Schedule {
+        task: u8,
+        delay: Duration,
+    },
+    QueryAll,
+    Query(u8),
+    /// Deletes every task.
+    Reset,
+}
+
+impl SchedulerRequest {
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Create { task, length } => {
+                let [lsb, msb] = message::encode_u14(*length);
+                frame(SchedulerCommand::CreateTask, &[*task & 0x7F, lsb, msb])
+            }
+            Self::AddTo { task, data } => {
+                let mut payload = vec![*task & 0x7F];
+                payload.extend_from_slice(&message::encode_7bit_packed(data));
+                frame(SchedulerCommand::AddToTask, &payload)
+            }
+            Self::Delete(task) => frame(SchedulerCommand::DeleteTask, &[*task & 0x7F]),
+            Self::Delay(delay) => frame(SchedulerCommand::DelayTask, &encode_millis(*delay)),
+            Self::Schedule { task, delay } => {
+                let mut payload = vec![*task & 0x7F];
+                payload.extend_from_slice(&encode_millis(*delay));
+                frame(SchedulerCommand::ScheduleTask, &payload)
+            }
+            Self::QueryAll => frame(SchedulerCommand::QueryAllTasks, &[]),
+            Self::Query(task) => frame(SchedulerCommand::QueryTask, &[*task & 0x7F]),
+            Self::Reset => frame(SchedulerCommand::ResetTasks, &[]),
+        }
+    }
+}
+
+fn frame(command: SchedulerCommand, payload: &[u8]) -> Vec<u8> {
+    let mut body = vec![command.to_u8()];
+    body.extend_from_slice(payload);
+    SysexCommand::SchedulerData.frame(&body)
+}
+
+/// The milliseconds of a delay as a packed 32 bit value, longer delays are capped.
+fn encode_millis(delay: Duration) -> Vec<u8> {
+    let millis = u32::try_from(delay.as_millis()).unwrap_or(u32::MAX);
+    message::encode_7bit_packed(&millis.to_le_bytes())
+}
+
+/// A stored task as reported by the firmware.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TaskState {
+    /// When the task runs next, in milliseconds of the firmware clock.
+    pub time_ms: u32,
+    pub length: u16,
+    /// The offset of the next command to run.
+    pub position: u16,
+    pub data: Vec<u8>,
+}
+
+impl TaskState {
+    /// Parses the packed part of a task reply, `None` if it is short.
+    #[must_use]
+    pub fn deserialize(packed: &[u8]) -> Option<Self> {
+        let data = message::decode_7bit_packed(packed);
+        let [t0, t1, t2, t3, l0, l1, p0, p1, ref data @ ..] = *data.as_slice() else {
+            return None;
+        };
+        Some(Self {
+            time_ms: u32::from_le_bytes([t0, t1, t2, t3]),
+            length: u16::from_le_bytes([l0, l1]),
+            position: u16::from_le_bytes([p0, p1]),
+            data: data.to_vec(),
+        })
+    }
+}
+
+/// Checks that a task can be stored.
+/// # Errors
+/// Returns [`FirmataError::OutOfRange`] if the id exceeds [`MAX_TASK_ID`] or the task
+/// is empty or longer than [`MAX_TASK_LENGTH`].
+pub fn validate_task(task: u8, data: &[u8]) -> Result<()> {
+    if task > MAX_TASK_ID {
+        return Err(FirmataError::OutOfRange("task id exceeds 127"));
+    }
+    if data.is_empty() || data.len() > MAX_TASK_LENGTH {
+        return Err(FirmataError::OutOfRange(
+            "task length must be between 1 and 16383 bytes",
+        ));
+    }
+    Ok(())
+}
+
+/// The requests that store a task: creating it and adding its bytes in chunks the
+/// firmware can buffer.
+/// # Errors
+/// Returns [`FirmataError::OutOfRange`] if the task can not be stored, see
+/// [`validate_task`].
+pub fn store_task(task: u8, data: &[u8]) -> Result<Vec<SchedulerRequest>> {
+    validate_task(task, data)?;
+    let mut requests = vec![SchedulerRequest::Create {
+        task,
+        length: data.len() as u16,
+    }];
+    requests.extend(
+        data.chunks(ADD_CHUNK_SIZE)
+            .map(|chunk| SchedulerRequest::AddTo {
+                task,
+                data: chunk.to_vec(),
+            }),
+    );
+    Ok(requests)
+}
+
+/// Builds the bytes of a task from commands as they would be sent to the board.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskBuilder {
+    data: Vec<u8>,
+}
+
+impl TaskBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn message(mut self, message: MessageOut) -> Self {
+        let mut buf = BytesMut::new();
+        if FirmataCodec::new().encode(message, &mut buf).is_ok() {
+            self.data.extend_from_slice(&buf);
+        }
+        self
+    }
+
+    /// Appends several commands in order.
+    #[must_use]
+    pub fn messages<I: IntoIterator<Item = MessageOut>>(self, messages: I) -> Self {
+        messages.into_iter().fold(self, Self::message)
+    }
+
+    /// Pauses the task before the commands that follow.
+    #[must_use]
+    pub fn delay(self, delay: Duration) -> Self {
+        self.message(MessageOut::Scheduler(SchedulerRequest::Delay(delay)))
+    }
+
+    /// Runs the task again once the interval passed, appended last this repeats it.
+    #[must_use]
+    pub fn repeat(self, task: u8, interval: Duration) -> Self {
+        self.message(MessageOut::Scheduler(SchedulerRequest::Schedule {
+            task,
+            delay: interval,
+        }))
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
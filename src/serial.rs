@@ -0,0 +1,474 @@
+//! Helpers for opening serial connections and surviving the device being unplugged.
+use crate::asynchronous::boardio::TransportBoardIo;
+use crate::transport::{
+    AsyncConnection, BoxedAsyncRead, BoxedAsyncWrite, BoxedConnection, ReconnectPolicy, Transport,
+    TransportInfo,
+};
+use crate::{FirmataError, Result};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::io::{ReadHalf, WriteHalf};
+use tokio_serial::{
+    SerialPort, SerialPortBuilder, SerialPortBuilderExt, SerialPortType, SerialStream,
+};
+
+pub use tokio_serial::FlowControl;
+
+/// The async [`BoardIo`](crate::asynchronous::boardio::BoardIo) type produced by [`open_board_io`].
+pub type SerialBoardIo = TransportBoardIo;
+
+/// A known USB vendor/product pair and the board it usually belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownBoard {
+    pub vid: u16,
+    pub pid: u16,
+    pub name: &'static str,
+}
+
+const fn known(vid: u16, pid: u16, name: &'static str) -> KnownBoard {
+    KnownBoard { vid, pid, name }
+}
+
+/// Common Arduino boards and the USB serial chips used on popular clones. Clone chips
+/// are shared across many boards so they only identify the adapter.
+pub const KNOWN_BOARDS: &[KnownBoard] = &[
+    known(0x2341, 0x0001, "Arduino Uno"),
+    known(0x2341, 0x0043, "Arduino Uno"),
+    known(0x2A03, 0x0043, "Arduino Uno"),
+    known(0x2341, 0x0069, "Arduino Uno R4 Minima"),
+    known(0x2341, 0x1002, "Arduino Uno R4 WiFi"),
+    known(0x2341, 0x0010, "Arduino Mega 2560"),
+    known(0x2341, 0x0042, "Arduino Mega 2560"),
+    known(0x2A03, 0x0042, "Arduino Mega 2560"),
+    known(0x2341, 0x0044, "Arduino Mega ADK"),
+    known(0x2341, 0x0036, "Arduino Leonardo"),
+    known(0x2341, 0x8036, "Arduino Leonardo"),
+    known(0x2341, 0x8037, "Arduino Micro"),
+    known(0x2341, 0x0058, "Arduino Nano Every"),
+    known(0x2341, 0x003D, "Arduino Due"),
+    known(0x2341, 0x003E, "Arduino Due"),
+    known(0x2341, 0x804D, "Arduino Zero"),
+    known(0x16C0, 0x0483, "Teensy"),
+    known(0x1A86, 0x7523, "CH340 serial adapter"),
+    known(0x0403, 0x6001, "FTDI FT232 serial adapter"),
+    known(0x10C4, 0xEA60, "CP210x serial adapter"),
+];
+
+/// Looks up a board name in [`KNOWN_BOARDS`].
+#[must_use]
+pub fn identify(vid: u16, pid: u16) -> Option<&'static str> {
+    KNOWN_BOARDS
+        .iter()
+        .find(|board| board.vid == vid && board.pid == pid)
+        .map(|board| board.name)
+}
+
+/// A USB serial device found by [`discover`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredBoard {
+    pub port: String,
+    pub vid: u16,
+    pub pid: u16,
+    pub serial_number: Option<String>,
+    /// The board name from [`KNOWN_BOARDS`] if the pair is known.
+    pub model: Option<&'static str>,
+    /// The firmware name and version, only populated by [`discover_with_firmware`].
+    pub firmware: Option<String>,
+}
+
+/// Lists all USB serial devices currently attached.
+/// # Errors
+/// Returns [`FirmataError::IoError`] if the ports could not be enumerated.
+pub fn discover() -> Result<Vec<DiscoveredBoard>> {
+    let ports = tokio_serial::available_ports().map_err(std::io::Error::from)?;
+    Ok(ports
+        .into_iter()
+        .filter_map(|port| match port.port_type {
+            SerialPortType::UsbPort(info) => Some(DiscoveredBoard {
+                port: port.port_name,
+                vid: info.vid,
+                pid: info.pid,
+                serial_number: info.serial_number,
+                model: identify(info.vid, info.pid),
+                firmware: None,
+            }),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Lists all USB serial devices and asks each one for its firmware. Devices that do
+/// not answer within the timeout are still returned with no firmware.
+/// # Errors
+/// Returns [`FirmataError::IoError`] if the ports could not be enumerated.
+pub async fn discover_with_firmware(
+    baud_rate: u32,
+    timeout: Duration,
+) -> Result<Vec<DiscoveredBoard>> {
+    let mut boards = discover()?;
+    for board in &mut boards {
+        let selector = PortSelector::Path(board.port.clone());
+        if let Ok(Ok(board_io)) =
+            tokio::time::timeout(timeout, open_board_io(&selector, baud_rate)).await
+        {
+            let handle = board_io.get_board();
+            board.firmware = Some(format!(
+                "{} {}",
+                handle.firmware_name(),
+                handle.firmware_version()
+            ));
+        }
+    }
+    Ok(boards)
+}
+
+/// Finds the first attached board whose model matches the name, e.g. `"Arduino Mega 2560"`.
+/// # Errors
+/// Returns [`FirmataError::NotFoundError`] if no such board is attached.
+pub fn find_model(name: &str) -> Result<DiscoveredBoard> {
+    discover()?
+        .into_iter()
+        .find(|board| board.model == Some(name))
+        .ok_or(FirmataError::NotFoundError(
+            "no attached board matched the model",
+        ))
+}
+
+/// Identifies the serial port a board is attached to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortSelector {
+    /// A fixed device path such as `/dev/ttyACM0` or `COM3`.
+    Path(String),
+    /// The serial number of a USB device, this survives the OS renaming the port.
+    UsbSerialNumber(String),
+}
+
+impl PortSelector {
+    /// Resolves the selector to a port path if the device is currently present.
+    #[must_use]
+    pub fn resolve(&self) -> Option<String> {
+        let ports = tokio_serial::available_ports().ok()?;
+        ports
+            .into_iter()
+            .find(|port| match self {
+                Self::Path(path) => port.port_name == *path,
+                Self::UsbSerialNumber(serial_number) => match &port.port_type {
+                    SerialPortType::UsbPort(info) => {
+                        info.serial_number.as_deref() == Some(serial_number.as_str())
+                    }
+                    _ => false,
+                },
+            })
+            .map(|port| port.port_name)
+            .or_else(|| match self {
+                // Not every platform enumerates every device, fall back to the path itself.
+                Self::Path(path) if std::path::Path::new(path).exists() => Some(path.clone()),
+                _ => None,
+            })
+    }
+}
+
+/// Settings for adapters and platforms where the defaults of the serial port cause
+/// open failures or add latency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerialOptions {
+    /// Stops other processes from opening the port while it is in use. Only applies on
+    /// unix, Windows always opens ports exclusively.
+    pub exclusive: bool,
+    /// Sets the latency timer of FTDI adapters to 1 ms instead of the default 16 ms,
+    /// which otherwise delays every report. Only applies on Linux, and only if the
+    /// process may write the adapter's `latency_timer` in sysfs. Other adapters and
+    /// platforms ignore the hint.
+    pub low_latency: bool,
+    pub flow_control: FlowControl,
+    /// Holds the line in a break condition for this long after opening, which resets
+    /// some boards and clears adapters left in a confused state.
+    pub break_on_open: Option<Duration>,
+}
+
+impl Default for SerialOptions {
+    fn default() -> Self {
+        Self {
+            exclusive: true,
+            low_latency: false,
+            flow_control: FlowControl::None,
+            break_on_open: None,
+        }
+    }
+}
+
+impl SerialOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn exclusive(mut self, exclusive: bool) -> Self {
+        self.exclusive = exclusive;
+        self
+    }
+
+    #[must_use]
+    pub fn low_latency(mut self, low_latency: bool) -> Self {
+        self.low_latency = low_latency;
+        self
+    }
+
+    #[must_use]
+    pub fn flow_control(mut self, flow_control: FlowControl) -> Self {
+        self.flow_control = flow_control;
+        self
+    }
+
+    #[must_use]
+    pub fn break_on_open(mut self, duration: Option<Duration>) -> Self {
+        self.break_on_open = duration;
+        self
+    }
+
+    fn builder(&self, path: &str, baud_rate: u32) -> SerialPortBuilder {
+        let builder = tokio_serial::new(path, baud_rate).flow_control(self.flow_control);
+        #[cfg(unix)]
+        let builder = builder.exclusive(self.exclusive);
+        builder
+    }
+
+    /// Applies the settings that need the port path rather than the open port.
+    fn prepare(&self, path: &str) {
+        if self.low_latency {
+            // A hint, adapters without a latency timer simply keep their behaviour.
+            let _ = set_latency_timer(path, 1);
+        }
+    }
+}
+
+/// Sets the latency timer of an FTDI adapter in milliseconds, the time the adapter
+/// waits for more data before passing a partial packet on to the host.
+/// # Errors
+/// Returns [`FirmataError::NotFoundError`] if the port has no latency timer, which is
+/// the case for other adapters and platforms than Linux, or [`FirmataError::IoError`]
+/// if it could not be written.
+pub fn set_latency_timer(path: &str, millis: u8) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        // `/dev/serial/by-id/...` style paths are links to the tty device.
+        let device = std::fs::canonicalize(path)?;
+        let name = device.file_name().ok_or(FirmataError::NotFoundError(
+            "serial port has no device name",
+        ))?;
+        let timer = std::path::Path::new("/sys/bus/usb-serial/devices")
+            .join(name)
+            .join("latency_timer");
+        if !timer.exists() {
+            return Err(FirmataError::NotFoundError(
+                "serial port has no latency timer",
+            ));
+        }
+        std::fs::write(timer, millis.to_string())?;
+        Ok(())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (path, millis);
+        Err(FirmataError::NotFoundError(
+            "latency timer is only supported on linux",
+        ))
+    }
+}
+
+/// Holds the line of an open port in a break condition for the duration.
+/// # Errors
+/// Returns [`FirmataError::IoError`] if the break could not be set or cleared.
+pub async fn send_break(stream: &SerialStream, duration: Duration) -> Result<()> {
+    stream.set_break().map_err(std::io::Error::from)?;
+    tokio::time::sleep(duration).await;
+    stream.clear_break().map_err(std::io::Error::from)?;
+    Ok(())
+}
+
+/// Configuration for [`poll_with_hotplug`].
+#[derive(Debug, Clone)]
+pub struct HotplugConfig {
+    pub selector: PortSelector,
+    pub baud_rate: u32,
+    pub options: SerialOptions,
+    /// How often to look for the device while it is missing.
+    pub poll_interval: Duration,
+}
+
+impl HotplugConfig {
+    #[must_use]
+    pub fn new(selector: PortSelector, baud_rate: u32) -> Self {
+        Self {
+            selector,
+            baud_rate,
+            options: SerialOptions::default(),
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+
+    #[must_use]
+    pub fn with_options(mut self, options: SerialOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The transport the device is reopened over.
+    #[must_use]
+    pub fn transport(&self) -> SerialTransport {
+        SerialTransport::new(self.selector.clone(), self.baud_rate)
+            .with_options(self.options.clone())
+    }
+}
+
+/// Returns true if the error indicates the underlying device went away.
+#[must_use]
+pub fn is_disconnect(error: &FirmataError) -> bool {
+    error.is_disconnect()
+}
+
+/// Opens the port selected by the selector and splits it into halves for [`BoardIo`](crate::asynchronous::boardio::BoardIo).
+/// # Errors
+/// Returns [`FirmataError::NotFoundError`] if the port is not present or
+/// [`FirmataError::IoError`] if it could not be opened.
+pub fn open_async(
+    selector: &PortSelector,
+    baud_rate: u32,
+) -> Result<(ReadHalf<SerialStream>, WriteHalf<SerialStream>)> {
+    let path = selector
+        .resolve()
+        .ok_or(FirmataError::NotFoundError("serial port is not present"))?;
+    let stream =
+        SerialStream::open(&tokio_serial::new(path, baud_rate)).map_err(std::io::Error::from)?;
+    Ok(tokio::io::split(stream))
+}
+
+/// Like [`open_async`] with [`SerialOptions`] applied.
+/// # Errors
+/// Returns [`FirmataError::NotFoundError`] if the port is not present or
+/// [`FirmataError::IoError`] if it could not be opened or configured.
+pub async fn open_async_with(
+    selector: &PortSelector,
+    baud_rate: u32,
+    options: &SerialOptions,
+) -> Result<(ReadHalf<SerialStream>, WriteHalf<SerialStream>)> {
+    let path = selector
+        .resolve()
+        .ok_or(FirmataError::NotFoundError("serial port is not present"))?;
+    options.prepare(&path);
+    let stream = options
+        .builder(&path, baud_rate)
+        .open_native_async()
+        .map_err(std::io::Error::from)?;
+    if let Some(duration) = options.break_on_open {
+        send_break(&stream, duration).await?;
+    }
+    Ok(tokio::io::split(stream))
+}
+
+/// A serial port found through a [`PortSelector`], resolved again on every open so a
+/// board that was renamed by the OS after being replugged is still found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerialTransport {
+    pub selector: PortSelector,
+    pub baud_rate: u32,
+    pub options: SerialOptions,
+}
+
+impl SerialTransport {
+    #[must_use]
+    pub fn new(selector: PortSelector, baud_rate: u32) -> Self {
+        Self {
+            selector,
+            baud_rate,
+            options: SerialOptions::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_options(mut self, options: SerialOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl Transport for SerialTransport {
+    fn info(&self) -> TransportInfo {
+        let target = match &self.selector {
+            PortSelector::Path(path) => path.clone(),
+            PortSelector::UsbSerialNumber(serial_number) => format!("usb:{serial_number}"),
+        };
+        TransportInfo {
+            kind: "serial",
+            target,
+            reconnectable: true,
+        }
+    }
+
+    fn open(&self) -> Result<BoxedConnection> {
+        let path = self
+            .selector
+            .resolve()
+            .ok_or(FirmataError::NotFoundError("serial port is not present"))?;
+        self.options.prepare(&path);
+        let port = self
+            .options
+            .builder(&path, self.baud_rate)
+            .open()
+            .map_err(std::io::Error::from)?;
+        if let Some(duration) = self.options.break_on_open {
+            port.set_break().map_err(std::io::Error::from)?;
+            std::thread::sleep(duration);
+            port.clear_break().map_err(std::io::Error::from)?;
+        }
+        Ok(Box::new(port))
+    }
+
+    fn open_async(&self) -> Pin<Box<dyn Future<Output = Result<AsyncConnection>> + Send + '_>> {
+        Box::pin(async move {
+            let (conn_read, conn_write) =
+                open_async_with(&self.selector, self.baud_rate, &self.options).await?;
+            Ok((
+                Box::new(conn_read) as BoxedAsyncRead,
+                Box::new(conn_write) as BoxedAsyncWrite,
+            ))
+        })
+    }
+}
+
+/// Opens a serial port and creates a [`BoardIo`](crate::asynchronous::boardio::BoardIo) with a populated board state.
+/// # Errors
+/// Returns an error if the port could not be opened or the board did not respond.
+pub async fn open_board_io(selector: &PortSelector, baud_rate: u32) -> Result<SerialBoardIo> {
+    open_board_io_with(selector, baud_rate, &SerialOptions::default()).await
+}
+
+/// Like [`open_board_io`] with [`SerialOptions`] applied.
+/// # Errors
+/// Returns an error if the port could not be opened or the board did not respond.
+pub async fn open_board_io_with(
+    selector: &PortSelector,
+    baud_rate: u32,
+    options: &SerialOptions,
+) -> Result<SerialBoardIo> {
+    let transport = SerialTransport::new(selector.clone(), baud_rate).with_options(options.clone());
+    let mut board_io = TransportBoardIo::open(&transport).await?;
+    board_io.generate_board_state().await?;
+    Ok(board_io)
+}
+
+/// Polls the board like [`BoardIo::poll`](crate::asynchronous::boardio::BoardIo::poll) but survives the device being unplugged.
+/// When the device disappears the port is reopened once it reappears, the board
+/// info handshake is repeated and every pin mode and report subscription is restored.
+/// # Errors
+/// Returns any error that is not caused by the device disconnecting.
+pub async fn poll_with_hotplug(board_io: &mut SerialBoardIo, config: &HotplugConfig) -> Result<()> {
+    board_io
+        .poll_with_transport(
+            &config.transport(),
+            &ReconnectPolicy::fixed(config.poll_interval),
+        )
+        .await
+}
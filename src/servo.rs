@@ -0,0 +1,38 @@
+//! Encoding of the servo configuration of StandardFirmata.
+//! See <https://github.com/firmata/protocol/blob/master/servos.md>.
+use crate::protocol::SysexCommand;
+use crate::{message, FirmataError, Pin, PinMode, Result};
+
+/// The pulse range the Arduino servo library uses until it is configured.
+pub const DEFAULT_MIN_PULSE: u16 = 544;
+pub const DEFAULT_MAX_PULSE: u16 = 2400;
+
+/// The largest pulse width in microseconds that fits the two 7 bit bytes of the message.
+pub const MAX_PULSE: u16 = 0x3FFF;
+
+/// Checks that a pin can drive a servo with the pulse range.
+/// # Errors
+/// Returns [`FirmataError::WrongType`] if the pin does not support servos or
+/// [`FirmataError::OutOfRange`] if the range is empty or too large.
+pub fn validate_config(pin: &Pin, min_pulse: u16, max_pulse: u16) -> Result<()> {
+    if !pin.modes.is_empty() && pin.resolution(PinMode::Servo).is_none() {
+        return Err(FirmataError::WrongType("pin does not support servo"));
+    }
+    if min_pulse >= max_pulse {
+        return Err(FirmataError::OutOfRange(
+            "servo min pulse must be below the max pulse",
+        ));
+    }
+    if max_pulse > MAX_PULSE {
+        return Err(FirmataError::OutOfRange("servo pulse exceeds 16383 us"));
+    }
+    Ok(())
+}
+
+/// Encodes a servo configuration, the firmware also switches the pin to servo mode.
+#[must_use]
+pub fn encode_config(pin: u8, min_pulse: u16, max_pulse: u16) -> Vec<u8> {
+    let [min_lsb, min_msb] = message::encode_u14(min_pulse);
+    let [max_lsb, max_msb] = message::encode_u14(max_pulse);
+    SysexCommand::ServoConfig.frame(&[pin, min_lsb, min_msb, max_lsb, max_msb])
+}
@@ -0,0 +1,45 @@
+//! Everything needed to reproduce the setup of a board on another machine or after the
+//! firmware was reflashed.
+use crate::calibration::CalibrationTable;
+use crate::journal::Journal;
+use crate::{FirmataError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Session {
+    pub journal: Journal,
+    /// User given pin names keyed by pin index.
+    pub labels: BTreeMap<u8, String>,
+    pub calibration: CalibrationTable,
+}
+
+impl Session {
+    /// # Errors
+    /// Returns [`FirmataError::ConversionFailure`] if the session could not be serialized.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|_| FirmataError::ConversionFailure("failed to serialize session"))
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::ConversionFailure`] if the json is not a valid session.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|_| FirmataError::ConversionFailure("failed to deserialize session"))
+    }
+
+    /// # Errors
+    /// Returns an error if the file could not be written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// # Errors
+    /// Returns an error if the file could not be read or parsed.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
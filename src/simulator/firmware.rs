@@ -0,0 +1,273 @@
+use super::signal::{DigitalPattern, Waveform};
+use crate::device::{DeviceMessage, DeviceState, HostMessage};
+use crate::profile::BoardProfile;
+use crate::{Pin, PinStates, Result};
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+/// A firmware answering like StandardFirmata for the pins of a profile, with analog
+/// channels following waveforms and digital pins following patterns. Run it on the
+/// device end of a [`super::link::simulated_link`] or a [`super::link::SimulatedTransport`].
+///
+/// Besides the bootstrap queries it handles pin modes, pin state queries, writes,
+/// reporting and the sampling interval, other commands are ignored. A
+/// [`FirmwareHandle`] shows what the host wrote and injects reports.
+#[derive(Debug, Clone)]
+pub struct SimulatedFirmware {
+    pub name: String,
+    /// Major and minor version reported with the name.
+    pub version: (u8, u8),
+    pins: PinStates,
+    analog: BTreeMap<u8, Waveform>,
+    digital: BTreeMap<u8, DigitalPattern>,
+    handle: FirmwareHandle,
+}
+
+/// A view into the running firmwares spawned from a [`SimulatedFirmware`] or its
+/// clones, to inspect what the host did and send reports it did not ask for. When
+/// several firmwares run at once, e.g. during a reconnect, injected reports go to one
+/// of them.
+#[derive(Debug, Clone, Default)]
+pub struct FirmwareHandle {
+    inner: Arc<HandleInner>,
+}
+
+#[derive(Debug, Default)]
+struct HandleInner {
+    pins: Mutex<Vec<Pin>>,
+    commands: Mutex<Vec<Vec<u8>>>,
+    injected: Mutex<VecDeque<u8>>,
+    notify: Notify,
+}
+
+impl FirmwareHandle {
+    /// The pins as the firmware sees them, with the modes and values the host set.
+    #[must_use]
+    pub fn pins(&self) -> Vec<Pin> {
+        self.inner
+            .pins
+            .lock()
+            .map(|pins| pins.clone())
+            .unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn pin(&self, pin: u8) -> Option<Pin> {
+        self.pins().get(usize::from(pin)).cloned()
+    }
+
+    /// Every complete command the firmware received, in order.
+    #[must_use]
+    pub fn commands(&self) -> Vec<Vec<u8>> {
+        self.inner
+            .commands
+            .lock()
+            .map(|commands| commands.clone())
+            .unwrap_or_default()
+    }
+
+    /// Sends raw bytes to the host as if the firmware wrote them.
+    pub fn inject(&self, bytes: &[u8]) {
+        if let Ok(mut injected) = self.inner.injected.lock() {
+            injected.extend(bytes);
+        }
+        self.inner.notify.notify_one();
+    }
+
+    pub fn inject_analog(&self, channel: u8, value: u16) {
+        self.inject(&DeviceMessage::Analog(channel, value).encode());
+    }
+
+    /// Reports the levels of the eight pins of a port, bit 0 being the first pin.
+    pub fn inject_digital_port(&self, port: u8, value: u8) {
+        self.inject(&DeviceMessage::DigitalPort(port, value).encode());
+    }
+
+    pub fn inject_i2c_reply(&self, address: u8, register: u8, data: &[u8]) {
+        self.inject(
+            &DeviceMessage::I2cReply {
+                address: u16::from(address),
+                register: u16::from(register),
+                data: data.to_vec(),
+            }
+            .encode(),
+        );
+    }
+
+    pub fn inject_string(&self, text: &str) {
+        self.inject(&DeviceMessage::StringData(text.to_string()).encode());
+    }
+
+    fn set_pins(&self, pins: &[Pin]) {
+        if let Ok(mut current) = self.inner.pins.lock() {
+            pins.clone_into(&mut current);
+        }
+    }
+
+    fn record(&self, command: &[u8]) {
+        if let Ok(mut commands) = self.inner.commands.lock() {
+            commands.push(command.to_vec());
+        }
+    }
+
+    fn take_injected(&self) -> Vec<u8> {
+        self.inner
+            .injected
+            .lock()
+            .map(|mut injected| injected.drain(..).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl SimulatedFirmware {
+    /// # Errors
+    /// Returns [`crate::FirmataError::StateError`] if the profile refers to a pin it
+    /// does not have.
+    pub fn new(profile: &BoardProfile) -> Result<Self> {
+        Ok(Self {
+            name: "SimulatedFirmata".to_string(),
+            version: (2, 5),
+            pins: profile.pin_states()?,
+            analog: BTreeMap::new(),
+            digital: BTreeMap::new(),
+            handle: FirmwareHandle::default(),
+        })
+    }
+
+    /// The handle shared by this firmware, its clones and everything they spawn.
+    #[must_use]
+    pub fn handle(&self) -> FirmwareHandle {
+        self.handle.clone()
+    }
+
+    /// Drives an analog channel, channels without a waveform read 0.
+    #[must_use]
+    pub fn analog(mut self, channel: u8, waveform: Waveform) -> Self {
+        self.analog.insert(channel, waveform);
+        self
+    }
+
+    /// Drives a digital pin by index, pins without a pattern read low unless written.
+    #[must_use]
+    pub fn digital(mut self, pin: u8, pattern: DigitalPattern) -> Self {
+        self.digital.insert(pin, pattern);
+        self
+    }
+
+    /// Spawns the firmware on a tokio task, e.g. from the device function of a
+    /// [`super::link::SimulatedTransport`].
+    pub fn spawn<S>(&self, stream: S)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let firmware = self.clone();
+        tokio::spawn(async move { firmware.run(stream).await });
+    }
+
+    /// Serves the stream until the other end closes it.
+    /// # Errors
+    /// Returns an error if reading or writing the stream failed.
+    pub async fn run<S: AsyncRead + AsyncWrite + Unpin>(self, mut stream: S) -> io::Result<()> {
+        let handle = self.handle.clone();
+        let mut device = Device::new(self);
+        let mut input = vec![];
+        let mut buf = [0_u8; 256];
+        let mut next_sample = Instant::now() + device.state.sampling_interval;
+        loop {
+            tokio::select! {
+                read = stream.read(&mut buf) => {
+                    let n = read?;
+                    if n == 0 {
+                        return Ok(());
+                    }
+                    input.extend_from_slice(&buf[..n]);
+                    let reply = device.handle(&mut input);
+                    handle.set_pins(&device.state.pins.pins);
+                    stream.write_all(&reply).await?;
+                }
+                () = handle.inner.notify.notified() => {
+                    stream.write_all(&handle.take_injected()).await?;
+                }
+                () = tokio::time::sleep_until(next_sample) => {
+                    next_sample += device.state.sampling_interval;
+                    let report = device.sample();
+                    stream.write_all(&report).await?;
+                }
+            }
+        }
+    }
+}
+
+/// The state of a running [`SimulatedFirmware`].
+#[derive(Debug)]
+struct Device {
+    firmware: SimulatedFirmware,
+    state: DeviceState,
+    started: Instant,
+    /// The last value sent per reported port.
+    port_values: BTreeMap<u8, u8>,
+}
+
+impl Device {
+    fn new(firmware: SimulatedFirmware) -> Self {
+        firmware.handle.set_pins(&firmware.pins.pins);
+        let mut state = DeviceState::new(&firmware.name, firmware.pins.clone());
+        state.version = firmware.version;
+        Self {
+            firmware,
+            state,
+            started: Instant::now(),
+            port_values: BTreeMap::new(),
+        }
+    }
+
+    /// Consumes every complete command of the input and returns the replies.
+    fn handle(&mut self, input: &mut Vec<u8>) -> Vec<u8> {
+        let mut reply = vec![];
+        while let Some(length) = HostMessage::frame_length(input) {
+            let command: Vec<u8> = input.drain(..length).collect();
+            self.firmware.handle.record(&command);
+            if let Some(message) = HostMessage::decode(&command) {
+                for message in self.state.apply(&message) {
+                    reply.extend_from_slice(&message.encode());
+                }
+            }
+        }
+        let reported = &self.state.reported_ports;
+        self.port_values.retain(|port, _| reported.contains(port));
+        reply
+    }
+
+    /// The reports due at a sampling tick, every reported analog channel and the
+    /// reported ports whose value changed.
+    fn sample(&mut self) -> Vec<u8> {
+        let elapsed = self.started.elapsed();
+        let mut report = vec![];
+        for channel in &self.state.reported_channels {
+            let value = self
+                .firmware
+                .analog
+                .get(channel)
+                .map_or(0, |waveform| waveform.value_at(elapsed));
+            report.extend_from_slice(&DeviceMessage::Analog(*channel, value).encode());
+        }
+        for port in &self.state.reported_ports {
+            let written = self.state.port_value(*port);
+            let value = (0..8).fold(0_u8, |value, bit| {
+                let level = match self.firmware.digital.get(&(port * 8 + bit)) {
+                    Some(pattern) => pattern.level_at(elapsed),
+                    None => written >> bit & 1 != 0,
+                };
+                value | u8::from(level) << bit
+            });
+            if self.port_values.insert(*port, value) != Some(value) {
+                report.extend_from_slice(&DeviceMessage::DigitalPort(*port, value).encode());
+            }
+        }
+        report
+    }
+}
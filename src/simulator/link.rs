@@ -0,0 +1,316 @@
+use crate::transport::{
+    AsyncConnection, BoxedAsyncRead, BoxedAsyncWrite, BoxedConnection, Transport, TransportInfo,
+};
+use crate::Result;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{mpsc as std_mpsc, Arc};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+const CHUNK_SIZE: usize = 256;
+/// How long a read of a [`BlockingLink`] waits for data before returning nothing, like
+/// the read timeout of a serial port.
+const BLOCKING_READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Timing behaviour of a simulated link, applied to every write passing through it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkConditions {
+    /// Fixed delay added to every message.
+    pub latency: Duration,
+    /// Upper bound of a random delay added on top of the latency. Messages are never
+    /// reordered, a message waits for the one before it.
+    pub jitter: Duration,
+    /// Throughput limit of the link, `None` for unlimited.
+    pub bytes_per_second: Option<u32>,
+    /// Seed of the jitter, the same seed reproduces the same delays.
+    pub seed: u64,
+}
+
+impl Default for LinkConditions {
+    fn default() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            bytes_per_second: None,
+            seed: 0x5EED,
+        }
+    }
+}
+
+impl LinkConditions {
+    /// A USB serial link at the given baud rate, 8N1 framing takes ten bits per byte.
+    #[must_use]
+    pub fn serial(baud: u32) -> Self {
+        Self {
+            latency: Duration::from_millis(1),
+            jitter: Duration::from_millis(1),
+            bytes_per_second: Some(baud / 10),
+            ..Self::default()
+        }
+    }
+
+    /// A typical WiFi link to a board running a network firmata.
+    #[must_use]
+    pub fn wifi() -> Self {
+        Self {
+            latency: Duration::from_millis(15),
+            jitter: Duration::from_millis(20),
+            bytes_per_second: Some(100_000),
+            ..Self::default()
+        }
+    }
+
+    /// How long it takes to push the bytes through the link at its throughput limit.
+    fn transfer_time(&self, bytes: usize) -> Duration {
+        self.bytes_per_second.map_or(Duration::ZERO, |rate| {
+            Duration::from_secs_f64(bytes as f64 / f64::from(rate.max(1)))
+        })
+    }
+}
+
+/// A xorshift generator, good enough for jitter and free of dependencies.
+#[derive(Debug)]
+struct Jitter {
+    state: u64,
+    max: Duration,
+}
+
+impl Jitter {
+    fn new(seed: u64, max: Duration) -> Self {
+        Self {
+            state: seed.max(1),
+            max,
+        }
+    }
+
+    fn next(&mut self) -> Duration {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        let max = self.max.as_nanos() as u64;
+        if max == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos(self.state % (max + 1))
+    }
+}
+
+/// Creates a pair of connected streams with both directions subject to the conditions.
+/// The application uses the first stream, a simulated board the second one.
+///
+/// Must be called from within a tokio runtime, the link is serviced by two tasks that
+/// stop once either end is dropped.
+#[must_use]
+pub fn simulated_link(conditions: LinkConditions) -> (DuplexStream, DuplexStream) {
+    let (client, client_inner) = tokio::io::duplex(CHUNK_SIZE * 16);
+    let (device_inner, device) = tokio::io::duplex(CHUNK_SIZE * 16);
+    let (client_read, client_write) = tokio::io::split(client_inner);
+    let (device_read, device_write) = tokio::io::split(device_inner);
+    let reverse = LinkConditions {
+        seed: conditions.seed.rotate_left(32),
+        ..conditions
+    };
+    tokio::spawn(condition(client_read, device_write, conditions));
+    tokio::spawn(condition(device_read, client_write, reverse));
+    (client, device)
+}
+
+/// Copies everything from the reader to the writer, delaying each chunk as the
+/// conditions demand.
+/// # Errors
+/// Returns an error if reading or writing failed.
+pub async fn condition<R, W>(
+    mut reader: R,
+    mut writer: W,
+    conditions: LinkConditions,
+) -> io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let (tx, mut rx) = mpsc::unbounded_channel::<(Instant, Vec<u8>)>();
+    let produce = async move {
+        let mut jitter = Jitter::new(conditions.seed, conditions.jitter);
+        let mut buf = [0_u8; CHUNK_SIZE];
+        let mut link_free = Instant::now();
+        loop {
+            let n = reader.read(&mut buf).await?;
+            if n == 0 {
+                return Ok::<(), io::Error>(());
+            }
+            // The link can only carry one chunk at a time, later chunks queue up behind it.
+            link_free = link_free.max(Instant::now()) + conditions.transfer_time(n);
+            let deliver = link_free + conditions.latency + jitter.next();
+            if tx.send((deliver, buf[..n].to_vec())).is_err() {
+                return Ok(());
+            }
+        }
+    };
+    let consume = async move {
+        let mut last = Instant::now();
+        while let Some((deliver, data)) = rx.recv().await {
+            last = last.max(deliver);
+            tokio::time::sleep_until(last).await;
+            writer.write_all(&data).await?;
+            writer.flush().await?;
+        }
+        writer.shutdown().await
+    };
+    let (produced, consumed) = tokio::join!(produce, consume);
+    produced.and(consumed)
+}
+
+/// The application end of a [`simulated_link`] as a blocking connection, for the sync
+/// board. The link and the device run on a thread with its own tokio runtime, which
+/// stops once the connection is dropped.
+#[derive(Debug)]
+pub struct BlockingLink {
+    incoming: std_mpsc::Receiver<Vec<u8>>,
+    outgoing: mpsc::UnboundedSender<Vec<u8>>,
+    pending: VecDeque<u8>,
+}
+
+impl BlockingLink {
+    /// Creates the link and hands its device end to the device function on the thread
+    /// of the link, where it can spawn tokio tasks.
+    /// # Errors
+    /// Returns an error if the runtime of the link could not be created.
+    pub fn new<F: FnOnce(DuplexStream) + Send + 'static>(
+        conditions: LinkConditions,
+        device: F,
+    ) -> io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let (incoming_tx, incoming) = std_mpsc::channel();
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        std::thread::spawn(move || {
+            runtime.block_on(async move {
+                let (client, device_end) = simulated_link(conditions);
+                device(device_end);
+                let (mut reader, mut writer) = tokio::io::split(client);
+                let mut buf = [0_u8; CHUNK_SIZE];
+                loop {
+                    tokio::select! {
+                        read = reader.read(&mut buf) => match read {
+                            Ok(n) if n > 0 => {
+                                if incoming_tx.send(buf[..n].to_vec()).is_err() {
+                                    return;
+                                }
+                            }
+                            _ => return,
+                        },
+                        bytes = outgoing_rx.recv() => match bytes {
+                            Some(bytes) => {
+                                if writer.write_all(&bytes).await.is_err() {
+                                    return;
+                                }
+                            }
+                            None => return,
+                        },
+                    }
+                }
+            });
+        });
+        Ok(Self {
+            incoming,
+            outgoing,
+            pending: VecDeque::new(),
+        })
+    }
+}
+
+impl io::Read for BlockingLink {
+    /// Returns 0 bytes if nothing arrived within the read timeout.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            match self.incoming.recv_timeout(BLOCKING_READ_TIMEOUT) {
+                Ok(bytes) => self.pending.extend(bytes),
+                Err(std_mpsc::RecvTimeoutError::Timeout) => return Ok(0),
+                Err(std_mpsc::RecvTimeoutError::Disconnected) => {
+                    return Err(io::ErrorKind::BrokenPipe.into())
+                }
+            }
+        }
+        let n = buf.len().min(self.pending.len());
+        for (slot, byte) in buf.iter_mut().zip(self.pending.drain(..n)) {
+            *slot = byte;
+        }
+        Ok(n)
+    }
+}
+
+impl io::Write for BlockingLink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.outgoing
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A [`simulated_link`] as a [`Transport`], every open creates a new link and hands its
+/// device end to the device function, e.g. to spawn a simulated firmware. Blocking
+/// connections are a [`BlockingLink`].
+#[derive(Clone)]
+pub struct SimulatedTransport {
+    pub conditions: LinkConditions,
+    device: Arc<dyn Fn(DuplexStream) + Send + Sync>,
+}
+
+impl SimulatedTransport {
+    pub fn new<F: Fn(DuplexStream) + Send + Sync + 'static>(
+        conditions: LinkConditions,
+        device: F,
+    ) -> Self {
+        Self {
+            conditions,
+            device: Arc::new(device),
+        }
+    }
+}
+
+impl std::fmt::Debug for SimulatedTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SimulatedTransport")
+            .field("conditions", &self.conditions)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Transport for SimulatedTransport {
+    fn info(&self) -> TransportInfo {
+        TransportInfo {
+            kind: "simulated",
+            target: format!("{:?}", self.conditions),
+            reconnectable: true,
+        }
+    }
+
+    fn open(&self) -> Result<BoxedConnection> {
+        let device = self.device.clone();
+        let link = BlockingLink::new(self.conditions, move |stream| device(stream))?;
+        Ok(Box::new(link))
+    }
+
+    fn open_async(&self) -> Pin<Box<dyn Future<Output = Result<AsyncConnection>> + Send + '_>> {
+        Box::pin(async move {
+            let (client, device) = simulated_link(self.conditions);
+            (self.device)(device);
+            let (conn_read, conn_write) = tokio::io::split(client);
+            Ok((
+                Box::new(conn_read) as BoxedAsyncRead,
+                Box::new(conn_write) as BoxedAsyncWrite,
+            ))
+        })
+    }
+}
@@ -0,0 +1,4 @@
+//! Tools for exercising applications without real hardware.
+pub mod firmware;
+pub mod link;
+pub mod signal;
@@ -0,0 +1,176 @@
+use crate::{FirmataError, Result};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::TAU;
+use std::path::Path;
+use std::time::Duration;
+
+/// The value of a virtual analog input over time, measured from when the simulated
+/// firmware started.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Waveform {
+    Constant(u16),
+    /// Starts in the middle of the range, rising.
+    Sine {
+        min: u16,
+        max: u16,
+        period: Duration,
+    },
+    /// Rises from `min` to `max` once per period and starts over.
+    Ramp {
+        min: u16,
+        max: u16,
+        period: Duration,
+    },
+    /// A new random value every millisecond, the same seed reproduces the same values.
+    Noise {
+        min: u16,
+        max: u16,
+        seed: u64,
+    },
+    /// Recorded samples, each value holds until the time of the next one. After the last
+    /// sample the recording starts over if looped, otherwise the last value is kept.
+    Playback {
+        samples: Vec<(Duration, u16)>,
+        looped: bool,
+    },
+}
+
+impl Waveform {
+    #[must_use]
+    pub fn value_at(&self, elapsed: Duration) -> u16 {
+        match self {
+            Self::Constant(value) => *value,
+            Self::Sine { min, max, period } => {
+                let phase = fraction(elapsed, *period) * TAU;
+                let (min, max) = (f64::from(*min), f64::from(*max));
+                ((min + max) / 2.0 + (max - min) / 2.0 * phase.sin()).round() as u16
+            }
+            Self::Ramp { min, max, period } => {
+                let span = f64::from(max.saturating_sub(*min));
+                min + (span * fraction(elapsed, *period)).round() as u16
+            }
+            Self::Noise { min, max, seed } => {
+                let span = u64::from(max.saturating_sub(*min)) + 1;
+                min + (mix(seed ^ elapsed.as_millis() as u64) % span) as u16
+            }
+            Self::Playback { samples, looped } => {
+                let Some((end, last)) = samples.last() else {
+                    return 0;
+                };
+                let elapsed = if *looped && !end.is_zero() {
+                    Duration::from_nanos((elapsed.as_nanos() % end.as_nanos()) as u64)
+                } else {
+                    elapsed
+                };
+                samples
+                    .iter()
+                    .take_while(|(at, _)| *at <= elapsed)
+                    .last()
+                    .map_or(*last, |(_, value)| *value)
+            }
+        }
+    }
+
+    /// Reads a recording with one `milliseconds,value` line per sample, a header line,
+    /// blank lines and lines starting with `#` are skipped.
+    /// # Errors
+    /// Returns [`FirmataError::ConversionFailure`] if a line is not a sample or the
+    /// recording has none.
+    pub fn from_csv(csv: &str, looped: bool) -> Result<Self> {
+        let mut samples = vec![];
+        for (index, line) in csv.lines().map(str::trim).enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let sample = line.split_once(',').and_then(|(at, value)| {
+                Some((
+                    Duration::from_millis(at.trim().parse().ok()?),
+                    value.trim().parse().ok()?,
+                ))
+            });
+            match sample {
+                Some(sample) => samples.push(sample),
+                None if index == 0 => continue,
+                None => {
+                    return Err(FirmataError::ConversionFailure(
+                        "expected a milliseconds,value sample",
+                    ))
+                }
+            }
+        }
+        if samples.is_empty() {
+            return Err(FirmataError::ConversionFailure("recording has no samples"));
+        }
+        samples.sort_by_key(|(at, _)| *at);
+        Ok(Self::Playback { samples, looped })
+    }
+
+    /// # Errors
+    /// Returns an error if the file could not be read or parsed.
+    pub fn load_csv<P: AsRef<Path>>(path: P, looped: bool) -> Result<Self> {
+        Self::from_csv(&std::fs::read_to_string(path)?, looped)
+    }
+}
+
+/// The level of a virtual digital input over time, a sequence of levels each held for
+/// its duration.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DigitalPattern {
+    pub steps: Vec<(bool, Duration)>,
+    /// Starts over after the last step, otherwise the last level is kept.
+    pub repeat: bool,
+}
+
+impl DigitalPattern {
+    #[must_use]
+    pub fn new(steps: Vec<(bool, Duration)>, repeat: bool) -> Self {
+        Self { steps, repeat }
+    }
+
+    /// High for `high`, then low for `low`, repeating.
+    #[must_use]
+    pub fn square(high: Duration, low: Duration) -> Self {
+        Self::new(vec![(true, high), (false, low)], true)
+    }
+
+    /// Low until `after`, then high once for `width`, e.g. a button press.
+    #[must_use]
+    pub fn pulse(after: Duration, width: Duration) -> Self {
+        Self::new(
+            vec![(false, after), (true, width), (false, Duration::ZERO)],
+            false,
+        )
+    }
+
+    #[must_use]
+    pub fn level_at(&self, elapsed: Duration) -> bool {
+        let total: Duration = self.steps.iter().map(|(_, duration)| *duration).sum();
+        let mut elapsed = if self.repeat && !total.is_zero() {
+            Duration::from_nanos((elapsed.as_nanos() % total.as_nanos()) as u64)
+        } else {
+            elapsed
+        };
+        for (level, duration) in &self.steps {
+            if elapsed < *duration {
+                return *level;
+            }
+            elapsed -= *duration;
+        }
+        self.steps.last().is_some_and(|(level, _)| *level)
+    }
+}
+
+/// How far into its period a waveform is, from 0 up to but excluding 1.
+fn fraction(elapsed: Duration, period: Duration) -> f64 {
+    if period.is_zero() {
+        return 0.0;
+    }
+    (elapsed.as_nanos() % period.as_nanos()) as f64 / period.as_nanos() as f64
+}
+
+/// The splitmix64 finaliser, spreads neighbouring inputs over the whole range.
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
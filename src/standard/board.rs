@@ -1,27 +1,100 @@
+use super::callbacks::{CallbackId, Callbacks, PinChange};
+use super::i2c::I2c;
 use super::parser;
-use crate::protocol_constants::{
-    ANALOG_MAPPING_QUERY, ANALOG_MESSAGE, CAPABILITY_QUERY, DIGITAL_MESSAGE, END_SYSEX, I2C_CONFIG,
-    I2C_MODE_READ, I2C_MODE_WRITE, I2C_REQUEST, PIN_MODE, REPORT_ANALOG, REPORT_DIGITAL,
-    REPORT_FIRMWARE, SAMPLEING_INTERVAL, START_SYSEX, STRING_DATA,
-};
-use crate::{message, FirmataError, I2CReply, Pin, PinId, PinMode, PinStates, Result};
-use byteorder::LittleEndian;
-use byteorder::WriteBytesExt;
-use message::MessageId;
+use crate::analog;
+use crate::board_info::BoardInfo;
+use crate::bounded::{BoundedBuffer, Eviction};
+use crate::calibration::{Calibration, CalibrationTable};
+use crate::capability_cache::{CacheKey, CapabilityCache};
+use crate::capability_report::CapabilityReport;
+use crate::comparator::Comparator;
+use crate::compat::{self, Feature};
+use crate::digital::{self, OutputLatches};
+use crate::features::FeatureSet;
+use crate::i2c::{self, I2cConfig, I2cReadOptions, I2cRetryPolicy};
+use crate::journal::{Journal, JournalEntry};
+use crate::message::{FrameError, OneWireReply, SchedulerReply, StepperReply};
+use crate::onewire::{self, OneWireAddress, OneWireRequest};
+use crate::profile::BoardProfile;
+use crate::protocol::{self, MidiCommand, SysexCommand};
+use crate::pulse::{self, PulseMeasurement, PulseRequest};
+use crate::pwm::{self, Ramp, RampCurve};
+use crate::recovery::RecoveryPolicy;
+use crate::sampling::{self, SampleDivider, SamplingConfig};
+use crate::scheduler::{self, SchedulerRequest, TaskState};
+use crate::servo;
+use crate::session::Session;
+use crate::stepper::{self, StepperConfig, StepperStatus, MAX_STEPPERS, MAX_STEPPER_GROUPS};
+use crate::text;
+use crate::transform::{SensorTransform, TransformTable};
+use crate::transport::{BoxedConnection, Transport};
+use crate::uart::{self, SerialReadMode, SerialRequest};
+use crate::{message, FirmataError, I2CReply, Pin, PinId, PinMode, PinStates, Result, Stats};
 use message::MessageIn;
+use message::{Message, MessageId};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::io;
 use std::str;
+use std::time::{Duration, Instant};
+
+/// The first pause before a stalled write is retried, it doubles up to
+/// [`MAX_WRITE_RETRY_PAUSE`] while the write stays stalled.
+const WRITE_RETRY_PAUSE: Duration = Duration::from_millis(1);
+const MAX_WRITE_RETRY_PAUSE: Duration = Duration::from_millis(50);
+
+/// How many received STRING_DATA messages the board keeps.
+pub const STRING_DATA_CAPACITY: usize = 64;
+
+/// How many uncollected OneWire replies the board keeps by default.
+pub const ONEWIRE_DATA_CAPACITY: usize = 64;
+
+/// How many uncollected scheduler replies the board keeps by default.
+pub const SCHEDULER_DATA_CAPACITY: usize = 64;
+
+/// How many unread bytes the board keeps per serial port by default.
+pub const SERIAL_DATA_CAPACITY: usize = 1024;
 
 /// A structure representing a firmata board.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Board<T: io::Read + io::Write> {
     connection: T,
     pin_state: PinStates,
-    i2c_data: Vec<I2CReply>,
+    i2c_data: BoundedBuffer<I2CReply>,
+    string_data: BoundedBuffer<String>,
     protocol_version: String,
     firmware_name: String,
     firmware_version: String,
+    stats: Stats,
+    calibration: CalibrationTable,
+    stepper_group_moves: BTreeMap<u8, u64>,
+    steppers: BTreeMap<u8, StepperStatus>,
+    pulses: BTreeMap<u8, PulseMeasurement>,
+    onewire_devices: BTreeMap<u8, Vec<OneWireAddress>>,
+    onewire_data: BoundedBuffer<OneWireReply>,
+    next_onewire_id: u16,
+    scheduler_data: BoundedBuffer<SchedulerReply>,
+    serial_data: BTreeMap<u8, BoundedBuffer<u8>>,
+    serial_capacity: (usize, Eviction),
+    lenient: bool,
+    retain_raw: bool,
+    #[serde(skip)]
+    last_raw_frame: Option<Vec<u8>>,
+    auto_report: bool,
+    sampling: SampleDivider,
+    journal: Journal,
+    labels: BTreeMap<u8, String>,
+    #[serde(skip)]
+    callbacks: Callbacks,
+    #[serde(skip)]
+    transforms: TransformTable,
+    write_timeout: Option<Duration>,
+    recovery: RecoveryPolicy,
+    active_sampling_interval: Option<Duration>,
+    active_i2c_config: Option<I2cConfig>,
+    comparators: BTreeMap<u8, Comparator>,
+    comparator_levels: BTreeMap<u8, bool>,
+    output_latches: OutputLatches,
 }
 
 impl<T: io::Read + io::Write> Board<T> {
@@ -33,15 +106,136 @@ impl<T: io::Read + io::Write> Board<T> {
             firmware_version: String::new(),
             protocol_version: String::new(),
             pin_state: PinStates::create(vec![]),
-            i2c_data: vec![],
+            i2c_data: BoundedBuffer::unbounded(),
+            string_data: BoundedBuffer::with_capacity(STRING_DATA_CAPACITY, Eviction::DropOldest),
+            stats: Stats {
+                recovery_policy: RecoveryPolicy::Bail,
+                ..Stats::default()
+            },
+            calibration: CalibrationTable::default(),
+            stepper_group_moves: BTreeMap::new(),
+            steppers: BTreeMap::new(),
+            pulses: BTreeMap::new(),
+            onewire_devices: BTreeMap::new(),
+            onewire_data: BoundedBuffer::with_capacity(ONEWIRE_DATA_CAPACITY, Eviction::DropOldest),
+            next_onewire_id: 0,
+            scheduler_data: BoundedBuffer::with_capacity(
+                SCHEDULER_DATA_CAPACITY,
+                Eviction::DropOldest,
+            ),
+            serial_data: BTreeMap::new(),
+            serial_capacity: (SERIAL_DATA_CAPACITY, Eviction::DropOldest),
+            lenient: false,
+            retain_raw: false,
+            last_raw_frame: None,
+            auto_report: false,
+            sampling: SampleDivider::default(),
+            journal: Journal::default(),
+            labels: BTreeMap::new(),
+            callbacks: Callbacks::default(),
+            transforms: TransformTable::default(),
+            write_timeout: None,
+            recovery: RecoveryPolicy::Bail,
+            active_sampling_interval: None,
+            active_i2c_config: None,
+            comparators: BTreeMap::new(),
+            comparator_levels: BTreeMap::new(),
+            output_latches: OutputLatches::default(),
         }
     }
 
+    /// Sets how corrupted frames are handled, see [`RecoveryPolicy`]. The default,
+    /// [`RecoveryPolicy::Bail`], returns the parse error from [`Board::read`], every
+    /// other policy keeps reading until a frame decodes or the timeout passes.
+    pub fn set_recovery_policy(&mut self, policy: RecoveryPolicy) {
+        self.recovery = policy;
+        self.stats.recovery_policy = policy;
+    }
+
+    /// Gives up on writes that make no progress for the timeout, with
+    /// [`FirmataError::WriteTimeout`] instead of an io error. The connection has to
+    /// return [`io::ErrorKind::TimedOut`] or [`io::ErrorKind::WouldBlock`] for a stalled
+    /// write, e.g. a serial port opened with a timeout or a non blocking socket. With
+    /// `None`, the default, those errors are returned straight away.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        self.write_timeout = timeout;
+    }
+
+    /// Writes a whole frame, retrying stalled writes with a growing pause until no byte
+    /// was written for the write timeout.
+    fn send(&mut self, frame: &[u8]) -> Result<()> {
+        let mut progressed = Instant::now();
+        let mut pause = WRITE_RETRY_PAUSE;
+        let mut written = 0;
+        while written < frame.len() {
+            match self.connection.write(&frame[written..]) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::WriteZero).into()),
+                Ok(n) => {
+                    written += n;
+                    progressed = Instant::now();
+                    pause = WRITE_RETRY_PAUSE;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+                    ) =>
+                {
+                    let Some(timeout) = self.write_timeout else {
+                        return Err(e.into());
+                    };
+                    let stalled = progressed.elapsed();
+                    if stalled >= timeout {
+                        return Err(FirmataError::WriteTimeout(
+                            timeout,
+                            protocol::describe(frame),
+                        ));
+                    }
+                    std::thread::sleep(pause.min(timeout - stalled));
+                    pause = (pause * 2).min(MAX_WRITE_RETRY_PAUSE);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes unknown sysex commands show up as [`MessageId::UnknownSysex`] and count
+    /// towards [`Stats::unknown_sysex`] instead of failing [`Board::read`].
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Keeps the bytes every message was read from, available as [`Message::raw`] from
+    /// [`Board::read_message`] and as [`Board::last_raw_frame`]. Off by default.
+    pub fn set_retain_raw(&mut self, retain: bool) {
+        self.retain_raw = retain;
+        if !retain {
+            self.last_raw_frame = None;
+        }
+    }
+
+    /// The bytes of the last message read, including any bytes skipped before it. Only
+    /// kept while [`Board::set_retain_raw`] is enabled.
+    #[must_use]
+    pub fn last_raw_frame(&self) -> Option<&[u8]> {
+        self.last_raw_frame.as_deref()
+    }
+
+    /// Makes [`Board::set_pin_mode`] enable reporting for input and analog pins, the
+    /// port of an input pin or the channel of an analog pin. Off by default.
+    pub fn set_auto_report(&mut self, enable: bool) {
+        self.auto_report = enable;
+    }
+
     /// Populates all the information of a given board
     /// # Errors
     /// This can return several firmata errors depending if its network, parsing
     /// or incorrect information.
     pub fn populate_board_info(&mut self) -> Result<()> {
+        // Not waited for, the version is picked up by the reads that follow.
+        self.query_protocol_version()?;
         self.query_firmware()?;
         self.read_until_message_found(MessageId::ReportFirmware)?;
         self.query_capabilities()?;
@@ -51,12 +245,56 @@ impl<T: io::Read + io::Write> Board<T> {
         Ok(())
     }
 
-    pub fn pin_id_to_pin(&self, pin_in: PinId) -> u8 {
-        match pin_in {
-            PinId::Analog(x) => x + self.pin_state.analog_pin_start,
-            PinId::Digital(y) => y,
-            PinId::Pin(z) => z,
+    /// Takes the pins from a profile instead of querying the board, for firmwares that
+    /// do not answer the capability or analog mapping queries.
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the profile is inconsistent.
+    pub fn assume_profile(&mut self, profile: &BoardProfile) -> Result<()> {
+        self.pin_state = profile.pin_states()?;
+        Ok(())
+    }
+
+    /// Like [`Board::populate_board_info`] but only queries the firmware if the pins of
+    /// the board are in the cache, otherwise the pins are queried and cached.
+    /// # Errors
+    /// This can return several firmata errors depending if its network, parsing
+    /// or incorrect information.
+    pub fn populate_board_info_cached(
+        &mut self,
+        cache: &mut CapabilityCache,
+        serial_number: Option<&str>,
+    ) -> Result<()> {
+        self.query_firmware()?;
+        self.read_until_message_found(MessageId::ReportFirmware)?;
+        let key = CacheKey::new(&self.firmware_name, &self.firmware_version, serial_number);
+        if let Some(pin_state) = cache.get(&key) {
+            self.pin_state = pin_state.clone();
+            return Ok(());
         }
+        self.query_capabilities()?;
+        self.read_until_message_found(MessageId::Capability)?;
+        self.query_analog_mapping()?;
+        self.read_until_message_found(MessageId::AnalogMapping)?;
+        cache.insert(key, self.pin_state.clone());
+        Ok(())
+    }
+
+    pub fn pin_id_to_pin(&self, pin_in: PinId) -> u8 {
+        self.pin_state.pin_id_to_u8(pin_in)
+    }
+
+    /// Returns the pin an id resolves to through the analog mapping.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn pin(&self, pin: PinId) -> Result<Pin> {
+        self.pin_state.get(pin).cloned()
+    }
+
+    /// Returns the last known value of a pin.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn try_value(&self, pin: PinId) -> Result<u16> {
+        self.pin_state.get(pin).map(|v| v.value)
     }
 
     pub fn get_physical_pin(&self, pin_in: PinId) -> Pin {
@@ -70,7 +308,9 @@ impl<T: io::Read + io::Write> Board<T> {
                 if !self.pin_state.pins.is_empty() {
                     let pin: usize = self.pin_id_to_pin(v.pin).into();
                     if self.pin_state.pins[pin].analog {
-                        self.pin_state.pins[pin].value = v.value;
+                        if self.sampling.accept(pin as u8) {
+                            self.pin_state.pins[pin].value = v.value;
+                        }
                         return Ok(());
                     }
                 }
@@ -84,7 +324,10 @@ impl<T: io::Read + io::Write> Board<T> {
                         let pin = (8 * v.port) + i;
 
                         if self.pin_state.pins.len() > pin as usize
-                            && self.pin_state.pins[pin as usize].mode == PinMode::Input
+                            && matches!(
+                                self.pin_state.pins[pin as usize].mode,
+                                PinMode::Input | PinMode::Pullup
+                            )
                         {
                             self.pin_state.pins[pin as usize].value =
                                 (v.value >> (i & 0x07)) & 0x01;
@@ -99,10 +342,7 @@ impl<T: io::Read + io::Write> Board<T> {
             message::MessageIn::System(v) => match v {
                 message::System::AnalogMappingResponse(v) => {
                     if !self.pin_state.pins.is_empty() {
-                        for id in v.supported_analog_pins {
-                            self.pin_state.pins[id].analog = true;
-                        }
-                        return Ok(());
+                        return self.pin_state.map_analog_channels(v.channels);
                     }
                     Err(FirmataError::UninitializedError(
                         "pins had not been initialised prior to mapping analog pins",
@@ -121,7 +361,68 @@ impl<T: io::Read + io::Write> Board<T> {
                     self.i2c_data.push(v.reply);
                     Ok(())
                 }
+                message::System::StringDataMessage(v) => {
+                    self.string_data.push(v.text);
+                    Ok(())
+                }
+                message::System::PinStateResponseMessage(v) => {
+                    if let Some(pin) = self.pin_state.pins.get_mut(usize::from(v.pin)) {
+                        v.apply(pin);
+                    }
+                    Ok(())
+                }
+                message::System::PulseReplyMessage(v) => {
+                    self.pulses.entry(v.pin).or_default().record(v.duration_us);
+                    Ok(())
+                }
+                message::System::OneWireReplyMessage(v) => {
+                    if let OneWireReply::Search { pin, addresses } = &v {
+                        self.onewire_devices.insert(*pin, addresses.clone());
+                    }
+                    self.onewire_data.push(v);
+                    Ok(())
+                }
+                message::System::SchedulerReplyMessage(v) => {
+                    self.scheduler_data.push(v);
+                    Ok(())
+                }
+                message::System::SerialReply { port, data } => {
+                    let (capacity, eviction) = self.serial_capacity;
+                    let buffer = self
+                        .serial_data
+                        .entry(port)
+                        .or_insert_with(|| BoundedBuffer::with_capacity(capacity, eviction));
+                    for byte in data {
+                        buffer.push(byte);
+                    }
+                    Ok(())
+                }
+                message::System::StepperReplyMessage(v) => {
+                    match v {
+                        StepperReply::MoveComplete { device, position } => {
+                            self.steppers
+                                .entry(device)
+                                .or_default()
+                                .record_move(position);
+                        }
+                        StepperReply::Position { device, position } => {
+                            self.steppers.entry(device).or_default().position = position;
+                        }
+                        StepperReply::MultiMoveComplete { group } => {
+                            *self.stepper_group_moves.entry(group).or_default() += 1;
+                        }
+                    }
+                    Ok(())
+                }
             },
+            message::MessageIn::UnknownSysex { .. } => {
+                self.stats.unknown_sysex += 1;
+                Ok(())
+            }
+            message::MessageIn::FrameError(v) => {
+                self.stats.record_frame_error(&v);
+                Ok(())
+            }
             message::MessageIn::ProtocolVersion(v) => {
                 self.protocol_version = v;
                 Ok(())
@@ -151,9 +452,63 @@ impl<T: io::Read + io::Write> Board<T> {
 }
 
 impl<T: io::Read + io::Write> Board<T> {
-    pub fn i2c_data(&mut self) -> &mut Vec<I2CReply> {
+    pub fn i2c_data(&mut self) -> &mut BoundedBuffer<I2CReply> {
         &mut self.i2c_data
     }
+
+    /// OneWire replies that were not collected by a blocking call yet, only the latest
+    /// [`ONEWIRE_DATA_CAPACITY`] are kept unless [`Board::set_onewire_capacity`] was called.
+    pub fn onewire_data(&mut self) -> &mut BoundedBuffer<OneWireReply> {
+        &mut self.onewire_data
+    }
+
+    /// Scheduler replies that were not collected by a blocking call yet, e.g. reports
+    /// of failed tasks. Only the latest [`SCHEDULER_DATA_CAPACITY`] are kept unless
+    /// [`Board::set_scheduler_capacity`] was called.
+    pub fn scheduler_data(&mut self) -> &mut BoundedBuffer<SchedulerReply> {
+        &mut self.scheduler_data
+    }
+
+    /// Bytes received by the serial ports of the board that were not read yet, by port.
+    /// Only the latest [`SERIAL_DATA_CAPACITY`] bytes per port are kept unless
+    /// [`Board::set_serial_capacity`] was called.
+    pub fn serial_data(&mut self) -> &mut BTreeMap<u8, BoundedBuffer<u8>> {
+        &mut self.serial_data
+    }
+
+    /// Text received from the firmware that was not collected yet, only the latest
+    /// [`STRING_DATA_CAPACITY`] messages are kept.
+    pub fn string_data(&mut self) -> &mut BoundedBuffer<String> {
+        &mut self.string_data
+    }
+
+    /// Limits how many uncollected I2C replies are kept, unbounded by default. Dropped
+    /// replies are counted in [`BoundedBuffer::overflows`].
+    pub fn set_i2c_capacity(&mut self, capacity: usize, eviction: Eviction) {
+        self.i2c_data.set_capacity(Some(capacity), eviction);
+    }
+
+    /// Limits how many uncollected OneWire replies are kept, [`ONEWIRE_DATA_CAPACITY`] by
+    /// default. Dropped replies are counted in [`BoundedBuffer::overflows`].
+    pub fn set_onewire_capacity(&mut self, capacity: usize, eviction: Eviction) {
+        self.onewire_data.set_capacity(Some(capacity), eviction);
+    }
+
+    /// Limits how many uncollected scheduler replies are kept, [`SCHEDULER_DATA_CAPACITY`]
+    /// by default. Dropped replies are counted in [`BoundedBuffer::overflows`].
+    pub fn set_scheduler_capacity(&mut self, capacity: usize, eviction: Eviction) {
+        self.scheduler_data.set_capacity(Some(capacity), eviction);
+    }
+
+    /// Limits how many unread bytes are kept per serial port, [`SERIAL_DATA_CAPACITY`] by
+    /// default. Dropped bytes are counted in [`BoundedBuffer::overflows`] of their port.
+    pub fn set_serial_capacity(&mut self, capacity: usize, eviction: Eviction) {
+        self.serial_capacity = (capacity, eviction);
+        for buffer in self.serial_data.values_mut() {
+            buffer.set_capacity(Some(capacity), eviction);
+        }
+    }
+
     pub fn pins(&self) -> Vec<Pin> {
         self.pin_state.pins.clone()
     }
@@ -167,67 +522,404 @@ impl<T: io::Read + io::Write> Board<T> {
     pub fn firmware_version(&self) -> &str {
         &self.firmware_version
     }
+    /// Sets the calibration used by [`Board::read_calibrated`] for a pin.
+    pub fn set_calibration(&mut self, pin: PinId, calibration: Calibration) {
+        let pin = self.pin_id_to_pin(pin);
+        self.calibration.set(pin, calibration);
+    }
+
+    pub fn clear_calibration(&mut self, pin: PinId) {
+        let pin = self.pin_id_to_pin(pin);
+        self.calibration.remove(pin);
+    }
+
+    /// Gives access to every calibration, e.g. to [`CalibrationTable::save`] it.
+    pub fn calibration_table(&mut self) -> &mut CalibrationTable {
+        &mut self.calibration
+    }
+
+    /// Returns the current value of a pin converted by its calibration.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the pin does not exist.
+    pub fn read_calibrated(&self, pin: PinId) -> Result<f64> {
+        let pin_id = self.pin_id_to_pin(pin);
+        let physical = self
+            .pin_state
+            .pins
+            .get(pin_id as usize)
+            .ok_or(FirmataError::OutOfRange(
+                "tried to address pin a pin that exceeded the max pin index",
+            ))?;
+        Ok(self.calibration.convert(pin_id, physical))
+    }
+
+    /// Sets the sensor model used by [`Board::read_engineering`] for a pin.
+    pub fn set_transform<S: SensorTransform + 'static>(&mut self, pin: PinId, transform: S) {
+        let pin = self.pin_id_to_pin(pin);
+        self.transforms.set(pin, transform);
+    }
+
+    pub fn clear_transform(&mut self, pin: PinId) {
+        let pin = self.pin_id_to_pin(pin);
+        self.transforms.remove(pin);
+    }
+
+    /// Returns the current value of a pin in physical units, pins without a sensor
+    /// transform are converted by their calibration.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the pin does not exist.
+    pub fn read_engineering(&self, pin: PinId) -> Result<f64> {
+        let physical = self.pin_state.get(pin)?;
+        let pin_id = self.pin_id_to_pin(pin);
+        Ok(self
+            .transforms
+            .convert(pin_id, physical)
+            .unwrap_or_else(|| self.calibration.convert(pin_id, physical)))
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Times a firmware query round trip, the result is also recorded in [`Board::stats`].
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if no reply arrived within the timeout.
+    pub fn ping(&mut self, timeout: std::time::Duration) -> Result<std::time::Duration> {
+        let start = std::time::Instant::now();
+        self.query_firmware()?;
+        loop {
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(FirmataError::Timeout(format!("{}", timeout.as_millis())));
+            }
+            match self.read(remaining) {
+                Ok(MessageId::ReportFirmware) => {
+                    let latency = start.elapsed();
+                    self.stats.record_latency(latency);
+                    return Ok(latency);
+                }
+                Ok(_) | Err(FirmataError::UninitializedError(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Fails if the protocol version reported by the firmware lacks the feature.
+    fn require(&self, feature: Feature) -> Result<()> {
+        compat::require(&self.protocol_version, feature)
+    }
+
+    pub fn query_protocol_version(&mut self) -> Result<()> {
+        self.send(&[MidiCommand::ProtocolVersion.to_u8()])?;
+        Ok(())
+    }
+
     pub fn query_analog_mapping(&mut self) -> Result<()> {
-        self.connection
-            .write_all(&[START_SYSEX, ANALOG_MAPPING_QUERY, END_SYSEX])?;
+        self.require(Feature::AnalogMappingQuery)?;
+        self.send(&SysexCommand::AnalogMappingQuery.frame(&[]))?;
         Ok(())
     }
     pub fn query_capabilities(&mut self) -> Result<()> {
-        self.connection
-            .write_all(&[START_SYSEX, CAPABILITY_QUERY, END_SYSEX])?;
+        self.require(Feature::CapabilityQuery)?;
+        self.send(&SysexCommand::CapabilityQuery.frame(&[]))?;
+        Ok(())
+    }
+    /// Resets the firmware to its power on configuration.
+    pub fn system_reset(&mut self) -> Result<()> {
+        self.send(&[MidiCommand::SystemReset.to_u8()])?;
+        self.journal.clear();
+        self.output_latches.clear();
         Ok(())
     }
+
+    /// The configuration commands issued through this board, in order.
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    /// Names a pin, labels are kept with the session.
+    pub fn set_label(&mut self, pin: PinId, label: &str) {
+        let pin = self.pin_id_to_pin(pin);
+        self.labels.insert(pin, label.to_string());
+    }
+
+    pub fn label(&self, pin: PinId) -> Option<&str> {
+        self.labels
+            .get(&self.pin_id_to_pin(pin))
+            .map(String::as_str)
+    }
+
+    /// The configuration, labels and calibration of the board.
+    pub fn session(&self) -> Session {
+        Session {
+            journal: self.journal.clone(),
+            labels: self.labels.clone(),
+            calibration: self.calibration.clone(),
+        }
+    }
+
+    /// Takes over the labels and calibration of a session and replays its journal.
+    /// # Errors
+    /// Returns an error if writing to the connection failed.
+    pub fn restore_session(&mut self, session: &Session) -> Result<()> {
+        self.labels.clone_from(&session.labels);
+        self.calibration.clone_from(&session.calibration);
+        self.replay(&session.journal)
+    }
+
+    /// Writes the session to a file, see [`Board::session`].
+    /// # Errors
+    /// Returns an error if the file could not be written.
+    pub fn export_session<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        self.session().save(path)
+    }
+
+    /// Reads a session from a file and restores it, see [`Board::restore_session`].
+    /// # Errors
+    /// Returns an error if the file could not be read or writing to the connection failed.
+    pub fn import_session<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<()> {
+        let session = Session::load(path)?;
+        self.restore_session(&session)
+    }
+
+    /// Re-issues every configuration command in the journal, e.g. after a reconnect or
+    /// to set up a board the same way as another one.
+    /// # Errors
+    /// Returns an error if writing to the connection failed.
+    pub fn replay(&mut self, journal: &Journal) -> Result<()> {
+        for entry in journal.entries() {
+            match entry {
+                JournalEntry::PinMode(pin, mode) => self.set_pin_mode(PinId::Pin(*pin), *mode)?,
+                JournalEntry::ReportAnalog(channel, enable) => {
+                    self.report_analog(PinId::Analog(*channel), *enable)?;
+                }
+                JournalEntry::ReportDigital(port, enable) => {
+                    self.report_digital_port(*port, *enable)?;
+                }
+                JournalEntry::SamplingInterval(interval) => self.sampling_interval(*interval)?,
+                JournalEntry::I2cConfig(config) => self.i2c_config(*config)?,
+                JournalEntry::PwmFrequency(pin, hz) => {
+                    self.set_pwm_frequency(PinId::Pin(*pin), *hz)?;
+                }
+                JournalEntry::StepperConfig(config) => self.stepper_config(config)?,
+                JournalEntry::MultiStepperConfig(group, devices) => {
+                    self.multi_stepper_config(*group, devices)?;
+                }
+                JournalEntry::StepperSpeed(device, speed) => {
+                    self.set_stepper_speed(*device, *speed)?;
+                }
+                JournalEntry::StepperAcceleration(device, acceleration) => {
+                    self.set_stepper_acceleration(*device, *acceleration)?;
+                }
+                JournalEntry::ServoConfig(pin, min, max) => {
+                    self.servo_config(PinId::Pin(*pin), *min, *max)?;
+                }
+                JournalEntry::OneWireConfig(pin, power) => {
+                    self.onewire_config(PinId::Pin(*pin), *power)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Asks the firmware for the mode and state of a pin. Once the answer is read it
+    /// updates the mode and, for outputs, the value of the cached pin.
+    /// # Errors
+    /// Returns [`FirmataError::UnsupportedByFirmware`] if the firmware is too old for
+    /// pin state queries or an error if the message could not be sent.
+    pub fn query_pin_state(&mut self, pin: PinId) -> Result<()> {
+        self.require(Feature::PinStateQuery)?;
+        let pin_out = self.pin_id_to_pin(pin);
+        self.send(&SysexCommand::PinStateQuery.frame(&[pin_out]))?;
+        Ok(())
+    }
+
     pub fn query_firmware(&mut self) -> Result<()> {
-        self.connection
-            .write_all(&[START_SYSEX, REPORT_FIRMWARE, END_SYSEX])?;
+        self.send(&SysexCommand::ReportFirmware.frame(&[]))?;
         Ok(())
     }
 
-    pub fn i2c_config(&mut self, delay: u16) -> Result<()> {
-        let bytes_out = delay.to_le_bytes();
-        self.connection.write_all(&[
-            START_SYSEX,
-            I2C_CONFIG,
-            bytes_out[0],
-            bytes_out[1],
-            END_SYSEX,
-        ])?;
+    /// The firmware, protocol version and size of the board in one value.
+    #[must_use]
+    pub fn info(&self) -> BoardInfo {
+        BoardInfo::new(
+            &self.firmware_name,
+            &self.firmware_version,
+            &self.protocol_version,
+            &self.pin_state,
+        )
+    }
+
+    /// A summary of every pin for display, see [`CapabilityReport`].
+    pub fn capability_report(&self) -> CapabilityReport {
+        CapabilityReport::new(
+            &self.firmware_name,
+            &self.firmware_version,
+            &self.protocol_version,
+            &self.pin_state,
+            &self.labels,
+        )
+    }
+
+    /// The optional features detected from the capabilities and protocol version.
+    #[must_use]
+    pub fn features(&self) -> FeatureSet {
+        FeatureSet::detect(&self.pin_state, &self.protocol_version)
+    }
+
+    /// Returns a handle for I2C, only available if a pin of the board supports it.
+    /// # Errors
+    /// Returns [`FirmataError::UnsupportedByFirmware`] if I2C was not detected.
+    pub fn i2c(&mut self) -> Result<I2c<'_, T>> {
+        self.features().require_i2c()?;
+        Ok(I2c::new(self))
+    }
+
+    /// Configures the I2C bus, the address size and restart become the defaults of
+    /// following reads. The configuration is journaled so it is sent again on replay.
+    /// # Errors
+    /// Returns [`FirmataError::UnsupportedByFirmware`] without I2C support or an error if
+    /// writing to the connection failed.
+    pub fn i2c_config(&mut self, config: I2cConfig) -> Result<()> {
+        self.require(Feature::I2c)?;
+        self.send(&config.encode())?;
+        self.active_i2c_config = Some(config);
+        self.journal.record(JournalEntry::I2cConfig(config));
         Ok(())
     }
 
+    /// The last configuration sent with [`Board::i2c_config`].
+    #[must_use]
+    pub const fn active_i2c_config(&self) -> Option<I2cConfig> {
+        self.active_i2c_config
+    }
+
     pub fn i2c_read(&mut self, addr: u8, size: u16) -> Result<()> {
-        let bytes_out = size.to_le_bytes();
-        self.connection.write_all(&[
-            START_SYSEX,
-            I2C_REQUEST,
-            addr,
-            (I2C_MODE_READ << 3),
-            bytes_out[0],
-            bytes_out[1],
-            END_SYSEX,
-        ])?;
+        self.i2c_read_with(addr, &I2cReadOptions::new(size))
+    }
+
+    /// Requests a read with a register and the restart bit, the reply is collected in
+    /// [`Board::i2c_data`].
+    /// # Errors
+    /// Returns an error if the firmware has no I2C support or the write failed.
+    pub fn i2c_read_with(&mut self, addr: u8, options: &I2cReadOptions) -> Result<()> {
+        self.require(Feature::I2c)?;
+        let options = self
+            .active_i2c_config
+            .map_or(*options, |config| config.apply(*options));
+        self.send(&i2c::encode_read(addr, &options))?;
         Ok(())
     }
 
-    pub fn i2c_write(&mut self, addr: u8, data: &[u8]) -> Result<()> {
-        let mut buf = Vec::with_capacity(4 + data.len() * 2 + 1);
+    /// Reads from the board until the pin is observed at the given level. Digital
+    /// reporting has to be enabled for the pin's port for the value to change.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the level was not seen within the timeout.
+    pub fn latch(&mut self, pin: PinId, level: bool, timeout: std::time::Duration) -> Result<()> {
+        let index = self.pin_id_to_pin(pin) as usize;
+        self.read_until(timeout, |board| {
+            board
+                .pin_state
+                .pins
+                .get(index)
+                .filter(|v| (v.value != 0) == level)
+                .map(|_| ())
+        })
+    }
+
+    /// See [`Board::latch`].
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the level was not seen within the timeout.
+    pub fn latch_high(&mut self, pin: PinId, timeout: std::time::Duration) -> Result<()> {
+        self.latch(pin, true, timeout)
+    }
 
-        buf.push(START_SYSEX);
-        buf.push(I2C_REQUEST);
-        buf.push(addr);
-        buf.push(I2C_MODE_WRITE << 3);
+    /// See [`Board::latch`].
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the level was not seen within the timeout.
+    pub fn latch_low(&mut self, pin: PinId, timeout: std::time::Duration) -> Result<()> {
+        self.latch(pin, false, timeout)
+    }
+
+    /// Reads from the board until an I2C reply from the given address arrives.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if no reply arrived within the timeout.
+    pub fn wait_for_i2c_reply(
+        &mut self,
+        addr: u8,
+        timeout: std::time::Duration,
+    ) -> Result<I2CReply> {
+        self.read_until(timeout, |board| {
+            board
+                .i2c_data
+                .iter()
+                .position(|reply| reply.address == i32::from(addr))
+                .and_then(|index| board.i2c_data.remove(index))
+        })
+    }
 
-        for i in data.iter() {
-            buf.push(i & 0x7F);
-            buf.push(((i32::from(*i) >> 7) & 0x7F) as u8);
+    /// Reads from a device and waits for the reply, the request is sent again each time
+    /// an attempt times out.
+    /// # Errors
+    /// Returns [`FirmataError::I2cTimeout`] if every attempt timed out.
+    pub fn i2c_read_reply(
+        &mut self,
+        addr: u8,
+        options: &I2cReadOptions,
+        policy: &I2cRetryPolicy,
+    ) -> Result<I2CReply> {
+        for _ in 0..=policy.retries {
+            self.i2c_read_with(addr, options)?;
+            let reply = self.read_until(policy.timeout, |board| {
+                board
+                    .i2c_data
+                    .iter()
+                    .position(|reply| i2c::reply_matches(reply, addr, options))
+                    .and_then(|index| board.i2c_data.remove(index))
+            });
+            match reply {
+                Err(FirmataError::Timeout(_)) => {}
+                reply => return reply,
+            }
         }
-        buf.push(END_SYSEX);
-        self.connection.write_all(&buf[..])?;
+        Err(FirmataError::I2cTimeout(addr, options.register))
+    }
+
+    /// Reads from the board until the check returns a value or the timeout elapses.
+    fn read_until<R, F: FnMut(&mut Self) -> Option<R>>(
+        &mut self,
+        timeout: std::time::Duration,
+        mut check: F,
+    ) -> Result<R> {
+        let start = std::time::Instant::now();
+        loop {
+            if let Some(result) = check(self) {
+                return Ok(result);
+            }
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(FirmataError::Timeout(format!("{}", timeout.as_millis())));
+            }
+            match self.read(remaining) {
+                Ok(_) | Err(FirmataError::UninitializedError(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub fn i2c_write(&mut self, addr: u8, data: &[u8]) -> Result<()> {
+        self.require(Feature::I2c)?;
+        self.send(&i2c::encode_write(addr, data))?;
         Ok(())
     }
 
+    /// Enables or disables reporting of a digital port, the protocol reports the eight
+    /// pins of a port together. The pin number is taken as the port number, prefer
+    /// [`Board::report_digital_port`] or [`Board::report_digital_pin`].
+    /// # Errors
+    /// Returns [`FirmataError::WrongType`] for an analog pin id.
     pub fn report_digital(&mut self, pin: PinId, state: bool) -> Result<()> {
-        let pin_out = match pin {
+        let port = match pin {
             PinId::Analog(_) => {
                 return Err(FirmataError::WrongType(
                     "found analog pin expected analog pin",
@@ -235,25 +927,98 @@ impl<T: io::Read + io::Write> Board<T> {
             }
             PinId::Digital(v) | PinId::Pin(v) => v,
         };
-        self.connection
-            .write_all(&[REPORT_DIGITAL | pin_out, state as u8])?;
+        self.report_digital_port(port, state)
+    }
+
+    /// Enables or disables reporting of the eight pins of a digital port.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the port is above 15.
+    pub fn report_digital_port(&mut self, port: u8, state: bool) -> Result<()> {
+        if port > 0x0F {
+            return Err(FirmataError::OutOfRange("digital port exceeds 15"));
+        }
+        self.send(&[
+            MidiCommand::ReportDigital.with_channel(port),
+            u8::from(state),
+        ])?;
+        self.journal
+            .record(JournalEntry::ReportDigital(port, state));
         Ok(())
     }
 
+    /// Enables or disables reporting of the port a pin belongs to, the other pins of
+    /// the port are reported as well.
+    /// # Errors
+    /// Returns [`FirmataError::NotFoundError`] if the board has no such pin.
+    pub fn report_digital_pin(&mut self, pin: PinId, state: bool) -> Result<()> {
+        self.pin_state.get(pin)?;
+        self.report_digital_port(self.pin_id_to_pin(pin) / 8, state)
+    }
+
+    /// Enables or disables reporting for the analog channel of a pin.
+    /// # Errors
+    /// Returns [`FirmataError::WrongType`] for a digital pin id,
+    /// [`FirmataError::NotFoundError`] if the board has no such pin or
+    /// [`FirmataError::OutOfRange`] if the pin has no analog channel.
     pub fn report_analog(&mut self, pin: PinId, state: bool) -> Result<()> {
-        let pin_out = match pin {
-            PinId::Analog(_) => self.pin_id_to_pin(pin),
-            PinId::Digital(_) => {
-                return Err(FirmataError::WrongType(
-                    "found digital pin expected analog pin",
-                ))
-            }
-            PinId::Pin(v) => v,
-        };
-        let state: u8 = state.into();
+        if let PinId::Digital(_) = pin {
+            return Err(FirmataError::WrongType(
+                "found digital pin expected analog pin",
+            ));
+        }
+        let channel = self.pin_state.analog_channel(pin)?;
+        self.send(&[
+            MidiCommand::ReportAnalog.with_channel(channel),
+            u8::from(state),
+        ])?;
+        self.journal
+            .record(JournalEntry::ReportAnalog(channel, state));
+        Ok(())
+    }
+
+    /// Enables or disables reporting for every digital port the board has.
+    /// # Errors
+    /// Returns [`FirmataError::UninitializedError`] if the pins have not been queried.
+    pub fn report_all_digital(&mut self, state: bool) -> Result<()> {
+        let ports = self.pin_state.ports();
+        if ports == 0 {
+            return Err(FirmataError::UninitializedError(
+                "pins have not been queried, the ports are unknown",
+            ));
+        }
+        for port in 0..ports {
+            self.report_digital_port(port, state)?;
+        }
+        Ok(())
+    }
 
-        self.connection
-            .write_all(&[REPORT_ANALOG | (pin_out + 1), state])?;
+    /// Enables or disables reporting for every analog channel the board has.
+    /// # Errors
+    /// Returns [`FirmataError::UninitializedError`] if the analog mapping is unknown.
+    pub fn report_all_analog(&mut self, state: bool) -> Result<()> {
+        let pins: Vec<u8> = self.pin_state.analog_channels.keys().copied().collect();
+        if pins.is_empty() {
+            return Err(FirmataError::UninitializedError(
+                "analog mapping has not been queried, the channels are unknown",
+            ));
+        }
+        for pin in pins {
+            self.report_analog(PinId::Pin(pin), state)?;
+        }
+        Ok(())
+    }
+
+    /// Stops every digital and analog report, e.g. before closing the connection so the
+    /// board does not keep streaming.
+    /// # Errors
+    /// Returns an error if writing to the connection failed.
+    pub fn quiesce(&mut self) -> Result<()> {
+        if self.pin_state.ports() > 0 {
+            self.report_all_digital(false)?;
+        }
+        if !self.pin_state.analog_channels.is_empty() {
+            self.report_all_analog(false)?;
+        }
         Ok(())
     }
 
@@ -262,11 +1027,108 @@ impl<T: io::Read + io::Write> Board<T> {
             PinId::Analog(_) => self.pin_id_to_pin(pin),
             PinId::Digital(v) | PinId::Pin(v) => v,
         };
+        self.pin_state.pins[pin_out as usize].validate_write(output)?;
+        if analog::needs_extended(pin_out, output) {
+            self.require(Feature::ExtendedAnalog)?;
+        }
+        self.send(&analog::encode_write(pin_out, output)?)?;
         self.pin_state.pins[pin_out as usize].value = output;
-        let bytes_out = output.to_le_bytes();
+        Ok(())
+    }
+
+    /// Writes several analog or PWM outputs in one burst, e.g. the channels of an RGB
+    /// LED, so they change together. Nothing is written if any value is invalid.
+    /// # Errors
+    /// Returns an error if a pin does not exist, a value does not fit its pin or writing
+    /// to the connection failed.
+    pub fn analog_write_group(&mut self, writes: &[(PinId, u16)]) -> Result<()> {
+        let mut buf = Vec::with_capacity(writes.len() * 3);
+        for (pin, output) in writes {
+            self.pin_state.get(*pin)?.validate_write(*output)?;
+            let pin_out = self.pin_id_to_pin(*pin);
+            if analog::needs_extended(pin_out, *output) {
+                self.require(Feature::ExtendedAnalog)?;
+            }
+            buf.extend_from_slice(&analog::encode_write(pin_out, *output)?);
+        }
+        self.send(&buf)?;
+        for (pin, output) in writes {
+            let index = usize::from(self.pin_id_to_pin(*pin));
+            self.pin_state.pins[index].value = *output;
+        }
+        Ok(())
+    }
+
+    /// Writes a duty cycle between 0.0 and 1.0, scaled to the pin's PWM resolution.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the fraction is outside of 0.0 to 1.0 or
+    /// [`FirmataError::WrongType`] if the pin does not support PWM.
+    pub fn set_duty_fraction(&mut self, pin: PinId, fraction: f64) -> Result<()> {
+        let value = self.pin(pin)?.duty_to_value(fraction)?;
+        self.analog_write(pin, value)
+    }
 
-        self.connection
-            .write_all(&[ANALOG_MESSAGE | pin_out, bytes_out[0], bytes_out[1]])?;
+    /// Writes the steps of a ramp to an analog, PWM or servo output, blocking until the
+    /// end value is written. The steps are spaced by [`pwm::MIN_RAMP_STEP`].
+    /// # Errors
+    /// Returns an error if a value does not fit the pin or could not be sent.
+    pub fn ramp(&mut self, pin: PinId, ramp: Ramp) -> Result<()> {
+        let start = Instant::now();
+        for (at, value) in ramp.steps(pwm::MIN_RAMP_STEP) {
+            std::thread::sleep(at.saturating_sub(start.elapsed()));
+            self.analog_write(pin, value)?;
+        }
+        Ok(())
+    }
+
+    /// Moves the duty cycle of a PWM pin from one fraction to another, see [`Self::ramp`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if a fraction is outside of 0.0 to 1.0,
+    /// [`FirmataError::WrongType`] if the pin does not support PWM or an error if a
+    /// write could not be sent.
+    pub fn ramp_duty(
+        &mut self,
+        pin: PinId,
+        from: f64,
+        to: f64,
+        duration: Duration,
+        curve: RampCurve,
+    ) -> Result<()> {
+        let physical = self.pin(pin)?;
+        let ramp = Ramp::new(
+            physical.duty_to_value(from)?,
+            physical.duty_to_value(to)?,
+            duration,
+            curve,
+        );
+        self.ramp(pin, ramp)
+    }
+
+    /// Changes the PWM frequency of a pin on firmwares that support it.
+    /// # Errors
+    /// Returns [`FirmataError::WrongType`] if the pin does not support PWM or
+    /// [`FirmataError::OutOfRange`] if the frequency can not be sent.
+    pub fn set_pwm_frequency(&mut self, pin: PinId, hz: u32) -> Result<()> {
+        let pin_out = self.pin_id_to_pin(pin);
+        pwm::validate_frequency(self.pin_state.get(pin)?, hz)?;
+        self.send(&pwm::encode_frequency(pin_out, hz))?;
+        self.journal.record(JournalEntry::PwmFrequency(pin_out, hz));
+        Ok(())
+    }
+
+    /// Sets the pulse range of a servo in microseconds, e.g. 500 to 2500 for servos that
+    /// do not reach their full travel with the defaults of 544 to 2400. The firmware also
+    /// switches the pin to servo mode.
+    /// # Errors
+    /// Returns [`FirmataError::WrongType`] if the pin does not support servos or
+    /// [`FirmataError::OutOfRange`] if the pulse range is invalid.
+    pub fn servo_config(&mut self, pin: PinId, min_pulse: u16, max_pulse: u16) -> Result<()> {
+        let pin_out = self.pin_id_to_pin(pin);
+        servo::validate_config(self.pin_state.get(pin)?, min_pulse, max_pulse)?;
+        self.send(&servo::encode_config(pin_out, min_pulse, max_pulse))?;
+        self.pin_state.pins[pin_out as usize].mode = PinMode::Servo;
+        self.journal
+            .record(JournalEntry::ServoConfig(pin_out, min_pulse, max_pulse));
         Ok(())
     }
 
@@ -279,52 +1141,212 @@ impl<T: io::Read + io::Write> Board<T> {
             }
             PinId::Digital(v) | PinId::Pin(v) => v,
         };
-        let port = (f64::from(pin_out) / 8_f64).floor() as usize;
-        let mut value = 0_i32;
-        let mut i = 0;
-
-        self.pin_state.pins[pin_out as usize].value = output;
+        let physical =
+            self.pin_state
+                .pins
+                .get_mut(usize::from(pin_out))
+                .ok_or(FirmataError::OutOfRange(
+                    "tried to address pin a pin that exceeded the max pin index",
+                ))?;
+        let level = output != 0;
+        physical.value = u16::from(level);
+        let value = self.output_latches.write_pin(pin_out, level);
+        if self.require(Feature::SetDigitalPinValue).is_ok() {
+            self.send(&digital::encode_pin_write(pin_out, level))?;
+        } else {
+            self.send(&digital::encode_port_write(
+                digital::port_of(pin_out),
+                value,
+            ))?;
+        }
+        Ok(())
+    }
 
-        while i < 8 {
-            if self.pin_state.pins[8 * port + i].value != 0 {
-                value |= 1 << i;
+    /// Sets the pins of a port selected by the mask to their bits in the value with one
+    /// message, the other pins keep the level last written to them.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the port can not be addressed or an error
+    /// if the write failed.
+    pub fn digital_port_write(&mut self, port: u8, mask: u8, value: u8) -> Result<()> {
+        digital::validate_port(port)?;
+        let value = self.output_latches.write_port(port, mask, value);
+        for bit in 0..8 {
+            if let Some(physical) = self.pin_state.pins.get_mut(usize::from(port * 8 + bit)) {
+                if mask & 1 << bit != 0 {
+                    physical.value = u16::from(value >> bit & 1);
+                }
             }
-            i += 1;
         }
-        let bytes_out = value.to_le_bytes();
-        self.connection
-            .write_all(&[DIGITAL_MESSAGE | port as u8, bytes_out[0], bytes_out[1]])?;
+        self.send(&digital::encode_port_write(port, value))?;
         Ok(())
     }
 
+    /// The levels written to the outputs of every port, apart from reported inputs.
+    #[must_use]
+    pub const fn output_latches(&self) -> &OutputLatches {
+        &self.output_latches
+    }
+
+    /// Sends text to the firmware, split into messages of at most
+    /// [`MAX_STRING_CHUNK`](crate::text::MAX_STRING_CHUNK) bytes.
+    /// # Errors
+    /// Returns an error if the write failed.
     pub fn string_write(&mut self, string: &str) -> Result<()> {
-        let mut buf: Vec<u8> = vec![START_SYSEX, STRING_DATA];
-        for x in string.as_bytes().iter() {
-            let double_byte: u16 = (*x).into();
-            buf.write_u16::<LittleEndian>(double_byte)?;
-        }
-        buf.push(END_SYSEX);
-        self.connection.write_all(buf.as_slice())?;
+        self.send(&text::encode(string))?;
         Ok(())
     }
 
     pub fn set_pin_mode(&mut self, pin: PinId, mode: PinMode) -> Result<()> {
+        if mode == PinMode::Pullup {
+            self.require(Feature::PullupMode)?;
+        }
         let pin_out = match pin {
             PinId::Analog(_) => self.pin_id_to_pin(pin),
             PinId::Digital(v) | PinId::Pin(v) => v,
         };
         self.pin_state.pins[pin_out as usize].mode = mode;
-        self.connection
-            .write_all(&[PIN_MODE, pin_out, mode.to_u8()])?;
+        self.send(&[MidiCommand::SetPinMode.to_u8(), pin_out, mode.to_u8()])?;
+        self.journal.record(JournalEntry::PinMode(pin_out, mode));
+        if self.auto_report {
+            match mode {
+                PinMode::Input | PinMode::Pullup => {
+                    self.report_digital_port(pin_out / 8, true)?;
+                }
+                PinMode::Analog => self.report_analog(PinId::Pin(pin_out), true)?,
+                _ => {}
+            }
+        }
         Ok(())
     }
 
     pub fn read(&mut self, timeout: std::time::Duration) -> Result<MessageId> {
-        let message = parser::read_and_parse(&mut self.connection, timeout)?;
-        self.handle_message(message.message)?;
+        let message = self.next_message(timeout)?;
+        self.dispatch(message.message)?;
         Ok(message.message_id)
     }
 
+    /// Like [`Board::read`] but returns the whole message, with the raw bytes attached if
+    /// [`Board::set_retain_raw`] is enabled.
+    /// # Errors
+    /// Returns an error if reading or parsing failed.
+    pub fn read_message(&mut self, timeout: std::time::Duration) -> Result<Message> {
+        let message = self.next_message(timeout)?;
+        self.dispatch(message.message.clone())?;
+        Ok(message)
+    }
+
+    fn next_message(&mut self, timeout: std::time::Duration) -> Result<Message> {
+        loop {
+            match self.next_frame(timeout) {
+                Err(FirmataError::ParseError(reason, discarded)) => {
+                    self.stats.record_frame_error(&FrameError {
+                        reason: reason.to_string(),
+                        discarded: discarded.clone(),
+                    });
+                    match self.recovery {
+                        RecoveryPolicy::Bail => {
+                            return Err(FirmataError::ParseError(reason, discarded))
+                        }
+                        RecoveryPolicy::SkipFrame => {}
+                        RecoveryPolicy::ResetDecoder => self.stats.decoder_resets += 1,
+                        RecoveryPolicy::SystemReset => {
+                            self.send(&[MidiCommand::SystemReset.to_u8()])?;
+                            self.stats.system_resets += 1;
+                            let journal = self.journal.clone();
+                            self.replay(&journal)?;
+                        }
+                    }
+                }
+                message => return message,
+            }
+        }
+    }
+
+    fn next_frame(&mut self, timeout: std::time::Duration) -> Result<Message> {
+        let lenient = self.lenient;
+        if self.retain_raw {
+            let mut recorder = parser::Recorder::new(&mut self.connection);
+            let message = parser::read_and_parse(&mut recorder, timeout, lenient);
+            let raw = recorder.into_bytes();
+            self.last_raw_frame = Some(raw.clone());
+            message.map(|message| Message {
+                raw: Some(raw),
+                ..message
+            })
+        } else {
+            parser::read_and_parse(&mut self.connection, timeout, lenient)
+        }
+    }
+
+    fn dispatch(&mut self, message: MessageIn) -> Result<()> {
+        self.handle_message(message)?;
+        for (pin, comparator) in &self.comparators {
+            if let Some(value) = self.pin_state.pins.get(usize::from(*pin)).map(|v| v.value) {
+                let previous = self.comparator_levels.get(pin).copied();
+                self.comparator_levels
+                    .insert(*pin, comparator.level(previous, value));
+            }
+        }
+        if !self.callbacks.is_empty() {
+            let pins = &self.pin_state.pins;
+            self.callbacks
+                .dispatch(|pin| pins.get(pin as usize).map(|v| v.value));
+        }
+        Ok(())
+    }
+
+    /// Gives an analog pin a digital view with hysteresis, the level follows the values
+    /// read from [`Board::read`] and [`Board::poll`].
+    pub fn set_comparator(&mut self, pin: PinId, comparator: Comparator) {
+        let pin = self.pin_id_to_pin(pin);
+        self.comparators.insert(pin, comparator);
+        self.comparator_levels.remove(&pin);
+    }
+
+    pub fn clear_comparator(&mut self, pin: PinId) {
+        let pin = self.pin_id_to_pin(pin);
+        self.comparators.remove(&pin);
+        self.comparator_levels.remove(&pin);
+    }
+
+    /// The level of a pin's comparator, `None` without a comparator or before the
+    /// first message was read after setting it.
+    #[must_use]
+    pub fn comparator_level(&self, pin: PinId) -> Option<bool> {
+        self.comparator_levels
+            .get(&self.pin_id_to_pin(pin))
+            .copied()
+    }
+
+    /// Registers a callback that is called from [`Board::read`] and [`Board::poll`]
+    /// whenever the value of the pin changes.
+    pub fn on_change<F: FnMut(PinChange) + Send + 'static>(
+        &mut self,
+        pin: PinId,
+        callback: F,
+    ) -> CallbackId {
+        self.on_change_threshold(pin, 1, callback)
+    }
+
+    /// Like [`Board::on_change`] but only calls back once the value moved at least
+    /// `threshold` away from the value of the last call, useful for noisy analog pins.
+    pub fn on_change_threshold<F: FnMut(PinChange) + Send + 'static>(
+        &mut self,
+        pin: PinId,
+        threshold: u16,
+        callback: F,
+    ) -> CallbackId {
+        let pin = self.pin_id_to_pin(pin);
+        let current = self.pin_state.pins.get(pin as usize).map_or(0, |v| v.value);
+        self.callbacks
+            .register(pin, threshold.max(1), current, callback)
+    }
+
+    /// Removes a callback, returns false if it was not registered.
+    pub fn remove_callback(&mut self, id: CallbackId) -> bool {
+        self.callbacks.remove(id)
+    }
+
     pub fn poll(&mut self, loop_times: usize) -> Result<()> {
         let mut i = 0;
         while i < loop_times {
@@ -340,16 +1362,573 @@ impl<T: io::Read + io::Write> Board<T> {
         Ok(())
     }
 
+    /// Configures an AccelStepper device.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+    pub fn stepper_config(&mut self, config: &StepperConfig) -> Result<()> {
+        if config.device >= MAX_STEPPERS {
+            return Err(FirmataError::OutOfRange("stepper device number exceeds 9"));
+        }
+        self.send(&stepper::encode_config(config))?;
+        self.journal.record(JournalEntry::StepperConfig(*config));
+        Ok(())
+    }
+
+    /// Sets the current position of a device as its zero position.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+    pub fn stepper_zero(&mut self, device: u8) -> Result<()> {
+        stepper::validate_device(device)?;
+        self.send(&stepper::encode_zero(device))?;
+        Ok(())
+    }
+
+    /// Starts moving a device by a number of steps without waiting, negative steps
+    /// move backwards.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+    pub fn stepper_step(&mut self, device: u8, steps: i32) -> Result<()> {
+        stepper::validate_device(device)?;
+        self.send(&stepper::encode_step(device, steps))?;
+        Ok(())
+    }
+
+    /// Starts moving a device to an absolute position without waiting.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+    pub fn stepper_to(&mut self, device: u8, position: i32) -> Result<()> {
+        stepper::validate_device(device)?;
+        self.send(&stepper::encode_to(device, position))?;
+        Ok(())
+    }
+
+    /// Switches the outputs of a device on or off, e.g. to let the motor turn freely.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+    pub fn stepper_enable(&mut self, device: u8, enable: bool) -> Result<()> {
+        stepper::validate_device(device)?;
+        self.send(&stepper::encode_enable(device, enable))?;
+        Ok(())
+    }
+
+    /// Stops a device, decelerating if it has an acceleration.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+    pub fn stepper_stop(&mut self, device: u8) -> Result<()> {
+        stepper::validate_device(device)?;
+        self.send(&stepper::encode_stop(device))?;
+        Ok(())
+    }
+
+    /// Asks for the position of a device, the answer updates [`Board::stepper_position`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+    pub fn report_stepper_position(&mut self, device: u8) -> Result<()> {
+        stepper::validate_device(device)?;
+        self.send(&stepper::encode_report_position(device))?;
+        Ok(())
+    }
+
+    /// The last position a device reported, `None` before its first report.
+    #[must_use]
+    pub fn stepper_position(&self, device: u8) -> Option<i32> {
+        self.steppers.get(&device).map(|v| v.position)
+    }
+
+    /// Sets the maximum speed of a device in steps per second.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number or speed is not supported.
+    pub fn set_stepper_speed(&mut self, device: u8, speed: f32) -> Result<()> {
+        stepper::validate_device(device)?;
+        stepper::validate_float(speed)?;
+        self.send(&stepper::encode_speed(device, speed))?;
+        self.journal
+            .record(JournalEntry::StepperSpeed(device, speed));
+        Ok(())
+    }
+
+    /// Sets the acceleration of a device in steps per second squared, zero moves at
+    /// constant speed.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the device number or acceleration is not
+    /// supported.
+    pub fn set_stepper_acceleration(&mut self, device: u8, acceleration: f32) -> Result<()> {
+        stepper::validate_device(device)?;
+        stepper::validate_float(acceleration)?;
+        self.send(&stepper::encode_acceleration(device, acceleration))?;
+        self.journal
+            .record(JournalEntry::StepperAcceleration(device, acceleration));
+        Ok(())
+    }
+
+    /// Moves a device to an absolute position and blocks until it got there, returns
+    /// the position it reported.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the move did not complete within the timeout.
+    pub fn move_stepper(
+        &mut self,
+        device: u8,
+        position: i32,
+        timeout: std::time::Duration,
+    ) -> Result<i32> {
+        let before = self.steppers.get(&device).map_or(0, |v| v.moves);
+        self.stepper_to(device, position)?;
+        self.read_until(timeout, |board| {
+            board
+                .steppers
+                .get(&device)
+                .filter(|v| v.moves > before)
+                .map(|v| v.position)
+        })
+    }
+
+    /// Groups configured stepper devices so they can be moved together with [`Board::move_all`].
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the group or a device number is not supported.
+    pub fn multi_stepper_config(&mut self, group: u8, devices: &[u8]) -> Result<()> {
+        if group >= MAX_STEPPER_GROUPS {
+            return Err(FirmataError::OutOfRange("stepper group number exceeds 4"));
+        }
+        if devices.iter().any(|device| *device >= MAX_STEPPERS) {
+            return Err(FirmataError::OutOfRange("stepper device number exceeds 9"));
+        }
+        self.send(&stepper::encode_multi_config(group, devices))?;
+        self.journal
+            .record(JournalEntry::MultiStepperConfig(group, devices.to_vec()));
+        Ok(())
+    }
+
+    /// Starts moving every member of a group to its absolute position without waiting.
+    pub fn multi_stepper_to(&mut self, group: u8, positions: &[i32]) -> Result<()> {
+        self.send(&stepper::encode_multi_to(group, positions))?;
+        Ok(())
+    }
+
+    pub fn multi_stepper_stop(&mut self, group: u8) -> Result<()> {
+        self.send(&stepper::encode_multi_stop(group))?;
+        Ok(())
+    }
+
+    /// Moves every member of a group to its absolute position and blocks until all of
+    /// them finished together.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the move did not complete within the timeout.
+    pub fn move_all(
+        &mut self,
+        group: u8,
+        positions: &[i32],
+        timeout: std::time::Duration,
+    ) -> Result<()> {
+        let completed = |board: &Self| board.stepper_group_moves.get(&group).copied();
+        let before = completed(self).unwrap_or(0);
+        self.multi_stepper_to(group, positions)?;
+        self.read_until(timeout, |board| {
+            (completed(board).unwrap_or(0) > before).then_some(())
+        })
+    }
+
+    /// Sets up a OneWire bus on a pin, the firmware switches the pin to OneWire mode.
+    /// With parasitic power the pin is driven high after writes to power the devices.
+    /// # Errors
+    /// Returns [`FirmataError::WrongType`] if the pin does not support OneWire.
+    pub fn onewire_config(&mut self, pin: PinId, parasitic_power: bool) -> Result<()> {
+        let pin_out = self.pin_id_to_pin(pin);
+        onewire::validate_pin(self.pin_state.get(pin)?)?;
+        self.send(&onewire::encode_config(pin_out, parasitic_power))?;
+        self.pin_state.pins[pin_out as usize].mode = PinMode::Onewire;
+        self.journal
+            .record(JournalEntry::OneWireConfig(pin_out, parasitic_power));
+        Ok(())
+    }
+
+    /// Searches the bus of a pin and blocks until the addresses of every device on it
+    /// arrived.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the search did not finish in time.
+    pub fn onewire_search(
+        &mut self,
+        pin: PinId,
+        timeout: std::time::Duration,
+    ) -> Result<Vec<OneWireAddress>> {
+        self.search_onewire(pin, false, timeout)
+    }
+
+    /// Like [`Board::onewire_search`], only devices with an alarm set answer.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the search did not finish in time.
+    pub fn onewire_search_alarms(
+        &mut self,
+        pin: PinId,
+        timeout: std::time::Duration,
+    ) -> Result<Vec<OneWireAddress>> {
+        self.search_onewire(pin, true, timeout)
+    }
+
+    fn search_onewire(
+        &mut self,
+        pin: PinId,
+        alarms: bool,
+        timeout: std::time::Duration,
+    ) -> Result<Vec<OneWireAddress>> {
+        let pin_out = self.pin_id_to_pin(pin);
+        self.send(&onewire::encode_search(pin_out, alarms))?;
+        self.read_until(timeout, |board| {
+            let index = board.onewire_data.iter().position(|reply| match reply {
+                OneWireReply::Search { pin, .. } => !alarms && *pin == pin_out,
+                OneWireReply::SearchAlarms { pin, .. } => alarms && *pin == pin_out,
+                OneWireReply::Read { .. } => false,
+            })?;
+            match board.onewire_data.remove(index)? {
+                OneWireReply::Search { addresses, .. }
+                | OneWireReply::SearchAlarms { addresses, .. } => Some(addresses),
+                OneWireReply::Read { .. } => None,
+            }
+        })
+    }
+
+    /// The devices found by the last search of a pin.
+    pub fn onewire_devices(&self, pin: PinId) -> Vec<OneWireAddress> {
+        let pin = self.pin_id_to_pin(pin);
+        self.onewire_devices.get(&pin).cloned().unwrap_or_default()
+    }
+
+    /// Runs a bus transaction without waiting for it, replies are kept in
+    /// [`Board::onewire_data`].
+    pub fn onewire_send(&mut self, pin: PinId, request: &OneWireRequest) -> Result<()> {
+        let pin_out = self.pin_id_to_pin(pin);
+        self.send(&onewire::encode_request(pin_out, request))?;
+        Ok(())
+    }
+
+    /// Runs a bus transaction and, if it reads, blocks until the bytes arrived. The
+    /// correlation id of the request is replaced with one unique to the board.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the read bytes did not arrive in time.
+    pub fn onewire_transfer(
+        &mut self,
+        pin: PinId,
+        request: &OneWireRequest,
+        timeout: std::time::Duration,
+    ) -> Result<Vec<u8>> {
+        let pin_out = self.pin_id_to_pin(pin);
+        let id = self.next_onewire_id;
+        self.next_onewire_id = id.wrapping_add(1);
+        let request = request.clone().read(request.read, id);
+        self.onewire_send(pin, &request)?;
+        if request.read == 0 {
+            return Ok(vec![]);
+        }
+        self.read_until(timeout, |board| {
+            let index = board.onewire_data.iter().position(|reply| {
+                matches!(reply, OneWireReply::Read { pin, correlation_id, .. }
+                    if *pin == pin_out && *correlation_id == id)
+            })?;
+            match board.onewire_data.remove(index)? {
+                OneWireReply::Read { data, .. } => Some(data),
+                _ => None,
+            }
+        })
+    }
+
+    /// Starts a conversion on a DS18B20 and blocks until its temperature in degrees
+    /// Celsius was read. Without an address the only sensor on the bus is read.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the sensor did not answer in time or
+    /// [`FirmataError::ConversionFailure`] if the reading was corrupted.
+    pub fn read_ds18b20(
+        &mut self,
+        pin: PinId,
+        address: Option<OneWireAddress>,
+        timeout: std::time::Duration,
+    ) -> Result<f64> {
+        self.onewire_send(pin, &OneWireRequest::ds18b20_convert(address))?;
+        // The firmware only runs the read once the conversion delay has passed.
+        let scratchpad = self.onewire_transfer(
+            pin,
+            &OneWireRequest::ds18b20_read(address, 0),
+            timeout + onewire::DS18B20_CONVERSION_TIME,
+        )?;
+        onewire::ds18b20_temperature(&scratchpad)
+    }
+
+    /// Stores a task in the scheduler of the firmware, see [`crate::scheduler::TaskBuilder`]
+    /// for the bytes. The task only runs once it is scheduled.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the task can not be stored or an error if
+    /// a message could not be sent.
+    pub fn create_task(&mut self, task: u8, data: &[u8]) -> Result<()> {
+        for request in scheduler::store_task(task, data)? {
+            self.send(&request.encode())?;
+        }
+        Ok(())
+    }
+
+    /// Runs a stored task once the delay passed.
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub fn schedule_task(&mut self, task: u8, delay: std::time::Duration) -> Result<()> {
+        self.send(&SchedulerRequest::Schedule { task, delay }.encode())
+    }
+
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub fn delete_task(&mut self, task: u8) -> Result<()> {
+        self.send(&SchedulerRequest::Delete(task).encode())
+    }
+
+    /// Deletes every task.
+    /// # Errors
+    /// Returns an error if the message could not be sent.
+    pub fn reset_tasks(&mut self) -> Result<()> {
+        self.send(&SchedulerRequest::Reset.encode())
+    }
+
+    /// Blocks until the firmware listed the ids of its stored tasks.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the firmware did not answer in time, e.g.
+    /// because it has no scheduler.
+    pub fn query_tasks(&mut self, timeout: std::time::Duration) -> Result<Vec<u8>> {
+        self.send(&SchedulerRequest::QueryAll.encode())?;
+        self.read_until(timeout, |board| {
+            let index = board
+                .scheduler_data
+                .iter()
+                .position(|reply| matches!(reply, SchedulerReply::Tasks(_)))?;
+            match board.scheduler_data.remove(index)? {
+                SchedulerReply::Tasks(tasks) => Some(tasks),
+                _ => None,
+            }
+        })
+    }
+
+    /// Blocks until the firmware reported a task, `None` if it has no such task.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if the firmware did not answer in time.
+    pub fn query_task(
+        &mut self,
+        task: u8,
+        timeout: std::time::Duration,
+    ) -> Result<Option<TaskState>> {
+        self.send(&SchedulerRequest::Query(task).encode())?;
+        self.read_until(timeout, |board| {
+            let index = board
+                .scheduler_data
+                .iter()
+                .position(|reply| matches!(reply, SchedulerReply::Task(id, _) if *id == task))?;
+            match board.scheduler_data.remove(index)? {
+                SchedulerReply::Task(_, state) => Some(state),
+                _ => None,
+            }
+        })
+    }
+
+    /// Opens a serial port of the board, see [`crate::uart`]. Software ports, 8 to 11,
+    /// need their RX and TX pins.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] for an invalid port or baud rate,
+    /// [`FirmataError::UnsupportedByFirmware`] if the firmware is older than protocol
+    /// 2.5 or an error if the message could not be sent.
+    pub fn serial_config(&mut self, port: u8, baud: u32, pins: Option<(u8, u8)>) -> Result<()> {
+        self.require(Feature::SerialData)?;
+        uart::validate_config(port, baud, pins)?;
+        self.send(&SerialRequest::Config { port, baud, pins }.encode())
+    }
+
+    /// Opens a serial port and has the firmware report what it receives.
+    /// # Errors
+    /// See [`Board::serial_config`].
+    pub fn serial_open(&mut self, port: u8, baud: u32, pins: Option<(u8, u8)>) -> Result<()> {
+        self.serial_config(port, baud, pins)?;
+        self.serial_start_reading(port, None)
+    }
+
+    /// Writes bytes to a serial port, split into messages of at most
+    /// [`WRITE_CHUNK_SIZE`](crate::uart::WRITE_CHUNK_SIZE) bytes.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] for an invalid port or an error if a message
+    /// could not be sent.
+    pub fn serial_write(&mut self, port: u8, data: &[u8]) -> Result<()> {
+        uart::validate_port(port)?;
+        for request in uart::write_requests(port, data) {
+            self.send(&request.encode())?;
+        }
+        Ok(())
+    }
+
+    /// Has the firmware report what a port receives, at most `max_bytes` per message if
+    /// given.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] for an invalid port or an error if the
+    /// message could not be sent.
+    pub fn serial_start_reading(&mut self, port: u8, max_bytes: Option<u16>) -> Result<()> {
+        uart::validate_port(port)?;
+        let request = SerialRequest::Read {
+            port,
+            mode: SerialReadMode::Continuous,
+            max_bytes,
+        };
+        self.send(&request.encode())
+    }
+
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] for an invalid port or an error if the
+    /// message could not be sent.
+    pub fn serial_stop_reading(&mut self, port: u8) -> Result<()> {
+        uart::validate_port(port)?;
+        let request = SerialRequest::Read {
+            port,
+            mode: SerialReadMode::Stop,
+            max_bytes: None,
+        };
+        self.send(&request.encode())
+    }
+
+    /// Closes a serial port, bytes it received before are kept until read.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] for an invalid port or an error if the
+    /// message could not be sent.
+    pub fn serial_close(&mut self, port: u8) -> Result<()> {
+        uart::validate_port(port)?;
+        self.send(&SerialRequest::Close(port).encode())
+    }
+
+    /// Has the firmware wait until a port sent everything that was written to it.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] for an invalid port or an error if the
+    /// message could not be sent.
+    pub fn serial_flush(&mut self, port: u8) -> Result<()> {
+        uart::validate_port(port)?;
+        self.send(&SerialRequest::Flush(port).encode())
+    }
+
+    /// Makes a software port the one that receives, only one of them can at a time.
+    /// # Errors
+    /// Returns [`FirmataError::OutOfRange`] if the port is not a software port or an
+    /// error if the message could not be sent.
+    pub fn serial_listen(&mut self, port: u8) -> Result<()> {
+        if !uart::is_software_port(port) {
+            return Err(FirmataError::OutOfRange(
+                "only software serial ports listen",
+            ));
+        }
+        self.send(&SerialRequest::Listen(port).encode())
+    }
+
+    /// Blocks until a port received bytes and returns every byte received so far, the
+    /// port has to be reading, see [`Board::serial_start_reading`].
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if nothing was received in time.
+    pub fn serial_read(&mut self, port: u8, timeout: std::time::Duration) -> Result<Vec<u8>> {
+        self.read_until(timeout, |board| {
+            let data = board.serial_data.get_mut(&port)?;
+            (!data.is_empty()).then(|| data.drain().collect())
+        })
+    }
+
+    /// Sends an optional trigger pulse and blocks until the following pulse was measured.
+    /// # Errors
+    /// Returns [`FirmataError::Timeout`] if no pulse was seen within the request timeout.
+    pub fn pulse_in(&mut self, request: &PulseRequest) -> Result<std::time::Duration> {
+        let replies = |board: &Self| board.pulses.get(&request.pin).map_or(0, |v| v.replies);
+        let before = replies(self);
+        self.send(&pulse::encode_request(request))?;
+        // Give the firmware a moment beyond its own timeout to report back.
+        let wait = request.timeout + pulse::REPLY_MARGIN;
+        let duration = self.read_until(wait, |board| {
+            (replies(board) > before).then(|| board.pulses[&request.pin].duration)
+        })?;
+        if duration.is_zero() {
+            return Err(FirmataError::Timeout(format!(
+                "{}",
+                request.timeout.as_millis()
+            )));
+        }
+        Ok(duration)
+    }
+
+    /// Sets how often the firmware reports analog and I2C values. The interval is
+    /// clamped to what the firmware accepts, see [`sampling::clamp_interval`].
+    /// # Errors
+    /// Returns an error if the firmware lacks the feature or writing failed.
     pub fn sampling_interval(&mut self, duration: std::time::Duration) -> Result<()> {
-        let dur_in_ms: u16 = duration.as_millis() as u16;
-        let bytes = dur_in_ms.to_le_bytes();
-        self.connection.write_all(&[
-            START_SYSEX,
-            SAMPLEING_INTERVAL,
-            bytes[0],
-            bytes[1],
-            END_SYSEX,
-        ])?;
+        self.require(Feature::SamplingInterval)?;
+        let duration = sampling::clamp_interval(duration);
+        self.send(&sampling::encode_interval(duration))?;
+        self.active_sampling_interval = Some(duration);
+        self.journal
+            .record(JournalEntry::SamplingInterval(duration));
         Ok(())
     }
+
+    /// The sampling interval last sent to the firmware, `None` while the firmware
+    /// still uses its own default.
+    pub fn active_sampling_interval(&self) -> Option<Duration> {
+        self.active_sampling_interval
+    }
+
+    /// Applies the sampling interval, enables or disables reporting of every listed
+    /// channel and sets up the client-side division of its samples.
+    /// # Errors
+    /// Returns an error if writing to the connection failed.
+    pub fn configure_sampling(&mut self, config: &SamplingConfig) -> Result<()> {
+        if let Some(interval) = config.interval {
+            self.sampling_interval(interval)?;
+        }
+        for (channel, every) in &config.channels {
+            let pin = self.pin_id_to_pin(PinId::Analog(*channel));
+            self.sampling.set(pin, *every);
+            self.report_analog(PinId::Analog(*channel), *every > 0)?;
+        }
+        Ok(())
+    }
+}
+
+/// A sync [`Board`] over any [`Transport`].
+pub type TransportBoard = Board<BoxedConnection>;
+
+impl TransportBoard {
+    /// Connects over the transport, the board info still has to be queried.
+    /// # Errors
+    /// Returns an error if the transport could not be opened.
+    pub fn open(transport: &dyn Transport) -> Result<Self> {
+        Ok(Self::new(transport.open()?))
+    }
+
+    /// Opens the transport again after the connection dropped, queries the board again
+    /// and replays the journal.
+    /// # Errors
+    /// Returns [`FirmataError::StateError`] if the transport can not be reopened or an
+    /// error if connecting or setting up the board failed.
+    pub fn reconnect(&mut self, transport: &dyn Transport) -> Result<()> {
+        if !transport.info().reconnectable {
+            return Err(FirmataError::StateError(
+                "the transport can not be reopened",
+            ));
+        }
+        self.connection = transport.open()?;
+        self.populate_board_info()?;
+        let journal = self.journal.clone();
+        self.replay(&journal)
+    }
+}
+
+#[cfg(unix)]
+impl Board<std::os::unix::net::UnixStream> {
+    /// Connects to a board exposed on a unix domain socket, e.g. by `ser2net` or `socat`.
+    /// # Errors
+    /// Returns an error if the socket could not be connected.
+    pub fn connect_unix<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(std::os::unix::net::UnixStream::connect(path)?))
+    }
+
+    /// Reconnects to the socket after the connection dropped, queries the board again
+    /// and replays the journal.
+    /// # Errors
+    /// Returns an error if the socket could not be connected or the board not set up.
+    pub fn reconnect_unix<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<()> {
+        self.connection = std::os::unix::net::UnixStream::connect(path)?;
+        self.populate_board_info()?;
+        let journal = self.journal.clone();
+        self.replay(&journal)
+    }
 }
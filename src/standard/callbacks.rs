@@ -0,0 +1,93 @@
+use std::sync::{Arc, Mutex};
+
+/// A change of a pin value delivered to an `on_change` callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PinChange {
+    pub pin: u8,
+    pub old: u16,
+    pub new: u16,
+}
+
+/// Identifies a registered callback so it can be removed again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CallbackId(u64);
+
+type Callback = Arc<Mutex<dyn FnMut(PinChange) + Send>>;
+
+#[derive(Clone)]
+struct Registration {
+    id: CallbackId,
+    pin: u8,
+    threshold: u16,
+    last: u16,
+    callback: Callback,
+}
+
+/// The callbacks registered on a board, clones of a board share the callbacks.
+#[derive(Clone, Default)]
+pub struct Callbacks {
+    next_id: u64,
+    registrations: Vec<Registration>,
+}
+
+impl std::fmt::Debug for Callbacks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Callbacks")
+            .field("registered", &self.registrations.len())
+            .finish()
+    }
+}
+
+impl Callbacks {
+    pub fn register<F: FnMut(PinChange) + Send + 'static>(
+        &mut self,
+        pin: u8,
+        threshold: u16,
+        current: u16,
+        callback: F,
+    ) -> CallbackId {
+        let id = CallbackId(self.next_id);
+        self.next_id += 1;
+        self.registrations.push(Registration {
+            id,
+            pin,
+            threshold,
+            last: current,
+            callback: Arc::new(Mutex::new(callback)),
+        });
+        id
+    }
+
+    pub fn remove(&mut self, id: CallbackId) -> bool {
+        let before = self.registrations.len();
+        self.registrations.retain(|v| v.id != id);
+        before != self.registrations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registrations.is_empty()
+    }
+
+    /// Calls every callback whose pin moved at least its threshold away from the value
+    /// it was last called with.
+    pub fn dispatch<F: Fn(u8) -> Option<u16>>(&mut self, value_of: F) {
+        for registration in &mut self.registrations {
+            let Some(new) = value_of(registration.pin) else {
+                continue;
+            };
+            if new == registration.last || new.abs_diff(registration.last) < registration.threshold
+            {
+                continue;
+            }
+            let change = PinChange {
+                pin: registration.pin,
+                old: registration.last,
+                new,
+            };
+            registration.last = new;
+            if let Ok(mut callback) = registration.callback.lock() {
+                callback(change);
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use super::board::Board;
+use crate::i2c::{I2cConfig, I2cReadOptions, I2cRetryPolicy};
+use crate::{I2CReply, Result};
+use std::io;
+
+/// I2C access to a board that reported I2C support, created by [`Board::i2c`].
+#[derive(Debug)]
+pub struct I2c<'a, T: io::Read + io::Write> {
+    board: &'a mut Board<T>,
+}
+
+impl<'a, T: io::Read + io::Write> I2c<'a, T> {
+    pub(crate) fn new(board: &'a mut Board<T>) -> Self {
+        Self { board }
+    }
+
+    /// See [`Board::i2c_config`].
+    /// # Errors
+    /// Returns an error if writing to the connection failed.
+    pub fn config(&mut self, config: I2cConfig) -> Result<()> {
+        self.board.i2c_config(config)
+    }
+
+    /// See [`Board::i2c_read_with`].
+    /// # Errors
+    /// Returns an error if writing to the connection failed.
+    pub fn read(&mut self, addr: u8, options: &I2cReadOptions) -> Result<()> {
+        self.board.i2c_read_with(addr, options)
+    }
+
+    /// See [`Board::i2c_read_reply`].
+    /// # Errors
+    /// Returns [`crate::FirmataError::I2cTimeout`] if every attempt timed out.
+    pub fn read_reply(
+        &mut self,
+        addr: u8,
+        options: &I2cReadOptions,
+        policy: &I2cRetryPolicy,
+    ) -> Result<I2CReply> {
+        self.board.i2c_read_reply(addr, options, policy)
+    }
+
+    /// See [`Board::i2c_write`].
+    /// # Errors
+    /// Returns an error if writing to the connection failed.
+    pub fn write(&mut self, addr: u8, data: &[u8]) -> Result<()> {
+        self.board.i2c_write(addr, data)
+    }
+}
@@ -1,2 +1,4 @@
 pub mod board;
-mod parser;
+pub mod callbacks;
+pub mod i2c;
+pub(crate) mod parser;
@@ -1,19 +1,47 @@
 use crate::message::{get_header_type, Header};
-use crate::message::{AnalogMappingResponse, CapabilityResponse, I2cReply, ReportFirmware};
-use crate::protocol_constants::{
-    ANALOG_MAPPING_RESPONSE, CAPABILITY_RESPONSE, END_SYSEX, I2C_MODE_READ, REPORT_FIRMWARE,
+use crate::message::{
+    AnalogMappingResponse, CapabilityResponse, I2cReply, OneWireReply, PinStateResponse,
+    PulseReply, ReportFirmware, SchedulerReply, StepperReply, StringData, System,
 };
-use crate::{message, FirmataError, PinId, Result};
-use byteorder::{ByteOrder, LittleEndian};
+use crate::protocol::{SysexCommand, END_SYSEX};
+use crate::{message, uart, FirmataError, PinId, Result};
 use message::{Analog, Digital, Message, MessageId, MessageIn};
 
+/// Keeps a copy of every byte read through it, used to retain the raw frame of a message.
+pub struct Recorder<'a, R> {
+    inner: &'a mut R,
+    bytes: Vec<u8>,
+}
+
+impl<'a, R: std::io::Read> Recorder<'a, R> {
+    pub fn new(inner: &'a mut R) -> Self {
+        Self {
+            inner,
+            bytes: vec![],
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for Recorder<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
 pub fn read_and_parse<T: std::io::Read>(
     reader: &mut T,
     timeout: std::time::Duration,
+    lenient: bool,
 ) -> Result<Message> {
     let start_of_header: &mut [u8; 1] = &mut [0; 1];
+    let start = std::time::Instant::now();
     let header_enum = loop {
-        let start = std::time::Instant::now();
         let n = reader.read(start_of_header)?;
         if n == 1 {
             let header_result = get_header_type(start_of_header[0]);
@@ -27,7 +55,7 @@ pub fn read_and_parse<T: std::io::Read>(
     };
 
     match header_enum {
-        Header::System => read_and_parse_system(reader),
+        Header::System => read_and_parse_system(reader, lenient),
         Header::AnalogMessage => read_and_parse_analog(reader, start_of_header[0]),
         Header::DigitalMessage => read_and_parse_digital(reader, start_of_header[0]),
         Header::ProtocolVersion => read_and_parse_protocol_version(reader),
@@ -39,9 +67,9 @@ pub fn read_and_parse<T: std::io::Read>(
 pub fn read_and_parse_analog<T: std::io::Read>(reader: &mut T, first_byte: u8) -> Result<Message> {
     let buf: &mut [u8; 2] = &mut [0; 2];
     reader.read_exact(buf)?;
-    let value: u16 = LittleEndian::read_u16(buf);
-    // Analog message can only do a range between 0..15, if you need to address
-    // greater then 15 you need to use ANALOG_EXTENDED.
+    let value = message::decode_u14(buf[0], buf[1]);
+    // Analog message can only do a range between 0..15, channels above are
+    // reported with EXTENDED_ANALOG.
     let pin = first_byte & 0x0F;
     let analog_message = Analog {
         pin: PinId::Analog(pin),
@@ -50,6 +78,7 @@ pub fn read_and_parse_analog<T: std::io::Read>(reader: &mut T, first_byte: u8) -
     Ok(Message {
         message_id: MessageId::Analog,
         message: MessageIn::Analog(analog_message),
+        raw: None,
     })
 }
 
@@ -59,25 +88,29 @@ pub fn read_and_parse_digital<T: std::io::Read>(reader: &mut T, first_byte: u8)
     let buf: &mut [u8; 2] = &mut [0; 2];
     reader.read_exact(buf)?;
     let port = first_byte & 0x0F;
-    let value: u16 = LittleEndian::read_u16(buf);
+    let value = message::decode_u14(buf[0], buf[1]);
     let digital_message = Digital { port, value };
     Ok(Message {
         message_id: MessageId::Digital,
         message: MessageIn::Digital(digital_message),
+        raw: None,
     })
 }
 
 pub fn read_and_parse_protocol_version<T: std::io::Read>(reader: &mut T) -> Result<Message> {
     let buf: &mut [u8; 2] = &mut [0; 2];
     reader.read_exact(buf)?;
-    let protocol_version = format!("{:o}.{:o}", buf[0], buf[1]);
+    let protocol_version = format!("{}.{}", buf[0], buf[1]);
     Ok(Message {
         message_id: MessageId::ProtocolVersion,
         message: MessageIn::ProtocolVersion(protocol_version),
+        raw: None,
     })
 }
 
-pub fn read_and_parse_system<T: std::io::Read>(reader: &mut T) -> Result<Message> {
+/// Reads a sysex message, with `lenient` unknown commands become
+/// [`MessageIn::UnknownSysex`] instead of an error.
+pub fn read_and_parse_system<T: std::io::Read>(reader: &mut T, lenient: bool) -> Result<Message> {
     let mut payload: Vec<u8> = vec![];
     let byte_in: &mut [u8; 1] = &mut [0; 1];
     // Read until we find our end of system flag or another header that should not of been there.
@@ -98,27 +131,70 @@ pub fn read_and_parse_system<T: std::io::Read>(reader: &mut T) -> Result<Message
     }
 
     // The first byte in the payload contains what message we expect.
-    let byte = payload
+    let command = *payload
         .get(0)
         .ok_or(FirmataError::OutOfRange("index out of range"))?;
-    match *byte {
-        ANALOG_MAPPING_RESPONSE => {
+    match SysexCommand::try_from(command) {
+        Ok(SysexCommand::AnalogMappingResponse) => {
             let message_out = AnalogMappingResponse::deserialize(&payload[1..]);
             Ok(AnalogMappingResponse::into_message(message_out))
         }
 
-        CAPABILITY_RESPONSE => {
+        Ok(SysexCommand::CapabilityResponse) => {
             let message_out = CapabilityResponse::deserialize(&payload[1..])?;
             Ok(CapabilityResponse::into_message(message_out))
         }
-        I2C_MODE_READ => {
-            let message_out = I2cReply::deserialize(&payload[1..]);
+        Ok(SysexCommand::I2cReply) => {
+            let message_out = I2cReply::deserialize(&payload[1..])?;
             Ok(I2cReply::into_message(message_out))
         }
-        REPORT_FIRMWARE => {
+        Ok(SysexCommand::ReportFirmware) => {
             let message_out = ReportFirmware::deserialize(&payload[1..])?;
             Ok(ReportFirmware::into_message(message_out))
         }
+        Ok(SysexCommand::AccelStepperData) => {
+            let message_out = StepperReply::deserialize(&payload[1..])?;
+            Ok(StepperReply::into_message(message_out))
+        }
+        Ok(SysexCommand::PingRead) => {
+            let message_out = PulseReply::deserialize(&payload[1..])?;
+            Ok(PulseReply::into_message(message_out))
+        }
+        Ok(SysexCommand::StringData) => Ok(StringData::into_message(StringData::deserialize(
+            &payload[1..],
+        ))),
+        Ok(SysexCommand::PinStateResponse) => {
+            let message_out = PinStateResponse::deserialize(&payload[1..])?;
+            Ok(PinStateResponse::into_message(message_out))
+        }
+        Ok(SysexCommand::OnewireData) => {
+            let message_out = OneWireReply::deserialize(&payload[1..])?;
+            Ok(OneWireReply::into_message(message_out))
+        }
+        Ok(SysexCommand::ExtendedAnalog) => {
+            let message_out = Analog::deserialize_extended(&payload[1..])?;
+            Ok(Analog::into_message(message_out))
+        }
+        Ok(SysexCommand::SchedulerData) => {
+            let message_out = SchedulerReply::deserialize(&payload[1..])?;
+            Ok(SchedulerReply::into_message(message_out))
+        }
+        Ok(SysexCommand::SerialData) => {
+            let (port, data) = uart::decode_reply(&payload[1..])?;
+            Ok(Message {
+                message_id: MessageId::SerialReply,
+                message: MessageIn::System(System::SerialReply { port, data }),
+                raw: None,
+            })
+        }
+        _ if lenient => Ok(Message {
+            message_id: MessageId::UnknownSysex,
+            message: MessageIn::UnknownSysex {
+                command,
+                payload: payload[1..].to_vec(),
+            },
+            raw: None,
+        }),
         _ => Err(FirmataError::ParseError(
             "did not find an expected system message",
             payload,
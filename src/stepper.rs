@@ -0,0 +1,301 @@
+//! Encoding of the ConfigurableFirmata AccelStepper extension.
+//! See <https://github.com/firmata/protocol/blob/master/accelStepperFirmata.md>.
+use crate::protocol::{AccelStepperCommand, SysexCommand};
+use crate::{FirmataError, Result};
+use serde::{Deserialize, Serialize};
+
+/// The firmware supports up to 10 steppers and up to 5 multi stepper groups.
+pub const MAX_STEPPERS: u8 = 10;
+pub const MAX_STEPPER_GROUPS: u8 = 5;
+
+/// The largest speed or acceleration the float format of the extension can carry,
+/// a 23 bit significand with a decimal exponent of at most 4.
+pub const MAX_STEPPER_FLOAT: f32 = 8.388_607e10;
+
+/// How the stepper motor is wired to the board.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum StepperInterface {
+    /// A step/direction driver board.
+    Driver {
+        step: u8,
+        direction: u8,
+    },
+    TwoWire {
+        pin1: u8,
+        pin2: u8,
+    },
+    ThreeWire {
+        pin1: u8,
+        pin2: u8,
+        pin3: u8,
+    },
+    FourWire {
+        pin1: u8,
+        pin2: u8,
+        pin3: u8,
+        pin4: u8,
+    },
+}
+
+impl StepperInterface {
+    const fn wire_code(&self) -> u8 {
+        match self {
+            Self::Driver { .. } => 1,
+            Self::TwoWire { .. } => 2,
+            Self::ThreeWire { .. } => 3,
+            Self::FourWire { .. } => 4,
+        }
+    }
+
+    fn pins(&self) -> Vec<u8> {
+        match *self {
+            Self::Driver { step, direction } => vec![step, direction],
+            Self::TwoWire { pin1, pin2 } => vec![pin1, pin2],
+            Self::ThreeWire { pin1, pin2, pin3 } => vec![pin1, pin2, pin3],
+            Self::FourWire {
+                pin1,
+                pin2,
+                pin3,
+                pin4,
+            } => vec![pin1, pin2, pin3, pin4],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum StepSize {
+    Whole = 0,
+    Half = 1,
+    Quarter = 2,
+}
+
+/// Configuration of a single AccelStepper device.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct StepperConfig {
+    pub device: u8,
+    pub interface: StepperInterface,
+    pub step_size: StepSize,
+    pub enable_pin: Option<u8>,
+    /// Bit mask of inverted pins, bit 0 to 3 are the motor pins and bit 4 the enable pin.
+    pub invert: u8,
+}
+
+impl StepperConfig {
+    #[must_use]
+    pub const fn new(device: u8, interface: StepperInterface) -> Self {
+        Self {
+            device,
+            interface,
+            step_size: StepSize::Whole,
+            enable_pin: None,
+            invert: 0,
+        }
+    }
+}
+
+/// Checks that the firmware can address a device.
+/// # Errors
+/// Returns [`FirmataError::OutOfRange`] if the device number is not supported.
+pub const fn validate_device(device: u8) -> Result<()> {
+    if device >= MAX_STEPPERS {
+        return Err(FirmataError::OutOfRange("stepper device number exceeds 9"));
+    }
+    Ok(())
+}
+
+/// What is known about a stepper device from its reports.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StepperStatus {
+    /// The last reported position.
+    pub position: i32,
+    /// Number of completed moves, used to detect the end of a fresh move.
+    pub moves: u64,
+}
+
+impl StepperStatus {
+    pub fn record_move(&mut self, position: i32) {
+        self.moves += 1;
+        self.position = position;
+    }
+}
+
+/// Packs a signed 32 bit value into the five 7 bit bytes the extension uses, the sign
+/// is carried in bit 3 of the last byte.
+#[must_use]
+pub fn encode_i32(value: i32) -> [u8; 5] {
+    let magnitude = value.unsigned_abs();
+    let mut bytes = [
+        (magnitude & 0x7F) as u8,
+        ((magnitude >> 7) & 0x7F) as u8,
+        ((magnitude >> 14) & 0x7F) as u8,
+        ((magnitude >> 21) & 0x7F) as u8,
+        ((magnitude >> 28) & 0x07) as u8,
+    ];
+    if value < 0 {
+        bytes[4] |= 0x08;
+    }
+    bytes
+}
+
+/// Reverses [`encode_i32`].
+/// # Errors
+/// Returns [`FirmataError::ParseError`] if less than five bytes are given.
+pub fn decode_i32(bytes: &[u8]) -> Result<i32> {
+    if bytes.len() < 5 {
+        return Err(FirmataError::ParseError(
+            "not enough bytes to decode a stepper position",
+            bytes.to_vec(),
+        ));
+    }
+    let magnitude = u32::from(bytes[0] & 0x7F)
+        | u32::from(bytes[1] & 0x7F) << 7
+        | u32::from(bytes[2] & 0x7F) << 14
+        | u32::from(bytes[3] & 0x7F) << 21
+        | u32::from(bytes[4] & 0x07) << 28;
+    let value = magnitude as i32;
+    Ok(if bytes[4] & 0x08 == 0 { value } else { -value })
+}
+
+/// Packs a float into the four 7 bit bytes of the extension: a 23 bit significand, a
+/// decimal exponent from -11 to 4 and a sign bit. Values beyond
+/// [`MAX_STEPPER_FLOAT`] saturate, values that are not finite are sent as zero.
+#[must_use]
+pub fn encode_float(value: f32) -> [u8; 4] {
+    const MAX_SIGNIFICAND: f64 = ((1 << 23) - 1) as f64;
+    let magnitude = if value.is_finite() {
+        f64::from(value.abs())
+    } else {
+        0.0
+    };
+    let (significand, exponent) = (-11_i32..=4)
+        .map(|exponent| ((magnitude / 10_f64.powi(exponent)).round(), exponent))
+        .find(|(significand, _)| *significand <= MAX_SIGNIFICAND)
+        .unwrap_or((MAX_SIGNIFICAND, 4));
+    let significand = significand as u32;
+    let exponent = (exponent + 11) as u8;
+    [
+        (significand & 0x7F) as u8,
+        ((significand >> 7) & 0x7F) as u8,
+        ((significand >> 14) & 0x7F) as u8,
+        ((significand >> 21) & 0x03) as u8
+            | exponent << 2
+            | u8::from(value.is_sign_negative() && magnitude > 0.0) << 6,
+    ]
+}
+
+/// Checks a speed in steps per second or an acceleration in steps per second squared.
+/// # Errors
+/// Returns [`FirmataError::OutOfRange`] if the value is negative, not finite or too
+/// large for the float format.
+pub fn validate_float(value: f32) -> Result<()> {
+    if !value.is_finite() || !(0.0..=MAX_STEPPER_FLOAT).contains(&value) {
+        return Err(FirmataError::OutOfRange(
+            "stepper speed and acceleration must be between 0 and 8.388607e10",
+        ));
+    }
+    Ok(())
+}
+
+fn sysex(command: AccelStepperCommand, payload: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(payload.len() + 1);
+    data.push(command.to_u8());
+    data.extend_from_slice(payload);
+    SysexCommand::AccelStepperData.frame(&data)
+}
+
+/// Encodes the configuration of a stepper device.
+#[must_use]
+pub fn encode_config(config: &StepperConfig) -> Vec<u8> {
+    let interface = config.interface.wire_code() << 4
+        | (config.step_size as u8) << 1
+        | u8::from(config.enable_pin.is_some());
+    let mut payload = vec![config.device, interface];
+    payload.extend(config.interface.pins());
+    if let Some(pin) = config.enable_pin {
+        payload.push(pin);
+    }
+    payload.push(config.invert & 0x1F);
+    sysex(AccelStepperCommand::Config, &payload)
+}
+
+/// Groups stepper devices so they can be moved together.
+#[must_use]
+pub fn encode_multi_config(group: u8, devices: &[u8]) -> Vec<u8> {
+    let mut payload = vec![group];
+    payload.extend_from_slice(devices);
+    sysex(AccelStepperCommand::MultiConfig, &payload)
+}
+
+/// Moves every member of a group to its absolute position so that they arrive together.
+#[must_use]
+pub fn encode_multi_to(group: u8, positions: &[i32]) -> Vec<u8> {
+    let mut payload = vec![group];
+    for position in positions {
+        payload.extend_from_slice(&encode_i32(*position));
+    }
+    sysex(AccelStepperCommand::MultiTo, &payload)
+}
+
+#[must_use]
+pub fn encode_multi_stop(group: u8) -> Vec<u8> {
+    sysex(AccelStepperCommand::MultiStop, &[group])
+}
+
+/// Sets the current position of a device as its zero position.
+#[must_use]
+pub fn encode_zero(device: u8) -> Vec<u8> {
+    sysex(AccelStepperCommand::Zero, &[device])
+}
+
+/// Moves a device by a number of steps relative to its position.
+#[must_use]
+pub fn encode_step(device: u8, steps: i32) -> Vec<u8> {
+    let mut payload = vec![device];
+    payload.extend_from_slice(&encode_i32(steps));
+    sysex(AccelStepperCommand::Step, &payload)
+}
+
+/// Moves a device to an absolute position.
+#[must_use]
+pub fn encode_to(device: u8, position: i32) -> Vec<u8> {
+    let mut payload = vec![device];
+    payload.extend_from_slice(&encode_i32(position));
+    sysex(AccelStepperCommand::To, &payload)
+}
+
+/// Switches the outputs of a device on or off, e.g. to let the motor spin freely.
+#[must_use]
+pub fn encode_enable(device: u8, enable: bool) -> Vec<u8> {
+    sysex(AccelStepperCommand::Enable, &[device, u8::from(enable)])
+}
+
+/// Stops a device, decelerating if an acceleration is set.
+#[must_use]
+pub fn encode_stop(device: u8) -> Vec<u8> {
+    sysex(AccelStepperCommand::Stop, &[device])
+}
+
+#[must_use]
+pub fn encode_report_position(device: u8) -> Vec<u8> {
+    sysex(AccelStepperCommand::Position, &[device])
+}
+
+/// Sets the acceleration in steps per second squared, zero moves at constant speed.
+#[must_use]
+pub fn encode_acceleration(device: u8, acceleration: f32) -> Vec<u8> {
+    let mut payload = vec![device];
+    payload.extend_from_slice(&encode_float(acceleration));
+    sysex(AccelStepperCommand::Acceleration, &payload)
+}
+
+/// Sets the maximum speed in steps per second.
+#[must_use]
+pub fn encode_speed(device: u8, speed: f32) -> Vec<u8> {
+    let mut payload = vec![device];
+    payload.extend_from_slice(&encode_float(speed));
+    sysex(AccelStepperCommand::Speed, &payload)
+}
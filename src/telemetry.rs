@@ -0,0 +1,146 @@
+//! Forwarding of pin samples to time series databases.
+use crate::asynchronous::board::Board;
+use crate::asynchronous::events::BoardEvent;
+use crate::{PinId, Result};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A pin value at a point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sample {
+    pub pin: u8,
+    pub label: Option<String>,
+    pub value: u16,
+    pub timestamp: SystemTime,
+}
+
+/// Receives samples, a sink may batch them until [`TelemetrySink::flush`].
+pub trait TelemetrySink: Send {
+    /// # Errors
+    /// Returns an error if a full batch could not be written.
+    fn record(&mut self, sample: Sample) -> Result<()>;
+
+    /// # Errors
+    /// Returns an error if the pending samples could not be written.
+    fn flush(&mut self) -> Result<()>;
+}
+
+fn escape(value: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == '\\' || special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Writes samples as InfluxDB line protocol, one line per sample with the pin and label
+/// as tags and the raw value as an integer field.
+#[derive(Debug)]
+pub struct InfluxSink<W: Write + Send> {
+    writer: W,
+    measurement: String,
+    tags: BTreeMap<String, String>,
+    batch_size: usize,
+    pending: Vec<String>,
+}
+
+impl<W: Write + Send> InfluxSink<W> {
+    /// Creates a sink writing batches of 100 lines, e.g. to a file or a socket.
+    pub fn new(writer: W, measurement: &str) -> Self {
+        Self {
+            writer,
+            measurement: measurement.to_string(),
+            tags: BTreeMap::new(),
+            batch_size: 100,
+            pending: vec![],
+        }
+    }
+
+    /// Adds a tag to every line, e.g. the board name.
+    #[must_use]
+    pub fn tag(mut self, key: &str, value: &str) -> Self {
+        self.tags.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Sets how many lines are collected before they are written, at least one.
+    #[must_use]
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Formats a sample as a line without the trailing newline.
+    #[must_use]
+    pub fn line(&self, sample: &Sample) -> String {
+        let tag_chars = [',', '=', ' '];
+        let mut line = escape(&self.measurement, &[',', ' ']);
+        let mut tags = self.tags.clone();
+        tags.insert("pin".to_string(), sample.pin.to_string());
+        if let Some(label) = &sample.label {
+            tags.insert("label".to_string(), label.clone());
+        }
+        for (key, value) in &tags {
+            line.push(',');
+            line.push_str(&escape(key, &tag_chars));
+            line.push('=');
+            line.push_str(&escape(value, &tag_chars));
+        }
+        let nanos = sample
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        line.push_str(&format!(" value={}i {}", sample.value, nanos));
+        line
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + Send> TelemetrySink for InfluxSink<W> {
+    fn record(&mut self, sample: Sample) -> Result<()> {
+        self.pending.push(self.line(&sample));
+        if self.pending.len() >= self.batch_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let mut batch = self.pending.join("\n");
+        batch.push('\n');
+        self.writer.write_all(batch.as_bytes())?;
+        self.writer.flush()?;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+/// Records every pin change of the board into the sink until the board io stops, the
+/// pending samples are flushed before returning.
+/// # Errors
+/// Returns an error if the sink failed to write.
+pub async fn forward<S: TelemetrySink>(mut board: Board, sink: &mut S) -> Result<()> {
+    loop {
+        match board.next_event().await {
+            Ok(BoardEvent::PinChanged { pin, new, .. }) => sink.record(Sample {
+                pin,
+                label: board.label(PinId::Pin(pin)),
+                value: new,
+                timestamp: SystemTime::now(),
+            })?,
+            Ok(_) => {}
+            Err(_) => return sink.flush(),
+        }
+    }
+}
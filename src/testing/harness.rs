@@ -0,0 +1,131 @@
+//! Helpers for hardware in the loop tests. A test acquires the board named by an
+//! environment variable, starts from a freshly reset board, runs under a global timeout
+//! and leaves every output in a safe state afterwards, whether it passed or not.
+//!
+//! ```no_run
+//! firmata::hil_test!(blink_once, |mut board| async move {
+//!     board.digital_write(firmata::PinId::Digital(13), true).await
+//! });
+//! ```
+use crate::asynchronous::board::Board;
+#[cfg(feature = "serial")]
+use crate::FirmataError;
+use crate::{PinId, PinMode, Result};
+#[cfg(feature = "serial")]
+use std::future::Future;
+use std::time::Duration;
+
+/// Environment variable naming the serial port of the board under test.
+pub const PORT_ENV: &str = "FIRMATA_TEST_PORT";
+/// Environment variable overriding the baud rate.
+pub const BAUD_ENV: &str = "FIRMATA_TEST_BAUD";
+pub const DEFAULT_BAUD_RATE: u32 = 57_600;
+
+#[derive(Debug, Clone)]
+pub struct HarnessConfig {
+    pub port_env: String,
+    pub baud_rate: u32,
+    /// Upper bound of the whole test, excluding connecting to the board.
+    pub timeout: Duration,
+}
+
+impl Default for HarnessConfig {
+    fn default() -> Self {
+        Self {
+            port_env: PORT_ENV.to_string(),
+            baud_rate: std::env::var(BAUD_ENV)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_BAUD_RATE),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl HarnessConfig {
+    /// The port of the board under test, `None` means hardware tests should be skipped.
+    #[must_use]
+    pub fn port(&self) -> Option<String> {
+        std::env::var(&self.port_env).ok().filter(|v| !v.is_empty())
+    }
+}
+
+/// Drives every output low and returns the pins to inputs so nothing stays powered.
+/// # Errors
+/// Returns an error if the messages could not be sent.
+pub async fn safe_outputs(board: &mut Board) -> Result<()> {
+    for (index, pin) in board.pins().iter().enumerate() {
+        let id = PinId::Pin(index as u8);
+        match pin.mode {
+            PinMode::Output => board.digital_write(id, false).await?,
+            PinMode::Pwm => board.analog_write(id, 0).await?,
+            PinMode::Servo => {}
+            _ => continue,
+        }
+        board.set_pin_mode(id, PinMode::Input).await?;
+    }
+    Ok(())
+}
+
+/// Connects to the board named by the configuration and runs the test against it.
+/// Returns `Ok(false)` without running the test if no board is configured.
+/// # Errors
+/// Returns the error of the test, [`FirmataError::Timeout`] if it ran out of time, or
+/// an error if the board could not be acquired or returned to a safe state.
+#[cfg(feature = "serial")]
+pub async fn run<F, Fut>(config: &HarnessConfig, test: F) -> Result<bool>
+where
+    F: FnOnce(Board) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let port = match config.port() {
+        Some(port) => port,
+        None => return Ok(false),
+    };
+    let selector = crate::serial::PortSelector::Path(port);
+    let mut board_io = crate::serial::open_board_io(&selector, config.baud_rate).await?;
+    let mut board = board_io.get_board();
+    let io_task = tokio::spawn(async move { board_io.poll().await });
+
+    board.system_reset().await?;
+    let outcome = tokio::time::timeout(config.timeout, test(board.clone())).await;
+    let restored = safe_outputs(&mut board).await;
+    // A round trip guarantees the safe outputs reached the board before disconnecting.
+    let flushed = board.ping(Duration::from_secs(2)).await;
+    io_task.abort();
+
+    match outcome {
+        Ok(result) => result?,
+        Err(_) => {
+            return Err(FirmataError::Timeout(format!(
+                "{}",
+                config.timeout.as_millis()
+            )))
+        }
+    }
+    restored?;
+    flushed?;
+    Ok(true)
+}
+
+/// Declares a tokio test that runs against real hardware through [`run`], the test
+/// returns an error naming the missing variable when [`PORT_ENV`] is not set.
+#[cfg(feature = "serial")]
+#[macro_export]
+macro_rules! hil_test {
+    ($name:ident, $test:expr) => {
+        #[tokio::test]
+        async fn $name() -> ::std::result::Result<(), String> {
+            let config = $crate::testing::harness::HarnessConfig::default();
+            match $crate::testing::harness::run(&config, $test).await {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(format!(
+                    "{} needs a board, {} is not set",
+                    stringify!($name),
+                    config.port_env
+                )),
+                Err(e) => Err(e.to_string()),
+            }
+        }
+    };
+}
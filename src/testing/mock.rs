@@ -0,0 +1,95 @@
+//! A simulated board for testing application code without hardware. It speaks the
+//! wire protocol over an in-memory link, answers the bootstrap queries for a board
+//! profile, keeps the pin writes of the host for inspection and injects reports.
+//!
+//! ```no_run
+//! # async fn run() -> firmata::Result<()> {
+//! let mock = firmata::testing::mock::MockBoard::uno()?;
+//! let mut board_io = mock.board_io().await?;
+//! let mut board = board_io.get_board();
+//! tokio::spawn(async move { board_io.poll().await });
+//! board.set_pin_mode(firmata::PinId::Digital(13), firmata::PinMode::Output).await?;
+//! board.digital_write(firmata::PinId::Digital(13), true).await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::asynchronous::boardio::TransportBoardIo;
+use crate::profile::BoardProfile;
+use crate::simulator::firmware::{FirmwareHandle, SimulatedFirmware};
+use crate::simulator::link::{LinkConditions, SimulatedTransport};
+use crate::standard::board::TransportBoard;
+use crate::Result;
+
+/// A [`SimulatedFirmware`] with a transport to reach it, inspect and inject through
+/// [`MockBoard::handle`].
+#[derive(Debug, Clone)]
+pub struct MockBoard {
+    handle: FirmwareHandle,
+    transport: SimulatedTransport,
+}
+
+impl MockBoard {
+    /// # Errors
+    /// Returns [`crate::FirmataError::StateError`] if the profile is inconsistent.
+    pub fn new(profile: &BoardProfile) -> Result<Self> {
+        Ok(Self::from_firmware(SimulatedFirmware::new(profile)?))
+    }
+
+    /// # Errors
+    /// Never fails for the built-in profile.
+    pub fn uno() -> Result<Self> {
+        Self::new(&BoardProfile::arduino_uno())
+    }
+
+    /// # Errors
+    /// Never fails for the built-in profile.
+    pub fn mega() -> Result<Self> {
+        Self::new(&BoardProfile::arduino_mega())
+    }
+
+    /// Uses a configured firmware, e.g. with waveforms on its analog channels.
+    #[must_use]
+    pub fn from_firmware(firmware: SimulatedFirmware) -> Self {
+        let handle = firmware.handle();
+        let transport =
+            SimulatedTransport::new(LinkConditions::default(), move |s| firmware.spawn(s));
+        Self { handle, transport }
+    }
+
+    /// Replaces the timing of the link, e.g. [`LinkConditions::serial`].
+    #[must_use]
+    pub fn with_conditions(mut self, conditions: LinkConditions) -> Self {
+        self.transport.conditions = conditions;
+        self
+    }
+
+    /// The transport every connection to the mock is made over, e.g. for reconnects.
+    #[must_use]
+    pub fn transport(&self) -> SimulatedTransport {
+        self.transport.clone()
+    }
+
+    /// The pins the host set, the commands it sent and injection of reports.
+    #[must_use]
+    pub const fn handle(&self) -> &FirmwareHandle {
+        &self.handle
+    }
+
+    /// Connects an async board io and queries the board, it still has to be polled.
+    /// # Errors
+    /// Returns an error if the simulated board did not answer.
+    pub async fn board_io(&self) -> Result<TransportBoardIo> {
+        let mut board_io = TransportBoardIo::open(&self.transport).await?;
+        board_io.generate_board_state().await?;
+        Ok(board_io)
+    }
+
+    /// Connects a sync board and queries the board.
+    /// # Errors
+    /// Returns an error if the simulated board did not answer.
+    pub fn board(&self) -> Result<TransportBoard> {
+        let mut board = TransportBoard::open(&self.transport)?;
+        board.populate_board_info()?;
+        Ok(board)
+    }
+}
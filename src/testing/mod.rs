@@ -0,0 +1,3 @@
+//! Support for projects that test their own code against this crate.
+pub mod harness;
+pub mod mock;
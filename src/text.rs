@@ -0,0 +1,49 @@
+//! Encoding and decoding of STRING_DATA messages.
+use crate::protocol::{SysexCommand, END_SYSEX, START_SYSEX};
+
+/// The most bytes of text sent in one STRING_DATA message. Every byte takes two 7 bit
+/// bytes and the command byte has to fit as well, StandardFirmata buffers 64 bytes of
+/// sysex data.
+pub const MAX_STRING_CHUNK: usize = 31;
+
+/// Splits text into chunks of at most [`MAX_STRING_CHUNK`] bytes without splitting a
+/// character.
+#[must_use]
+pub fn chunks(text: &str) -> Vec<&str> {
+    let mut chunks = vec![];
+    let mut rest = text;
+    while !rest.is_empty() {
+        let mut end = rest.len().min(MAX_STRING_CHUNK);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+/// Encodes text as STRING_DATA messages, one per chunk.
+#[must_use]
+pub fn encode(text: &str) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(text.len() * 2 + 3);
+    for chunk in chunks(text) {
+        buf.extend_from_slice(&[START_SYSEX, SysexCommand::StringData.to_u8()]);
+        for byte in chunk.bytes() {
+            buf.extend_from_slice(&[byte & 0x7F, byte >> 7]);
+        }
+        buf.push(END_SYSEX);
+    }
+    buf
+}
+
+/// Decodes the 7 bit byte pairs of a STRING_DATA payload, without the command byte.
+#[must_use]
+pub fn decode(payload: &[u8]) -> String {
+    let bytes: Vec<u8> = payload
+        .chunks_exact(2)
+        .map(|pair| (pair[0] & 0x7F) | (pair[1] << 7))
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
@@ -0,0 +1,122 @@
+//! Conversion of analog readings into physical units by per pin sensor models, for
+//! sensors that are not linear enough for a [`Calibration`](crate::calibration::Calibration).
+use crate::calibration::{analog_resolution, Calibration};
+use crate::Pin;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Converts a raw reading taken at the given resolution in bits into a physical value.
+/// Closures taking the raw reading and the resolution implement it as well.
+pub trait SensorTransform: Send + Sync {
+    fn convert(&self, raw: u16, resolution: u8) -> f64;
+}
+
+impl<F: Fn(u16, u8) -> f64 + Send + Sync> SensorTransform for F {
+    fn convert(&self, raw: u16, resolution: u8) -> f64 {
+        self(raw, resolution)
+    }
+}
+
+impl SensorTransform for Calibration {
+    fn convert(&self, raw: u16, resolution: u8) -> f64 {
+        self.apply(raw, resolution)
+    }
+}
+
+fn max_reading(resolution: u8) -> f64 {
+    f64::from((1_u32 << resolution.min(31)) - 1).max(1.0)
+}
+
+/// A TMP36 temperature sensor, reports degrees Celsius.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tmp36 {
+    pub reference_voltage: f64,
+}
+
+impl Default for Tmp36 {
+    fn default() -> Self {
+        Self {
+            reference_voltage: 5.0,
+        }
+    }
+}
+
+impl SensorTransform for Tmp36 {
+    fn convert(&self, raw: u16, resolution: u8) -> f64 {
+        let volts = f64::from(raw) / max_reading(resolution) * self.reference_voltage;
+        (volts - 0.5) * 100.0
+    }
+}
+
+/// A thermistor between the pin and ground with a fixed resistor between the pin and
+/// the reference voltage, converted with the Steinhart-Hart equation into degrees Celsius.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SteinhartHart {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    /// Resistance of the fixed resistor in ohms.
+    pub series_resistance: f64,
+}
+
+impl SteinhartHart {
+    /// Coefficients of a common 10k NTC thermistor with a 10k series resistor.
+    #[must_use]
+    pub fn ntc_10k() -> Self {
+        Self {
+            a: 1.009_249_522e-3,
+            b: 2.378_405_444e-4,
+            c: 2.019_202_697e-7,
+            series_resistance: 10_000.0,
+        }
+    }
+}
+
+impl SensorTransform for SteinhartHart {
+    fn convert(&self, raw: u16, resolution: u8) -> f64 {
+        let raw = f64::from(raw);
+        let max = max_reading(resolution);
+        if raw <= 0.0 || raw >= max {
+            return f64::NAN;
+        }
+        let ln_r = (self.series_resistance * raw / (max - raw)).ln();
+        1.0 / (self.a + self.b * ln_r + self.c * ln_r.powi(3)) - 273.15
+    }
+}
+
+/// Sensor transforms keyed by pin index, clones share the transforms.
+#[derive(Clone, Default)]
+pub struct TransformTable {
+    pins: BTreeMap<u8, Arc<dyn SensorTransform>>,
+}
+
+impl std::fmt::Debug for TransformTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TransformTable")
+            .field("pins", &self.pins.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl TransformTable {
+    pub fn set<S: SensorTransform + 'static>(&mut self, pin: u8, transform: S) {
+        self.pins.insert(pin, Arc::new(transform));
+    }
+
+    pub fn remove(&mut self, pin: u8) -> bool {
+        self.pins.remove(&pin).is_some()
+    }
+
+    #[must_use]
+    pub fn contains(&self, pin: u8) -> bool {
+        self.pins.contains_key(&pin)
+    }
+
+    /// Converts a pin's current value, `None` if the pin has no transform.
+    #[must_use]
+    pub fn convert(&self, index: u8, pin: &Pin) -> Option<f64> {
+        self.pins
+            .get(&index)
+            .map(|transform| transform.convert(pin.value, analog_resolution(pin)))
+    }
+}
@@ -0,0 +1,258 @@
+//! Connections that can be (re)opened on demand, shared by the sync [`standard::Board`]
+//! and the async [`BoardIo`], so reconnecting works the same way for every kind of
+//! connection on both.
+//!
+//! [`standard::Board`]: crate::standard::board::Board
+//! [`BoardIo`]: crate::asynchronous::boardio::BoardIo
+use crate::{FirmataError, Result};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// A blocking connection as used by the sync board.
+pub trait Connection: io::Read + io::Write + Send {}
+
+impl<T: io::Read + io::Write + Send> Connection for T {}
+
+pub type BoxedConnection = Box<dyn Connection>;
+pub type BoxedAsyncRead = Box<dyn AsyncRead + Unpin + Send>;
+pub type BoxedAsyncWrite = Box<dyn AsyncWrite + Unpin + Send>;
+/// The read and write halves of an async connection.
+pub type AsyncConnection = (BoxedAsyncRead, BoxedAsyncWrite);
+
+/// Describes a transport for logs and error messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransportInfo {
+    /// The kind of connection, e.g. `serial` or `tcp`.
+    pub kind: &'static str,
+    /// What the transport connects to, e.g. a port path or socket address.
+    pub target: String,
+    /// Whether opening the transport again after the connection dropped can succeed,
+    /// false for connections that can only be made once.
+    pub reconnectable: bool,
+}
+
+/// Something a board can be reached over. Every call to an open method makes a new
+/// connection, dropping a connection closes it.
+pub trait Transport: Send + Sync {
+    fn info(&self) -> TransportInfo;
+
+    /// Opens a blocking connection.
+    /// # Errors
+    /// Returns an error if the connection could not be made or the transport has no
+    /// blocking connections.
+    fn open(&self) -> Result<BoxedConnection>;
+
+    /// Opens an async connection, must be called from within a tokio runtime.
+    /// # Errors
+    /// Returns an error if the connection could not be made.
+    fn open_async(&self) -> Pin<Box<dyn Future<Output = Result<AsyncConnection>> + Send + '_>>;
+}
+
+/// A TCP connection, e.g. to a board running a WiFi or Ethernet firmata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpTransport {
+    pub address: String,
+}
+
+impl TcpTransport {
+    #[must_use]
+    pub fn new(address: &str) -> Self {
+        Self {
+            address: address.to_string(),
+        }
+    }
+}
+
+impl Transport for TcpTransport {
+    fn info(&self) -> TransportInfo {
+        TransportInfo {
+            kind: "tcp",
+            target: self.address.clone(),
+            reconnectable: true,
+        }
+    }
+
+    fn open(&self) -> Result<BoxedConnection> {
+        let stream = std::net::TcpStream::connect(&self.address)?;
+        stream.set_nodelay(true)?;
+        Ok(Box::new(stream))
+    }
+
+    fn open_async(&self) -> Pin<Box<dyn Future<Output = Result<AsyncConnection>> + Send + '_>> {
+        Box::pin(async move {
+            let stream = tokio::net::TcpStream::connect(&self.address).await?;
+            stream.set_nodelay(true)?;
+            let (conn_read, conn_write) = stream.into_split();
+            Ok((
+                Box::new(conn_read) as BoxedAsyncRead,
+                Box::new(conn_write) as BoxedAsyncWrite,
+            ))
+        })
+    }
+}
+
+/// A unix domain socket, e.g. exposed by `ser2net` or `socat`.
+#[cfg(unix)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnixTransport {
+    pub path: std::path::PathBuf,
+}
+
+#[cfg(unix)]
+impl UnixTransport {
+    #[must_use]
+    pub fn new<P: AsRef<std::path::Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Transport for UnixTransport {
+    fn info(&self) -> TransportInfo {
+        TransportInfo {
+            kind: "unix",
+            target: self.path.display().to_string(),
+            reconnectable: true,
+        }
+    }
+
+    fn open(&self) -> Result<BoxedConnection> {
+        Ok(Box::new(std::os::unix::net::UnixStream::connect(
+            &self.path,
+        )?))
+    }
+
+    fn open_async(&self) -> Pin<Box<dyn Future<Output = Result<AsyncConnection>> + Send + '_>> {
+        Box::pin(async move {
+            let (conn_read, conn_write) = tokio::net::UnixStream::connect(&self.path)
+                .await?
+                .into_split();
+            Ok((
+                Box::new(conn_read) as BoxedAsyncRead,
+                Box::new(conn_write) as BoxedAsyncWrite,
+            ))
+        })
+    }
+}
+
+/// An async connection made by a closure, e.g. for a bridge that needs a login or
+/// custom setup before the firmata bytes flow.
+pub struct FnTransport<F> {
+    kind: &'static str,
+    target: String,
+    connect: F,
+}
+
+impl<F, Fut> FnTransport<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<AsyncConnection>> + Send,
+{
+    /// The closure is called for every connection, including reconnects.
+    pub fn new(kind: &'static str, target: &str, connect: F) -> Self {
+        Self {
+            kind,
+            target: target.to_string(),
+            connect,
+        }
+    }
+}
+
+impl<F> std::fmt::Debug for FnTransport<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FnTransport")
+            .field("kind", &self.kind)
+            .field("target", &self.target)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F, Fut> Transport for FnTransport<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<AsyncConnection>> + Send + 'static,
+{
+    fn info(&self) -> TransportInfo {
+        TransportInfo {
+            kind: self.kind,
+            target: self.target.clone(),
+            reconnectable: true,
+        }
+    }
+
+    fn open(&self) -> Result<BoxedConnection> {
+        Err(async_only())
+    }
+
+    fn open_async(&self) -> Pin<Box<dyn Future<Output = Result<AsyncConnection>> + Send + '_>> {
+        Box::pin((self.connect)())
+    }
+}
+
+/// How often and how fast a dropped connection is reopened. The delay before an
+/// attempt starts at `initial_delay` and is multiplied by `multiplier` after every
+/// failed attempt, up to `max_delay`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: u32,
+    /// Gives up after this many failed attempts in a row, `None` retries forever.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    /// Backs off from 100 ms to 5 s and never gives up.
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            multiplier: 2,
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Retries every interval and never gives up.
+    #[must_use]
+    pub const fn fixed(interval: Duration) -> Self {
+        Self {
+            initial_delay: interval,
+            max_delay: interval,
+            multiplier: 1,
+            max_attempts: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = Some(attempts);
+        self
+    }
+
+    /// The delay after the failed attempt with the index, counting from 0.
+    #[must_use]
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.max(1).saturating_pow(attempt);
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay.max(self.initial_delay))
+    }
+
+    /// Whether another attempt is allowed after the number of failed attempts.
+    #[must_use]
+    pub fn allows(&self, failed: u32) -> bool {
+        self.max_attempts.is_none_or(|max| failed < max)
+    }
+}
+
+/// The error for transports without blocking connections.
+pub(crate) const fn async_only() -> FirmataError {
+    FirmataError::WrongType("the transport only supports async connections")
+}
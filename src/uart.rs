@@ -0,0 +1,171 @@
+//! Encoding of SERIAL_DATA, which bridges the serial ports of a board, e.g. a GPS or
+//! LoRa module on a spare UART, to the host. See
+//! <https://github.com/firmata/protocol/blob/master/serial-1.0.md>.
+//!
+//! Not to be confused with [`crate::serial`], the serial connection to the board itself.
+use crate::message;
+use crate::protocol::{SerialCommand, SysexCommand};
+use crate::{FirmataError, Result};
+use serde::{Deserialize, Serialize};
+
+/// The hardware serial ports are 0 to 3.
+pub const MAX_HARDWARE_PORT: u8 = 0x03;
+/// The first software serial port, software ports are 8 to 11.
+pub const FIRST_SOFTWARE_PORT: u8 = 0x08;
+pub const MAX_SOFTWARE_PORT: u8 = 0x0B;
+/// The fastest baud rate, it is sent as three 7 bit bytes.
+pub const MAX_BAUD: u32 = 0x1F_FFFF;
+/// Data bytes sent per SERIAL_WRITE, two bytes each they fit the 64 byte sysex buffer
+/// of the firmware.
+pub const WRITE_CHUNK_SIZE: usize = 31;
+
+/// Whether the firmware keeps sending what a port receives.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum SerialReadMode {
+    Continuous = 0,
+    Stop = 1,
+}
+
+/// A command for a serial port of the firmware.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum SerialRequest {
+    /// Opens a port at a baud rate, software ports also need their RX and TX pins.
+    Config {
+        port: u8,
+        baud: u32,
+        pins: Option<(u8, u8)>,
+    },
+    Write {
+        port: u8,
+        data: Vec<u8>,
+    },
+    /// Starts or stops reporting received bytes as SERIAL_REPLY, at most `max_bytes`
+    /// per reply if given.
+    Read {
+        port: u8,
+        mode: SerialReadMode,
+        max_bytes: Option<u16>,
+    },
+    Close(u8),
+    /// Waits for the transmit buffer of the port to drain.
+    Flush(u8),
+    /// Switches receiving to a software port.
+    Listen(u8),
+}
+
+impl SerialRequest {
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Config { port, baud, pins } => {
+                let baud = (*baud).min(MAX_BAUD);
+                let mut payload = vec![
+                    SerialCommand::Config.with_port(*port),
+                    (baud & 0x7F) as u8,
+                    (baud >> 7 & 0x7F) as u8,
+                    (baud >> 14 & 0x7F) as u8,
+                ];
+                if let Some((rx, tx)) = pins {
+                    payload.extend_from_slice(&[*rx & 0x7F, *tx & 0x7F]);
+                }
+                SysexCommand::SerialData.frame(&payload)
+            }
+            Self::Write { port, data } => {
+                let mut payload = vec![SerialCommand::Write.with_port(*port)];
+                for byte in data {
+                    payload.extend_from_slice(&message::encode_u14(u16::from(*byte)));
+                }
+                SysexCommand::SerialData.frame(&payload)
+            }
+            Self::Read {
+                port,
+                mode,
+                max_bytes,
+            } => {
+                let mut payload = vec![SerialCommand::Read.with_port(*port), *mode as u8];
+                if let Some(max_bytes) = max_bytes {
+                    payload.extend_from_slice(&message::encode_u14(*max_bytes));
+                }
+                SysexCommand::SerialData.frame(&payload)
+            }
+            Self::Close(port) => frame(SerialCommand::Close, *port),
+            Self::Flush(port) => frame(SerialCommand::Flush, *port),
+            Self::Listen(port) => frame(SerialCommand::Listen, *port),
+        }
+    }
+}
+
+fn frame(command: SerialCommand, port: u8) -> Vec<u8> {
+    SysexCommand::SerialData.frame(&[command.with_port(port)])
+}
+
+#[must_use]
+pub const fn is_software_port(port: u8) -> bool {
+    port >= FIRST_SOFTWARE_PORT && port <= MAX_SOFTWARE_PORT
+}
+
+/// Checks that a port can be addressed.
+/// # Errors
+/// Returns [`FirmataError::OutOfRange`] if the port is neither a hardware port, 0 to 3,
+/// nor a software port, 8 to 11.
+pub const fn validate_port(port: u8) -> Result<()> {
+    if port > MAX_HARDWARE_PORT && !is_software_port(port) {
+        return Err(FirmataError::OutOfRange(
+            "serial port must be 0 to 3 or 8 to 11",
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that a port can be opened with the settings.
+/// # Errors
+/// Returns [`FirmataError::OutOfRange`] if the port is invalid, see [`validate_port`],
+/// the baud rate exceeds [`MAX_BAUD`] or a software port has no pins.
+pub fn validate_config(port: u8, baud: u32, pins: Option<(u8, u8)>) -> Result<()> {
+    validate_port(port)?;
+    if baud == 0 || baud > MAX_BAUD {
+        return Err(FirmataError::OutOfRange(
+            "baud rate must be between 1 and 2097151",
+        ));
+    }
+    if is_software_port(port) && pins.is_none() {
+        return Err(FirmataError::OutOfRange(
+            "a software serial port needs its rx and tx pins",
+        ));
+    }
+    Ok(())
+}
+
+/// The requests that write bytes to a port, in chunks the firmware can buffer.
+#[must_use]
+pub fn write_requests(port: u8, data: &[u8]) -> Vec<SerialRequest> {
+    data.chunks(WRITE_CHUNK_SIZE)
+        .map(|chunk| SerialRequest::Write {
+            port,
+            data: chunk.to_vec(),
+        })
+        .collect()
+}
+
+/// Parses the payload of a SERIAL_REPLY into the port and the received bytes.
+/// # Errors
+/// Returns [`FirmataError::ParseError`] if the message is not a reply.
+pub fn decode_reply(byte_stream: &[u8]) -> Result<(u8, Vec<u8>)> {
+    match byte_stream {
+        [command, data @ ..]
+            if SerialCommand::try_from(*command).ok() == Some(SerialCommand::Reply) =>
+        {
+            let data = data
+                .chunks_exact(2)
+                .map(|pair| message::decode_u14(pair[0], pair[1]) as u8)
+                .collect();
+            Ok((command & 0x0F, data))
+        }
+        _ => Err(FirmataError::ParseError(
+            "failed to parse serial reply",
+            byte_stream.to_vec(),
+        )),
+    }
+}
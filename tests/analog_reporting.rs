@@ -0,0 +1,120 @@
+//! REPORT_ANALOG addresses analog channels, analog pins are resolved to their channel
+//! before the command is sent.
+use firmata::sampling::SamplingConfig;
+use firmata::testing::mock::MockBoard;
+use firmata::PinId;
+use std::time::{Duration, Instant};
+
+/// The REPORT_ANALOG commands the mock received, waiting a moment for them to arrive.
+fn report_analog_commands(mock: &MockBoard, expected: usize) -> Vec<Vec<u8>> {
+    let deadline = Instant::now() + Duration::from_secs(1);
+    loop {
+        let commands: Vec<Vec<u8>> = mock
+            .handle()
+            .commands()
+            .into_iter()
+            .filter(|command| command.first().is_some_and(|v| v & 0xF0 == 0xC0))
+            .collect();
+        if commands.len() >= expected || Instant::now() > deadline {
+            return commands;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[tokio::test]
+async fn async_report_analog_sends_the_channel() {
+    let mock = MockBoard::uno().unwrap();
+    let mut board_io = mock.board_io().await.unwrap();
+    let mut board = board_io.get_board();
+    tokio::spawn(async move { board_io.poll().await });
+    board.report_analog(PinId::Analog(0), true).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(report_analog_commands(&mock, 1), [vec![0xC0, 0x01]]);
+}
+
+#[test]
+fn sync_report_analog_sends_the_channel() {
+    let mock = MockBoard::uno().unwrap();
+    let mut board = mock.board().unwrap();
+    board.report_analog(PinId::Analog(0), true).unwrap();
+    assert_eq!(report_analog_commands(&mock, 1), [vec![0xC0, 0x01]]);
+}
+
+#[test]
+fn pins_without_a_channel_are_rejected() {
+    let mock = MockBoard::uno().unwrap();
+    let mut board = mock.board().unwrap();
+    assert!(board.report_analog(PinId::Pin(3), true).is_err());
+}
+
+/// The SAMPLING_INTERVAL frames the mock received.
+fn sampling_interval_commands(mock: &MockBoard) -> Vec<Vec<u8>> {
+    mock.handle()
+        .commands()
+        .into_iter()
+        .filter(|command| command.starts_with(&[0xF0, 0x7A]))
+        .collect()
+}
+
+fn sampling_config() -> SamplingConfig {
+    SamplingConfig::new(Duration::from_millis(200))
+        .channel(1, 2)
+        .disable(3)
+}
+
+#[tokio::test]
+async fn async_configure_sampling_sets_the_interval_and_channels() {
+    let mock = MockBoard::uno().unwrap();
+    let mut board_io = mock.board_io().await.unwrap();
+    let mut board = board_io.get_board();
+    tokio::spawn(async move { board_io.poll().await });
+    board.configure_sampling(&sampling_config()).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(
+        sampling_interval_commands(&mock),
+        [vec![0xF0, 0x7A, 0x48, 0x01, 0xF7]]
+    );
+    assert_eq!(
+        report_analog_commands(&mock, 2),
+        [vec![0xC1, 0x01], vec![0xC3, 0x00]]
+    );
+}
+
+#[test]
+fn sync_configure_sampling_sets_the_interval_and_channels() {
+    let mock = MockBoard::uno().unwrap();
+    let mut board = mock.board().unwrap();
+    board.configure_sampling(&sampling_config()).unwrap();
+    assert_eq!(
+        report_analog_commands(&mock, 2),
+        [vec![0xC1, 0x01], vec![0xC3, 0x00]]
+    );
+    assert_eq!(
+        sampling_interval_commands(&mock),
+        [vec![0xF0, 0x7A, 0x48, 0x01, 0xF7]]
+    );
+}
+
+fn all_uno_channels() -> Vec<Vec<u8>> {
+    (0xC0..=0xC5).map(|command| vec![command, 0x01]).collect()
+}
+
+#[tokio::test]
+async fn async_report_all_analog_reports_every_channel() {
+    let mock = MockBoard::uno().unwrap();
+    let mut board_io = mock.board_io().await.unwrap();
+    let mut board = board_io.get_board();
+    tokio::spawn(async move { board_io.poll().await });
+    board.report_all_analog(true).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(report_analog_commands(&mock, 6), all_uno_channels());
+}
+
+#[test]
+fn sync_report_all_analog_reports_every_channel() {
+    let mock = MockBoard::uno().unwrap();
+    let mut board = mock.board().unwrap();
+    board.report_all_analog(true).unwrap();
+    assert_eq!(report_analog_commands(&mock, 6), all_uno_channels());
+}
@@ -0,0 +1,49 @@
+//! The blocking board writes through the handle its claims are keyed by.
+use firmata::asynchronous::blocking::BlockingBoard;
+use firmata::testing::mock::MockBoard;
+use firmata::transport::Transport;
+use firmata::{FirmataError, PinId};
+use tokio::runtime::Builder;
+
+fn blocking_board(mock: &MockBoard) -> BlockingBoard {
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .unwrap();
+    let transport = mock.transport();
+    let (conn_read, conn_write) = runtime.block_on(transport.open_async()).unwrap();
+    BlockingBoard::with_runtime(runtime, conn_read, conn_write).unwrap()
+}
+
+#[test]
+fn claimed_pins_can_be_written() {
+    let mock = MockBoard::uno().unwrap();
+    let mut board = blocking_board(&mock);
+
+    board.claim_output(PinId::Pin(13)).unwrap();
+    board.digital_write(PinId::Pin(13), true).unwrap();
+
+    let mut other = board.board();
+    let error = board
+        .block_on(other.digital_write(PinId::Pin(13), false))
+        .unwrap_err();
+    assert!(matches!(error, FirmataError::PinBusy(13, _)));
+}
+
+#[test]
+fn reserved_pins_can_be_written_by_their_owner() {
+    let mock = MockBoard::uno().unwrap();
+    let mut board = blocking_board(&mock);
+
+    board
+        .reserve(PinId::Pin(13), "conveyor_motor", "direction")
+        .unwrap();
+    board.digital_write(PinId::Pin(13), true).unwrap();
+
+    let mut other = board.board();
+    let error = board
+        .block_on(other.digital_write(PinId::Pin(13), false))
+        .unwrap_err();
+    assert!(matches!(error, FirmataError::PinBusy(13, _)));
+}
@@ -0,0 +1,184 @@
+//! Fuzz tests of the incremental decoder of the async codec: arbitrary input, arbitrary
+//! chunking and frames beyond the size limits of the old decoder.
+use bytes::BytesMut;
+use firmata::asynchronous::network::FirmataCodec;
+use firmata::message::{MessageIn, System};
+use firmata::FirmataError;
+use proptest::prelude::*;
+use tokio_util::codec::Decoder;
+
+const START_SYSEX: u8 = 0xF0;
+const END_SYSEX: u8 = 0xF7;
+
+/// A frame the firmware sends, as bytes.
+fn frame() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        (0..16_u8, 0..0x80_u8, 0..0x80_u8).prop_map(|(pin, lsb, msb)| vec![0xE0 | pin, lsb, msb]),
+        (0..16_u8, 0..0x80_u8, 0..2_u8).prop_map(|(port, lsb, msb)| vec![0x90 | port, lsb, msb]),
+        "[a-zA-Z0-9 ]{0,1500}".prop_map(|text| {
+            let mut bytes = vec![START_SYSEX, 0x71];
+            for byte in text.bytes() {
+                bytes.extend_from_slice(&[byte & 0x7F, byte >> 7]);
+            }
+            bytes.push(END_SYSEX);
+            bytes
+        }),
+        (1..400_usize).prop_map(|pins| {
+            let mut bytes = vec![START_SYSEX, 0x6C];
+            for _ in 0..pins {
+                bytes.extend_from_slice(&[0x00, 0x01, 0x01, 0x01, 0x03, 0x08, 0x7F]);
+            }
+            bytes.push(END_SYSEX);
+            bytes
+        }),
+    ]
+}
+
+/// Splits the bytes at the given points, in order and without empty chunks.
+fn chunks(bytes: &[u8], cuts: &[usize]) -> Vec<Vec<u8>> {
+    let mut cuts: Vec<usize> = cuts.iter().map(|v| v % (bytes.len() + 1)).collect();
+    cuts.sort_unstable();
+    cuts.push(bytes.len());
+    let mut start = 0;
+    let mut out = vec![];
+    for cut in cuts {
+        if cut > start {
+            out.push(bytes[start..cut].to_vec());
+            start = cut;
+        }
+    }
+    out
+}
+
+/// Feeds the chunks one at a time, draining every message in between, and returns the
+/// messages with the bytes each one was decoded from.
+fn decode_chunked(
+    codec: &mut FirmataCodec,
+    chunks: &[Vec<u8>],
+) -> Result<Vec<(MessageIn, Vec<u8>)>, FirmataError> {
+    let mut buf = BytesMut::new();
+    let mut messages = vec![];
+    for chunk in chunks {
+        buf.extend_from_slice(chunk);
+        while let Some(message) = codec.decode(&mut buf)? {
+            messages.push((message, codec.take_raw().unwrap_or_default()));
+        }
+    }
+    Ok(messages)
+}
+
+fn retaining_codec() -> FirmataCodec {
+    let mut codec = FirmataCodec::lenient();
+    codec.set_retain_raw(true);
+    codec
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_input_accounts_for_every_byte(
+        bytes in prop::collection::vec(any::<u8>(), 0..2000),
+        cuts in prop::collection::vec(any::<usize>(), 0..16),
+    ) {
+        let mut codec = retaining_codec();
+        let mut buf = BytesMut::new();
+        let mut consumed = 0;
+        for chunk in chunks(&bytes, &cuts) {
+            buf.extend_from_slice(&chunk);
+            while let Some(_message) = codec.decode(&mut buf).expect("input stays below the limit") {
+                consumed += codec.take_raw().map_or(0, |raw| raw.len());
+            }
+        }
+        prop_assert_eq!(consumed + buf.len(), bytes.len());
+    }
+
+    #[test]
+    fn chunking_does_not_change_the_messages(
+        frames in prop::collection::vec(frame(), 1..6),
+        cuts in prop::collection::vec(any::<usize>(), 0..32),
+    ) {
+        let bytes = frames.concat();
+        let whole = decode_chunked(&mut retaining_codec(), std::slice::from_ref(&bytes)).unwrap();
+        let chunked = decode_chunked(&mut retaining_codec(), &chunks(&bytes, &cuts)).unwrap();
+        prop_assert_eq!(&whole, &chunked);
+        let raw: Vec<Vec<u8>> = whole.into_iter().map(|(_, raw)| raw).collect();
+        prop_assert_eq!(raw, frames);
+    }
+
+    #[test]
+    fn a_header_inside_a_sysex_resynchronises_on_it(
+        payload in prop::collection::vec(0..0x80_u8, 0..64),
+        lsb in 0..0x80_u8,
+        msb in 0..0x80_u8,
+    ) {
+        let bytes = [&[START_SYSEX, 0x71][..], &payload, &[0xE3, lsb, msb]].concat();
+        let messages = decode_chunked(&mut retaining_codec(), &[bytes]).unwrap();
+        prop_assert_eq!(messages.len(), 2);
+        prop_assert!(matches!(messages[0].0, MessageIn::FrameError(_)));
+        prop_assert_eq!(&messages[1].1, &vec![0xE3, lsb, msb]);
+    }
+
+    #[test]
+    fn oversized_sysex_fails_with_a_parse_error(
+        size in 16..256_usize,
+        excess in 0..64_usize,
+        cuts in prop::collection::vec(any::<usize>(), 0..8),
+    ) {
+        let mut codec = FirmataCodec::new();
+        codec.set_max_frame_size(size);
+        let mut bytes = vec![START_SYSEX, 0x71];
+        bytes.resize(size + excess, 0x41);
+        bytes.push(END_SYSEX);
+        let result = decode_chunked(&mut codec, &chunks(&bytes, &cuts));
+        prop_assert!(matches!(result, Err(FirmataError::ParseError(..))), "{:?}", result);
+    }
+}
+
+#[test]
+fn capability_response_beyond_a_thousand_bytes_decodes() {
+    let mut bytes = vec![START_SYSEX, 0x6C];
+    for _ in 0..300 {
+        bytes.extend_from_slice(&[0x00, 0x01, 0x01, 0x01, 0x7F]);
+    }
+    bytes.push(END_SYSEX);
+    let messages = decode_chunked(&mut FirmataCodec::new(), &chunks(&bytes, &[700])).unwrap();
+    let [(MessageIn::System(System::CapabilityResponseMessage(response)), _)] = &messages[..]
+    else {
+        panic!("decoded to {messages:?}");
+    };
+    assert_eq!(response.pins.len(), 300);
+}
+
+#[test]
+fn decoding_continues_after_an_oversized_frame() {
+    let mut codec = FirmataCodec::new();
+    codec.set_max_frame_size(8);
+    let mut buf = BytesMut::from(&[START_SYSEX, 0x71, 1, 2, 3, 4, 5, 6, 7, 8][..]);
+    assert!(matches!(
+        codec.decode(&mut buf),
+        Err(FirmataError::ParseError(..))
+    ));
+    buf.extend_from_slice(&[9, END_SYSEX, 0xE1, 0x05, 0x00]);
+    let mut messages = vec![];
+    while let Some(message) = codec.decode(&mut buf).unwrap() {
+        messages.push(message);
+    }
+    assert!(matches!(
+        messages[..],
+        [MessageIn::FrameError(_), MessageIn::Analog(_)]
+    ));
+}
+
+#[test]
+fn unknown_sysex_follows_the_configured_mode() {
+    let frame = [START_SYSEX, 0x0F, 1, 2, END_SYSEX];
+    let mut buf = BytesMut::from(&frame[..]);
+    assert!(matches!(
+        FirmataCodec::new().decode(&mut buf),
+        Err(FirmataError::ParseError(..))
+    ));
+    let mut buf = BytesMut::from(&frame[..]);
+    assert!(matches!(
+        FirmataCodec::lenient().decode(&mut buf),
+        Ok(Some(MessageIn::UnknownSysex { command: 0x0F, .. }))
+    ));
+}
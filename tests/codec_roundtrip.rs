@@ -0,0 +1,684 @@
+//! Property tests of the async codec against a small reference model of the protocol.
+use bytes::BytesMut;
+use firmata::asynchronous::boardio::MessageOut;
+use firmata::asynchronous::network::FirmataCodec;
+use firmata::i2c::{I2cConfig, I2cReadOptions};
+use firmata::message::{MessageIn, OneWireReply, System};
+use firmata::onewire::{OneWireAddress, OneWireRequest};
+use firmata::pulse::PulseRequest;
+use firmata::scheduler::{SchedulerRequest, ADD_CHUNK_SIZE, MAX_TASK_ID, MAX_TASK_LENGTH};
+use firmata::stepper::{StepperConfig, StepperInterface, MAX_STEPPERS, MAX_STEPPER_GROUPS};
+use firmata::uart::{SerialReadMode, SerialRequest, MAX_BAUD, WRITE_CHUNK_SIZE};
+use firmata::{PinId, PinMode};
+use proptest::prelude::*;
+use std::time::Duration;
+use tokio_util::codec::{Decoder, Encoder};
+
+const MAX_U14: u16 = 0x3FFF;
+
+/// The protocol as written down in the Firmata documentation, independent of the crate.
+mod reference {
+    pub const START_SYSEX: u8 = 0xF0;
+    pub const END_SYSEX: u8 = 0xF7;
+
+    pub fn u14(value: u16) -> [u8; 2] {
+        [(value & 0x7F) as u8, (value >> 7) as u8]
+    }
+
+    /// The `Encoder7Bit` of ConfigurableFirmata, used by the OneWire extension.
+    pub fn pack_7bit(data: &[u8]) -> Vec<u8> {
+        let mut out = vec![];
+        let (mut shift, mut previous) = (0, 0_u8);
+        for byte in data {
+            if shift == 0 {
+                out.push(byte & 0x7F);
+                shift += 1;
+                previous = byte >> 7;
+            } else {
+                out.push(((byte << shift) & 0x7F) | previous);
+                if shift == 6 {
+                    out.push(byte >> 1);
+                    shift = 0;
+                } else {
+                    shift += 1;
+                    previous = byte >> (8 - shift);
+                }
+            }
+        }
+        if shift > 0 {
+            out.push(previous);
+        }
+        out
+    }
+
+    /// Length of a frame that is not a sysex message, by its command byte.
+    fn frame_len(command: u8) -> Option<usize> {
+        match command {
+            0xF4 | 0xF5 => Some(3),
+            0xF9 | 0xFF => Some(1),
+            _ => match command & 0xF0 {
+                0x90 | 0xE0 => Some(3),
+                0xC0 | 0xD0 => Some(2),
+                _ => None,
+            },
+        }
+    }
+
+    /// Splits bytes sent to the firmware into frames, rejecting anything a firmware
+    /// would not accept.
+    pub fn split_frames(bytes: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+        let mut frames = vec![];
+        let mut rest = bytes;
+        while let Some(command) = rest.first() {
+            let len = if *command == START_SYSEX {
+                let end = rest
+                    .iter()
+                    .position(|v| *v == END_SYSEX)
+                    .ok_or("unterminated sysex message")?;
+                if end < 2 {
+                    return Err("sysex message without a command".into());
+                }
+                end + 1
+            } else {
+                frame_len(*command).ok_or(format!("unknown command {command:#04x}"))?
+            };
+            if rest.len() < len {
+                return Err(format!("truncated frame {rest:02x?}"));
+            }
+            let (frame, tail) = rest.split_at(len);
+            let data = if frame[0] == START_SYSEX {
+                &frame[1..len - 1]
+            } else {
+                &frame[1..]
+            };
+            if data.iter().any(|v| *v > 0x7F) {
+                return Err(format!("data byte with the high bit set in {frame:02x?}"));
+            }
+            frames.push(frame.to_vec());
+            rest = tail;
+        }
+        Ok(frames)
+    }
+}
+
+/// Encodes a message with the crate's codec.
+fn encode(message: MessageOut) -> Vec<u8> {
+    let mut buf = BytesMut::new();
+    FirmataCodec::default()
+        .encode(message, &mut buf)
+        .expect("encoding never fails");
+    buf.to_vec()
+}
+
+/// Decodes every frame with the crate's codec.
+fn decode(bytes: &[u8]) -> Vec<MessageIn> {
+    let mut codec = FirmataCodec::default();
+    let mut buf = BytesMut::from(bytes);
+    let mut messages = vec![];
+    while let Some(message) = codec.decode(&mut buf).expect("decoding never fails") {
+        messages.push(message);
+    }
+    messages
+}
+
+/// What the reference model sends for messages with a single obvious encoding.
+fn expected_encoding(message: &MessageOut) -> Option<Vec<u8>> {
+    let [lsb, msb] = match message {
+        MessageOut::AnalogWrite(_, value) => reference::u14(*value),
+        MessageOut::I2cConfig(config) => reference::u14(config.read_delay_us),
+        MessageOut::SampleingInterval(interval) => {
+            reference::u14(interval.as_millis().clamp(1, 0x3FFF) as u16)
+        }
+        MessageOut::DigitalPortWrite(_, mask, value) => reference::u14(u16::from(*value & *mask)),
+        _ => [0, 0],
+    };
+    Some(match message {
+        MessageOut::AnalogWrite(pin, _) => vec![0xE0 | pin, lsb, msb],
+        MessageOut::DigitalPortWrite(port, ..) => vec![0x90 | port, lsb, msb],
+        MessageOut::I2cConfig(_) => vec![0xF0, 0x78, lsb, msb, 0xF7],
+        MessageOut::SampleingInterval(_) => vec![0xF0, 0x7A, lsb, msb, 0xF7],
+        MessageOut::ReportDigital(port, enable) => vec![0xD0 | port, u8::from(*enable)],
+        MessageOut::ReportAnalog(channel, enable) => vec![0xC0 | channel, u8::from(*enable)],
+        MessageOut::DigitalWrite(pin, level) => vec![0xF5, *pin, u8::from(*level)],
+        MessageOut::SystemReset => vec![0xFF],
+        MessageOut::ProtocolVersionQuery => vec![0xF9],
+        MessageOut::I2cWrite(address, data) => [
+            &[0xF0, 0x76, *address, 0x00][..],
+            &data
+                .iter()
+                .flat_map(|v| reference::u14(u16::from(*v)))
+                .collect::<Vec<_>>(),
+            &[0xF7],
+        ]
+        .concat(),
+        MessageOut::PinStateQuery(pin) => vec![0xF0, 0x6D, *pin, 0xF7],
+        MessageOut::OneWireConfig(pin, power) => {
+            vec![0xF0, 0x73, 0x41, *pin, u8::from(*power), 0xF7]
+        }
+        MessageOut::OneWireSearch(pin, alarms) => {
+            vec![0xF0, 0x73, if *alarms { 0x44 } else { 0x40 }, *pin, 0xF7]
+        }
+        MessageOut::OneWireRequest(pin, request) => {
+            let command = u8::from(request.reset)
+                | u8::from(request.skip) << 1
+                | u8::from(request.select.is_some()) << 2
+                | u8::from(request.read > 0) << 3
+                | u8::from(!request.delay.is_zero()) << 4
+                | u8::from(!request.write.is_empty()) << 5;
+            let mut data = request.select.map(|v| v.0.to_vec()).unwrap_or_default();
+            if request.read > 0 {
+                data.extend_from_slice(&request.read.to_le_bytes());
+                data.extend_from_slice(&request.correlation_id.to_le_bytes());
+            }
+            if !request.delay.is_zero() {
+                data.extend_from_slice(&(request.delay.as_millis() as u32).to_le_bytes());
+            }
+            data.extend_from_slice(&request.write);
+            [
+                &[0xF0, 0x73, command, *pin][..],
+                &reference::pack_7bit(&data),
+                &[0xF7],
+            ]
+            .concat()
+        }
+        MessageOut::ServoConfig(pin, min, max) => [
+            &[0xF0, 0x70, *pin][..],
+            &reference::u14(*min),
+            &reference::u14(*max),
+            &[0xF7],
+        ]
+        .concat(),
+        MessageOut::StepperZero(device) => vec![0xF0, 0x62, 0x01, *device, 0xF7],
+        MessageOut::StepperEnable(device, enable) => {
+            vec![0xF0, 0x62, 0x04, *device, u8::from(*enable), 0xF7]
+        }
+        MessageOut::StepperStop(device) => vec![0xF0, 0x62, 0x05, *device, 0xF7],
+        MessageOut::StepperReportPosition(device) => vec![0xF0, 0x62, 0x06, *device, 0xF7],
+        MessageOut::Sysex(command, payload) => [&[0xF0, *command][..], payload, &[0xF7]].concat(),
+        MessageOut::Scheduler(request) => match request {
+            SchedulerRequest::Create { task, length } => [
+                &[0xF0, 0x7B, 0x00, *task][..],
+                &reference::u14(*length),
+                &[0xF7],
+            ]
+            .concat(),
+            SchedulerRequest::Delete(task) => vec![0xF0, 0x7B, 0x01, *task, 0xF7],
+            SchedulerRequest::AddTo { task, data } => [
+                &[0xF0, 0x7B, 0x02, *task][..],
+                &reference::pack_7bit(data),
+                &[0xF7],
+            ]
+            .concat(),
+            SchedulerRequest::Delay(delay) => [
+                &[0xF0, 0x7B, 0x03][..],
+                &reference::pack_7bit(&(delay.as_millis() as u32).to_le_bytes()),
+                &[0xF7],
+            ]
+            .concat(),
+            SchedulerRequest::Schedule { task, delay } => [
+                &[0xF0, 0x7B, 0x04, *task][..],
+                &reference::pack_7bit(&(delay.as_millis() as u32).to_le_bytes()),
+                &[0xF7],
+            ]
+            .concat(),
+            SchedulerRequest::QueryAll => vec![0xF0, 0x7B, 0x05, 0xF7],
+            SchedulerRequest::Query(task) => vec![0xF0, 0x7B, 0x06, *task, 0xF7],
+            SchedulerRequest::Reset => vec![0xF0, 0x7B, 0x07, 0xF7],
+        },
+        MessageOut::Serial(request) => match request {
+            SerialRequest::Config { port, baud, pins } => {
+                let baud = [*baud & 0x7F, *baud >> 7 & 0x7F, *baud >> 14 & 0x7F].map(|v| v as u8);
+                let pins = pins.map_or(vec![], |(rx, tx)| vec![rx, tx]);
+                [&[0xF0, 0x60, 0x10 | port][..], &baud, &pins, &[0xF7]].concat()
+            }
+            SerialRequest::Write { port, data } => [
+                &[0xF0, 0x60, 0x20 | port][..],
+                &data
+                    .iter()
+                    .flat_map(|v| reference::u14(u16::from(*v)))
+                    .collect::<Vec<u8>>(),
+                &[0xF7],
+            ]
+            .concat(),
+            SerialRequest::Read {
+                port,
+                mode,
+                max_bytes,
+            } => [
+                &[0xF0, 0x60, 0x30 | port, *mode as u8][..],
+                &max_bytes.map_or(vec![], |v| reference::u14(v).to_vec()),
+                &[0xF7],
+            ]
+            .concat(),
+            SerialRequest::Close(port) => vec![0xF0, 0x60, 0x50 | port, 0xF7],
+            SerialRequest::Flush(port) => vec![0xF0, 0x60, 0x60 | port, 0xF7],
+            SerialRequest::Listen(port) => vec![0xF0, 0x60, 0x70 | port, 0xF7],
+        },
+        _ => return None,
+    })
+}
+
+fn onewire_request() -> impl Strategy<Value = OneWireRequest> {
+    (
+        prop::option::of(any::<[u8; 8]>()),
+        prop::collection::vec(any::<u8>(), 0..16),
+        any::<u16>(),
+        any::<u16>(),
+        0..2000_u64,
+    )
+        .prop_map(|(address, data, read, id, delay)| {
+            OneWireRequest::new()
+                .target(address.map(OneWireAddress))
+                .write(&data)
+                .read(read, id)
+                .delay(Duration::from_millis(delay))
+        })
+}
+
+fn pin_mode() -> impl Strategy<Value = PinMode> {
+    prop::sample::select(vec![
+        PinMode::Input,
+        PinMode::Output,
+        PinMode::Analog,
+        PinMode::Pwm,
+        PinMode::Servo,
+        PinMode::I2c,
+        PinMode::Onewire,
+        PinMode::Stepper,
+        PinMode::Encoder,
+        PinMode::Serial,
+        PinMode::Pullup,
+    ])
+}
+
+fn stepper_interface() -> impl Strategy<Value = StepperInterface> {
+    prop_oneof![
+        (0..128_u8, 0..128_u8)
+            .prop_map(|(step, direction)| StepperInterface::Driver { step, direction }),
+        (0..128_u8, 0..128_u8).prop_map(|(pin1, pin2)| StepperInterface::TwoWire { pin1, pin2 }),
+        (0..128_u8, 0..128_u8, 0..128_u8)
+            .prop_map(|(pin1, pin2, pin3)| StepperInterface::ThreeWire { pin1, pin2, pin3 }),
+        (0..128_u8, 0..128_u8, 0..128_u8, 0..128_u8).prop_map(|(pin1, pin2, pin3, pin4)| {
+            StepperInterface::FourWire {
+                pin1,
+                pin2,
+                pin3,
+                pin4,
+            }
+        }),
+    ]
+}
+
+fn scheduler_request() -> impl Strategy<Value = SchedulerRequest> {
+    let task = 0..=MAX_TASK_ID;
+    let delay = (0..=u64::from(u32::MAX)).prop_map(Duration::from_millis);
+    prop_oneof![
+        (task.clone(), 1..=MAX_TASK_LENGTH as u16)
+            .prop_map(|(task, length)| SchedulerRequest::Create { task, length }),
+        (
+            task.clone(),
+            prop::collection::vec(any::<u8>(), 1..=ADD_CHUNK_SIZE)
+        )
+            .prop_map(|(task, data)| SchedulerRequest::AddTo { task, data }),
+        task.clone().prop_map(SchedulerRequest::Delete),
+        delay.clone().prop_map(SchedulerRequest::Delay),
+        (task.clone(), delay).prop_map(|(task, delay)| SchedulerRequest::Schedule { task, delay }),
+        Just(SchedulerRequest::QueryAll),
+        task.prop_map(SchedulerRequest::Query),
+        Just(SchedulerRequest::Reset),
+    ]
+}
+
+fn serial_request() -> impl Strategy<Value = SerialRequest> {
+    let port = prop_oneof![0..=3_u8, 8..=11_u8];
+    prop_oneof![
+        (
+            port.clone(),
+            1..=MAX_BAUD,
+            prop::option::of((0..128_u8, 0..128_u8))
+        )
+            .prop_map(|(port, baud, pins)| SerialRequest::Config { port, baud, pins }),
+        (
+            port.clone(),
+            prop::collection::vec(any::<u8>(), 1..=WRITE_CHUNK_SIZE)
+        )
+            .prop_map(|(port, data)| SerialRequest::Write { port, data }),
+        (
+            port.clone(),
+            prop_oneof![Just(SerialReadMode::Continuous), Just(SerialReadMode::Stop)],
+            prop::option::of(0..=MAX_U14)
+        )
+            .prop_map(|(port, mode, max_bytes)| SerialRequest::Read {
+                port,
+                mode,
+                max_bytes
+            }),
+        port.clone().prop_map(SerialRequest::Close),
+        port.clone().prop_map(SerialRequest::Flush),
+        port.prop_map(SerialRequest::Listen),
+    ]
+}
+
+/// Messages whose arguments are within what the protocol can express.
+fn message_out() -> impl Strategy<Value = MessageOut> {
+    let pin = 0..128_u8;
+    let port = 0..16_u8;
+    let u14 = 0..=MAX_U14;
+    prop_oneof![
+        Just(MessageOut::AnalogMappingQuery),
+        Just(MessageOut::CapabilityQuery),
+        Just(MessageOut::ReportFirmware),
+        Just(MessageOut::ProtocolVersionQuery),
+        Just(MessageOut::SystemReset),
+        (u14.clone(), any::<bool>()).prop_map(|(delay, restart)| {
+            MessageOut::I2cConfig(I2cConfig {
+                read_delay_us: delay,
+                restart,
+                ..I2cConfig::default()
+            })
+        }),
+        (
+            pin.clone(),
+            u14.clone(),
+            prop::option::of(u14.clone()),
+            any::<bool>()
+        )
+            .prop_map(|(addr, size, register, restart)| {
+                MessageOut::I2cRead(
+                    addr,
+                    I2cReadOptions {
+                        size,
+                        register,
+                        restart,
+                        ..I2cReadOptions::new(size)
+                    },
+                )
+            }),
+        (port.clone(), any::<bool>()).prop_map(|(port, v)| MessageOut::ReportDigital(port, v)),
+        (0..16_u8, any::<bool>()).prop_map(|(channel, v)| MessageOut::ReportAnalog(channel, v)),
+        (port.clone(), u14.clone()).prop_map(|(pin, v)| MessageOut::AnalogWrite(pin, v)),
+        prop::collection::vec((port.clone(), u14.clone()), 0..8)
+            .prop_map(MessageOut::AnalogWriteGroup),
+        (pin.clone(), any::<bool>()).prop_map(|(pin, v)| MessageOut::DigitalWrite(pin, v)),
+        ".{0,80}".prop_map(MessageOut::StringWrite),
+        (pin.clone(), pin_mode()).prop_map(|(pin, mode)| MessageOut::PinMode(pin, mode)),
+        u14.clone()
+            .prop_map(|ms| MessageOut::SampleingInterval(Duration::from_millis(u64::from(ms)))),
+        (0..MAX_STEPPERS, stepper_interface()).prop_map(|(device, interface)| {
+            MessageOut::StepperConfig(StepperConfig::new(device, interface))
+        }),
+        (
+            0..MAX_STEPPER_GROUPS,
+            prop::collection::vec(0..MAX_STEPPERS, 1..4)
+        )
+            .prop_map(|(group, devices)| MessageOut::MultiStepperConfig(group, devices)),
+        (
+            0..MAX_STEPPER_GROUPS,
+            prop::collection::vec(any::<i32>(), 1..4)
+        )
+            .prop_map(|(group, positions)| MessageOut::MultiStepperTo(group, positions)),
+        (0..MAX_STEPPER_GROUPS).prop_map(MessageOut::MultiStepperStop),
+        (0..MAX_STEPPERS).prop_map(MessageOut::StepperZero),
+        (0..MAX_STEPPERS, any::<i32>())
+            .prop_map(|(device, steps)| MessageOut::StepperStep(device, steps)),
+        (0..MAX_STEPPERS, any::<i32>())
+            .prop_map(|(device, position)| MessageOut::StepperTo(device, position)),
+        (0..MAX_STEPPERS, any::<bool>())
+            .prop_map(|(device, enable)| MessageOut::StepperEnable(device, enable)),
+        (0..MAX_STEPPERS).prop_map(MessageOut::StepperStop),
+        (0..MAX_STEPPERS).prop_map(MessageOut::StepperReportPosition),
+        (0..MAX_STEPPERS, 0.0..10_000_f32)
+            .prop_map(|(device, speed)| MessageOut::StepperSpeed(device, speed)),
+        (0..MAX_STEPPERS, 0.0..10_000_f32).prop_map(|(device, acceleration)| {
+            MessageOut::StepperAcceleration(device, acceleration)
+        }),
+        pin.clone()
+            .prop_map(|pin| MessageOut::PulseIn(PulseRequest::ping(pin))),
+        (pin.clone(), 1..=firmata::pwm::MAX_PWM_FREQUENCY)
+            .prop_map(|(pin, hz)| MessageOut::PwmFrequency(pin, hz)),
+        (port, any::<u8>(), any::<u8>())
+            .prop_map(|(port, mask, v)| MessageOut::DigitalPortWrite(port, mask, v)),
+        prop::collection::vec((pin.clone(), pin_mode()), 1..8).prop_map(MessageOut::PinModeGroup),
+        pin.clone().prop_map(MessageOut::PinStateQuery),
+        (pin.clone(), u14.clone(), u14.clone())
+            .prop_map(|(pin, min, max)| MessageOut::ServoConfig(pin, min, max)),
+        (pin.clone(), any::<bool>()).prop_map(|(pin, power)| MessageOut::OneWireConfig(pin, power)),
+        (pin.clone(), any::<bool>())
+            .prop_map(|(pin, alarms)| MessageOut::OneWireSearch(pin, alarms)),
+        (pin.clone(), onewire_request())
+            .prop_map(|(pin, request)| MessageOut::OneWireRequest(pin, request)),
+        (0..0x80_u8, prop::collection::vec(any::<u8>(), 0..8))
+            .prop_map(|(address, data)| MessageOut::I2cWrite(address, data)),
+        (0..0x80_u8, prop::collection::vec(0..0x80_u8, 0..16))
+            .prop_map(|(command, payload)| MessageOut::Sysex(command, payload)),
+        scheduler_request().prop_map(MessageOut::Scheduler),
+        serial_request().prop_map(MessageOut::Serial),
+    ]
+}
+
+/// Messages the firmware sends, as the reference model understands them.
+#[derive(Debug, Clone, PartialEq)]
+enum Inbound {
+    Analog {
+        pin: u8,
+        value: u16,
+    },
+    Digital {
+        port: u8,
+        value: u16,
+    },
+    ProtocolVersion {
+        major: u8,
+        minor: u8,
+    },
+    StringData(String),
+    I2cReply {
+        address: u16,
+        register: u16,
+        data: Vec<u8>,
+    },
+    Pulse {
+        pin: u8,
+        duration_us: u32,
+    },
+    OneWireRead {
+        pin: u8,
+        correlation_id: u16,
+        data: Vec<u8>,
+    },
+    SerialReply {
+        port: u8,
+        data: Vec<u8>,
+    },
+}
+
+impl Inbound {
+    fn encode(&self) -> Vec<u8> {
+        let sysex = |command: u8, data: &[u8]| {
+            let mut frame = vec![reference::START_SYSEX, command];
+            frame.extend_from_slice(data);
+            frame.push(reference::END_SYSEX);
+            frame
+        };
+        let bytes = |values: &[u8]| -> Vec<u8> {
+            values
+                .iter()
+                .flat_map(|v| reference::u14(u16::from(*v)))
+                .collect()
+        };
+        match self {
+            Self::Analog { pin, value } => [&[0xE0 | pin][..], &reference::u14(*value)].concat(),
+            Self::Digital { port, value } => [&[0x90 | port][..], &reference::u14(*value)].concat(),
+            Self::ProtocolVersion { major, minor } => vec![0xF9, *major, *minor],
+            Self::StringData(text) => sysex(0x71, &bytes(text.as_bytes())),
+            Self::I2cReply {
+                address,
+                register,
+                data,
+            } => sysex(
+                0x77,
+                &[
+                    &reference::u14(*address)[..],
+                    &reference::u14(*register),
+                    &bytes(data),
+                ]
+                .concat(),
+            ),
+            Self::Pulse { pin, duration_us } => sysex(
+                0x75,
+                &[
+                    &reference::u14(u16::from(*pin))[..],
+                    &bytes(&duration_us.to_be_bytes()),
+                ]
+                .concat(),
+            ),
+            Self::OneWireRead {
+                pin,
+                correlation_id,
+                data,
+            } => sysex(
+                0x73,
+                &[
+                    &[0x43, *pin][..],
+                    &reference::pack_7bit(&[&correlation_id.to_le_bytes()[..], data].concat()),
+                ]
+                .concat(),
+            ),
+            Self::SerialReply { port, data } => {
+                sysex(0x60, &[&[0x40 | port][..], &bytes(data)].concat())
+            }
+        }
+    }
+
+    fn from_message(message: MessageIn) -> Option<Self> {
+        Some(match message {
+            MessageIn::Analog(v) => match v.pin {
+                PinId::Analog(pin) => Self::Analog {
+                    pin,
+                    value: v.value,
+                },
+                _ => return None,
+            },
+            MessageIn::Digital(v) => Self::Digital {
+                port: v.port,
+                value: v.value,
+            },
+            MessageIn::ProtocolVersion(version) => {
+                let (major, minor) = version.split_once('.')?;
+                Self::ProtocolVersion {
+                    major: major.parse().ok()?,
+                    minor: minor.parse().ok()?,
+                }
+            }
+            MessageIn::System(System::StringDataMessage(v)) => Self::StringData(v.text),
+            MessageIn::System(System::I2cReplyMessage(v)) => Self::I2cReply {
+                address: u16::try_from(v.reply.address).ok()?,
+                register: u16::try_from(v.reply.register).ok()?,
+                data: v.reply.data,
+            },
+            MessageIn::System(System::PulseReplyMessage(v)) => Self::Pulse {
+                pin: v.pin,
+                duration_us: v.duration_us,
+            },
+            MessageIn::System(System::OneWireReplyMessage(OneWireReply::Read {
+                pin,
+                correlation_id,
+                data,
+            })) => Self::OneWireRead {
+                pin,
+                correlation_id,
+                data,
+            },
+            MessageIn::System(System::SerialReply { port, data }) => {
+                Self::SerialReply { port, data }
+            }
+            _ => return None,
+        })
+    }
+}
+
+fn inbound() -> impl Strategy<Value = Inbound> {
+    prop_oneof![
+        (0..16_u8, 0..=MAX_U14).prop_map(|(pin, value)| Inbound::Analog { pin, value }),
+        (0..16_u8, 0..=MAX_U14).prop_map(|(port, value)| Inbound::Digital { port, value }),
+        (0..128_u8, 0..128_u8).prop_map(|(major, minor)| Inbound::ProtocolVersion { major, minor }),
+        ".{0,40}".prop_map(Inbound::StringData),
+        (
+            0..=MAX_U14,
+            0..=MAX_U14,
+            prop::collection::vec(any::<u8>(), 1..16)
+        )
+            .prop_map(|(address, register, data)| Inbound::I2cReply {
+                address,
+                register,
+                data
+            }),
+        (0..128_u8, any::<u32>())
+            .prop_map(|(pin, duration_us)| Inbound::Pulse { pin, duration_us }),
+        (
+            0..128_u8,
+            any::<u16>(),
+            prop::collection::vec(any::<u8>(), 0..16)
+        )
+            .prop_map(|(pin, correlation_id, data)| Inbound::OneWireRead {
+                pin,
+                correlation_id,
+                data
+            }),
+        (
+            prop_oneof![0..=3_u8, 8..=11_u8],
+            prop::collection::vec(any::<u8>(), 0..32)
+        )
+            .prop_map(|(port, data)| Inbound::SerialReply { port, data }),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn every_message_encodes_to_valid_frames(message in message_out()) {
+        let bytes = encode(message.clone());
+        let frames = reference::split_frames(&bytes);
+        prop_assert!(frames.is_ok(), "{:?} encoded to {:02x?}: {:?}", message, bytes, frames);
+        if let Some(expected) = expected_encoding(&message) {
+            prop_assert_eq!(bytes, expected);
+        }
+    }
+
+    #[test]
+    fn encoded_messages_stay_in_sync(messages in prop::collection::vec(message_out(), 1..8)) {
+        let bytes: Vec<u8> = messages.into_iter().flat_map(encode).collect();
+        prop_assert!(reference::split_frames(&bytes).is_ok());
+    }
+
+    #[test]
+    fn inbound_frames_round_trip(message in inbound()) {
+        let bytes = message.encode();
+        let decoded = decode(&bytes);
+        prop_assert_eq!(decoded.len(), 1, "{:02x?} decoded to {:?}", bytes, decoded);
+        let model = Inbound::from_message(decoded[0].clone());
+        prop_assert_eq!(model.as_ref(), Some(&message));
+        prop_assert_eq!(model.map(|v| v.encode()), Some(bytes));
+    }
+
+    #[test]
+    fn inbound_streams_decode_frame_by_frame(messages in prop::collection::vec(inbound(), 1..8)) {
+        let bytes: Vec<u8> = messages.iter().flat_map(Inbound::encode).collect();
+        let decoded: Vec<Option<Inbound>> =
+            decode(&bytes).into_iter().map(Inbound::from_message).collect();
+        let expected: Vec<Option<Inbound>> = messages.into_iter().map(Some).collect();
+        prop_assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn extended_analog_reports_decode_like_compact_ones(channel in 0..128_u8, value in any::<u16>()) {
+        let bytes = [
+            &[reference::START_SYSEX, 0x6F, channel][..],
+            &[(value & 0x7F) as u8, (value >> 7 & 0x7F) as u8, (value >> 14) as u8],
+            &[reference::END_SYSEX],
+        ]
+        .concat();
+        let decoded: Vec<Option<Inbound>> =
+            decode(&bytes).into_iter().map(Inbound::from_message).collect();
+        prop_assert_eq!(decoded, vec![Some(Inbound::Analog { pin: channel, value })]);
+    }
+}
@@ -0,0 +1,34 @@
+//! Masked port writes are merged with the output latches by the board io, so writes
+//! queued before it runs build on each other.
+use firmata::testing::mock::MockBoard;
+use firmata::PinId;
+use std::time::Duration;
+
+#[tokio::test]
+async fn back_to_back_masked_writes_keep_each_other() {
+    let mock = MockBoard::uno().unwrap();
+    let mut board_io = mock.board_io().await.unwrap();
+    let mut board = board_io.get_board();
+    // Both writes are queued before the board io handles either of them.
+    board.digital_port_write(0, 0x01, 0x01).await.unwrap();
+    board.digital_port_write(0, 0x02, 0x02).await.unwrap();
+    tokio::spawn(async move { board_io.poll().await });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let writes: Vec<Vec<u8>> = mock
+        .handle()
+        .commands()
+        .into_iter()
+        .filter(|command| command.first() == Some(&0x90))
+        .collect();
+    assert_eq!(writes, vec![vec![0x90, 0x01, 0x00], vec![0x90, 0x03, 0x00]]);
+    assert_eq!(board.observer().state().output_latches.port(0), 0x03);
+}
+
+#[test]
+fn sync_digital_writes_cache_the_level() {
+    let mock = MockBoard::uno().unwrap();
+    let mut board = mock.board().unwrap();
+    board.digital_write(PinId::Pin(13), 255).unwrap();
+    assert_eq!(board.try_value(PinId::Pin(13)).unwrap(), 1);
+}
@@ -0,0 +1,25 @@
+//! Reservations claim their pin, so writes from other handles name the owner.
+use firmata::testing::mock::MockBoard;
+use firmata::{FirmataError, PinId};
+
+#[tokio::test]
+async fn writes_to_a_reserved_pin_name_the_owner() {
+    let mock = MockBoard::uno().unwrap();
+    let mut board_io = mock.board_io().await.unwrap();
+    let motor = board_io.get_board();
+    let mut other = board_io.get_board();
+    tokio::spawn(async move { board_io.poll().await });
+
+    motor
+        .reserve(PinId::Pin(13), "conveyor_motor", "direction")
+        .unwrap();
+    let error = other.digital_write(PinId::Pin(13), true).await.unwrap_err();
+    assert!(matches!(error, FirmataError::PinBusy(13, _)));
+    assert_eq!(
+        error.to_string(),
+        "pin 13 already reserved by 'conveyor_motor'"
+    );
+
+    motor.release_reservation(PinId::Pin(13), "conveyor_motor");
+    other.digital_write(PinId::Pin(13), true).await.unwrap();
+}
@@ -0,0 +1,19 @@
+//! STRING_DATA sent by the firmware reaches the observer stream.
+use firmata::testing::mock::MockBoard;
+use futures::StreamExt;
+use std::time::Duration;
+
+#[tokio::test]
+async fn observers_stream_string_data() {
+    let mock = MockBoard::uno().unwrap();
+    let mut board_io = mock.board_io().await.unwrap();
+    let strings = board_io.get_board().observer().strings();
+    tokio::spawn(async move { board_io.poll().await });
+
+    mock.handle().inject_string("low battery");
+    let mut strings = Box::pin(strings);
+    let text = tokio::time::timeout(Duration::from_secs(1), strings.next())
+        .await
+        .unwrap();
+    assert_eq!(text.as_deref(), Some("low battery"));
+}
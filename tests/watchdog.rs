@@ -0,0 +1,43 @@
+//! The watchdog runs on tokio time, so a missed report is caught with the clock paused.
+use firmata::asynchronous::interceptor::Interceptor;
+use firmata::asynchronous::watchdog::{Report, WatchdogConfig};
+use firmata::message::MessageIn;
+use firmata::testing::mock::MockBoard;
+use firmata::PinId;
+use std::time::Duration;
+
+/// Loses every analog report, like a sensor that stopped reporting.
+struct DropAnalog;
+
+impl Interceptor for DropAnalog {
+    fn inbound(&mut self, message: MessageIn) -> Option<MessageIn> {
+        match message {
+            MessageIn::Analog(_) => None,
+            message => Some(message),
+        }
+    }
+}
+
+#[tokio::test]
+async fn a_missed_report_flips_the_watchdog_status() {
+    let mock = MockBoard::uno().unwrap();
+    let mut board_io = mock.board_io().await.unwrap();
+    board_io.set_watchdog(WatchdogConfig {
+        report_timeout: Some(Duration::from_secs(1)),
+        ..WatchdogConfig::default()
+    });
+    board_io.add_interceptor(DropAnalog);
+    let mut board = board_io.get_board();
+    tokio::time::pause();
+    tokio::spawn(async move { board_io.poll().await });
+    board.report_analog(PinId::Analog(0), true).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(board.observer().state().watchdog.stale_reports.is_empty());
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    // A0 is pin 14 of an uno.
+    assert_eq!(
+        board.observer().state().watchdog.stale_reports,
+        vec![Report::Analog(14)]
+    );
+}